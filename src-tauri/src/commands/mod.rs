@@ -334,37 +334,33 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
-    modules::save_app_config(&config)?;
+    // [NEW] 校验 + 保存 + 记录版本 (校验失败时返回字段级错误)
+    let (config, commit) = tokio::task::spawn_blocking(move || {
+        let commit = modules::config::commit_app_config(
+            &config,
+            modules::config_history::ConfigSource::Ui,
+            Some("tauri"),
+        )
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>((config, commit))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    crate::proxy::security_events::emit(
+        crate::modules::security_db::SecurityEvent::new(
+            crate::modules::security_db::SecurityEventKind::ConfigChange,
+            "Configuration saved via desktop app",
+        )
+        .actor("tauri"),
+    );
 
     // 通知托盘配置已更新
     let _ = app.emit("config://updated", ());
 
-    // 热更新正在运行的服务
+    // 热更新正在运行的服务 (仅变化的分区)
     let instance_lock = proxy_state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
-        // 更新模型映射
-        instance.axum_server.update_mapping(&config.proxy).await;
-        instance.axum_server.update_pm_router(&config.proxy).await;
-        // 更新上游代理
-        instance
-            .axum_server
-            .update_proxy(config.proxy.upstream_proxy.clone())
-            .await;
-        // 更新安全策略 (auth)
-        instance.axum_server.update_security(&config.proxy).await;
-        // 更新 z.ai 配置
-        instance.axum_server.update_zai(&config.proxy).await;
-        // 更新实验性配置
-        instance.axum_server.update_experimental(&config.proxy).await;
-        // 更新调试日志配置
-        instance.axum_server.update_debug_logging(&config.proxy).await;
-        // [NEW] 更新 User-Agent 配置
-        instance.axum_server.update_user_agent(&config.proxy).await;
-        // [NEW] 更新 Thinking Budget 配置
-        crate::proxy::update_thinking_budget_config(config.proxy.thinking_budget.clone());
-        // 更新熔断配置
-        instance.token_manager.update_circuit_breaker_config(config.circuit_breaker.clone()).await;
-        tracing::debug!("已同步热更新反代服务配置");
+        instance.axum_server.apply_config_changes(&config, &commit).await;
     }
 
     Ok(())
@@ -734,6 +730,11 @@ pub async fn toggle_proxy_status(
     let mut account_json: serde_json::Value = serde_json::from_str(&content)
         .map_err(|e| format!("解析账号文件失败: {}", e))?;
 
+    let before = serde_json::json!({
+        "proxy_disabled": account_json.get("proxy_disabled").cloned().unwrap_or(serde_json::Value::Bool(false)),
+        "proxy_disabled_reason": account_json.get("proxy_disabled_reason").cloned().unwrap_or(serde_json::Value::Null),
+    });
+
     // 2. 更新 proxy_disabled 字段
     if enable {
         // 启用反代
@@ -754,6 +755,22 @@ pub async fn toggle_proxy_status(
     std::fs::write(&account_path, serde_json::to_string_pretty(&account_json).unwrap())
         .map_err(|e| format!("写入账号文件失败: {}", e))?;
 
+    modules::audit_db::record(
+        if enable {
+            modules::audit_db::AuditAction::ProxyEnable
+        } else {
+            modules::audit_db::AuditAction::ProxyDisable
+        },
+        Some(&account_id),
+        account_json.get("email").and_then(|v| v.as_str()),
+        Some(before),
+        Some(serde_json::json!({
+            "proxy_disabled": account_json["proxy_disabled"],
+            "proxy_disabled_reason": account_json["proxy_disabled_reason"],
+        })),
+        account_json["proxy_disabled_reason"].as_str(),
+    );
+
     modules::logger::log_info(&format!(
         "账号反代状态已更新: {} ({})",
         account_id,
@@ -126,6 +126,8 @@ pub async fn internal_start_proxy_service(
 
     // 同步配置到运行中的 TokenManager
     token_manager.start_auto_cleanup().await;
+    token_manager.start_codex_usage_refresh().await;
+    token_manager.start_model_discovery_refresh().await;
     token_manager.update_sticky_config(config.scheduling.clone()).await;
 
     // [NEW] 加载熔断配置 (从主配置加载)
@@ -150,7 +152,7 @@ pub async fn internal_start_proxy_service(
             return Ok(ProxyStatus {
                 running: false,
                 port: config.port,
-                base_url: format!("http://127.0.0.1:{}", config.port),
+                base_url: crate::proxy::tls::local_base_url(config.port),
                 active_accounts: 0,
             });
         }
@@ -178,7 +180,7 @@ pub async fn internal_start_proxy_service(
     Ok(ProxyStatus {
         running: true,
         port: config.port,
-        base_url: format!("http://127.0.0.1:{}", config.port),
+        base_url: crate::proxy::tls::local_base_url(config.port),
         active_accounts,
     })
 }
@@ -215,6 +217,9 @@ pub async fn ensure_admin_server(
     // [NEW] 加载账号数据，否则管理界面统计为 0
     let _ = token_manager.load_accounts().await;
 
+    // [NEW] 启动监听前加载 HTTPS 证书
+    crate::proxy::tls::manager().apply_config(&config.tls);
+
     let (axum_server, server_handle) =
         match crate::proxy::AxumServer::start(
             config.get_bind_address().to_string(),
@@ -244,6 +249,14 @@ pub async fn ensure_admin_server(
 
     // [NEW] 初始化全局 Thinking Budget 配置
     crate::proxy::update_thinking_budget_config(config.thinking_budget.clone());
+    // [NEW] 初始化模型能力注册表 (内置 + 用户覆盖)
+    crate::proxy::common::model_registry::update_model_registry(&config.model_overrides);
+    // [NEW] 初始化工具结果压缩配置
+    crate::proxy::update_tool_result_compression_config(config.tool_result_compression.clone());
+    // [NEW] 初始化日志脱敏配置
+    crate::proxy::redaction::update_redaction_config(&config.log_redaction);
+    crate::proxy::guardrails::update_guardrail_config(&config.guardrails);
+    crate::proxy::security_events::update_event_export_config(&config.security_monitor.event_export);
 
     Ok(())
 }
@@ -293,7 +306,7 @@ pub async fn get_proxy_status(
                 Some(instance) => Ok(ProxyStatus {
                     running: true,
                     port: instance.config.port,
-                    base_url: format!("http://127.0.0.1:{}", instance.config.port),
+                    base_url: crate::proxy::tls::local_base_url(instance.config.port),
                     active_accounts: instance.token_manager.len(),
                 }),
                 None => Ok(ProxyStatus {
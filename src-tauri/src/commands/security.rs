@@ -139,6 +139,22 @@ pub async fn check_ip_in_blacklist(ip: String) -> Result<bool, String> {
     security_db::is_ip_in_blacklist(&ip)
 }
 
+// ==================== 自动封禁命令 ====================
+
+/// 获取生效中的自动封禁
+#[tauri::command]
+pub async fn get_auto_bans() -> Result<Vec<security_db::AutoBanEntry>, String> {
+    security_db::get_active_auto_bans()
+}
+
+/// 解除自动封禁并清空累犯记录
+#[tauri::command]
+pub async fn lift_auto_ban(ip_pattern: String) -> Result<(), String> {
+    security_db::lift_auto_ban(&ip_pattern)?;
+    crate::proxy::auto_ban::engine().forget(&ip_pattern);
+    Ok(())
+}
+
 // ==================== IP 白名单命令 ====================
 
 /// 获取 IP 白名单列表
@@ -221,11 +237,20 @@ pub async fn update_security_config(
     app_state: State<'_, crate::commands::proxy::ProxyServiceState>,
 ) -> Result<(), String> {
     // 1. 同步保存到配置文件
-    let mut app_config = crate::modules::config::load_app_config()
-        .map_err(|e| format!("Failed to load config: {}", e))?;
-    app_config.proxy.security_monitor = config.clone();
-    crate::modules::config::save_app_config(&app_config)
-        .map_err(|e| format!("Failed to save config: {}", e))?;
+    let task_config = config.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut app_config = crate::modules::config::load_app_config()
+            .map_err(|e| format!("Failed to load config: {}", e))?;
+        app_config.proxy.security_monitor = task_config;
+        crate::modules::config::commit_app_config(
+            &app_config,
+            crate::modules::config_history::ConfigSource::Ui,
+            Some("tauri"),
+        )
+        .map_err(|e| format!("Failed to save config: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
 
     // 2. 更新内存中的配置 (如果服务正在运行)
     {
@@ -240,6 +265,15 @@ pub async fn update_security_config(
         }
     }
 
+    crate::proxy::security_events::update_event_export_config(&config.event_export);
+    crate::proxy::security_events::emit(
+        crate::modules::security_db::SecurityEvent::new(
+            crate::modules::security_db::SecurityEventKind::ConfigChange,
+            "Security config updated via desktop app",
+        )
+        .actor("tauri"),
+    );
+
     tracing::info!("[Security] Security monitor config updated and saved");
     Ok(())
 }
@@ -262,47 +296,8 @@ pub async fn get_ip_token_stats(
 
 /// 验证 IP 模式格式 (支持单个 IP 和 CIDR)
 fn is_valid_ip_pattern(pattern: &str) -> bool {
-    // 检查是否为 CIDR 格式
-    if pattern.contains('/') {
-        let parts: Vec<&str> = pattern.split('/').collect();
-        if parts.len() != 2 {
-            return false;
-        }
-        
-        // 验证 IP 部分
-        if !is_valid_ip(parts[0]) {
-            return false;
-        }
-        
-        // 验证掩码部分
-        if let Ok(mask) = parts[1].parse::<u8>() {
-            return mask <= 32;
-        }
-        return false;
-    }
-    
-    // 单个 IP 地址
-    is_valid_ip(pattern)
-}
-
-/// 验证 IP 地址格式
-fn is_valid_ip(ip: &str) -> bool {
-    let parts: Vec<&str> = ip.split('.').collect();
-    if parts.len() != 4 {
-        return false;
-    }
-    
-    for part in parts {
-        if let Ok(num) = part.parse::<u8>() {
-            if num > 255 {
-                return false;
-            }
-        } else {
-            return false;
-        }
-    }
-    
-    true
+    // IPv4 / IPv6 地址或 CIDR
+    crate::proxy::client_ip::parse_ip_pattern(pattern).is_some()
 }
 
 #[cfg(test)]
@@ -316,6 +311,8 @@ mod tests {
         assert!(is_valid_ip_pattern("172.16.0.0/16"));
         assert!(is_valid_ip_pattern("192.168.1.0/24"));
         assert!(is_valid_ip_pattern("8.8.8.8/32"));
+        assert!(is_valid_ip_pattern("2001:db8::1"));
+        assert!(is_valid_ip_pattern("2606:4700::/32"));
     }
 
     #[test]
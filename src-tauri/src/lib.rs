@@ -8,7 +8,7 @@ pub mod constants;
 
 use tauri::Manager;
 use modules::logger;
-use tracing::{info, warn, error};
+use tracing::{info, error};
 use std::sync::Arc;
 
 fn mask_sensitive(value: &str) -> String {
@@ -43,7 +43,7 @@ fn increase_nofile_limit() {
                 if libc::setrlimit(libc::RLIMIT_NOFILE, &rl) == 0 {
                     info!("Successfully increased hard file limit to {}", target);
                 } else {
-                    warn!("Failed to increase file descriptor limit");
+                    tracing::warn!("Failed to increase file descriptor limit");
                 }
             }
         }
@@ -61,6 +61,40 @@ pub fn run() {
     // Check for headless mode
     let args: Vec<String> = std::env::args().collect();
     let is_headless = args.iter().any(|arg| arg == "--headless");
+    // [NEW] 声明式配置文件 (TOML / YAML / JSON)
+    let config_file = modules::config_overlay::config_path_from_args(&args);
+
+    // [NEW] 输出合并后的有效配置 (敏感字段掩码) 后退出
+    if args.iter().any(|arg| arg == "--print-effective-config") {
+        let result = modules::config::load_app_config().and_then(|base| {
+            modules::config_overlay::build_effective_config(
+                &base,
+                config_file.as_deref().map(std::path::Path::new),
+                std::env::vars(),
+            )
+        });
+        match result {
+            Ok(mut effective) => {
+                // 与无头模式启动时的调整保持一致 (强制 LAN 访问) 后再输出与校验
+                if is_headless {
+                    effective.config.proxy.allow_lan_access = true;
+                }
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&effective.to_masked_json()).unwrap_or_default()
+                );
+                let errors = modules::config::validate_app_config(&effective.config);
+                for e in &errors {
+                    eprintln!("invalid config: {}: {}", e.field, e.message);
+                }
+                std::process::exit(if errors.is_empty() { 0 } else { 2 });
+            }
+            Err(e) => {
+                eprintln!("Failed to build effective config: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     // Increase file descriptor limit (macOS only)
     #[cfg(target_os = "macos")]
@@ -78,6 +112,30 @@ pub fn run() {
     if let Err(e) = modules::security_db::init_db() {
         error!("Failed to initialize security database: {}", e);
     }
+    // 预加载分享令牌签名密钥与吊销列表
+    proxy::share_token::init();
+    // 恢复生效中的自动封禁并启动到期检查
+    proxy::auto_ban::init();
+
+    // Initialize admin users database
+    if let Err(e) = modules::admin_db::init_db() {
+        error!("Failed to initialize admin users database: {}", e);
+    }
+
+    // Initialize account audit database
+    if let Err(e) = modules::audit_db::init_db() {
+        error!("Failed to initialize audit database: {}", e);
+    }
+
+    // Initialize config version history database
+    if let Err(e) = modules::config_history::init_db() {
+        error!("Failed to initialize config history database: {}", e);
+    }
+
+    // Initialize context summary database
+    if let Err(e) = modules::summary_db::init_db() {
+        error!("Failed to initialize context summary database: {}", e);
+    }
 
 
     if is_headless {
@@ -89,65 +147,24 @@ pub fn run() {
             let proxy_state = commands::proxy::ProxyServiceState::new();
             let cf_state = Arc::new(commands::cloudflared::CloudflaredState::new());
 
-            // Load config
-            match modules::config::load_app_config() {
-                Ok(mut config) => {
-                    let mut modified = false;
+            // Load config (gui_config.json -> --config 文件 -> 环境变量)
+            let loaded = modules::config::load_app_config().and_then(|base| {
+                modules::config_overlay::build_effective_config(
+                    &base,
+                    config_file.as_deref().map(std::path::Path::new),
+                    std::env::vars(),
+                )
+            });
+            match loaded {
+                Ok(effective) => {
+                    let mut config = effective.config.clone();
+                    let modified = !effective.overrides.is_empty();
+                    for source in &effective.overrides {
+                        info!("Config override applied: {}", source);
+                    }
                     // Force LAN access in headless/docker mode so it binds to 0.0.0.0
                     config.proxy.allow_lan_access = true;
 
-                    // [NEW] 支持通过环境变量注入 API Key
-                    // 优先级：ABV_API_KEY > API_KEY > 配置文件
-                    let env_key = std::env::var("ABV_API_KEY")
-                        .or_else(|_| std::env::var("API_KEY"))
-                        .ok();
-
-                    if let Some(key) = env_key {
-                        if !key.trim().is_empty() {
-                            info!("Using API Key from environment variable");
-                            config.proxy.api_key = key;
-                            modified = true;
-                        }
-                    }
-
-                    // [NEW] 支持通过环境变量注入 Web UI 密码
-                    // 优先级：ABV_WEB_PASSWORD > WEB_PASSWORD > 配置文件
-                    let env_web_password = std::env::var("ABV_WEB_PASSWORD")
-                        .or_else(|_| std::env::var("WEB_PASSWORD"))
-                        .ok();
-
-                    if let Some(pwd) = env_web_password {
-                        if !pwd.trim().is_empty() {
-                            info!("Using Web UI Password from environment variable");
-                            config.proxy.admin_password = Some(pwd);
-                            modified = true;
-                        }
-                    }
-
-                    // [NEW] 支持通过环境变量注入鉴权模式
-                    // 优先级：ABV_AUTH_MODE > AUTH_MODE > 配置文件
-                    let env_auth_mode = std::env::var("ABV_AUTH_MODE")
-                        .or_else(|_| std::env::var("AUTH_MODE"))
-                        .ok();
-
-                    if let Some(mode_str) = env_auth_mode {
-                        let mode = match mode_str.to_lowercase().as_str() {
-                            "off" => Some(crate::proxy::ProxyAuthMode::Off),
-                            "strict" => Some(crate::proxy::ProxyAuthMode::Strict),
-                            "all_except_health" => Some(crate::proxy::ProxyAuthMode::AllExceptHealth),
-                            "auto" => Some(crate::proxy::ProxyAuthMode::Auto),
-                            _ => {
-                                warn!("Invalid AUTH_MODE: {}, ignoring", mode_str);
-                                None
-                            }
-                        };
-                        if let Some(m) = mode {
-                            info!("Using Auth Mode from environment variable: {:?}", m);
-                            config.proxy.auth_mode = m;
-                            modified = true;
-                        }
-                    }
-
                     info!("--------------------------------------------------");
                     info!("🚀 Headless mode proxy service starting...");
                     info!("📍 Port: {}", config.proxy.port);
@@ -162,15 +179,28 @@ pub fn run() {
                     info!("💡 Search docker logs or grep gui_config.json to find them.");
                     info!("--------------------------------------------------");
 
-                    // [FIX #1460] Persist environment overrides to ensure they are visible in Web UI/load_config
+                    // [NEW] 覆盖层只保留在内存 (load_app_config 读取时叠加)，不写入 gui_config.json
+                    let errors = modules::config::validate_app_config(&config);
+                    if !errors.is_empty() {
+                        for e in &errors {
+                            error!("Invalid config: {}: {}", e.field, e.message);
+                        }
+                        error!("Refusing to start with invalid configuration");
+                        std::process::exit(1);
+                    }
                     if modified {
-                        if let Err(e) = modules::config::save_app_config(&config) {
-                            error!("Failed to persist environment overrides: {}", e);
-                        } else {
-                            info!("Environment overrides persisted to gui_config.json");
+                        if let Err(e) = modules::config_overlay::install_runtime_overlay(&effective) {
+                            error!("Failed to install config overlay: {}", e);
+                            std::process::exit(1);
                         }
                     }
 
+                    // [NEW] 导入配置中声明的账号种子
+                    let account_service = modules::account_service::AccountService::new(
+                        crate::modules::integration::SystemManager::Headless,
+                    );
+                    modules::config_overlay::seed_accounts(&effective.accounts, &account_service).await;
+
                     // Start proxy service
                     if let Err(e) = commands::proxy::internal_start_proxy_service(
                         config.proxy,
@@ -439,6 +469,8 @@ pub fn run() {
             commands::security::remove_ip_from_blacklist,
             commands::security::clear_ip_blacklist,
             commands::security::check_ip_in_blacklist,
+            commands::security::get_auto_bans,
+            commands::security::lift_auto_ban,
             commands::security::get_ip_whitelist,
             commands::security::add_ip_to_whitelist,
             commands::security::remove_ip_from_whitelist,
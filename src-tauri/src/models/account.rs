@@ -39,6 +39,12 @@ pub struct Account {
     /// 受配额保护禁用的模型列表 [NEW #621]
     #[serde(default, skip_serializing_if = "HashSet::is_empty")]
     pub protected_models: HashSet<String>,
+    /// 反代用量上限（按本地自然日/自然月统计，数据来自 token_stats）[NEW]
+    #[serde(default, skip_serializing_if = "AccountUsageLimits::is_empty")]
+    pub usage_limits: AccountUsageLimits,
+    /// 反代可用时间窗口，为空表示全天可用 [NEW]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub availability_windows: Vec<AvailabilityWindow>,
     pub created_at: i64,
     pub last_used: i64,
 }
@@ -62,6 +68,8 @@ impl Account {
             proxy_disabled_reason: None,
             proxy_disabled_at: None,
             protected_models: HashSet::new(),
+            usage_limits: AccountUsageLimits::default(),
+            availability_windows: Vec::new(),
             created_at: now,
             last_used: now,
         }
@@ -152,3 +160,178 @@ pub struct AccountExportItem {
 pub struct AccountExportResponse {
     pub accounts: Vec<AccountExportItem>,
 }
+
+/// 账号用量上限 [NEW]
+/// 任一上限达到后，该账号在当前统计周期内不再参与反代调度
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountUsageLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_requests: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monthly_requests: Option<u64>,
+}
+
+/// 账号在当前统计周期内的用量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountUsageSnapshot {
+    pub daily_tokens: u64,
+    pub monthly_tokens: u64,
+    pub daily_requests: u64,
+    pub monthly_requests: u64,
+}
+
+impl AccountUsageLimits {
+    pub fn is_empty(&self) -> bool {
+        self.daily_tokens.is_none()
+            && self.monthly_tokens.is_none()
+            && self.daily_requests.is_none()
+            && self.monthly_requests.is_none()
+    }
+
+    /// 返回所有已触达的上限
+    pub fn exceeded(&self, usage: &AccountUsageSnapshot) -> Vec<IneligibleReason> {
+        let checks = [
+            (self.daily_tokens, usage.daily_tokens, UsageCapKind::DailyTokens),
+            (self.monthly_tokens, usage.monthly_tokens, UsageCapKind::MonthlyTokens),
+            (self.daily_requests, usage.daily_requests, UsageCapKind::DailyRequests),
+            (self.monthly_requests, usage.monthly_requests, UsageCapKind::MonthlyRequests),
+        ];
+        checks
+            .into_iter()
+            .filter_map(|(limit, used, kind)| {
+                limit
+                    .filter(|l| used >= *l)
+                    .map(|limit| IneligibleReason::UsageCap { kind, used, limit })
+            })
+            .collect()
+    }
+}
+
+/// 可用时间窗口（cron 风格）[NEW]
+///
+/// - `days`: cron 星期字段，0-6 (0/7 = 周日)，支持 `*`、`1-5`、`1,3,5`
+/// - `start` / `end`: 本地时间 "HH:MM"，`end` 早于 `start` 表示跨午夜（归属于开始那天）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityWindow {
+    #[serde(default = "default_window_days")]
+    pub days: String,
+    pub start: String,
+    pub end: String,
+}
+
+fn default_window_days() -> String {
+    "*".to_string()
+}
+
+impl AvailabilityWindow {
+    /// 校验字段格式
+    pub fn validate(&self) -> Result<(), String> {
+        parse_hhmm(&self.start).ok_or_else(|| format!("无效的开始时间: {}", self.start))?;
+        parse_hhmm(&self.end).ok_or_else(|| format!("无效的结束时间: {}", self.end))?;
+        for part in self.days.split(',') {
+            let part = part.trim();
+            if part == "*" {
+                continue;
+            }
+            let valid = match part.split_once('-') {
+                Some((a, b)) => matches!((a.parse::<u32>(), b.parse::<u32>()), (Ok(a), Ok(b)) if a <= b && b <= 7),
+                None => part.parse::<u32>().map(|d| d <= 7).unwrap_or(false),
+            };
+            if !valid {
+                return Err(format!("无效的星期字段: {}", self.days));
+            }
+        }
+        Ok(())
+    }
+
+    /// 判断给定时刻是否落在窗口内
+    pub fn is_active_at<T: chrono::Datelike + chrono::Timelike>(&self, t: &T) -> bool {
+        let (Some(start), Some(end)) = (parse_hhmm(&self.start), parse_hhmm(&self.end)) else {
+            return false;
+        };
+        let minute = t.hour() * 60 + t.minute();
+        let today = t.weekday().num_days_from_sunday();
+
+        if start <= end {
+            self.day_matches(today) && minute >= start && minute < end
+        } else {
+            // 跨午夜：开始当天的晚段，或前一天开始的凌晨段
+            let yesterday = (today + 6) % 7;
+            (self.day_matches(today) && minute >= start)
+                || (self.day_matches(yesterday) && minute < end)
+        }
+    }
+
+    fn day_matches(&self, day: u32) -> bool {
+        self.days.split(',').any(|part| {
+            let part = part.trim();
+            if part == "*" {
+                return true;
+            }
+            let normalize = |d: u32| if d == 7 { 0 } else { d };
+            match part.split_once('-') {
+                Some((a, b)) => match (a.parse::<u32>(), b.parse::<u32>()) {
+                    (Ok(a), Ok(b)) => (a..=b).any(|d| normalize(d) == day),
+                    _ => false,
+                },
+                None => part.parse::<u32>().map(|d| normalize(d) == day).unwrap_or(false),
+            }
+        })
+    }
+}
+
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+    // 允许 "24:00" 表示一天结束
+    if (h < 24 && m < 60) || (h == 24 && m == 0) {
+        Some(h * 60 + m)
+    } else {
+        None
+    }
+}
+
+/// 上限类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageCapKind {
+    DailyTokens,
+    MonthlyTokens,
+    DailyRequests,
+    MonthlyRequests,
+}
+
+/// 账号当前不可调度的原因 [NEW]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IneligibleReason {
+    /// 不在任何可用时间窗口内
+    OutsideAvailabilityWindow,
+    /// 已触达用量上限
+    UsageCap {
+        kind: UsageCapKind,
+        used: u64,
+        limit: u64,
+    },
+}
+
+impl std::fmt::Display for IneligibleReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OutsideAvailabilityWindow => write!(f, "outside availability window"),
+            Self::UsageCap { kind, used, limit } => {
+                let name = match kind {
+                    UsageCapKind::DailyTokens => "daily token",
+                    UsageCapKind::MonthlyTokens => "monthly token",
+                    UsageCapKind::DailyRequests => "daily request",
+                    UsageCapKind::MonthlyRequests => "monthly request",
+                };
+                write!(f, "{} cap reached ({}/{})", name, used, limit)
+            }
+        }
+    }
+}
@@ -38,13 +38,9 @@ pub struct ScheduledWarmupConfig {
     pub monitored_models: Vec<String>,
 }
 
+/// 默认预热模型：模型注册表中的各配额分组
 fn default_warmup_models() -> Vec<String> {
-    vec![
-        "gemini-3-flash".to_string(),
-        "claude-sonnet-4-5".to_string(),
-        "gemini-3-pro-high".to_string(),
-        "gemini-3-pro-image".to_string(),
-    ]
+    crate::proxy::common::model_registry::default_quota_groups()
 }
 
 impl ScheduledWarmupConfig {
@@ -76,13 +72,9 @@ pub struct QuotaProtectionConfig {
     pub monitored_models: Vec<String>,
 }
 
+/// 默认监控模型：模型注册表中的各配额分组
 fn default_monitored_models() -> Vec<String> {
-    vec![
-        "claude-sonnet-4-5".to_string(),
-        "gemini-3-pro-high".to_string(),
-        "gemini-3-flash".to_string(),
-        "gemini-3-pro-image".to_string(),
-    ]
+    crate::proxy::common::model_registry::default_quota_groups()
 }
 
 impl QuotaProtectionConfig {
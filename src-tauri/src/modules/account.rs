@@ -300,6 +300,11 @@ pub fn delete_account(account_id: &str) -> Result<(), String> {
         .lock()
         .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
     let mut index = load_account_index()?;
+    let deleted_email = index
+        .accounts
+        .iter()
+        .find(|s| s.id == account_id)
+        .map(|s| s.email.clone());
 
     // Remove from index
     let original_len = index.accounts.len();
@@ -325,6 +330,15 @@ pub fn delete_account(account_id: &str) -> Result<(), String> {
             .map_err(|e| format!("failed_to_delete_account_file: {}", e))?;
     }
 
+    modules::audit_db::record(
+        modules::audit_db::AuditAction::Delete,
+        Some(account_id),
+        deleted_email.as_deref(),
+        None,
+        None,
+        None,
+    );
+
     Ok(())
 }
 
@@ -337,7 +351,12 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
 
     let accounts_dir = get_accounts_dir()?;
 
+    let mut deleted = Vec::new();
     for account_id in account_ids {
+        if let Some(summary) = index.accounts.iter().find(|s| &s.id == account_id) {
+            deleted.push((summary.id.clone(), summary.email.clone()));
+        }
+
         // Remove from index
         index.accounts.retain(|s| &s.id != account_id);
 
@@ -358,7 +377,20 @@ pub fn delete_accounts(account_ids: &[String]) -> Result<(), String> {
         index.current_account_id = index.accounts.first().map(|s| s.id.clone());
     }
 
-    save_account_index(&index)
+    save_account_index(&index)?;
+
+    for (id, email) in &deleted {
+        modules::audit_db::record(
+            modules::audit_db::AuditAction::Delete,
+            Some(id),
+            Some(email),
+            None,
+            None,
+            Some("batch"),
+        );
+    }
+
+    Ok(())
 }
 
 /// Reorder account list
@@ -419,6 +451,7 @@ pub async fn switch_account(
     if !index.accounts.iter().any(|s| s.id == account_id) {
         return Err(format!("Account not found: {}", account_id));
     }
+    let previous_account_id = index.current_account_id.clone();
 
     let mut account = load_account(account_id)?;
     crate::modules::logger::log_info(&format!(
@@ -443,6 +476,7 @@ pub async fn switch_account(
 
         account.update_last_used();
         save_account(&account)?;
+        record_switch(&account, previous_account_id.as_deref());
         integration.update_tray();
         return Ok(());
     }
@@ -488,6 +522,7 @@ pub async fn switch_account(
 
     account.update_last_used();
     save_account(&account)?;
+    record_switch(&account, previous_account_id.as_deref());
 
     crate::modules::logger::log_info(&format!(
         "Account switch core logic completed: {}",
@@ -497,6 +532,17 @@ pub async fn switch_account(
     Ok(())
 }
 
+fn record_switch(account: &Account, previous_account_id: Option<&str>) {
+    modules::audit_db::record(
+        modules::audit_db::AuditAction::Switch,
+        Some(&account.id),
+        Some(&account.email),
+        previous_account_id.map(|id| serde_json::json!({ "current_account_id": id })),
+        Some(serde_json::json!({ "current_account_id": account.id })),
+        None,
+    );
+}
+
 /// Get device profile info: current storage.json + account bound profile
 #[derive(Debug, Serialize)]
 pub struct DeviceProfiles {
@@ -529,8 +575,15 @@ pub fn bind_device_profile(account_id: &str, mode: &str) -> Result<DeviceProfile
     };
 
     let mut account = load_account(account_id)?;
+    let before = account.device_profile.clone();
     let _ = device::save_global_original(&profile);
     apply_profile_to_account(&mut account, profile.clone(), Some(mode.to_string()), true)?;
+    record_device_change(
+        modules::audit_db::AuditAction::DeviceBind,
+        &account,
+        before.as_ref(),
+        Some(mode),
+    );
 
     Ok(profile)
 }
@@ -542,12 +595,38 @@ pub fn bind_device_profile_with_profile(
     label: Option<String>,
 ) -> Result<DeviceProfile, String> {
     let mut account = load_account(account_id)?;
+    let before = account.device_profile.clone();
     let _ = crate::modules::device::save_global_original(&profile);
-    apply_profile_to_account(&mut account, profile.clone(), label, true)?;
+    apply_profile_to_account(&mut account, profile.clone(), label.clone(), true)?;
+    record_device_change(
+        modules::audit_db::AuditAction::DeviceBind,
+        &account,
+        before.as_ref(),
+        label.as_deref(),
+    );
 
     Ok(profile)
 }
 
+fn record_device_change(
+    action: modules::audit_db::AuditAction,
+    account: &Account,
+    before: Option<&DeviceProfile>,
+    reason: Option<&str>,
+) {
+    modules::audit_db::record(
+        action,
+        Some(&account.id),
+        Some(&account.email),
+        before.and_then(|p| serde_json::to_value(p).ok()),
+        account
+            .device_profile
+            .as_ref()
+            .and_then(|p| serde_json::to_value(p).ok()),
+        reason,
+    );
+}
+
 fn apply_profile_to_account(
     account: &mut Account,
     profile: DeviceProfile,
@@ -594,11 +673,18 @@ pub fn restore_device_version(account_id: &str, version_id: &str) -> Result<Devi
         return Err("Device profile version not found".to_string());
     };
 
+    let before = account.device_profile.clone();
     account.device_profile = Some(target_profile.clone());
     for h in account.device_history.iter_mut() {
         h.is_current = h.id == version_id;
     }
     save_account(&account)?;
+    record_device_change(
+        modules::audit_db::AuditAction::DeviceRestore,
+        &account,
+        before.as_ref(),
+        Some(version_id),
+    );
     Ok(target_profile)
 }
 
@@ -643,11 +729,18 @@ pub fn restore_original_device() -> Result<String, String> {
     if let Some(current_id) = get_current_account_id()? {
         if let Ok(mut account) = load_account(&current_id) {
             if let Some(original) = crate::modules::device::load_global_original() {
+                let before = account.device_profile.clone();
                 account.device_profile = Some(original);
                 for h in account.device_history.iter_mut() {
                     h.is_current = false;
                 }
                 save_account(&account)?;
+                record_device_change(
+                    modules::audit_db::AuditAction::DeviceRestore,
+                    &account,
+                    before.as_ref(),
+                    Some("original"),
+                );
                 return Ok(
                     "Reset current account bound profile to original (not applied to storage)"
                         .to_string(),
@@ -687,6 +780,8 @@ pub fn set_current_account_id(account_id: &str) -> Result<(), String> {
 pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), String> {
     let mut account = load_account(account_id)?;
     account.update_quota(quota);
+    let protected_before = account.protected_models.clone();
+    let was_quota_disabled = account.proxy_disabled;
 
     // --- Quota protection logic start ---
     if let Ok(config) = crate::modules::config::load_app_config() {
@@ -757,6 +852,31 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
     // 先保存账号
     save_account(&account)?;
 
+    if account.protected_models != protected_before {
+        let mut before: Vec<_> = protected_before.into_iter().collect();
+        let mut after: Vec<_> = account.protected_models.iter().cloned().collect();
+        before.sort();
+        after.sort();
+        modules::audit_db::record(
+            modules::audit_db::AuditAction::ProtectedModelsChange,
+            Some(&account.id),
+            Some(&account.email),
+            Some(serde_json::json!({ "protected_models": before })),
+            Some(serde_json::json!({ "protected_models": after })),
+            Some("quota_protection"),
+        );
+    }
+    if was_quota_disabled && !account.proxy_disabled {
+        modules::audit_db::record(
+            modules::audit_db::AuditAction::ProxyEnable,
+            Some(&account.id),
+            Some(&account.email),
+            Some(serde_json::json!({ "proxy_disabled": true })),
+            Some(serde_json::json!({ "proxy_disabled": false })),
+            Some("quota_protection_migrated"),
+        );
+    }
+
     // [FIX] 触发 TokenManager 的账号重新加载信号
     // 这样内存中的 protected_models 会被同步更新
     crate::proxy::server::trigger_account_reload(account_id);
@@ -771,6 +891,10 @@ pub fn toggle_proxy_status(
     reason: Option<&str>,
 ) -> Result<(), String> {
     let mut account = load_account(account_id)?;
+    let before = serde_json::json!({
+        "proxy_disabled": account.proxy_disabled,
+        "proxy_disabled_reason": account.proxy_disabled_reason,
+    });
 
     account.proxy_disabled = !enable;
     account.proxy_disabled_reason = if !enable {
@@ -793,9 +917,101 @@ pub fn toggle_proxy_status(
         save_account_index(&index)?;
     }
 
+    modules::audit_db::record(
+        if enable {
+            modules::audit_db::AuditAction::ProxyEnable
+        } else {
+            modules::audit_db::AuditAction::ProxyDisable
+        },
+        Some(&account.id),
+        Some(&account.email),
+        Some(before),
+        Some(serde_json::json!({
+            "proxy_disabled": account.proxy_disabled,
+            "proxy_disabled_reason": account.proxy_disabled_reason,
+        })),
+        reason,
+    );
+
+    Ok(())
+}
+
+/// 更新用量上限失败的原因 (管理接口据此区分 404 / 400 / 500)
+#[derive(Debug, Clone)]
+pub enum AccountLimitsError {
+    NotFound(String),
+    Invalid(String),
+    Failed(String),
+}
+
+impl std::fmt::Display for AccountLimitsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "Account not found: {}", id),
+            Self::Invalid(e) | Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// 更新账号 (含 Codex 账号) 的用量上限与可用时间窗口 [NEW]
+pub fn update_account_limits(
+    account_id: &str,
+    usage_limits: crate::models::AccountUsageLimits,
+    availability_windows: Vec<crate::models::AvailabilityWindow>,
+) -> Result<(), AccountLimitsError> {
+    for window in &availability_windows {
+        window.validate().map_err(AccountLimitsError::Invalid)?;
+    }
+
+    let accounts_dir = get_accounts_dir().map_err(AccountLimitsError::Failed)?;
+    if !accounts_dir.join(format!("{}.json", account_id)).exists() {
+        // Codex 账号保存在独立存储中
+        let previous = crate::modules::codex::storage::set_codex_account_limits(
+            account_id,
+            usage_limits.clone(),
+            availability_windows.clone(),
+        )
+        .map_err(AccountLimitsError::Failed)?
+        .ok_or_else(|| AccountLimitsError::NotFound(account_id.to_string()))?;
+        record_limits_change(
+            &previous.id,
+            previous.email.as_deref().unwrap_or(&previous.name),
+            (&previous.usage_limits, &previous.availability_windows),
+            (&usage_limits, &availability_windows),
+        );
+        return Ok(());
+    }
+    let mut account = load_account(account_id).map_err(AccountLimitsError::Failed)?;
+    let before = (account.usage_limits.clone(), account.availability_windows.clone());
+    account.usage_limits = usage_limits;
+    account.availability_windows = availability_windows;
+    save_account(&account).map_err(AccountLimitsError::Failed)?;
+
+    record_limits_change(
+        &account.id,
+        &account.email,
+        (&before.0, &before.1),
+        (&account.usage_limits, &account.availability_windows),
+    );
     Ok(())
 }
 
+fn record_limits_change(
+    account_id: &str,
+    email: &str,
+    before: (&crate::models::AccountUsageLimits, &[crate::models::AvailabilityWindow]),
+    after: (&crate::models::AccountUsageLimits, &[crate::models::AvailabilityWindow]),
+) {
+    modules::audit_db::record(
+        modules::audit_db::AuditAction::LimitsChange,
+        Some(account_id),
+        Some(email),
+        Some(serde_json::json!({ "usage_limits": before.0, "availability_windows": before.1 })),
+        Some(serde_json::json!({ "usage_limits": after.0, "availability_windows": after.1 })),
+        None,
+    );
+}
+
 /// Export accounts by IDs (for backup/migration)
 pub fn export_accounts_by_ids(account_ids: &[String]) -> Result<crate::models::AccountExportResponse, String> {
     use crate::models::{AccountExportItem, AccountExportResponse};
@@ -829,6 +1045,17 @@ pub fn export_accounts() -> Result<Vec<(String, String)>, String> {
     Ok(exports)
 }
 
+fn record_disable(account: &Account) {
+    modules::audit_db::record(
+        modules::audit_db::AuditAction::Disable,
+        Some(&account.id),
+        Some(&account.email),
+        Some(serde_json::json!({ "disabled": false })),
+        Some(serde_json::json!({ "disabled": true })),
+        account.disabled_reason.as_deref(),
+    );
+}
+
 /// Quota query with retry (moved from commands to modules for reuse)
 pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppResult<QuotaData> {
     use crate::error::AppError;
@@ -854,6 +1081,7 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                 account.disabled_at = Some(chrono::Utc::now().timestamp());
                 account.disabled_reason = Some(format!("invalid_grant: {}", e));
                 let _ = save_account(account);
+                record_disable(account);
             }
             return Err(AppError::OAuth(e));
         }
@@ -953,6 +1181,7 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                             account.disabled_at = Some(chrono::Utc::now().timestamp());
                             account.disabled_reason = Some(format!("invalid_grant: {}", e));
                             let _ = save_account(account);
+                            record_disable(account);
                         }
                         return Err(AppError::OAuth(e));
                     }
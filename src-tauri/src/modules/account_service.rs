@@ -41,6 +41,15 @@ impl AccountService {
             token
         )?;
 
+        modules::audit_db::record(
+            modules::audit_db::AuditAction::Add,
+            Some(&account.id),
+            Some(&account.email),
+            None,
+            None,
+            Some("refresh_token"),
+        );
+
         modules::logger::log_info(&format!("[Service] Added/Updated account: {}", account.email));
         Ok(account)
     }
@@ -128,6 +137,15 @@ impl AccountService {
             token_data,
         )?;
 
+        modules::audit_db::record(
+            modules::audit_db::AuditAction::Add,
+            Some(&account.id),
+            Some(&account.email),
+            None,
+            None,
+            Some("oauth"),
+        );
+
         // 发送 UI 更新通知 (通过 integration)
         self.integration.update_tray();
 
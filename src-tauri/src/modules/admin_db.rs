@@ -0,0 +1,435 @@
+//! Admin Users Module
+//! 管理后台用户、角色与登录会话 (密码使用 Argon2 哈希，会话令牌仅保存 SHA-256 摘要)
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// 会话令牌前缀 (用于与旧版共享密码区分)
+pub const SESSION_TOKEN_PREFIX: &str = "ags_";
+
+/// 管理角色 (权限依次递增)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    /// 只读: 统计与日志
+    Viewer,
+    /// 运维: 账号管理、预热、调度状态
+    Operator,
+    /// 管理员: 配置、安全、用户管理
+    Admin,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Viewer => "viewer",
+            Self::Operator => "operator",
+            Self::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "viewer" => Some(Self::Viewer),
+            "operator" => Some(Self::Operator),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// 管理用户 (不含密码哈希)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub id: String,
+    pub username: String,
+    pub role: AdminRole,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub last_login_at: Option<i64>,
+}
+
+/// 已验证的会话
+#[derive(Debug, Clone)]
+pub struct AdminSession {
+    pub user: AdminUser,
+    pub token_hash: String,
+    pub expires_at: i64,
+}
+
+/// 获取管理用户数据库路径
+pub fn get_admin_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("admin.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_admin_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化管理用户数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS admin_users (
+            id TEXT PRIMARY KEY,
+            username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            password_hash TEXT NOT NULL,
+            role TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            last_login_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS admin_sessions (
+            token_hash TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_seen_at INTEGER NOT NULL,
+            client_ip TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_admin_sessions_user ON admin_sessions (user_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+/// 用户不存在时用于校验的占位哈希，使未知用户与错误密码耗时一致
+static DUMMY_PASSWORD_HASH: once_cell::sync::Lazy<String> = once_cell::sync::Lazy::new(|| {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    hash_password(&format!("{:x?}", bytes)).unwrap_or_default()
+});
+
+/// 降级、禁用或删除最后一个可用管理员时的错误
+pub const LAST_ADMIN_ERROR: &str = "Cannot demote, disable or delete the last enabled admin";
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn validate_new_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < 8 {
+        return Err("Password must be at least 8 characters".to_string());
+    }
+    Ok(())
+}
+
+fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<AdminUser> {
+    let role: String = row.get(2)?;
+    Ok(AdminUser {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        role: AdminRole::parse(&role).unwrap_or(AdminRole::Viewer),
+        enabled: row.get::<_, i64>(3)? != 0,
+        created_at: row.get(4)?,
+        last_login_at: row.get(5)?,
+    })
+}
+
+const USER_COLUMNS: &str = "id, username, role, enabled, created_at, last_login_at";
+
+/// 管理用户数量 (为 0 时管理接口沿用共享密码鉴权)
+pub fn users_count() -> Result<u64, String> {
+    let conn = connect_db()?;
+    conn.query_row("SELECT COUNT(*) FROM admin_users", [], |row| row.get::<_, i64>(0))
+        .map(|c| c as u64)
+        .map_err(|e| e.to_string())
+}
+
+/// 创建管理用户
+pub fn create_user(username: &str, password: &str, role: AdminRole) -> Result<AdminUser, String> {
+    let username = username.trim();
+    if username.is_empty() {
+        return Err("Username must not be empty".to_string());
+    }
+    validate_new_password(password)?;
+
+    let user = AdminUser {
+        id: uuid::Uuid::new_v4().to_string(),
+        username: username.to_string(),
+        role,
+        enabled: true,
+        created_at: chrono::Utc::now().timestamp(),
+        last_login_at: None,
+    };
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO admin_users (id, username, password_hash, role, enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+        params![user.id, user.username, hash_password(password)?, role.as_str(), user.created_at],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("User {} already exists", username)
+        }
+        other => other.to_string(),
+    })?;
+    Ok(user)
+}
+
+/// 获取管理用户列表
+pub fn list_users() -> Result<Vec<AdminUser>, String> {
+    let conn = connect_db()?;
+    let sql = format!("SELECT {} FROM admin_users ORDER BY created_at ASC", USER_COLUMNS);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], row_to_user).map_err(|e| e.to_string())?;
+
+    let mut users = Vec::new();
+    for row in rows {
+        users.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(users)
+}
+
+/// 除指定用户外仍启用的管理员数量
+fn other_enabled_admins(conn: &Connection, id: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM admin_users WHERE role = ?1 AND enabled = 1 AND id != ?2",
+        params![AdminRole::Admin.as_str(), id],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn is_active_admin(user: &AdminUser) -> bool {
+    user.role == AdminRole::Admin && user.enabled
+}
+
+/// 更新管理用户 (修改密码、禁用或降级时吊销其全部会话)
+pub fn update_user(
+    id: &str,
+    role: Option<AdminRole>,
+    password: Option<&str>,
+    enabled: Option<bool>,
+) -> Result<AdminUser, String> {
+    // 密码哈希较慢，放在事务外完成
+    let password_hash = match password {
+        Some(password) => {
+            validate_new_password(password)?;
+            Some(hash_password(password)?)
+        }
+        None => None,
+    };
+
+    // 末位管理员检查与全部写入在同一个 IMMEDIATE 事务内，避免并发降级/禁用绕过检查
+    let mut conn = connect_db()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let sql = format!("SELECT {} FROM admin_users WHERE id = ?1", USER_COLUMNS);
+    let mut user = tx
+        .query_row(&sql, [id], row_to_user)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("User {} not found", id))?;
+
+    let was_active_admin = is_active_admin(&user);
+    let mut revoke = false;
+    if let Some(role) = role {
+        revoke |= role < user.role;
+        user.role = role;
+    }
+    if let Some(enabled) = enabled {
+        revoke |= !enabled;
+        user.enabled = enabled;
+    }
+    // 至少保留一个可用管理员，避免管理后台被锁死
+    if was_active_admin && !is_active_admin(&user) && other_enabled_admins(&tx, id)? == 0 {
+        return Err(LAST_ADMIN_ERROR.to_string());
+    }
+
+    if let Some(password_hash) = password_hash {
+        tx.execute(
+            "UPDATE admin_users SET password_hash = ?1 WHERE id = ?2",
+            params![password_hash, id],
+        )
+        .map_err(|e| e.to_string())?;
+        revoke = true;
+    }
+    tx.execute(
+        "UPDATE admin_users SET role = ?1, enabled = ?2 WHERE id = ?3",
+        params![user.role.as_str(), user.enabled as i64, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    if revoke {
+        tx.execute("DELETE FROM admin_sessions WHERE user_id = ?1", [id])
+            .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(user)
+}
+
+/// 删除管理用户及其会话 (不允许删除最后一个可用管理员)
+pub fn delete_user(id: &str) -> Result<(), String> {
+    let mut conn = connect_db()?;
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+    let sql = format!("SELECT {} FROM admin_users WHERE id = ?1", USER_COLUMNS);
+    let user = tx
+        .query_row(&sql, [id], row_to_user)
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("User {} not found", id))?;
+    if is_active_admin(&user) && other_enabled_admins(&tx, id)? == 0 {
+        return Err(LAST_ADMIN_ERROR.to_string());
+    }
+
+    tx.execute("DELETE FROM admin_sessions WHERE user_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM admin_users WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 校验用户名与密码 (用户不存在、已禁用或密码错误均返回 None)
+pub fn verify_credentials(username: &str, password: &str) -> Result<Option<AdminUser>, String> {
+    let conn = connect_db()?;
+    let sql = format!("SELECT {}, password_hash FROM admin_users WHERE username = ?1", USER_COLUMNS);
+    let found = conn
+        .query_row(&sql, [username.trim()], |row| Ok((row_to_user(row)?, row.get::<_, String>(6)?)))
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    // 用户不存在或已禁用时同样执行一次 Argon2 校验，避免通过响应耗时枚举用户名
+    Ok(match found {
+        Some((user, hash)) => {
+            let valid = verify_password(password, &hash);
+            (valid && user.enabled).then_some(user)
+        }
+        None => {
+            verify_password(password, &DUMMY_PASSWORD_HASH);
+            None
+        }
+    })
+}
+
+/// 创建登录会话，返回 (明文令牌, 到期时间)
+pub fn create_session(user: &AdminUser, ttl_secs: i64, client_ip: Option<&str>) -> Result<(String, i64), String> {
+    let bytes: [u8; 32] = rand::thread_rng().gen();
+    let token = format!(
+        "{}{}",
+        SESSION_TOKEN_PREFIX,
+        bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    );
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = now + ttl_secs;
+
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM admin_sessions WHERE expires_at <= ?1", [now])
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO admin_sessions (token_hash, user_id, created_at, expires_at, last_seen_at, client_ip)
+         VALUES (?1, ?2, ?3, ?4, ?3, ?5)",
+        params![hash_token(&token), user.id, now, expires_at, client_ip],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE admin_users SET last_login_at = ?1 WHERE id = ?2",
+        params![now, user.id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok((token, expires_at))
+}
+
+/// 校验会话令牌 (过期、用户被禁用或删除时返回 None)
+pub fn validate_session(token: &str) -> Result<Option<AdminSession>, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    let token_hash = hash_token(token);
+    let sql = "SELECT u.id, u.username, u.role, u.enabled, u.created_at, u.last_login_at, s.expires_at
+               FROM admin_sessions s
+               JOIN admin_users u ON u.id = s.user_id
+               WHERE s.token_hash = ?1 AND s.expires_at > ?2 AND u.enabled = 1";
+    let found = conn
+        .query_row(sql, params![token_hash, now], |row| Ok((row_to_user(row)?, row.get::<_, i64>(6)?)))
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let Some((user, expires_at)) = found else {
+        return Ok(None);
+    };
+    let _ = conn.execute(
+        "UPDATE admin_sessions SET last_seen_at = ?1 WHERE token_hash = ?2",
+        params![now, token_hash],
+    );
+    Ok(Some(AdminSession { user, token_hash, expires_at }))
+}
+
+/// 吊销会话 (登出)
+pub fn revoke_session(token_hash: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute("DELETE FROM admin_sessions WHERE token_hash = ?1", [token_hash])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_hash_roundtrip() {
+        let hash = hash_password("correct horse").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not-a-hash"));
+        // 占位哈希必须是真实的 Argon2 哈希，才能与已存在用户的校验耗时一致
+        assert!(DUMMY_PASSWORD_HASH.starts_with("$argon2"));
+        assert!(!verify_password("correct horse", &DUMMY_PASSWORD_HASH));
+    }
+
+    #[test]
+    fn test_role_order_and_parse() {
+        assert!(AdminRole::Viewer < AdminRole::Operator);
+        assert!(AdminRole::Operator < AdminRole::Admin);
+        assert_eq!(AdminRole::parse("Operator"), Some(AdminRole::Operator));
+        assert_eq!(AdminRole::parse("root"), None);
+        assert!(validate_new_password("short").is_err());
+    }
+}
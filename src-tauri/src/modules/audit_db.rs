@@ -0,0 +1,411 @@
+//! Account Audit Module
+//! 账号生命周期变更审计 (仅追加，不可修改/删除)
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 操作来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditActor {
+    /// 桌面端 Tauri 命令 / 托盘菜单
+    Tauri,
+    /// 管理 HTTP API (Web 控制台 / 本地 HTTP API)
+    AdminApi,
+    /// 后台定时任务 (预热、配额刷新)
+    Scheduler,
+    /// 反代运行时自动处理 (invalid_grant 禁用、配额保护)
+    Proxy,
+}
+
+impl AuditActor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tauri => "tauri",
+            Self::AdminApi => "admin_api",
+            Self::Scheduler => "scheduler",
+            Self::Proxy => "proxy",
+        }
+    }
+}
+
+/// 审计动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Switch,
+    Add,
+    Import,
+    Delete,
+    Disable,
+    ProxyDisable,
+    ProxyEnable,
+    ProtectedModelsChange,
+    DeviceBind,
+    DeviceRestore,
+    LimitsChange,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Switch => "switch",
+            Self::Add => "add",
+            Self::Import => "import",
+            Self::Delete => "delete",
+            Self::Disable => "disable",
+            Self::ProxyDisable => "proxy_disable",
+            Self::ProxyEnable => "proxy_enable",
+            Self::ProtectedModelsChange => "protected_models_change",
+            Self::DeviceBind => "device_bind",
+            Self::DeviceRestore => "device_restore",
+            Self::LimitsChange => "limits_change",
+        }
+    }
+}
+
+/// 审计事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub timestamp: i64,
+    pub actor: String,
+    pub action: String,
+    pub account_id: Option<String>,
+    pub account_email: Option<String>,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+    pub reason: Option<String>,
+}
+
+/// 审计查询过滤条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub account_id: Option<String>,
+    /// 邮箱模糊匹配
+    pub email: Option<String>,
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    /// 起始时间 (unix 秒，含)
+    pub since: Option<i64>,
+    /// 结束时间 (unix 秒，含)
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+tokio::task_local! {
+    static AUDIT_ACTOR: AuditActor;
+}
+
+/// 在指定来源下执行 future，期间的审计记录都归属于该来源
+pub async fn scope<F: std::future::Future>(actor: AuditActor, f: F) -> F::Output {
+    AUDIT_ACTOR.scope(actor, f).await
+}
+
+/// 当前来源 (未设置时视为 Tauri 命令)
+pub fn current_actor() -> AuditActor {
+    AUDIT_ACTOR.try_with(|a| *a).unwrap_or(AuditActor::Tauri)
+}
+
+/// 获取审计数据库路径
+pub fn get_audit_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("audit.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_audit_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化审计数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS account_audit (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            action TEXT NOT NULL,
+            account_id TEXT,
+            account_email TEXT,
+            before_value TEXT,
+            after_value TEXT,
+            reason TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON account_audit (timestamp DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_audit_account ON account_audit (account_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 仅追加：禁止修改与删除
+    conn.execute_batch(
+        "CREATE TRIGGER IF NOT EXISTS account_audit_no_update
+            BEFORE UPDATE ON account_audit
+            BEGIN SELECT RAISE(ABORT, 'account_audit is append-only'); END;
+         CREATE TRIGGER IF NOT EXISTS account_audit_no_delete
+            BEFORE DELETE ON account_audit
+            BEGIN SELECT RAISE(ABORT, 'account_audit is append-only'); END;",
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 记录审计事件 (来源取自当前上下文)
+pub fn record(
+    action: AuditAction,
+    account_id: Option<&str>,
+    account_email: Option<&str>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    reason: Option<&str>,
+) {
+    record_with_actor(
+        current_actor(),
+        action,
+        account_id,
+        account_email,
+        before,
+        after,
+        reason,
+    );
+}
+
+/// 以指定来源记录审计事件；写入失败只记日志，不影响业务流程
+pub fn record_with_actor(
+    actor: AuditActor,
+    action: AuditAction,
+    account_id: Option<&str>,
+    account_email: Option<&str>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    reason: Option<&str>,
+) {
+    let result = connect_db().and_then(|conn| {
+        conn.execute(
+            "INSERT INTO account_audit (timestamp, actor, action, account_id, account_email, before_value, after_value, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                chrono::Utc::now().timestamp(),
+                actor.as_str(),
+                action.as_str(),
+                account_id,
+                account_email,
+                before.map(|v| v.to_string()),
+                after.map(|v| v.to_string()),
+                reason,
+            ],
+        )
+        .map_err(|e| e.to_string())
+    });
+
+    if let Err(e) = result {
+        tracing::warn!(
+            "[Audit] Failed to record {} ({}): {}",
+            action.as_str(),
+            account_id.unwrap_or("-"),
+            e
+        );
+    }
+}
+
+fn build_where(filter: &AuditFilter) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut clauses: Vec<&str> = Vec::new();
+    let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(id) = &filter.account_id {
+        clauses.push("account_id = ?");
+        values.push(Box::new(id.clone()));
+    }
+    if let Some(email) = &filter.email {
+        clauses.push("account_email LIKE ?");
+        values.push(Box::new(format!("%{}%", email)));
+    }
+    if let Some(action) = &filter.action {
+        clauses.push("action = ?");
+        values.push(Box::new(action.clone()));
+    }
+    if let Some(actor) = &filter.actor {
+        clauses.push("actor = ?");
+        values.push(Box::new(actor.clone()));
+    }
+    if let Some(since) = filter.since {
+        clauses.push("timestamp >= ?");
+        values.push(Box::new(since));
+    }
+    if let Some(until) = filter.until {
+        clauses.push("timestamp <= ?");
+        values.push(Box::new(until));
+    }
+
+    let where_sql = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", clauses.join(" AND "))
+    };
+    (where_sql, values)
+}
+
+/// 查询审计事件 (按时间倒序)
+pub fn get_audit_events(filter: &AuditFilter) -> Result<Vec<AuditEvent>, String> {
+    let conn = connect_db()?;
+    let (where_sql, values) = build_where(filter);
+    let sql = format!(
+        "SELECT id, timestamp, actor, action, account_id, account_email, before_value, after_value, reason
+         FROM account_audit {}
+         ORDER BY timestamp DESC, id DESC
+         LIMIT {} OFFSET {}",
+        where_sql,
+        filter.limit.unwrap_or(100),
+        filter.offset.unwrap_or(0)
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+            |row| {
+                let before: Option<String> = row.get(6)?;
+                let after: Option<String> = row.get(7)?;
+                Ok(AuditEvent {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    actor: row.get(2)?,
+                    action: row.get(3)?,
+                    account_id: row.get(4)?,
+                    account_email: row.get(5)?,
+                    before: before.and_then(|s| serde_json::from_str(&s).ok()),
+                    after: after.and_then(|s| serde_json::from_str(&s).ok()),
+                    reason: row.get(8)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(result)
+}
+
+/// 统计符合条件的审计事件数量
+pub fn get_audit_events_count(filter: &AuditFilter) -> Result<u64, String> {
+    let conn = connect_db()?;
+    let (where_sql, values) = build_where(filter);
+    let sql = format!("SELECT COUNT(*) FROM account_audit {}", where_sql);
+    conn.query_row(
+        &sql,
+        rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|c| c as u64)
+    .map_err(|e| e.to_string())
+}
+
+/// 导出审计事件 (format: "jsonl" | "csv")，不分页
+pub fn export_audit_events(filter: &AuditFilter, format: &str) -> Result<String, String> {
+    let mut all = filter.clone();
+    all.limit = Some(usize::MAX >> 1);
+    all.offset = None;
+    let events = get_audit_events(&all)?;
+
+    match format {
+        "jsonl" => Ok(events
+            .iter()
+            .filter_map(|e| serde_json::to_string(e).ok())
+            .map(|line| line + "\n")
+            .collect()),
+        "csv" => {
+            let mut out = String::from(
+                "id,timestamp,actor,action,account_id,account_email,before,after,reason\n",
+            );
+            for e in &events {
+                let fields = [
+                    e.id.to_string(),
+                    e.timestamp.to_string(),
+                    e.actor.clone(),
+                    e.action.clone(),
+                    e.account_id.clone().unwrap_or_default(),
+                    e.account_email.clone().unwrap_or_default(),
+                    e.before.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+                    e.after.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+                    e.reason.clone().unwrap_or_default(),
+                ];
+                let line: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+                out.push_str(&line.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        other => Err(format!("Unsupported export format: {}", other)),
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("{\"k\":1}"), "\"{\"\"k\"\":1}\"");
+    }
+
+    #[test]
+    fn test_build_where() {
+        let (sql, values) = build_where(&AuditFilter::default());
+        assert!(sql.is_empty());
+        assert!(values.is_empty());
+
+        let filter = AuditFilter {
+            account_id: Some("acc-1".to_string()),
+            action: Some("switch".to_string()),
+            since: Some(100),
+            ..Default::default()
+        };
+        let (sql, values) = build_where(&filter);
+        assert_eq!(sql, "WHERE account_id = ? AND action = ? AND timestamp >= ?");
+        assert_eq!(values.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_actor_scope() {
+        assert_eq!(current_actor(), AuditActor::Tauri);
+        let inner = scope(AuditActor::Scheduler, async { current_actor() }).await;
+        assert_eq!(inner, AuditActor::Scheduler);
+    }
+}
@@ -234,7 +234,9 @@ impl CloudflaredManager {
             return Err("Cloudflared not installed".to_string());
         }
 
-        let local_url = format!("http://localhost:{}", config.port);
+        let https_origin = crate::proxy::tls::manager().is_active();
+        let scheme = if https_origin { "https" } else { "http" };
+        let local_url = format!("{}://localhost:{}", scheme, config.port);
         info!("[cloudflared] Starting tunnel to: {}", local_url);
 
         let mut cmd = Command::new(&self.bin_path);
@@ -258,6 +260,11 @@ impl CloudflaredManager {
                 if config.use_http2 {
                     cmd.arg("--protocol").arg("http2");
                 }
+
+                // 本地监听使用自签名证书时跳过源站证书校验 (用户提供的证书照常校验)
+                if https_origin && crate::proxy::tls::manager().is_self_signed() {
+                    cmd.arg("--no-tls-verify");
+                }
                 
                 // 注意：--loglevel 参数在此上下文中也会导致 Incorrect Usage 错误，故移除以使用默认值
                 // cmd.arg("--loglevel").arg("info");
@@ -243,9 +243,24 @@ pub async fn refresh_codex_account_tokens(
         account.plan_type = Some(plan_type.clone());
     }
 
+    let updated = account.clone();
     storage::save_codex_accounts(&store)?;
 
-    Ok((account.clone(), refresh_result))
+    Ok((updated, refresh_result))
+}
+
+/// JWT access token 의 `exp` 클레임(만료 unix timestamp) 추출
+pub fn parse_jwt_expiry(token: &str) -> Option<i64> {
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(parts[1].trim_end_matches('='))
+        .ok()?;
+    let json: serde_json::Value = serde_json::from_slice(&payload).ok()?;
+    json.get("exp").and_then(|v| v.as_i64())
 }
 
 /// JWT ID 토큰에서 클레임 추출
@@ -6,6 +6,7 @@ use std::path::PathBuf;
 use chrono::Utc;
 
 use super::types::{CodexAccount, CodexAccountsStore};
+use crate::models::{AccountUsageLimits, AvailabilityWindow};
 
 const CODEX_DIR: &str = "codex";
 const ACCOUNTS_FILE: &str = "accounts.json";
@@ -146,3 +147,21 @@ pub fn rename_codex_account(account_id: &str, new_name: String) -> Result<(), St
         Err(format!("계정을 찾을 수 없습니다: {}", account_id))
     }
 }
+
+/// 계정 사용량 상한 / 사용 가능 시간대 변경, 변경 전 계정을 반환 (계정이 없으면 None)
+pub fn set_codex_account_limits(
+    account_id: &str,
+    usage_limits: AccountUsageLimits,
+    availability_windows: Vec<AvailabilityWindow>,
+) -> Result<Option<CodexAccount>, String> {
+    let mut store = load_codex_accounts()?;
+
+    let Some(account) = store.accounts.iter_mut().find(|a| a.id == account_id) else {
+        return Ok(None);
+    };
+    let previous = account.clone();
+    account.usage_limits = usage_limits;
+    account.availability_windows = availability_windows;
+    save_codex_accounts(&store)?;
+    Ok(Some(previous))
+}
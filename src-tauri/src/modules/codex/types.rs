@@ -4,6 +4,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::models::{AccountUsageLimits, AvailabilityWindow};
+
 /// Codex 계정 저장소
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodexAccountsStore {
@@ -44,6 +46,12 @@ pub struct CodexAccount {
     pub created_at: DateTime<Utc>,
     /// 마지막 사용 시간
     pub last_used_at: Option<DateTime<Utc>>,
+    /// 프록시 사용량 상한 (Google 계정과 동일한 형식) [NEW]
+    #[serde(default, skip_serializing_if = "AccountUsageLimits::is_empty")]
+    pub usage_limits: AccountUsageLimits,
+    /// 프록시 사용 가능 시간대, 비어 있으면 종일 사용 가능 [NEW]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub availability_windows: Vec<AvailabilityWindow>,
 }
 
 impl CodexAccount {
@@ -58,6 +66,8 @@ impl CodexAccount {
             auth_data: CodexAuthData::ApiKey { key: api_key },
             created_at: Utc::now(),
             last_used_at: None,
+            usage_limits: AccountUsageLimits::default(),
+            availability_windows: Vec::new(),
         }
     }
 
@@ -85,6 +95,8 @@ impl CodexAccount {
             },
             created_at: Utc::now(),
             last_used_at: None,
+            usage_limits: AccountUsageLimits::default(),
+            availability_windows: Vec::new(),
         }
     }
 }
@@ -1,20 +1,31 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeSet;
 use std::fs;
-use serde_json;
+use std::sync::Mutex;
 
 use crate::models::AppConfig;
+use crate::proxy::config::{ProxyAuthMode, ThinkingBudgetMode};
 use super::account::get_data_dir;
+use super::config_history::{self, ConfigSource};
 
 const CONFIG_FILE: &str = "gui_config.json";
 
 /// Load application configuration
+/// [NEW] Headless 模式下叠加内存中的运行时覆盖层 (环境变量 / --config)
 pub fn load_app_config() -> Result<AppConfig, String> {
+    load_persisted_config().map(super::config_overlay::apply_runtime_overlay)
+}
+
+/// 读取 gui_config.json 本身 (不含运行时覆盖层)
+fn load_persisted_config() -> Result<AppConfig, String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
     
     if !config_path.exists() {
         let config = AppConfig::new();
         // [FIX #1460] Persist initial config to prevent new API Key on every refresh
-        let _ = save_app_config(&config);
+        let _ = write_config_file(&config_path, &config);
         return Ok(config);
     }
     
@@ -72,20 +83,407 @@ pub fn load_app_config() -> Result<AppConfig, String> {
     
     // If migration occurred, auto-save once to clean up the file
     if modified {
-        let _ = save_app_config(&config);
+        let _ = write_config_file(&config_path, &config);
     }
 
     Ok(config)
 }
 
 /// Save application configuration
+/// [NEW] 运行时覆盖层中的字段保持磁盘原值，覆盖值只存在于内存
 pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     let data_dir = get_data_dir()?;
     let config_path = data_dir.join(CONFIG_FILE);
-    
+
+    let value = super::config_overlay::strip_runtime_overlay(config, || {
+        fs::read_to_string(&config_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    })?;
+    write_config_file(&config_path, &value)
+}
+
+fn write_config_file(config_path: &std::path::Path, config: &impl Serialize) -> Result<(), String> {
     let content = serde_json::to_string_pretty(config)
         .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
     
-    fs::write(&config_path, content)
+    fs::write(config_path, content)
         .map_err(|e| format!("failed_to_save_config: {}", e))
 }
+
+/// 修改后需重启反代服务才能生效的分区 (监听地址/端口)
+const RESTART_REQUIRED_SECTIONS: &[&str] = &["proxy.port", "proxy.allow_lan_access"];
+
+/// 串行化 "读取旧配置 -> 保存 -> 记录版本"，避免并发保存时版本与 diff 错位
+static COMMIT_LOCK: Mutex<()> = Mutex::new(());
+
+/// 字段级校验错误
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ConfigFieldError {
+    /// 点分路径，如 proxy.upstream_proxy.url
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigFieldError {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+/// 配置差异项 (叶子字段，数组整体比较)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigDiffEntry {
+    pub path: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// 保存结果
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigCommit {
+    /// 新版本号 (无变更或历史写入失败时为空)
+    pub version_id: Option<i64>,
+    pub changed_sections: Vec<String>,
+    /// 需重启反代服务才能生效的分区
+    pub restart_required: Vec<String>,
+}
+
+/// 旧配置不可读时的变更分区，表示全部分区都可能变化
+const ALL_SECTIONS: &str = "*";
+
+impl ConfigCommit {
+    pub fn touches(&self, section: &str) -> bool {
+        self.changed_sections.iter().any(|s| s == section || s == ALL_SECTIONS)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigSaveError {
+    Invalid(Vec<ConfigFieldError>),
+    Failed(String),
+}
+
+impl std::fmt::Display for ConfigSaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(errors) => {
+                let details: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+                write!(f, "invalid_config: {}", details.join("; "))
+            }
+            Self::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+fn is_valid_url(value: &str, schemes: &[&str]) -> bool {
+    url::Url::parse(value.trim())
+        .map(|u| schemes.contains(&u.scheme()) && u.host_str().is_some())
+        .unwrap_or(false)
+}
+
+/// 保存前的配置校验，返回全部字段级错误
+pub fn validate_app_config(config: &AppConfig) -> Vec<ConfigFieldError> {
+    let mut errors = Vec::new();
+    let proxy = &config.proxy;
+
+    if config.auto_refresh && config.refresh_interval <= 0 {
+        errors.push(ConfigFieldError::new("refresh_interval", "must be at least 1 minute"));
+    }
+    if config.auto_sync && config.sync_interval <= 0 {
+        errors.push(ConfigFieldError::new("sync_interval", "must be at least 1 minute"));
+    }
+    if config.circuit_breaker.enabled && config.circuit_breaker.backoff_steps.is_empty() {
+        errors.push(ConfigFieldError::new("circuit_breaker.backoff_steps", "must not be empty when enabled"));
+    }
+
+    // 反代基础配置
+    if proxy.port == 0 {
+        errors.push(ConfigFieldError::new("proxy.port", "must be between 1 and 65535"));
+    }
+    if proxy.request_timeout == 0 {
+        errors.push(ConfigFieldError::new("proxy.request_timeout", "must be greater than 0"));
+    }
+    let auth_required = match proxy.auth_mode {
+        ProxyAuthMode::Off => false,
+        ProxyAuthMode::Auto => proxy.allow_lan_access,
+        _ => true,
+    };
+    if proxy.allow_lan_access && auth_required && proxy.api_key.trim().is_empty() {
+        errors.push(ConfigFieldError::new("proxy.api_key", "is required when LAN access is enabled"));
+    }
+    let mut seen_keys = std::collections::HashSet::new();
+    for (i, key) in proxy.api_keys.iter().enumerate() {
+        let field = format!("proxy.api_keys[{}].key", i);
+        if key.key.trim().is_empty() {
+            errors.push(ConfigFieldError::new(field, "must not be empty"));
+        } else if key.key == proxy.api_key || !seen_keys.insert(key.key.as_str()) {
+            errors.push(ConfigFieldError::new(field, "duplicates another API key"));
+        }
+    }
+
+    if proxy.upstream_proxy.enabled
+        && !is_valid_url(&proxy.upstream_proxy.url, &["http", "https", "socks5", "socks5h"])
+    {
+        errors.push(ConfigFieldError::new(
+            "proxy.upstream_proxy.url",
+            "must be a valid http(s):// or socks5:// URL",
+        ));
+    }
+
+    if proxy.zai.enabled {
+        if !is_valid_url(&proxy.zai.base_url, &["http", "https"]) {
+            errors.push(ConfigFieldError::new("proxy.zai.base_url", "must be a valid http(s):// URL"));
+        }
+        if proxy.zai.api_key.trim().is_empty() {
+            errors.push(ConfigFieldError::new("proxy.zai.api_key", "is required when z.ai is enabled"));
+        }
+    }
+
+    if proxy.thinking_budget.mode == ThinkingBudgetMode::Custom && proxy.thinking_budget.custom_value == 0 {
+        errors.push(ConfigFieldError::new("proxy.thinking_budget.custom_value", "must be greater than 0 in custom mode"));
+    }
+
+    // HTTPS: 证书与私钥需成对配置
+    if proxy.tls.enabled && proxy.tls.cert_path.is_some() != proxy.tls.key_path.is_some() {
+        errors.push(ConfigFieldError::new("proxy.tls", "cert_path and key_path must be set together"));
+    }
+
+    // 安全监控
+    let monitor = &proxy.security_monitor;
+    for (i, pattern) in monitor.trusted_proxies.proxies.iter().enumerate() {
+        if crate::proxy::client_ip::parse_ip_pattern(pattern).is_none() {
+            errors.push(ConfigFieldError::new(
+                format!("proxy.security_monitor.trusted_proxies.proxies[{}]", i),
+                "must be an IP address or CIDR",
+            ));
+        }
+    }
+    for (i, pattern) in monitor.auto_ban.exempt_ips.iter().enumerate() {
+        if crate::proxy::client_ip::parse_ip_pattern(pattern).is_none() {
+            errors.push(ConfigFieldError::new(
+                format!("proxy.security_monitor.auto_ban.exempt_ips[{}]", i),
+                "must be an IP address or CIDR",
+            ));
+        }
+    }
+    let export = &monitor.event_export;
+    if export.syslog_enabled {
+        if export.syslog_host.trim().is_empty() {
+            errors.push(ConfigFieldError::new("proxy.security_monitor.event_export.syslog_host", "must not be empty"));
+        }
+        if export.syslog_port == 0 {
+            errors.push(ConfigFieldError::new("proxy.security_monitor.event_export.syslog_port", "must be between 1 and 65535"));
+        }
+    }
+
+    // 正则规则需可编译
+    for (i, rule) in proxy.log_redaction.custom_rules.iter().enumerate() {
+        if let Err(e) = regex::Regex::new(&rule.pattern) {
+            errors.push(ConfigFieldError::new(
+                format!("proxy.log_redaction.custom_rules[{}].pattern", i),
+                format!("invalid regex: {}", e),
+            ));
+        }
+    }
+    for (i, rule) in proxy.guardrails.rules.iter().enumerate() {
+        match &rule.pattern {
+            Some(pattern) => {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    errors.push(ConfigFieldError::new(
+                        format!("proxy.guardrails.rules[{}].pattern", i),
+                        format!("invalid regex: {}", e),
+                    ));
+                }
+            }
+            None if rule.keywords.is_empty() => {
+                errors.push(ConfigFieldError::new(
+                    format!("proxy.guardrails.rules[{}]", i),
+                    "requires a pattern or at least one keyword",
+                ));
+            }
+            None => {}
+        }
+    }
+
+    errors
+}
+
+fn diff_values(path: &str, before: &Value, after: &Value, out: &mut Vec<ConfigDiffEntry>) {
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                diff_values(
+                    &child,
+                    a.get(key).unwrap_or(&Value::Null),
+                    b.get(key).unwrap_or(&Value::Null),
+                    out,
+                );
+            }
+        }
+        _ if before != after => out.push(ConfigDiffEntry {
+            path: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// 对比两份配置，返回变化的叶子字段
+pub fn diff_app_configs(before: &AppConfig, after: &AppConfig) -> Vec<ConfigDiffEntry> {
+    let before = serde_json::to_value(before).unwrap_or_default();
+    let after = serde_json::to_value(after).unwrap_or_default();
+    let mut out = Vec::new();
+    diff_values("", &before, &after, &mut out);
+    out
+}
+
+/// 差异所属分区: proxy 下取两级 (proxy.zai)，其余取顶层字段
+pub fn changed_sections(diff: &[ConfigDiffEntry]) -> Vec<String> {
+    let sections: BTreeSet<String> = diff
+        .iter()
+        .map(|entry| {
+            let depth = if entry.path.starts_with("proxy.") { 2 } else { 1 };
+            entry.path.split('.').take(depth).collect::<Vec<_>>().join(".")
+        })
+        .collect();
+    sections.into_iter().collect()
+}
+
+/// 校验并保存配置，记录版本历史，返回变更分区供调用方按需热更新
+pub fn commit_app_config(
+    config: &AppConfig,
+    source: ConfigSource,
+    actor: Option<&str>,
+) -> Result<ConfigCommit, ConfigSaveError> {
+    let mut errors = validate_app_config(config);
+    // [FIX] 被环境变量 / --config 覆盖的字段写盘时会被还原，拒绝修改而不是静默丢弃
+    errors.extend(super::config_overlay::overridden_edits(config).into_iter().map(|(field, source)| {
+        ConfigFieldError::new(field, format!("is overridden by {}; change it there instead", source))
+    }));
+    if !errors.is_empty() {
+        return Err(ConfigSaveError::Invalid(errors));
+    }
+
+    let _guard = COMMIT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = load_app_config().ok();
+    let changed = previous
+        .as_ref()
+        .map(|prev| changed_sections(&diff_app_configs(prev, config)))
+        .unwrap_or_else(|| vec![ALL_SECTIONS.to_string()]);
+
+    save_app_config(config).map_err(ConfigSaveError::Failed)?;
+
+    let version_id = if changed.is_empty() {
+        None
+    } else {
+        // 历史写入失败不影响保存本身
+        let recorded = (|| {
+            if let Some(prev) = previous.as_ref() {
+                if config_history::is_empty()? {
+                    config_history::record_version(prev, ConfigSource::Baseline, None, &[])?;
+                }
+            }
+            config_history::record_version(config, source, actor, &changed)
+        })();
+        match recorded {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("[Config-History] Failed to record config version: {}", e);
+                None
+            }
+        }
+    };
+
+    let mut commit = ConfigCommit { version_id, changed_sections: changed, restart_required: Vec::new() };
+    commit.restart_required = RESTART_REQUIRED_SECTIONS
+        .iter()
+        .filter(|s| commit.touches(s))
+        .map(|s| s.to_string())
+        .collect();
+    Ok(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_field_errors() {
+        assert!(validate_app_config(&AppConfig::new()).is_empty());
+
+        let mut config = AppConfig::new();
+        config.proxy.port = 0;
+        config.proxy.allow_lan_access = true;
+        config.proxy.api_key = " ".to_string();
+        config.proxy.upstream_proxy.enabled = true;
+        config.proxy.upstream_proxy.url = "127.0.0.1:7890".to_string();
+        config.proxy.log_redaction.custom_rules.push(crate::proxy::config::RedactionRule {
+            name: "bad".to_string(),
+            pattern: "(unclosed".to_string(),
+        });
+
+        let fields: Vec<String> = validate_app_config(&config).into_iter().map(|e| e.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "proxy.port",
+                "proxy.api_key",
+                "proxy.upstream_proxy.url",
+                "proxy.log_redaction.custom_rules[0].pattern",
+            ]
+        );
+
+        // 显式关闭鉴权时允许空 Key
+        config = AppConfig::new();
+        config.proxy.allow_lan_access = true;
+        config.proxy.auth_mode = ProxyAuthMode::Off;
+        config.proxy.api_key.clear();
+        assert!(validate_app_config(&config).is_empty());
+    }
+
+    #[test]
+    fn test_diff_and_changed_sections() {
+        let before = AppConfig::new();
+        let mut after = before.clone();
+        after.proxy.zai.enabled = true;
+        after.proxy.zai.api_key = "zai-key".to_string();
+        after.proxy.port = 9000;
+        after.circuit_breaker.enabled = !before.circuit_breaker.enabled;
+
+        let diff = diff_app_configs(&before, &after);
+        let paths: Vec<&str> = diff.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["circuit_breaker.enabled", "proxy.port", "proxy.zai.api_key", "proxy.zai.enabled"]
+        );
+        assert_eq!(diff[1].after, serde_json::json!(9000));
+        assert_eq!(
+            changed_sections(&diff),
+            vec!["circuit_breaker", "proxy.port", "proxy.zai"]
+        );
+        assert!(diff_app_configs(&before, &before.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_commit_touches_all_sections_on_wildcard() {
+        let commit = ConfigCommit {
+            version_id: None,
+            changed_sections: vec!["proxy.zai".to_string()],
+            restart_required: Vec::new(),
+        };
+        assert!(commit.touches("proxy.zai"));
+        assert!(!commit.touches("proxy.port"));
+
+        // 旧配置不可读时无法比较，所有分区都需要重新应用
+        let commit = ConfigCommit {
+            changed_sections: vec![ALL_SECTIONS.to_string()],
+            ..commit
+        };
+        assert!(commit.touches("proxy.zai"));
+        assert!(commit.touches("proxy.port"));
+    }
+}
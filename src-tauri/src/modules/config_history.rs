@@ -0,0 +1,317 @@
+//! Config History Module
+//! 配置版本历史 (每次保存记录完整快照，保留最近 N 个版本，用于对比与回滚)
+//! 快照中的密钥字段以掩码存储，回滚时按掩码还原为当前配置中的明文
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::models::AppConfig;
+
+/// 保留的最大版本数
+pub const MAX_CONFIG_VERSIONS: usize = 50;
+
+/// 视为密钥的字段名 (完整匹配或后缀匹配)
+const SECRET_FIELDS: &[&str] = &["key", "password", "secret", "token", "refresh_tokens"];
+const SECRET_SUFFIXES: &[&str] = &["_key", "_password", "_secret", "_token"];
+
+/// 配置变更来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigSource {
+    /// 首次记录历史前的原始配置
+    Baseline,
+    /// 桌面端设置页 (Tauri save_config)
+    Ui,
+    /// 管理 HTTP API (Web 控制台)
+    AdminApi,
+    /// Headless 模式环境变量覆盖
+    Env,
+    /// 回滚到历史版本
+    Rollback,
+}
+
+impl ConfigSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Ui => "ui",
+            Self::AdminApi => "admin_api",
+            Self::Env => "env",
+            Self::Rollback => "rollback",
+        }
+    }
+}
+
+/// 版本元数据 (不含快照内容)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigVersion {
+    pub id: i64,
+    /// unix 秒
+    pub timestamp: i64,
+    pub source: String,
+    pub actor: Option<String>,
+    /// 本次变更涉及的配置分区 (如 proxy.zai)
+    pub changed_sections: Vec<String>,
+}
+
+/// 获取配置历史数据库路径
+pub fn get_config_history_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("config_history.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_config_history_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化配置历史数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS config_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            actor TEXT,
+            changed_sections TEXT NOT NULL,
+            content TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 字段名是否为密钥 (api_key、密码、令牌等)
+pub fn is_secret_field(key: &str) -> bool {
+    SECRET_FIELDS.contains(&key) || SECRET_SUFFIXES.iter().any(|suffix| key.ends_with(suffix))
+}
+
+fn mask_value(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = crate::mask_sensitive(s),
+        Value::Array(items) => items.iter_mut().for_each(mask_value),
+        _ => {}
+    }
+}
+
+/// 递归掩码敏感字段 (api_key、密码、令牌等)
+pub fn mask_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if is_secret_field(key) {
+                    mask_value(child);
+                } else {
+                    mask_secrets(child);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_secrets),
+        _ => {}
+    }
+}
+
+/// 掩码配置中的密钥字段 (快照与版本对比使用)
+pub fn mask_config(config: &AppConfig) -> Result<AppConfig, String> {
+    let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    mask_secrets(&mut value);
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// 是否尚无任何历史版本
+pub fn is_empty() -> Result<bool, String> {
+    let conn = connect_db()?;
+    let count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM config_versions", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(count == 0)
+}
+
+/// 记录一个配置快照，并裁剪到最近 MAX_CONFIG_VERSIONS 个版本
+pub fn record_version(
+    config: &AppConfig,
+    source: ConfigSource,
+    actor: Option<&str>,
+    changed_sections: &[String],
+) -> Result<i64, String> {
+    let content = serde_json::to_string(&mask_config(config)?).map_err(|e| e.to_string())?;
+    let sections = serde_json::to_string(changed_sections).map_err(|e| e.to_string())?;
+
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO config_versions (timestamp, source, actor, changed_sections, content)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            chrono::Utc::now().timestamp(),
+            source.as_str(),
+            actor,
+            sections,
+            content
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+
+    conn.execute(
+        "DELETE FROM config_versions WHERE id NOT IN (
+            SELECT id FROM config_versions ORDER BY id DESC LIMIT ?1
+        )",
+        [MAX_CONFIG_VERSIONS as i64],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+/// 列出历史版本 (新到旧)
+pub fn list_versions() -> Result<Vec<ConfigVersion>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, source, actor, changed_sections
+             FROM config_versions ORDER BY id DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let sections: String = row.get(4)?;
+            Ok(ConfigVersion {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                source: row.get(2)?,
+                actor: row.get(3)?,
+                changed_sections: serde_json::from_str(&sections).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut versions = Vec::new();
+    for row in rows {
+        versions.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(versions)
+}
+
+/// 读取指定版本的配置快照 (密钥为掩码)
+pub fn get_version_config(id: i64) -> Result<Option<AppConfig>, String> {
+    let conn = connect_db()?;
+    let content: Option<String> = conn
+        .query_row(
+            "SELECT content FROM config_versions WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    content
+        .map(|c| serde_json::from_str(&c).map_err(|e| format!("failed_to_parse_config_version: {}", e)))
+        .transpose()
+}
+
+fn collect_secrets(value: &Value, secret: bool, pool: &mut HashMap<String, BTreeSet<String>>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                collect_secrets(child, secret || is_secret_field(key), pool);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_secrets(item, secret, pool)),
+        Value::String(plain) if secret => {
+            pool.entry(crate::mask_sensitive(plain)).or_default().insert(plain.clone());
+        }
+        _ => {}
+    }
+}
+
+fn restore_value(
+    value: &mut Value,
+    current: Option<&Value>,
+    secret: bool,
+    pool: &HashMap<String, BTreeSet<String>>,
+    path: &mut Vec<String>,
+) -> Result<(), String> {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                path.push(key.clone());
+                restore_value(child, current.and_then(|c| c.get(key)), secret || is_secret_field(key), pool, path)?;
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                restore_value(item, current.and_then(|c| c.get(i)), secret, pool, path)?;
+                path.pop();
+            }
+        }
+        Value::String(masked) if secret => {
+            let same_path = current.and_then(Value::as_str);
+            let restored = match same_path {
+                Some(plain) if crate::mask_sensitive(plain) == *masked => plain.to_string(),
+                _ => match pool.get(masked.as_str()) {
+                    Some(candidates) if candidates.len() == 1 => candidates.iter().next().cloned().unwrap_or_default(),
+                    // 旧密钥已被替换: 同一位置保留当前密钥
+                    _ => same_path
+                        .map(str::to_string)
+                        .ok_or_else(|| format!("secret '{}' of this version cannot be restored", path.join(".")))?,
+                },
+            };
+            *masked = restored;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 将快照中的掩码密钥还原为当前配置中的明文 (回滚前调用)
+///
+/// 同一位置掩码一致时取当前值，否则在当前全部密钥中按掩码唯一匹配；
+/// 密钥已被替换时保留该位置的当前密钥，位置已不存在则拒绝回滚。
+pub fn restore_secrets(snapshot: &AppConfig, current: &AppConfig) -> Result<AppConfig, String> {
+    let mut value = serde_json::to_value(snapshot).map_err(|e| e.to_string())?;
+    let current = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    let mut pool = HashMap::new();
+    collect_secrets(&current, false, &mut pool);
+    restore_value(&mut value, Some(&current), false, &pool, &mut Vec::new())?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_secrets_are_masked_and_restored() {
+        let mut current = AppConfig::new();
+        current.proxy.api_key = "sk-current-0123456789".to_string();
+        current.proxy.zai.api_key = "zai-0123456789".to_string();
+
+        let masked = mask_config(&current).unwrap();
+        assert_eq!(masked.proxy.zai.api_key, "zai-****6789");
+        assert_eq!(masked.proxy.port, current.proxy.port);
+
+        // 快照之后 API Key 被轮换: 保留当前密钥，其余密钥按掩码还原
+        let mut rotated = current.clone();
+        rotated.proxy.api_key = "sk-rotated-abcdefghij".to_string();
+        let restored = restore_secrets(&masked, &rotated).unwrap();
+        assert_eq!(restored.proxy.api_key, "sk-rotated-abcdefghij");
+        assert_eq!(restored.proxy.zai.api_key, "zai-0123456789");
+    }
+}
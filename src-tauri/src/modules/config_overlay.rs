@@ -0,0 +1,692 @@
+//! Config Overlay Module
+//! 声明式配置层 (Headless / Docker / Kubernetes)
+//!
+//! 合并顺序 (后者覆盖前者):
+//! 1. gui_config.json (GUI / 管理 API 写入的配置)
+//! 2. `--config <path>` 指定的 TOML / YAML / JSON 文件
+//! 3. 旧版环境变量 ABV_API_KEY / ABV_WEB_PASSWORD / ABV_AUTH_MODE (及无前缀形式)
+//! 4. `ABV_<SECTION>__<FIELD>` 结构化环境变量，`__` 分隔层级，数组可用下标:
+//!    - ABV_PROXY__PORT=9000
+//!    - ABV_PROXY__ZAI__API_KEY=...
+//!    - ABV_PROXY__API_KEYS__0__KEY=...
+//!    - ABV_APP__LANGUAGE=en (APP 表示 AppConfig 顶层字段)
+//!    - ABV_ACCOUNTS__REFRESH_TOKENS=1//a,1//b (账号种子，启动时导入)
+//!
+//! 覆盖层只存在于内存: Headless 启动时安装，load_app_config 读取时叠加，
+//! save_app_config 写盘前还原为磁盘上的值，取消环境变量后重启即恢复。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::models::AppConfig;
+use crate::modules::config_history::mask_secrets;
+
+pub const ENV_PREFIX: &str = "ABV_";
+const PATH_SEPARATOR: &str = "__";
+/// 代表 AppConfig 顶层字段的段名
+const ROOT_SECTION: &str = "app";
+/// 账号种子段 (不属于 AppConfig，不写入 gui_config.json)
+const ACCOUNTS_SECTION: &str = "accounts";
+
+/// 旧版环境变量 -> 配置路径
+const LEGACY_ENV_VARS: &[(&[&str], &str)] = &[
+    (&["ABV_API_KEY", "API_KEY"], "proxy.api_key"),
+    (&["ABV_WEB_PASSWORD", "WEB_PASSWORD"], "proxy.admin_password"),
+    (&["ABV_AUTH_MODE", "AUTH_MODE"], "proxy.auth_mode"),
+];
+const AUTH_MODES: &[&str] = &["off", "strict", "all_except_health", "auto"];
+
+/// 账号种子 (仅可由配置文件 / 环境变量提供)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountSeeds {
+    #[serde(default)]
+    pub refresh_tokens: Vec<String>,
+}
+
+/// 合并后的有效配置
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    pub config: AppConfig,
+    pub accounts: AccountSeeds,
+    /// 生效的覆盖来源 (file:<path> / env:<NAME>)，为空表示与 gui_config.json 一致
+    pub overrides: Vec<String>,
+    /// 被覆盖的 AppConfig 字段路径及其覆盖来源 (不含账号种子)
+    pub overridden_paths: Vec<(Vec<String>, String)>,
+}
+
+/// 运行时覆盖层 (被覆盖的字段路径、覆盖来源及其生效值)
+struct RuntimeOverlay {
+    values: Value,
+    paths: Vec<(Vec<String>, String)>,
+}
+
+static RUNTIME_OVERLAY: RwLock<Option<RuntimeOverlay>> = RwLock::new(None);
+
+impl EffectiveConfig {
+    /// 掩码后的合并结果 (用于 --print-effective-config)
+    pub fn to_masked_json(&self) -> Value {
+        let mut value = serde_json::to_value(&self.config).unwrap_or_default();
+        if let Value::Object(root) = &mut value {
+            root.insert(
+                ACCOUNTS_SECTION.to_string(),
+                serde_json::to_value(&self.accounts).unwrap_or_default(),
+            );
+        }
+        mask_secrets(&mut value);
+        value
+    }
+}
+
+/// 从命令行参数中解析 `--config <path>` / `--config=<path>`
+pub fn config_path_from_args(args: &[String]) -> Option<String> {
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "--config" {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--config=").map(str::to_string)
+        }
+    })
+}
+
+/// 按扩展名解析配置文件
+pub fn parse_config_file(path: &Path) -> Result<Value, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed_to_read_config_file {}: {}", path.display(), e))?;
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let value: Value = match ext.as_str() {
+        "toml" => toml::from_str(&content).map_err(|e| format!("invalid TOML in {}: {}", path.display(), e))?,
+        "yaml" | "yml" => {
+            serde_yaml::from_str(&content).map_err(|e| format!("invalid YAML in {}: {}", path.display(), e))?
+        }
+        "json" => serde_json::from_str(&content).map_err(|e| format!("invalid JSON in {}: {}", path.display(), e))?,
+        other => return Err(format!("unsupported config file extension '{}' (expected toml/yaml/yml/json)", other)),
+    };
+    if !value.is_object() {
+        return Err(format!("config file {} must contain a table/mapping at the top level", path.display()));
+    }
+    Ok(value)
+}
+
+/// 深度合并: 对象逐键合并，其它类型整体替换
+pub fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 按目标字段的现有类型解析环境变量值
+fn parse_env_value(raw: &str, existing: Option<&Value>) -> Value {
+    match existing {
+        Some(Value::String(_)) => Value::String(raw.to_string()),
+        Some(Value::Array(items)) if !raw.trim_start().starts_with('[') => {
+            // 逗号分隔列表，元素类型参照现有元素
+            let sample = items.first();
+            Value::Array(
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(|s| match sample {
+                        Some(Value::String(_)) | None => Value::String(s.to_string()),
+                        _ => serde_json::from_str(s).unwrap_or_else(|_| Value::String(s.to_string())),
+                    })
+                    .collect(),
+            )
+        }
+        // 数值 / 布尔 / 对象 / 未设置的可选字段: 优先按 JSON 解析 (字符串可写成 "\"123\"")
+        _ => serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())),
+    }
+}
+
+/// 默认配置中为空的对象视为自由键的映射 (如 custom_mapping)，其余对象按结构体字段校验
+fn is_free_form_map(schema: Option<&Value>) -> bool {
+    matches!(schema, Some(Value::Object(map)) if map.is_empty())
+}
+
+/// 在现有配置中查找字段 (忽略大小写与下划线，环境变量名无法表达 camelCase)
+fn resolve_key(map: &Map<String, Value>, segment: &str) -> Option<String> {
+    if map.contains_key(segment) {
+        return Some(segment.to_string());
+    }
+    let normalize = |s: &str| s.replace('_', "").to_lowercase();
+    let wanted = normalize(segment);
+    map.keys().find(|k| normalize(k) == wanted).cloned()
+}
+
+/// 将值写入点分/下标路径，返回实际写入的字段路径
+///
+/// 路径必须存在于当前配置中 (未设置的可选字段序列化为 null，可整体赋值)；
+/// 仅自由键映射允许新增键，拼写错误的字段直接报错而不是被静默忽略。
+fn set_path(root: &mut Value, schema: &Value, segments: &[String], raw: &str) -> Result<Vec<String>, String> {
+    let mut resolved: Vec<String> = Vec::with_capacity(segments.len());
+    let mut current = root;
+    let mut schema = Some(schema);
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i + 1 == segments.len();
+        current = match current {
+            Value::Object(map) => {
+                let key = match resolve_key(map, segment) {
+                    Some(key) if !is_free_form_map(schema) => key,
+                    _ if is_free_form_map(schema) && last => segment.clone(),
+                    _ => {
+                        resolved.push(segment.clone());
+                        return Err(format!("unknown config key '{}'", resolved.join(".")));
+                    }
+                };
+                schema = schema.and_then(|s| s.get(&key));
+                resolved.push(key.clone());
+                let slot = map.entry(key).or_insert(Value::Null);
+                if last {
+                    let value = parse_env_value(raw, Some(slot));
+                    *slot = value;
+                    return Ok(resolved);
+                }
+                slot
+            }
+            Value::Array(items) => {
+                let index: usize = segment
+                    .parse()
+                    .map_err(|_| format!("'{}' is not an array index", segment))?;
+                let len = items.len();
+                let item = items
+                    .get_mut(index)
+                    .ok_or_else(|| format!("index {} out of range (len {})", index, len))?;
+                // 数组元素的结构以现有元素为准
+                schema = None;
+                resolved.push(segment.clone());
+                if last {
+                    let value = parse_env_value(raw, Some(item));
+                    *item = value;
+                    return Ok(resolved);
+                }
+                item
+            }
+            Value::Null => {
+                return Err(format!(
+                    "'{}' is not set; assign the whole value as JSON instead",
+                    resolved.join(".")
+                ))
+            }
+            _ => return Err(format!("'{}' is not a table", resolved.join("."))),
+        };
+    }
+    Ok(resolved)
+}
+
+/// 校验配置文件中的键均为已知字段 (与环境变量同样的规则)
+fn check_known_keys(
+    current: &Value,
+    schema: Option<&Value>,
+    overlay: &Value,
+    path: &mut Vec<String>,
+) -> Result<(), String> {
+    let (Value::Object(current), Value::Object(overlay)) = (current, overlay) else {
+        return Ok(());
+    };
+    if is_free_form_map(schema) {
+        return Ok(());
+    }
+    for (key, value) in overlay {
+        path.push(key.clone());
+        match current.get(key) {
+            Some(existing) => check_known_keys(existing, schema.and_then(|s| s.get(key)), value, path)?,
+            None => return Err(format!("unknown config key '{}'", path.join("."))),
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+/// 应用旧版与结构化环境变量，返回生效的变量名及其写入的字段路径
+pub fn apply_env_overrides(
+    root: &mut Value,
+    schema: &Value,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<Vec<(String, Vec<String>)>, String> {
+    let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+    // 保证覆盖顺序稳定 (父路径先于子路径)
+    vars.sort();
+    let mut applied = Vec::new();
+
+    for (names, path) in LEGACY_ENV_VARS {
+        let Some((name, value)) = names
+            .iter()
+            .find_map(|n| vars.iter().find(|(k, v)| k == n && !v.trim().is_empty()))
+        else {
+            continue;
+        };
+        let value = if *path == "proxy.auth_mode" {
+            let mode = value.trim().to_lowercase();
+            if !AUTH_MODES.contains(&mode.as_str()) {
+                tracing::warn!("Invalid {}: {}, ignoring", name, value);
+                continue;
+            }
+            mode
+        } else {
+            value.clone()
+        };
+        let segments: Vec<String> = path.split('.').map(str::to_string).collect();
+        let path = set_path(root, schema, &segments, &value).map_err(|e| format!("{}: {}", name, e))?;
+        applied.push((name.to_string(), path));
+    }
+
+    for (name, value) in &vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if !rest.contains(PATH_SEPARATOR) {
+            continue;
+        }
+        let mut segments: Vec<String> = rest.split(PATH_SEPARATOR).map(|s| s.to_lowercase()).collect();
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(format!("{}: empty path segment", name));
+        }
+        if segments[0] == ROOT_SECTION {
+            segments.remove(0);
+        }
+        let path = set_path(root, schema, &segments, value).map_err(|e| format!("{}: {}", name, e))?;
+        applied.push((name.clone(), path));
+    }
+
+    Ok(applied)
+}
+
+/// 合并 gui_config.json、配置文件与环境变量
+pub fn build_effective_config(
+    base: &AppConfig,
+    config_file: Option<&Path>,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<EffectiveConfig, String> {
+    // 预置账号种子段，使环境变量可按数组解析
+    let with_accounts = |config: &AppConfig| -> Result<Value, String> {
+        let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+        if let Value::Object(map) = &mut value {
+            map.insert(
+                ACCOUNTS_SECTION.to_string(),
+                serde_json::to_value(AccountSeeds::default()).unwrap_or_default(),
+            );
+        }
+        Ok(value)
+    };
+    let mut root = with_accounts(base)?;
+    // 默认配置用于区分结构体字段与自由键映射
+    let schema = with_accounts(&AppConfig::new())?;
+    let mut overrides = Vec::new();
+    let mut overridden_paths = Vec::new();
+
+    if let Some(path) = config_file {
+        let file = parse_config_file(path)?;
+        check_known_keys(&root, Some(&schema), &file, &mut Vec::new())
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let source = format!("file:{}", path.display());
+        let mut leaves = Vec::new();
+        collect_leaf_paths(&file, &mut Vec::new(), &mut leaves);
+        overridden_paths.extend(leaves.into_iter().map(|leaf| (leaf, source.clone())));
+        merge_values(&mut root, file);
+        overrides.push(source);
+    }
+    for (name, path) in apply_env_overrides(&mut root, &schema, vars)? {
+        let source = format!("env:{}", name);
+        overrides.push(source.clone());
+        overridden_paths.push((path, source));
+    }
+    overridden_paths.retain(|(path, _)| path.first().map(String::as_str) != Some(ACCOUNTS_SECTION));
+
+    let accounts = match root.as_object_mut().and_then(|m| m.remove(ACCOUNTS_SECTION)) {
+        Some(value) => serde_json::from_value::<AccountSeeds>(value).map_err(|e| format!("accounts: {}", e))?,
+        None => AccountSeeds::default(),
+    };
+    let config = serde_json::from_value::<AppConfig>(root).map_err(|e| format!("invalid effective config: {}", e))?;
+
+    Ok(EffectiveConfig { config, accounts, overrides, overridden_paths })
+}
+
+/// 收集对象中的叶子路径 (数组整体视为叶子)
+fn collect_leaf_paths(value: &Value, prefix: &mut Vec<String>, out: &mut Vec<Vec<String>>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                prefix.push(key.clone());
+                collect_leaf_paths(child, prefix, out);
+                prefix.pop();
+            }
+        }
+        _ if !prefix.is_empty() => out.push(prefix.clone()),
+        _ => {}
+    }
+}
+
+fn get_at<'a>(root: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(root, |current, segment| match current {
+        Value::Object(map) => map.get(segment),
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// 写入 (value 为 None 时删除) 指定路径的值，父路径不存在时返回 false
+fn set_at(root: &mut Value, path: &[String], value: Option<Value>) -> bool {
+    let Some((last, parents)) = path.split_last() else {
+        return false;
+    };
+    let parent = parents.iter().try_fold(root, |current, segment| match current {
+        Value::Object(map) => map.get_mut(segment),
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+        _ => None,
+    });
+    match (parent, value) {
+        (Some(Value::Object(map)), Some(value)) => {
+            map.insert(last.clone(), value);
+            true
+        }
+        (Some(Value::Object(map)), None) => map.remove(last).is_some(),
+        (Some(Value::Array(items)), Some(value)) => match last.parse::<usize>().ok().and_then(|i| items.get_mut(i)) {
+            Some(item) => {
+                *item = value;
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+impl RuntimeOverlay {
+    fn from_effective(effective: &EffectiveConfig) -> Result<Option<Self>, String> {
+        if effective.overridden_paths.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            values: serde_json::to_value(&effective.config).map_err(|e| e.to_string())?,
+            paths: effective.overridden_paths.clone(),
+        }))
+    }
+
+    fn apply(&self, value: &mut Value) {
+        for (path, _) in &self.paths {
+            if let Some(v) = get_at(&self.values, path) {
+                set_at(value, path, Some(v.clone()));
+            }
+        }
+    }
+
+    /// 被覆盖的字段还原为磁盘值，磁盘缺失时取默认值，仍缺失则删除
+    fn strip(&self, value: &mut Value, persisted: Option<&Value>, defaults: &Value) {
+        for (path, _) in &self.paths {
+            let original = persisted
+                .and_then(|p| get_at(p, path))
+                .or_else(|| get_at(defaults, path))
+                .cloned();
+            set_at(value, path, original);
+        }
+    }
+
+    /// 与生效值不同的被覆盖字段 (点分路径, 覆盖来源)，同一字段取最后生效的来源
+    fn conflicting_edits(&self, value: &Value) -> Vec<(String, String)> {
+        let mut seen = std::collections::HashSet::new();
+        let mut edits = Vec::new();
+        for (path, source) in self.paths.iter().rev() {
+            let field = path.join(".");
+            if seen.insert(field.clone()) && get_at(&self.values, path) != get_at(value, path) {
+                edits.push((field, source.clone()));
+            }
+        }
+        edits.reverse();
+        edits
+    }
+}
+
+/// 安装运行时覆盖层 (仅内存，不写入 gui_config.json 与版本历史)
+pub fn install_runtime_overlay(effective: &EffectiveConfig) -> Result<(), String> {
+    let overlay = RuntimeOverlay::from_effective(effective)?;
+    *RUNTIME_OVERLAY.write().unwrap_or_else(|e| e.into_inner()) = overlay;
+    Ok(())
+}
+
+/// 在磁盘配置上叠加运行时覆盖层
+pub fn apply_runtime_overlay(config: AppConfig) -> AppConfig {
+    let guard = RUNTIME_OVERLAY.read().unwrap_or_else(|e| e.into_inner());
+    let Some(overlay) = guard.as_ref() else {
+        return config;
+    };
+    let Ok(mut value) = serde_json::to_value(&config) else {
+        return config;
+    };
+    overlay.apply(&mut value);
+    match serde_json::from_value(value) {
+        Ok(merged) => merged,
+        Err(e) => {
+            tracing::warn!("Failed to apply runtime config overlay: {}", e);
+            config
+        }
+    }
+}
+
+/// 写盘前剥离覆盖层，避免把环境变量 / 配置文件中的密钥写入 gui_config.json
+pub fn strip_runtime_overlay(config: &AppConfig, persisted: impl FnOnce() -> Option<Value>) -> Result<Value, String> {
+    let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    let guard = RUNTIME_OVERLAY.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(overlay) = guard.as_ref() {
+        let defaults = serde_json::to_value(AppConfig::new()).map_err(|e| e.to_string())?;
+        overlay.strip(&mut value, persisted().as_ref(), &defaults);
+    }
+    Ok(value)
+}
+
+/// 保存的配置中修改了哪些被覆盖的字段 (点分路径, 覆盖来源)
+/// 覆盖值只存在于内存，写盘时会被还原，这类修改应直接拒绝而不是静默丢弃
+pub fn overridden_edits(config: &AppConfig) -> Vec<(String, String)> {
+    let guard = RUNTIME_OVERLAY.read().unwrap_or_else(|e| e.into_inner());
+    let Some(overlay) = guard.as_ref() else {
+        return Vec::new();
+    };
+    match serde_json::to_value(config) {
+        Ok(value) => overlay.conflicting_edits(&value),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 导入账号种子 (已存在相同 refresh_token 的账号跳过，避免每次启动都请求 OAuth)
+pub async fn seed_accounts(seeds: &AccountSeeds, service: &crate::modules::account_service::AccountService) {
+    if seeds.refresh_tokens.is_empty() {
+        return;
+    }
+    let existing: std::collections::HashSet<String> = crate::modules::account::list_accounts()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|a| a.token.refresh_token)
+        .collect();
+
+    for token in &seeds.refresh_tokens {
+        let token = token.trim();
+        if token.is_empty() || existing.contains(token) {
+            continue;
+        }
+        match service.add_account(token).await {
+            Ok(account) => tracing::info!("Seeded account from config: {}", account.email),
+            Err(e) => tracing::error!("Failed to seed account {}: {}", crate::mask_sensitive(token), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_env_overrides_are_typed_by_target_field() {
+        let mut base = AppConfig::new();
+        base.proxy
+            .api_keys
+            .push(serde_json::from_value(serde_json::json!({"key": "sk-old", "name": "ci"})).unwrap());
+
+        let effective = build_effective_config(
+            &base,
+            None,
+            vars(&[
+                ("ABV_PROXY__PORT", "9000"),
+                ("ABV_PROXY__ZAI__ENABLED", "true"),
+                ("ABV_PROXY__ZAI__API_KEY", "12345"),
+                ("ABV_PROXY__API_KEYS__0__KEY", "sk-new"),
+                ("ABV_PROXY__SECURITY_MONITOR__TRUSTED_PROXIES__PROXIES", "10.0.0.0/8, 172.16.0.0/12"),
+                ("ABV_APP__LANGUAGE", "en"),
+                ("ABV_ACCOUNTS__REFRESH_TOKENS", "1//a,1//b"),
+                ("ABV_AUTH_MODE", "STRICT"),
+                ("ABV_API_KEY", "sk-legacy"),
+                ("ABV_PROXY__API_KEY", "sk-structured"),
+                ("UNRELATED", "x"),
+            ]),
+        )
+        .unwrap();
+
+        let config = &effective.config;
+        assert_eq!(config.proxy.port, 9000);
+        assert!(config.proxy.zai.enabled);
+        assert_eq!(config.proxy.zai.api_key, "12345");
+        assert_eq!(config.proxy.api_keys[0].key, "sk-new");
+        assert_eq!(config.proxy.security_monitor.trusted_proxies.proxies, vec!["10.0.0.0/8", "172.16.0.0/12"]);
+        assert_eq!(config.language, "en");
+        assert!(matches!(config.proxy.auth_mode, crate::proxy::ProxyAuthMode::Strict));
+        // 结构化变量优先于旧版变量
+        assert_eq!(config.proxy.api_key, "sk-structured");
+        assert_eq!(effective.accounts.refresh_tokens, vec!["1//a", "1//b"]);
+        assert_eq!(effective.overrides.len(), 10);
+
+        let err = build_effective_config(&base, None, vars(&[("ABV_PROXY__API_KEYS__5__KEY", "x")])).unwrap_err();
+        assert!(err.starts_with("ABV_PROXY__API_KEYS__5__KEY: index 5 out of range"), "{}", err);
+    }
+
+    #[test]
+    fn test_runtime_overlay_is_applied_and_stripped() {
+        let mut disk = AppConfig::new();
+        disk.proxy.api_key = "sk-disk".to_string();
+        disk.proxy.port = 8045;
+        let effective = build_effective_config(
+            &disk,
+            None,
+            vars(&[("ABV_PROXY__API_KEY", "sk-env"), ("ABV_PROXY__CUSTOM_MAPPING__M", "gemini-2.5-flash")]),
+        )
+        .unwrap();
+        let overlay = RuntimeOverlay::from_effective(&effective).unwrap().unwrap();
+
+        // 界面修改端口后保存: 覆盖字段保持生效值
+        let mut loaded = serde_json::to_value(&disk).unwrap();
+        overlay.apply(&mut loaded);
+        assert_eq!(loaded["proxy"]["api_key"], "sk-env");
+        loaded["proxy"]["port"] = Value::from(9000);
+
+        // 写盘前还原为磁盘值，新增的映射键被删除
+        let disk_value = serde_json::to_value(&disk).unwrap();
+        let defaults = serde_json::to_value(AppConfig::new()).unwrap();
+        overlay.strip(&mut loaded, Some(&disk_value), &defaults);
+        assert_eq!(loaded["proxy"]["api_key"], "sk-disk");
+        assert_eq!(loaded["proxy"]["port"], 9000);
+        assert!(loaded["proxy"]["custom_mapping"].get("m").is_none());
+    }
+
+    #[test]
+    fn test_edits_to_overridden_fields_are_reported_with_source() {
+        let dir = std::env::temp_dir().join(format!("abv-overlay-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config.toml");
+        std::fs::write(&file, "[proxy]\nport = 9100\napi_key = \"sk-file\"\n").unwrap();
+
+        let effective = build_effective_config(
+            &AppConfig::new(),
+            Some(&file),
+            vars(&[("ABV_PROXY__PORT", "9200")]),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        let overlay = RuntimeOverlay::from_effective(&effective).unwrap().unwrap();
+
+        // 未修改被覆盖字段: 允许保存
+        let mut value = serde_json::to_value(&effective.config).unwrap();
+        value["proxy"]["request_timeout"] = Value::from(600);
+        assert!(overlay.conflicting_edits(&value).is_empty());
+
+        // 修改被覆盖字段: 报告最后生效的来源
+        value["proxy"]["port"] = Value::from(9300);
+        value["proxy"]["api_key"] = Value::from("sk-ui");
+        let edits = overlay.conflicting_edits(&value);
+        assert_eq!(
+            edits,
+            vec![
+                ("proxy.api_key".to_string(), format!("file:{}", file.display())),
+                ("proxy.port".to_string(), "env:ABV_PROXY__PORT".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let base = AppConfig::new();
+        let err = build_effective_config(&base, None, vars(&[("ABV_PROXY__PORTT", "9000")])).unwrap_err();
+        assert_eq!(err, "ABV_PROXY__PORTT: unknown config key 'proxy.portt'");
+
+        // 未设置的可选字段可整体赋值，自由键映射可新增键
+        let effective = build_effective_config(
+            &base,
+            None,
+            vars(&[
+                ("ABV_PROXY__ADMIN_PASSWORD", "secret-password"),
+                ("ABV_PROXY__CUSTOM_MAPPING__MY_MODEL", "gemini-2.5-flash"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(effective.config.proxy.admin_password.as_deref(), Some("secret-password"));
+        assert_eq!(effective.config.proxy.custom_mapping["my_model"], "gemini-2.5-flash");
+
+        let schema = serde_json::to_value(&base).unwrap();
+        let file = serde_json::json!({"proxy": {"zai": {"enabeld": true}}});
+        let err = check_known_keys(&schema, Some(&schema), &file, &mut Vec::new()).unwrap_err();
+        assert_eq!(err, "unknown config key 'proxy.zai.enabeld'");
+        let file = serde_json::json!({"proxy": {"custom_mapping": {"gpt-4o": "gemini-2.5-pro"}}});
+        assert!(check_known_keys(&schema, Some(&schema), &file, &mut Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn test_merge_file_and_mask_secrets() {
+        let mut base = serde_json::to_value(AppConfig::new()).unwrap();
+        let file: Value = toml::from_str(
+            r#"
+            [proxy]
+            port = 8100
+            admin_password = "super-secret-password"
+
+            [proxy.zai]
+            api_key = "zai-0123456789"
+            "#,
+        )
+        .unwrap();
+        merge_values(&mut base, file);
+        assert_eq!(base["proxy"]["port"], 8100);
+        // 未出现在文件中的字段保持原值
+        assert_eq!(base["proxy"]["zai"]["enabled"], false);
+
+        mask_secrets(&mut base);
+        assert_eq!(base["proxy"]["admin_password"], "supe****word");
+        assert_eq!(base["proxy"]["zai"]["api_key"], "zai-****6789");
+        assert_eq!(base["proxy"]["port"], 8100);
+        assert_eq!(base["proxy"]["tls"]["key_path"], Value::Null);
+    }
+}
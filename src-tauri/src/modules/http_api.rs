@@ -13,7 +13,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
 
-use crate::modules::{account, logger, proxy_db};
+use crate::modules::{account, audit_db, logger, proxy_db};
 
 /// Default port for HTTP API server
 pub const DEFAULT_PORT: u16 = 19527;
@@ -335,7 +335,7 @@ async fn switch_account(
     let state_clone = state.clone();
 
     // Execute switch asynchronously (non-blocking response)
-    tokio::spawn(async move {
+    tokio::spawn(audit_db::scope(audit_db::AuditActor::AdminApi, async move {
         logger::log_info(&format!("[HTTP API] Starting account switch: {}", account_id));
         
         match account::switch_account(&account_id, &state_clone.integration).await {
@@ -350,7 +350,7 @@ async fn switch_account(
         // Mark switch ended
         let mut switching = state_clone.switching.write().await;
         *switching = false;
-    });
+    }));
 
     // Immediately return 202 Accepted
     Ok((
@@ -367,7 +367,7 @@ async fn refresh_all_quotas() -> Result<impl IntoResponse, (StatusCode, Json<Err
     logger::log_info("[HTTP API] Starting refresh of all account quotas");
 
     // Execute refresh asynchronously
-    tokio::spawn(async {
+    tokio::spawn(audit_db::scope(audit_db::AuditActor::AdminApi, async {
         match account::refresh_all_quotas_logic().await {
             Ok(stats) => {
                 logger::log_info(&format!(
@@ -379,7 +379,7 @@ async fn refresh_all_quotas() -> Result<impl IntoResponse, (StatusCode, Json<Err
                 logger::log_error(&format!("[HTTP API] Quota refresh failed: {}", e));
             }
         }
-    });
+    }));
 
     Ok((
         StatusCode::ACCEPTED,
@@ -442,6 +442,14 @@ async fn get_logs(
 // Server
 // ============================================================================
 
+/// Attribute account changes made through this API to the admin_api audit actor
+async fn audit_scope_middleware(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    audit_db::scope(audit_db::AuditActor::AdminApi, next.run(request)).await
+}
+
 /// Start HTTP API server
 pub async fn start_server(port: u16, integration: crate::modules::integration::SystemManager) -> Result<(), String> {
     let state = ApiState::new(integration);
@@ -460,6 +468,7 @@ pub async fn start_server(port: u16, integration: crate::modules::integration::S
         .route("/accounts/refresh", post(refresh_all_quotas))
         .route("/accounts/{id}/bind-device", post(bind_device))
         .route("/logs", get(get_logs))
+        .layer(axum::middleware::from_fn(audit_scope_middleware))
         .layer(cors)
         .with_state(state);
 
@@ -169,6 +169,14 @@ pub async fn import_from_v1() -> Result<Vec<Account>, String> {
                         match account::upsert_account(email.clone(), None, token_data) {
                             Ok(acc) => {
                                 crate::modules::logger::log_info(&format!("Import successful: {}", email));
+                                crate::modules::audit_db::record(
+                                    crate::modules::audit_db::AuditAction::Import,
+                                    Some(&acc.id),
+                                    Some(&acc.email),
+                                    None,
+                                    None,
+                                    Some("v1"),
+                                );
                                 imported_accounts.push(acc);
                             },
                             Err(e) => crate::modules::logger::log_error(&format!("Import save failed {}: {}", email, e)),
@@ -219,7 +227,16 @@ pub async fn import_from_custom_db_path(path_str: String) -> Result<Account, Str
     );
     
     // 4. Add or update account
-    account::upsert_account(email.clone(), user_info.name, token_data)
+    let account = account::upsert_account(email.clone(), user_info.name, token_data)?;
+    crate::modules::audit_db::record(
+        crate::modules::audit_db::AuditAction::Import,
+        Some(&account.id),
+        Some(&account.email),
+        None,
+        None,
+        Some("db"),
+    );
+    Ok(account)
 }
 
 /// Import current logged-in account from default IDE database
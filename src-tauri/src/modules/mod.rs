@@ -1,9 +1,13 @@
 pub mod account;
 pub mod account_service;
+pub mod admin_db;
+pub mod audit_db;
 pub mod cache;
 pub mod cloudflared;
 pub mod codex;
 pub mod config;
+pub mod config_history;
+pub mod config_overlay;
 pub mod db;
 pub mod device;
 pub mod http_api;
@@ -13,12 +17,14 @@ pub mod log_bridge;
 pub mod logger;
 pub mod migration;
 pub mod oauth;
+pub mod oauth_batch;
 pub mod oauth_server;
 pub mod process;
 pub mod proxy_db;
 pub mod quota;
 pub mod scheduler;
 pub mod security_db;
+pub mod summary_db;
 pub mod token_stats;
 pub mod tray;
 pub mod update_checker;
@@ -0,0 +1,322 @@
+//! Batch OAuth Onboarding
+//! 批量生成 OAuth 授权链接 (每个链接独立 state)，通过反代的 /auth/callback 各自完成登录。
+//! 适用于 Headless/Docker 部署：把链接分发出去，登录完成后账号直接进入账号池。
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// 单个会话最多生成的链接数
+pub const MAX_LINKS_PER_SESSION: usize = 50;
+/// 默认链接有效期 (秒)
+pub const DEFAULT_LINK_TTL_SECS: i64 = 30 * 60;
+/// 会话过期后保留多久以便查看结果 (秒)
+const SESSION_RETENTION_SECS: i64 = 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchLinkStatus {
+    /// 等待用户打开链接并授权
+    Pending,
+    /// 已收到回调，正在交换 Token / 写入账号
+    Completing,
+    Completed,
+    Failed,
+    Expired,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchLink {
+    pub state: String,
+    pub auth_url: String,
+    pub status: BatchLinkStatus,
+    pub email: Option<String>,
+    pub account_id: Option<String>,
+    pub error: Option<String>,
+    pub expires_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSession {
+    pub id: String,
+    pub redirect_uri: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub links: Vec<BatchLink>,
+}
+
+impl BatchSession {
+    /// 将已过期但仍处于 Pending 的链接标记为 Expired
+    fn expire_stale(&mut self, now: i64) {
+        for link in self.links.iter_mut() {
+            if link.status == BatchLinkStatus::Pending && now >= link.expires_at {
+                link.status = BatchLinkStatus::Expired;
+            }
+        }
+    }
+}
+
+static BATCH_SESSIONS: Lazy<Mutex<HashMap<String, BatchSession>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn new_session(count: usize, redirect_uri: &str, ttl_secs: i64, now: i64) -> BatchSession {
+    let expires_at = now + ttl_secs;
+    let links = (0..count)
+        .map(|_| {
+            let state = uuid::Uuid::new_v4().to_string();
+            BatchLink {
+                auth_url: crate::modules::oauth::get_auth_url(redirect_uri, &state),
+                state,
+                status: BatchLinkStatus::Pending,
+                email: None,
+                account_id: None,
+                error: None,
+                expires_at,
+                completed_at: None,
+            }
+        })
+        .collect();
+
+    BatchSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        redirect_uri: redirect_uri.to_string(),
+        created_at: now,
+        expires_at,
+        links,
+    }
+}
+
+/// 创建批量授权会话，生成 `count` 个独立的授权链接
+pub fn create_session(
+    count: usize,
+    redirect_uri: &str,
+    ttl_secs: Option<i64>,
+) -> Result<BatchSession, String> {
+    if count == 0 || count > MAX_LINKS_PER_SESSION {
+        return Err(format!(
+            "count must be between 1 and {}",
+            MAX_LINKS_PER_SESSION
+        ));
+    }
+    let ttl = ttl_secs.unwrap_or(DEFAULT_LINK_TTL_SECS);
+    if ttl <= 0 {
+        return Err("ttl must be positive".to_string());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let session = new_session(count, redirect_uri, ttl, now);
+
+    let mut sessions = BATCH_SESSIONS.lock().map_err(|e| e.to_string())?;
+    sessions.retain(|_, s| now < s.expires_at + SESSION_RETENTION_SECS);
+    sessions.insert(session.id.clone(), session.clone());
+
+    crate::modules::logger::log_info(&format!(
+        "[OAuth Batch] Created session {} with {} links (ttl {}s)",
+        session.id, count, ttl
+    ));
+    Ok(session)
+}
+
+/// 获取会话当前状态
+pub fn get_session(session_id: &str) -> Option<BatchSession> {
+    let now = chrono::Utc::now().timestamp();
+    let mut sessions = BATCH_SESSIONS.lock().ok()?;
+    let session = sessions.get_mut(session_id)?;
+    session.expire_stale(now);
+    Some(session.clone())
+}
+
+/// 列出所有会话 (按创建时间倒序)
+pub fn list_sessions() -> Vec<BatchSession> {
+    let now = chrono::Utc::now().timestamp();
+    let Ok(mut sessions) = BATCH_SESSIONS.lock() else {
+        return Vec::new();
+    };
+    let mut result: Vec<BatchSession> = sessions
+        .values_mut()
+        .map(|s| {
+            s.expire_stale(now);
+            s.clone()
+        })
+        .collect();
+    result.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    result
+}
+
+/// 取消会话中所有尚未完成的链接
+pub fn cancel_session(session_id: &str) -> Result<BatchSession, String> {
+    let mut sessions = BATCH_SESSIONS.lock().map_err(|e| e.to_string())?;
+    let session = sessions
+        .get_mut(session_id)
+        .ok_or_else(|| format!("Batch session not found: {}", session_id))?;
+    for link in session.links.iter_mut() {
+        if link.status == BatchLinkStatus::Pending {
+            link.status = BatchLinkStatus::Cancelled;
+        }
+    }
+    Ok(session.clone())
+}
+
+/// 该 state 是否属于某个批量会话
+pub fn is_batch_state(state: &str) -> bool {
+    BATCH_SESSIONS
+        .lock()
+        .map(|sessions| {
+            sessions
+                .values()
+                .any(|s| s.links.iter().any(|l| l.state == state))
+        })
+        .unwrap_or(false)
+}
+
+/// 认领一个链接 (Pending -> Completing)，返回其 redirect_uri。
+/// 同一 state 只能被消费一次，防止回调重放。
+fn claim_link(state: &str, now: i64) -> Result<String, String> {
+    let mut sessions = BATCH_SESSIONS.lock().map_err(|e| e.to_string())?;
+    for session in sessions.values_mut() {
+        session.expire_stale(now);
+        let redirect_uri = session.redirect_uri.clone();
+        if let Some(link) = session.links.iter_mut().find(|l| l.state == state) {
+            return match link.status {
+                BatchLinkStatus::Pending => {
+                    link.status = BatchLinkStatus::Completing;
+                    Ok(redirect_uri)
+                }
+                BatchLinkStatus::Expired => Err("This authorization link has expired".to_string()),
+                BatchLinkStatus::Cancelled => {
+                    Err("This authorization link has been cancelled".to_string())
+                }
+                _ => Err("This authorization link has already been used".to_string()),
+            };
+        }
+    }
+    Err("Unknown OAuth state".to_string())
+}
+
+fn finish_link(state: &str, result: &Result<(String, Option<String>), String>, now: i64) {
+    let Ok(mut sessions) = BATCH_SESSIONS.lock() else {
+        return;
+    };
+    for session in sessions.values_mut() {
+        if let Some(link) = session.links.iter_mut().find(|l| l.state == state) {
+            match result {
+                Ok((email, account_id)) => {
+                    link.status = BatchLinkStatus::Completed;
+                    link.email = Some(email.clone());
+                    link.account_id = account_id.clone();
+                    link.error = None;
+                }
+                Err(e) => {
+                    link.status = BatchLinkStatus::Failed;
+                    link.error = Some(e.clone());
+                }
+            }
+            link.completed_at = Some(now);
+            return;
+        }
+    }
+}
+
+/// 使用回调中的 code 完成某个批量链接：交换 Token、获取邮箱、写入账号池。
+/// 返回新账号的邮箱。
+pub async fn complete_link(
+    token_manager: &crate::proxy::TokenManager,
+    state: &str,
+    code: &str,
+) -> Result<String, String> {
+    let redirect_uri = claim_link(state, chrono::Utc::now().timestamp())?;
+
+    let result = async {
+        let refresh_token = token_manager.exchange_code(code, &redirect_uri).await?;
+        let user_info = token_manager.get_user_info(&refresh_token).await?;
+        // add_account 内部通过 project_resolver::fetch_project_id 解析 project_id
+        token_manager
+            .add_account(&user_info.email, &refresh_token)
+            .await?;
+        let account_id = crate::modules::account::load_account_index()
+            .ok()
+            .and_then(|index| {
+                index
+                    .accounts
+                    .into_iter()
+                    .find(|s| s.email == user_info.email)
+                    .map(|s| s.id)
+            });
+        Ok::<_, String>((user_info.email, account_id))
+    }
+    .await;
+
+    finish_link(state, &result, chrono::Utc::now().timestamp());
+
+    match result {
+        Ok((email, _)) => {
+            crate::modules::logger::log_info(&format!(
+                "[OAuth Batch] Link completed: {}",
+                email
+            ));
+            Ok(email)
+        }
+        Err(e) => {
+            crate::modules::logger::log_error(&format!("[OAuth Batch] Link failed: {}", e));
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_session_distinct_states() {
+        let session = new_session(5, "http://localhost:8045/auth/callback", 600, 1_000);
+        let mut states: Vec<_> = session.links.iter().map(|l| l.state.clone()).collect();
+        states.sort();
+        states.dedup();
+        assert_eq!(states.len(), 5);
+        assert!(session
+            .links
+            .iter()
+            .all(|l| l.auth_url.contains(&l.state) && l.expires_at == 1_600));
+    }
+
+    #[test]
+    fn test_expire_stale_only_pending() {
+        let mut session = new_session(2, "http://localhost/auth/callback", 60, 0);
+        session.links[1].status = BatchLinkStatus::Completed;
+        session.expire_stale(59);
+        assert_eq!(session.links[0].status, BatchLinkStatus::Pending);
+        session.expire_stale(60);
+        assert_eq!(session.links[0].status, BatchLinkStatus::Expired);
+        assert_eq!(session.links[1].status, BatchLinkStatus::Completed);
+    }
+
+    #[test]
+    fn test_claim_link_once() {
+        let session = create_session(1, "http://localhost/auth/callback", Some(600)).unwrap();
+        let state = session.links[0].state.clone();
+        let now = chrono::Utc::now().timestamp();
+
+        assert!(is_batch_state(&state));
+        assert_eq!(
+            claim_link(&state, now).unwrap(),
+            "http://localhost/auth/callback"
+        );
+        assert!(claim_link(&state, now).is_err());
+
+        finish_link(&state, &Err("boom".to_string()), now);
+        let link = &get_session(&session.id).unwrap().links[0];
+        assert_eq!(link.status, BatchLinkStatus::Failed);
+        assert_eq!(link.error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn test_create_session_bounds() {
+        assert!(create_session(0, "http://localhost/auth/callback", None).is_err());
+        assert!(create_session(MAX_LINKS_PER_SESSION + 1, "http://localhost/auth/callback", None)
+            .is_err());
+    }
+}
@@ -51,6 +51,9 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN protocol TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_ip TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN pm_selected_model TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN thinking_budget INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN redaction_count INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN guard_result TEXT", []);
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
@@ -70,8 +73,8 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
         params![
             log.id,
             log.timestamp,
@@ -90,6 +93,9 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.protocol,
             log.client_ip,
             log.pm_selected_model,
+            log.thinking_budget,
+            log.redaction_count,
+            log.guard_result,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -103,7 +109,7 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
@@ -120,6 +126,9 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
             model: row.get(6)?,
             mapped_model: row.get(13).unwrap_or(None),
             pm_selected_model: row.get(16).unwrap_or(None),
+            thinking_budget: row.get(17).unwrap_or(None),
+            redaction_count: row.get(18).unwrap_or(None),
+            guard_result: row.get(19).unwrap_or(None),
             account_email: row.get(12).unwrap_or(None),
             error: row.get(7)?,
             request_body: None,  // Don't query large fields for list view
@@ -172,7 +181,7 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol, client_ip, pm_selected_model
+                account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          WHERE id = ?1"
     ).map_err(|e| e.to_string())?;
@@ -188,6 +197,9 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             model: row.get(6)?,
             mapped_model: row.get(13).unwrap_or(None),
             pm_selected_model: row.get(16).unwrap_or(None),
+            thinking_budget: row.get(17).unwrap_or(None),
+            redaction_count: row.get(18).unwrap_or(None),
+            guard_result: row.get(19).unwrap_or(None),
             account_email: row.get(12).unwrap_or(None),
             error: row.get(7)?,
             request_body: row.get(8).unwrap_or(None),
@@ -292,7 +304,7 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     let sql = if errors_only {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          WHERE (status < 200 OR status >= 400)
          ORDER BY timestamp DESC 
@@ -300,14 +312,14 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     } else if filter.is_empty() {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
     } else {
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3 OR client_ip LIKE ?3)
          ORDER BY timestamp DESC 
@@ -327,6 +339,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 model: row.get(6)?,
                 mapped_model: row.get(13).unwrap_or(None),
                 pm_selected_model: row.get(16).unwrap_or(None),
+                thinking_budget: row.get(17).unwrap_or(None),
+                redaction_count: row.get(18).unwrap_or(None),
+                guard_result: row.get(19).unwrap_or(None),
                 account_email: row.get(12).unwrap_or(None),
                 error: row.get(7)?,
                 request_body: None,
@@ -352,6 +367,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 model: row.get(6)?,
                 mapped_model: row.get(13).unwrap_or(None),
                 pm_selected_model: row.get(16).unwrap_or(None),
+                thinking_budget: row.get(17).unwrap_or(None),
+                redaction_count: row.get(18).unwrap_or(None),
+                guard_result: row.get(19).unwrap_or(None),
                 account_email: row.get(12).unwrap_or(None),
                 error: row.get(7)?,
                 request_body: None,
@@ -377,6 +395,9 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 model: row.get(6)?,
                 mapped_model: row.get(13).unwrap_or(None),
                 pm_selected_model: row.get(16).unwrap_or(None),
+                thinking_budget: row.get(17).unwrap_or(None),
+                redaction_count: row.get(18).unwrap_or(None),
+                guard_result: row.get(19).unwrap_or(None),
                 account_email: row.get(12).unwrap_or(None),
                 error: row.get(7)?,
                 request_body: None,
@@ -401,7 +422,7 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol, client_ip, pm_selected_model
+                account_email, mapped_model, protocol, client_ip, pm_selected_model, thinking_budget, redaction_count, guard_result
          FROM request_logs 
          ORDER BY timestamp DESC"
     ).map_err(|e| e.to_string())?;
@@ -417,6 +438,9 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
             model: row.get(6)?,
             mapped_model: row.get(13).unwrap_or(None),
             pm_selected_model: row.get(16).unwrap_or(None),
+            thinking_budget: row.get(17).unwrap_or(None),
+            redaction_count: row.get(18).unwrap_or(None),
+            guard_result: row.get(19).unwrap_or(None),
             account_email: row.get(12).unwrap_or(None),
             error: row.get(7)?,
             request_body: row.get(8).unwrap_or(None),
@@ -59,6 +59,10 @@ fn create_client() -> reqwest::Client {
 
 /// Get shared HTTP Client (60s timeout)
 fn create_warmup_client() -> reqwest::Client {
+    // 本机反代启用 HTTPS (可能为自签名证书) 时使用回环专用客户端
+    if crate::proxy::tls::manager().is_active() {
+        return crate::utils::http::get_loopback_client();
+    }
     crate::utils::http::get_long_client()
 }
 
@@ -279,7 +283,7 @@ pub async fn warmup_model_directly(
         .map(|c| c.proxy.port)
         .unwrap_or(8045);
 
-    let warmup_url = format!("http://127.0.0.1:{}/internal/warmup", port);
+    let warmup_url = format!("{}/internal/warmup", crate::proxy::tls::local_base_url(port));
     let body = json!({
         "email": email,
         "model": model_name,
@@ -397,7 +401,8 @@ pub async fn warm_up_all_accounts() -> Result<String, String> {
                 total
             ));
             
-            tokio::spawn(async move {
+            let actor = crate::modules::audit_db::current_actor();
+            tokio::spawn(crate::modules::audit_db::scope(actor, async move {
                 let mut success = 0;
                 let batch_size = 3;
                 let now_ts = chrono::Utc::now().timestamp();
@@ -438,7 +443,7 @@ pub async fn warm_up_all_accounts() -> Result<String, String> {
                 crate::modules::logger::log_info(&format!("[Warmup] Warmup task completed: success {}/{}", success, total));
                 tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
                 let _ = crate::modules::account::refresh_all_quotas_logic().await;
-            });
+            }));
             crate::modules::logger::log_info(&format!("[Warmup] Returning to frontend: Warmup task triggered for {} models", total));
             return Ok(format!("Warmup task triggered for {} models", total));
         }
@@ -484,7 +489,8 @@ pub async fn warm_up_account(account_id: &str) -> Result<String, String> {
 
     let warmed_count = models_to_warm.len();
     
-    tokio::spawn(async move {
+    let actor = crate::modules::audit_db::current_actor();
+    tokio::spawn(crate::modules::audit_db::scope(actor, async move {
         for (name, pct) in models_to_warm {
             if warmup_model_directly(&token, &name, &pid, &email, pct).await {
                 let history_key = format!("{}:{}:100", email, name);
@@ -494,7 +500,7 @@ pub async fn warm_up_account(account_id: &str) -> Result<String, String> {
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
         }
         let _ = crate::modules::account::refresh_all_quotas_logic().await;
-    });
+    }));
 
     Ok(format!("Successfully triggered warmup for {} model series", warmed_count))
 }
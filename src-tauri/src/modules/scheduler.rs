@@ -3,7 +3,7 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::sync::Mutex;
 use tokio::time::{self, Duration};
-use crate::modules::{config, logger, quota, account};
+use crate::modules::{account, audit_db, config, logger, quota};
 use crate::models::Account;
 use std::path::PathBuf;
 
@@ -178,7 +178,7 @@ pub fn start_scheduler(app_handle: Option<tauri::AppHandle>, proxy_state: crate:
                 let handle_for_warmup = app_handle.clone();
                 let state_for_warmup = proxy_state.clone();
 
-                tokio::spawn(async move {
+                tokio::spawn(audit_db::scope(audit_db::AuditActor::Scheduler, async move {
                     let mut success = 0;
                     let batch_size = 3;
                     let now_ts = chrono::Utc::now().timestamp();
@@ -230,7 +230,7 @@ pub fn start_scheduler(app_handle: Option<tauri::AppHandle>, proxy_state: crate:
                     // Refresh quota
                     tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
                     let _ = crate::commands::refresh_all_quotas_internal(&state_for_warmup, handle_for_warmup).await;
-                });
+                }));
             } else if skipped_cooldown > 0 {
                 logger::log_info(&format!(
                     "[Scheduler] Scan completed, all 100% models are in cooldown, skipped {}",
@@ -244,11 +244,11 @@ pub fn start_scheduler(app_handle: Option<tauri::AppHandle>, proxy_state: crate:
             if let Some(handle) = app_handle.as_ref() {
                 let handle_inner = handle.clone();
                 let state_inner = proxy_state.clone();
-                tokio::spawn(async move {
+                tokio::spawn(audit_db::scope(audit_db::AuditActor::Scheduler, async move {
                     tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                     let _ = crate::commands::refresh_all_quotas_internal(&state_inner, Some(handle_inner)).await;
                     logger::log_info("[Scheduler] Quota data synced to frontend");
-                });
+                }));
             }
 
             // Regularly clean up history (keep last 24 hours)
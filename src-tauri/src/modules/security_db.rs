@@ -1,7 +1,7 @@
 //! Security Database Module
 //! 安全监控相关的数据库操作
 
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -33,6 +33,100 @@ pub struct IpBlacklistEntry {
     pub hit_count: i64,
 }
 
+/// 自动封禁条目 (黑名单条目 + 累犯记录)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBanEntry {
+    #[serde(flatten)]
+    pub entry: IpBlacklistEntry,
+    /// 累计违规次数 (决定封禁时长阶梯)
+    pub offense_count: u32,
+    pub last_offense_at: Option<i64>,
+}
+
+/// 管理后台登录失败记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminLoginFailure {
+    pub id: String,
+    pub username: String,
+    pub client_ip: Option<String>,
+    pub timestamp: i64,
+    pub reason: String,
+}
+
+/// 安全事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventKind {
+    /// 普通访问
+    Access,
+    /// 被黑/白名单或自动封禁拦截
+    Blocked,
+    /// 鉴权失败 (代理 401 / 管理后台登录失败)
+    AuthFailure,
+    BanCreated,
+    /// 封禁到期或被管理员解除
+    BanExpired,
+    /// 管理配置变更
+    ConfigChange,
+}
+
+impl SecurityEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Access => "access",
+            Self::Blocked => "blocked",
+            Self::AuthFailure => "auth_failure",
+            Self::BanCreated => "ban_created",
+            Self::BanExpired => "ban_expired",
+            Self::ConfigChange => "config_change",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "access" => Some(Self::Access),
+            "blocked" => Some(Self::Blocked),
+            "auth_failure" => Some(Self::AuthFailure),
+            "ban_created" => Some(Self::BanCreated),
+            "ban_expired" => Some(Self::BanExpired),
+            "config_change" => Some(Self::ConfigChange),
+            _ => None,
+        }
+    }
+}
+
+/// 安全事件 (seq 为单调递增游标，写入前为 0)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub seq: i64,
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub kind: SecurityEventKind,
+    pub client_ip: Option<String>,
+    pub method: Option<String>,
+    pub path: Option<String>,
+    pub status: Option<u16>,
+    /// 操作者 (管理用户名 / auto / tauri)
+    pub actor: Option<String>,
+    pub message: String,
+}
+
+/// 已签发的分享令牌 (令牌本身仅在签发时返回，不落库)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareTokenRecord {
+    pub id: String,
+    pub label: Option<String>,
+    /// 允许的模型 (支持 * 通配符，空表示不限)
+    pub models: Vec<String>,
+    pub max_tokens: Option<u32>,
+    /// 绑定的客户端 IP / CIDR
+    pub ip: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub created_by: String,
+    pub revoked_at: Option<i64>,
+}
+
 /// IP 白名单条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpWhitelistEntry {
@@ -137,6 +231,81 @@ pub fn init_db() -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    // 自动封禁累犯记录 (黑名单条目过期删除后仍保留，用于阶梯式加长封禁)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS ip_auto_ban_history (
+            ip TEXT PRIMARY KEY,
+            offense_count INTEGER NOT NULL DEFAULT 0,
+            last_offense_at INTEGER NOT NULL,
+            last_reason TEXT
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 管理后台登录失败记录
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS admin_login_failures (
+            id TEXT PRIMARY KEY,
+            username TEXT NOT NULL,
+            client_ip TEXT,
+            timestamp INTEGER NOT NULL,
+            reason TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 安全事件 (seq 自增，作为导出游标；不随 ip_access_logs 一起清空)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS security_events (
+            seq INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            client_ip TEXT,
+            method TEXT,
+            path TEXT,
+            status INTEGER,
+            actor TEXT,
+            message TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_security_events_timestamp ON security_events (timestamp)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 分享令牌签发记录与签名密钥
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS share_tokens (
+            id TEXT PRIMARY KEY,
+            label TEXT,
+            models TEXT NOT NULL,
+            max_tokens INTEGER,
+            ip TEXT,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            created_by TEXT NOT NULL,
+            revoked_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS share_token_secret (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            secret TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     // 创建索引
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_ip_access_ip ON ip_access_logs (client_ip)",
@@ -508,42 +677,392 @@ pub fn get_blacklist_entry_for_ip(ip: &str) -> Result<Option<IpBlacklistEntry>,
     Ok(None)
 }
 
-/// 简单的 CIDR 匹配
-fn cidr_match(ip: &str, cidr: &str) -> bool {
-    let parts: Vec<&str> = cidr.split('/').collect();
-    if parts.len() != 2 {
-        return false;
-    }
+// ============================================================================
+// 自动封禁操作
+// ============================================================================
+
+/// 记录一次自动封禁违规，返回累计次数
+///
+/// # Arguments
+/// * `reset_after_secs` - 距上次违规超过该时长则重新计数
+pub fn record_auto_ban_offense(ip: &str, reason: &str, reset_after_secs: i64) -> Result<u32, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
 
-    let network = parts[0];
-    let prefix_len: u8 = match parts[1].parse() {
-        Ok(p) => p,
-        Err(_) => return false,
+    let previous: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT offense_count, last_offense_at FROM ip_auto_ban_history WHERE ip = ?1",
+            [ip],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let count = match previous {
+        Some((count, last)) if now - last <= reset_after_secs => count + 1,
+        _ => 1,
     };
 
-    let ip_parts: Vec<u8> = ip
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let net_parts: Vec<u8> = network
-        .split('.')
-        .filter_map(|s| s.parse().ok())
-        .collect();
-
-    if ip_parts.len() != 4 || net_parts.len() != 4 {
-        return false;
+    conn.execute(
+        "INSERT OR REPLACE INTO ip_auto_ban_history (ip, offense_count, last_offense_at, last_reason)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![ip, count, now, reason],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(count as u32)
+}
+
+/// 写入自动封禁 (created_by = "auto")
+///
+/// 已有手动封禁时保持不变；已有自动封禁时更新原因与到期时间
+pub fn upsert_auto_ban(ip: &str, reason: &str, expires_at: i64) -> Result<IpBlacklistEntry, String> {
+    let existing = {
+        let conn = connect_db()?;
+        conn.query_row(
+            "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
+             FROM ip_blacklist WHERE ip_pattern = ?1",
+            [ip],
+            |row| {
+                Ok(IpBlacklistEntry {
+                    id: row.get(0)?,
+                    ip_pattern: row.get(1)?,
+                    reason: row.get(2)?,
+                    created_at: row.get(3)?,
+                    expires_at: row.get(4)?,
+                    created_by: row.get(5)?,
+                    hit_count: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+    };
+
+    match existing {
+        Some(entry) if entry.created_by != "auto" => Ok(entry),
+        Some(mut entry) => {
+            let conn = connect_db()?;
+            let now = chrono::Utc::now().timestamp();
+            conn.execute(
+                "UPDATE ip_blacklist SET reason = ?1, created_at = ?2, expires_at = ?3 WHERE id = ?4",
+                params![reason, now, expires_at, entry.id],
+            )
+            .map_err(|e| e.to_string())?;
+            entry.reason = Some(reason.to_string());
+            entry.created_at = now;
+            entry.expires_at = Some(expires_at);
+            Ok(entry)
+        }
+        None => add_to_blacklist(ip, Some(reason), Some(expires_at), "auto"),
+    }
+}
+
+/// 获取生效中的自动封禁
+pub fn get_active_auto_bans() -> Result<Vec<AutoBanEntry>, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT b.id, b.ip_pattern, b.reason, b.created_at, b.expires_at, b.created_by, b.hit_count,
+                    h.offense_count, h.last_offense_at
+             FROM ip_blacklist b
+             LEFT JOIN ip_auto_ban_history h ON h.ip = b.ip_pattern
+             WHERE b.created_by = 'auto' AND (b.expires_at IS NULL OR b.expires_at > ?1)
+             ORDER BY b.created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([now], |row| {
+            Ok(AutoBanEntry {
+                entry: IpBlacklistEntry {
+                    id: row.get(0)?,
+                    ip_pattern: row.get(1)?,
+                    reason: row.get(2)?,
+                    created_at: row.get(3)?,
+                    expires_at: row.get(4)?,
+                    created_by: row.get(5)?,
+                    hit_count: row.get(6)?,
+                },
+                offense_count: row.get::<_, Option<i64>>(7)?.unwrap_or(0) as u32,
+                last_offense_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        entries.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(entries)
+}
+
+/// 解除自动封禁并清空该 IP 的累犯记录
+pub fn lift_auto_ban(ip: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "DELETE FROM ip_blacklist WHERE ip_pattern = ?1 AND created_by = 'auto'",
+        [ip],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM ip_auto_ban_history WHERE ip = ?1", [ip])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ============================================================================
+// 管理后台登录失败记录
+// ============================================================================
+
+/// 记录一次管理后台登录失败
+pub fn record_admin_login_failure(username: &str, client_ip: Option<&str>, reason: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO admin_login_failures (id, username, client_ip, timestamp, reason)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            username,
+            client_ip,
+            chrono::Utc::now().timestamp(),
+            reason
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 获取最近的管理后台登录失败记录
+pub fn get_admin_login_failures(limit: usize) -> Result<Vec<AdminLoginFailure>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, username, client_ip, timestamp, reason
+             FROM admin_login_failures
+             ORDER BY timestamp DESC
+             LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([limit as i64], |row| {
+            Ok(AdminLoginFailure {
+                id: row.get(0)?,
+                username: row.get(1)?,
+                client_ip: row.get(2)?,
+                timestamp: row.get(3)?,
+                reason: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut failures = Vec::new();
+    for row in rows {
+        failures.push(row.map_err(|e| e.to_string())?);
     }
+    Ok(failures)
+}
 
-    let ip_u32 = u32::from_be_bytes([ip_parts[0], ip_parts[1], ip_parts[2], ip_parts[3]]);
-    let net_u32 = u32::from_be_bytes([net_parts[0], net_parts[1], net_parts[2], net_parts[3]]);
+// ============================================================================
+// 安全事件
+// ============================================================================
+
+/// 写入安全事件，返回分配的 seq
+pub fn insert_security_event(event: &SecurityEvent) -> Result<i64, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "INSERT INTO security_events (timestamp, kind, client_ip, method, path, status, actor, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            event.timestamp,
+            event.kind.as_str(),
+            event.client_ip,
+            event.method,
+            event.path,
+            event.status,
+            event.actor,
+            event.message
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
 
-    let mask = if prefix_len == 0 {
-        0
+/// 按游标读取 seq 大于 `after` 的事件 (升序)
+pub fn get_security_events_after(
+    after: i64,
+    kinds: &[SecurityEventKind],
+    limit: usize,
+) -> Result<Vec<SecurityEvent>, String> {
+    let conn = connect_db()?;
+    // kinds 来自枚举，可安全内联
+    let kind_filter = if kinds.is_empty() {
+        String::new()
     } else {
-        !0u32 << (32 - prefix_len)
+        let list: Vec<String> = kinds.iter().map(|k| format!("'{}'", k.as_str())).collect();
+        format!(" AND kind IN ({})", list.join(","))
     };
+    let sql = format!(
+        "SELECT seq, timestamp, kind, client_ip, method, path, status, actor, message
+         FROM security_events
+         WHERE seq > ?1{}
+         ORDER BY seq ASC
+         LIMIT ?2",
+        kind_filter
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![after, limit as i64], |row| {
+            let kind: String = row.get(2)?;
+            Ok(SecurityEvent {
+                seq: row.get(0)?,
+                timestamp: row.get(1)?,
+                kind: SecurityEventKind::parse(&kind).unwrap_or(SecurityEventKind::Access),
+                client_ip: row.get(3)?,
+                method: row.get(4)?,
+                path: row.get(5)?,
+                status: row.get(6)?,
+                actor: row.get(7)?,
+                message: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut events = Vec::new();
+    for row in rows {
+        events.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(events)
+}
+
+/// 清理早于指定天数的安全事件
+pub fn cleanup_old_security_events(days: i64) -> Result<usize, String> {
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now().timestamp_millis() - days * 24 * 3600 * 1000;
+    conn.execute("DELETE FROM security_events WHERE timestamp < ?1", [cutoff])
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// 分享令牌
+// ============================================================================
+
+/// 读取分享令牌签名密钥 (hex)，不存在时生成并保存
+pub fn get_or_create_share_token_secret() -> Result<String, String> {
+    let conn = connect_db()?;
+    let existing: Option<String> = conn
+        .query_row("SELECT secret FROM share_token_secret WHERE id = 1", [], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    if let Some(secret) = existing {
+        return Ok(secret);
+    }
+
+    let bytes: [u8; 32] = rand::random();
+    let secret: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    conn.execute(
+        "INSERT OR IGNORE INTO share_token_secret (id, secret, created_at) VALUES (1, ?1, ?2)",
+        params![secret, chrono::Utc::now().timestamp()],
+    )
+    .map_err(|e| e.to_string())?;
+    // 并发初始化时以先写入者为准
+    conn.query_row("SELECT secret FROM share_token_secret WHERE id = 1", [], |row| row.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// 保存签发记录
+pub fn insert_share_token(record: &ShareTokenRecord) -> Result<(), String> {
+    let conn = connect_db()?;
+    let models = serde_json::to_string(&record.models).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO share_tokens (id, label, models, max_tokens, ip, created_at, expires_at, created_by, revoked_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            record.id,
+            record.label,
+            models,
+            record.max_tokens,
+            record.ip,
+            record.created_at,
+            record.expires_at,
+            record.created_by,
+            record.revoked_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 获取签发记录 (按签发时间倒序)
+pub fn list_share_tokens() -> Result<Vec<ShareTokenRecord>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, label, models, max_tokens, ip, created_at, expires_at, created_by, revoked_at
+             FROM share_tokens
+             ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let models: String = row.get(2)?;
+            Ok(ShareTokenRecord {
+                id: row.get(0)?,
+                label: row.get(1)?,
+                models: serde_json::from_str(&models).unwrap_or_default(),
+                max_tokens: row.get(3)?,
+                ip: row.get(4)?,
+                created_at: row.get(5)?,
+                expires_at: row.get(6)?,
+                created_by: row.get(7)?,
+                revoked_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    for row in rows {
+        records.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(records)
+}
+
+/// 吊销令牌，返回是否存在该记录
+pub fn revoke_share_token(id: &str) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let updated = conn
+        .execute(
+            "UPDATE share_tokens SET revoked_at = COALESCE(revoked_at, ?2) WHERE id = ?1",
+            params![id, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(updated > 0)
+}
+
+/// 已吊销且尚未过期的令牌 ID (启动时载入内存吊销列表)
+pub fn get_revoked_share_token_ids() -> Result<Vec<String>, String> {
+    let conn = connect_db()?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM share_tokens WHERE revoked_at IS NOT NULL AND expires_at > ?1")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([chrono::Utc::now().timestamp()], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+
+    let mut ids = Vec::new();
+    for row in rows {
+        ids.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(ids)
+}
 
-    (ip_u32 & mask) == (net_u32 & mask)
+/// CIDR 匹配 (IPv4 / IPv6)
+pub(crate) fn cidr_match(ip: &str, cidr: &str) -> bool {
+    crate::proxy::client_ip::ip_matches(cidr, ip)
 }
 
 // ============================================================================
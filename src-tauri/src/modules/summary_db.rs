@@ -0,0 +1,259 @@
+//! Context Summary Module
+//! Layer-3 上下文压缩摘要的持久化 (按会话指纹 + 消息前缀哈希复用)
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 每个会话保留的摘要数量上限 (旧摘要按覆盖范围淘汰)
+const MAX_SUMMARIES_PER_SESSION: i64 = 10;
+
+/// 全局保留的摘要数量上限 (按最近使用时间淘汰，防止会话指纹不断变化时数据库无限增长)
+const MAX_SUMMARIES_TOTAL: i64 = 5000;
+
+/// 超过该天数未被复用的摘要会被清理
+const SUMMARY_RETENTION_DAYS: i64 = 30;
+
+/// 已持久化的上下文摘要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContextSummary {
+    pub id: i64,
+    pub session_id: String,
+    /// 覆盖的消息前缀哈希 (见 `ContextManager::prefix_hashes`)
+    pub prefix_hash: String,
+    /// 覆盖的消息条数 (messages[..covered_messages])
+    pub covered_messages: usize,
+    /// 增量摘要的基础摘要 id (首次摘要为 None)
+    pub parent_id: Option<i64>,
+    pub summary: String,
+    /// 生成摘要所用模型
+    pub model: String,
+    pub created_at: i64,
+    pub last_used_at: i64,
+    pub hit_count: i64,
+}
+
+/// 获取摘要数据库路径
+pub fn get_summary_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("context_summaries.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let db_path = get_summary_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+
+    Ok(conn)
+}
+
+/// 初始化摘要数据库
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS context_summaries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            prefix_hash TEXT NOT NULL,
+            covered_messages INTEGER NOT NULL,
+            parent_id INTEGER,
+            summary TEXT NOT NULL,
+            model TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            last_used_at INTEGER NOT NULL,
+            hit_count INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_summary_session ON context_summaries (session_id, covered_messages DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_summary_last_used ON context_summaries (last_used_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let pruned = prune_expired(&conn)?;
+    if pruned > 0 {
+        tracing::info!("[SummaryDB] Pruned {} stale context summaries", pruned);
+    }
+
+    Ok(())
+}
+
+/// 全局清理: 删除超过保留期未使用的摘要，并只保留最近使用的 MAX_SUMMARIES_TOTAL 条
+fn prune_expired(conn: &Connection) -> Result<usize, String> {
+    let cutoff = chrono::Utc::now().timestamp() - SUMMARY_RETENTION_DAYS * 24 * 3600;
+    let expired = conn
+        .execute("DELETE FROM context_summaries WHERE last_used_at < ?1", [cutoff])
+        .map_err(|e| e.to_string())?;
+    let overflow = conn
+        .execute(
+            "DELETE FROM context_summaries WHERE id NOT IN (
+                SELECT id FROM context_summaries ORDER BY last_used_at DESC, id DESC LIMIT ?1
+             )",
+            [MAX_SUMMARIES_TOTAL],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(expired + overflow)
+}
+
+fn row_to_summary(row: &rusqlite::Row) -> rusqlite::Result<ContextSummary> {
+    Ok(ContextSummary {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        prefix_hash: row.get(2)?,
+        covered_messages: row.get::<_, i64>(3)? as usize,
+        parent_id: row.get(4)?,
+        summary: row.get(5)?,
+        model: row.get(6)?,
+        created_at: row.get(7)?,
+        last_used_at: row.get(8)?,
+        hit_count: row.get(9)?,
+    })
+}
+
+const SELECT_COLUMNS: &str =
+    "id, session_id, prefix_hash, covered_messages, parent_id, summary, model, created_at, last_used_at, hit_count";
+
+/// 查找可复用的摘要: 覆盖范围最长、且前缀哈希与当前请求一致的那一条
+///
+/// # Arguments
+/// * `prefix_hashes` - 当前请求的前缀哈希，`prefix_hashes[i]` 对应 messages[..=i]
+pub fn find_reusable(session_id: &str, prefix_hashes: &[String]) -> Result<Option<ContextSummary>, String> {
+    if prefix_hashes.is_empty() {
+        return Ok(None);
+    }
+    let conn = connect_db()?;
+    let sql = format!(
+        "SELECT {} FROM context_summaries
+         WHERE session_id = ?1 AND covered_messages <= ?2
+         ORDER BY covered_messages DESC, id DESC",
+        SELECT_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id, prefix_hashes.len() as i64], row_to_summary)
+        .map_err(|e| e.to_string())?;
+
+    for row in rows {
+        let summary = row.map_err(|e| e.to_string())?;
+        if summary.covered_messages > 0
+            && prefix_hashes[summary.covered_messages - 1] == summary.prefix_hash
+        {
+            return Ok(Some(summary));
+        }
+    }
+    Ok(None)
+}
+
+/// 保存摘要并淘汰该会话中多余的旧摘要 (同时执行全局清理)，返回新记录 id
+pub fn save_summary(
+    session_id: &str,
+    prefix_hash: &str,
+    covered_messages: usize,
+    parent_id: Option<i64>,
+    summary: &str,
+    model: &str,
+) -> Result<i64, String> {
+    let conn = connect_db()?;
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO context_summaries
+            (session_id, prefix_hash, covered_messages, parent_id, summary, model, created_at, last_used_at, hit_count)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, 0)",
+        params![session_id, prefix_hash, covered_messages as i64, parent_id, summary, model, now],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+
+    conn.execute(
+        "DELETE FROM context_summaries
+         WHERE session_id = ?1 AND id NOT IN (
+            SELECT id FROM context_summaries WHERE session_id = ?1
+            ORDER BY covered_messages DESC, id DESC LIMIT ?2
+         )",
+        params![session_id, MAX_SUMMARIES_PER_SESSION],
+    )
+    .map_err(|e| e.to_string())?;
+    prune_expired(&conn)?;
+
+    Ok(id)
+}
+
+/// 记录一次复用
+pub fn mark_used(id: i64) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "UPDATE context_summaries SET hit_count = hit_count + 1, last_used_at = ?1 WHERE id = ?2",
+        params![chrono::Utc::now().timestamp(), id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 查询摘要 (按最近使用时间倒序)
+pub fn get_summaries(session_id: Option<&str>, limit: usize, offset: usize) -> Result<Vec<ContextSummary>, String> {
+    let conn = connect_db()?;
+    let sql = format!(
+        "SELECT {} FROM context_summaries
+         WHERE (?1 IS NULL OR session_id = ?1)
+         ORDER BY last_used_at DESC, id DESC
+         LIMIT ?2 OFFSET ?3",
+        SELECT_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![session_id, limit as i64, offset as i64], row_to_summary)
+        .map_err(|e| e.to_string())?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(result)
+}
+
+/// 按 id 获取单条摘要
+pub fn get_summary(id: i64) -> Result<Option<ContextSummary>, String> {
+    let conn = connect_db()?;
+    let sql = format!("SELECT {} FROM context_summaries WHERE id = ?1", SELECT_COLUMNS);
+    conn.query_row(&sql, params![id], row_to_summary)
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+/// 统计摘要数量
+pub fn get_summaries_count(session_id: Option<&str>) -> Result<u64, String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM context_summaries WHERE (?1 IS NULL OR session_id = ?1)",
+        params![session_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|c| c as u64)
+    .map_err(|e| e.to_string())
+}
+
+/// 清除摘要 (指定会话或全部)，返回删除条数
+pub fn clear_summaries(session_id: Option<&str>) -> Result<usize, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "DELETE FROM context_summaries WHERE (?1 IS NULL OR session_id = ?1)",
+        params![session_id],
+    )
+    .map_err(|e| e.to_string())
+}
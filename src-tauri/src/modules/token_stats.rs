@@ -314,6 +314,19 @@ pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
     Ok(result)
 }
 
+/// Get (total_tokens, request_count) for one account since a unix timestamp
+pub fn get_account_usage_since(account_email: &str, since: i64) -> Result<(u64, u64), String> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COALESCE(SUM(total_tokens), 0), COUNT(*)
+         FROM token_usage
+         WHERE account_email = ?1 AND timestamp >= ?2",
+        params![account_email, since],
+        |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+    )
+    .map_err(|e| e.to_string())
+}
+
 /// Get summary statistics for a time range
 pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
     let conn = connect_db()?;
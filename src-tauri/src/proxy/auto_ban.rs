@@ -0,0 +1,336 @@
+// 自动封禁引擎 (fail2ban 风格)
+//
+// 由全局 auto_ban_middleware 喂入信号: 每个请求 (洪泛)、鉴权层自身拒绝的请求
+// (鉴权失败)、未知路由或扫描器特征路径 (探测)。上游透传的 401/404 不计入，
+// 拒绝方需通过 mark_rejection 在响应上打标记。各规则按 IP 维护滑动窗口，超过阈值即写入
+// ip_blacklist (created_by = "auto")，封禁时长按累犯次数阶梯式加长。
+
+use axum::response::Response;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+
+use crate::modules::security_db::{self, IpBlacklistEntry, SecurityEvent, SecurityEventKind};
+use crate::proxy::security_events;
+use crate::proxy::config::AutoBanConfig;
+
+/// 滑动窗口清理阈值: 跟踪的 IP 数超过该值时清理空闲窗口
+const MAX_TRACKED_IPS: usize = 10_000;
+/// 封禁到期检查间隔
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 滥用信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbuseSignal {
+    Request,
+    AuthFailure,
+    Probe,
+}
+
+/// 响应扩展: 标记由本服务 (而非上游) 产生的拒绝及其对应的滥用信号
+#[derive(Debug, Clone, Copy)]
+pub struct RejectionMarker(pub AbuseSignal);
+
+/// 为拒绝响应打上滥用信号标记
+pub fn mark_rejection(mut response: Response, signal: AbuseSignal) -> Response {
+    response.extensions_mut().insert(RejectionMarker(signal));
+    response
+}
+
+/// 读取响应上的滥用信号标记
+pub fn rejection_signal(response: &Response) -> Option<AbuseSignal> {
+    response.extensions().get::<RejectionMarker>().map(|marker| marker.0)
+}
+
+#[derive(Debug, Default)]
+struct IpWindows {
+    requests: VecDeque<i64>,
+    auth_failures: VecDeque<i64>,
+    probes: VecDeque<i64>,
+}
+
+impl IpWindows {
+    fn is_idle(&self, now_ms: i64, max_window_ms: i64) -> bool {
+        [&self.requests, &self.auth_failures, &self.probes]
+            .iter()
+            .all(|w| w.back().map_or(true, |&t| now_ms - t > max_window_ms))
+    }
+}
+
+/// 写入一次事件并判断窗口内次数是否达到阈值 (threshold 为 0 表示规则关闭)
+fn push_and_check(window: &mut VecDeque<i64>, now_ms: i64, window_ms: i64, threshold: u32) -> bool {
+    if threshold == 0 {
+        return false;
+    }
+    window.push_back(now_ms);
+    while window.front().is_some_and(|&t| now_ms - t > window_ms) {
+        window.pop_front();
+    }
+    window.len() >= threshold as usize
+}
+
+/// 第 `offense` 次违规的封禁时长 (秒)
+pub fn ban_duration_secs(config: &AutoBanConfig, offense: u32) -> i64 {
+    let index = (offense.max(1) as usize - 1).min(config.ban_durations_minutes.len().saturating_sub(1));
+    let minutes = config.ban_durations_minutes.get(index).copied().unwrap_or(10);
+    minutes as i64 * 60
+}
+
+/// 路径是否命中扫描器特征
+pub fn is_probe_path(path: &str, patterns: &[String]) -> bool {
+    let path = path.to_lowercase();
+    patterns
+        .iter()
+        .any(|p| !p.is_empty() && path.contains(&p.to_lowercase()))
+}
+
+/// IP 是否豁免 (配置豁免列表或白名单)
+pub fn is_exempt(ip: &str, config: &AutoBanConfig) -> bool {
+    let in_list = config
+        .exempt_ips
+        .iter()
+        .any(|p| p == ip || crate::proxy::client_ip::ip_matches(p, ip));
+    in_list || security_db::is_ip_in_whitelist(ip).unwrap_or(false)
+}
+
+#[derive(Default)]
+pub struct AutoBanEngine {
+    windows: DashMap<String, IpWindows>,
+    /// 已生效的自动封禁 (ip -> 到期时间)，仅用于快速判断，命中后以数据库为准
+    active: DashMap<String, i64>,
+}
+
+/// 生效中的封禁由 init() 在启动时从数据库恢复
+static ENGINE: Lazy<AutoBanEngine> = Lazy::new(AutoBanEngine::default);
+static SWEEPER: std::sync::Once = std::sync::Once::new();
+
+/// 启动时从数据库恢复生效中的自动封禁 (重启后继续拦截) 并启动到期检查线程
+pub fn init() {
+    match security_db::get_active_auto_bans() {
+        Ok(bans) => {
+            for ban in bans {
+                let expires_at = ban.entry.expires_at.unwrap_or(i64::MAX);
+                ENGINE.active.insert(ban.entry.ip_pattern, expires_at);
+            }
+        }
+        Err(e) => tracing::error!("[Auto-Ban] Failed to restore active bans: {}", e),
+    }
+    // 到期检查使用独立线程，不受安全事件导出 (文件 / syslog) 阻塞影响
+    SWEEPER.call_once(|| {
+        if let Err(e) = std::thread::Builder::new()
+            .name("auto-ban-sweep".to_string())
+            .spawn(sweep_loop)
+        {
+            tracing::error!("[Auto-Ban] Failed to start expiry sweeper: {}", e);
+        }
+    });
+}
+
+fn sweep_loop() {
+    loop {
+        std::thread::sleep(SWEEP_INTERVAL);
+        for ip in engine().sweep_expired() {
+            security_events::emit(
+                SecurityEvent::new(SecurityEventKind::BanExpired, "Auto ban expired").ip(ip).actor("auto"),
+            );
+        }
+    }
+}
+
+/// 全局自动封禁引擎
+pub fn engine() -> &'static AutoBanEngine {
+    &ENGINE
+}
+
+impl AutoBanEngine {
+    /// 查询 IP 当前的自动封禁 (管理员手动解除或已过期时返回 None)
+    pub fn active_ban(&self, ip: &str) -> Option<IpBlacklistEntry> {
+        let expires_at = *self.active.get(ip)?;
+        if expires_at <= chrono::Utc::now().timestamp() {
+            if self.active.remove(ip).is_some() {
+                security_events::emit(
+                    SecurityEvent::new(SecurityEventKind::BanExpired, "Auto ban expired").ip(ip).actor("auto"),
+                );
+            }
+            return None;
+        }
+        match security_db::get_blacklist_entry_for_ip(ip) {
+            Ok(Some(entry)) => Some(entry),
+            Ok(None) => {
+                self.active.remove(ip);
+                None
+            }
+            Err(e) => {
+                tracing::error!("[Auto-Ban] Failed to check blacklist for {}: {}", ip, e);
+                None
+            }
+        }
+    }
+
+    /// 记录信号，达到规则阈值时返回封禁原因
+    pub fn observe(&self, ip: &str, signal: AbuseSignal, config: &AutoBanConfig) -> Option<String> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let reason = {
+            let mut windows = self.windows.entry(ip.to_string()).or_default();
+            match signal {
+                AbuseSignal::Request => {
+                    let window_ms = config.flood_window_seconds as i64 * 1000;
+                    push_and_check(&mut windows.requests, now_ms, window_ms, config.flood_threshold).then(|| {
+                        format!(
+                            "Request flood: {} requests in {}s",
+                            config.flood_threshold, config.flood_window_seconds
+                        )
+                    })
+                }
+                AbuseSignal::AuthFailure => {
+                    let window_ms = config.auth_failure_window_minutes as i64 * 60_000;
+                    push_and_check(&mut windows.auth_failures, now_ms, window_ms, config.auth_failure_threshold)
+                        .then(|| {
+                            format!(
+                                "Auth failures: {} in {} min",
+                                config.auth_failure_threshold, config.auth_failure_window_minutes
+                            )
+                        })
+                }
+                AbuseSignal::Probe => {
+                    let window_ms = config.probe_window_minutes as i64 * 60_000;
+                    push_and_check(&mut windows.probes, now_ms, window_ms, config.probe_threshold).then(|| {
+                        format!(
+                            "Path probing: {} unknown paths in {} min",
+                            config.probe_threshold, config.probe_window_minutes
+                        )
+                    })
+                }
+            }
+        };
+
+        if reason.is_some() {
+            self.windows.remove(ip);
+        } else if self.windows.len() > MAX_TRACKED_IPS {
+            let max_window_ms = (config.flood_window_seconds as i64 * 1000)
+                .max(config.auth_failure_window_minutes as i64 * 60_000)
+                .max(config.probe_window_minutes as i64 * 60_000);
+            self.windows.retain(|_, w| !w.is_idle(now_ms, max_window_ms));
+        }
+        reason
+    }
+
+    /// 封禁 IP (阻塞调用，涉及数据库)，豁免 IP 返回 None
+    pub fn ban(&self, ip: &str, reason: &str, config: &AutoBanConfig) -> Result<Option<IpBlacklistEntry>, String> {
+        if is_exempt(ip, config) {
+            tracing::info!("[Auto-Ban] {} triggered '{}' but is exempt", ip, reason);
+            return Ok(None);
+        }
+
+        let offense = security_db::record_auto_ban_offense(ip, reason, config.offense_reset_hours as i64 * 3600)?;
+        let duration = ban_duration_secs(config, offense);
+        let expires_at = chrono::Utc::now().timestamp() + duration;
+        let reason = format!("[auto] {} (offense #{})", reason, offense);
+        let entry = security_db::upsert_auto_ban(ip, &reason, expires_at)?;
+        self.active.insert(ip.to_string(), entry.expires_at.unwrap_or(expires_at));
+
+        tracing::warn!("[Auto-Ban] Banned {} for {} min: {}", ip, duration / 60, reason);
+        security_events::emit(
+            SecurityEvent::new(SecurityEventKind::BanCreated, format!("{} for {} min", reason, duration / 60))
+                .ip(ip)
+                .actor("auto"),
+        );
+
+        let log = security_db::IpAccessLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            client_ip: ip.to_string(),
+            timestamp: chrono::Utc::now().timestamp(),
+            method: None,
+            path: None,
+            user_agent: None,
+            status: Some(403),
+            duration: Some(0),
+            api_key_hash: None,
+            blocked: true,
+            block_reason: Some(reason),
+        };
+        if let Err(e) = security_db::save_ip_access_log(&log) {
+            tracing::error!("[Auto-Ban] Failed to save ban access log: {}", e);
+        }
+        Ok(Some(entry))
+    }
+
+    /// 移除已到期的封禁缓存并返回对应 IP (安全事件线程定期调用，用于产生 ban_expired 事件)
+    pub fn sweep_expired(&self) -> Vec<String> {
+        let now = chrono::Utc::now().timestamp();
+        let expired: Vec<String> = self
+            .active
+            .iter()
+            .filter(|entry| *entry.value() <= now)
+            .map(|entry| entry.key().clone())
+            .collect();
+        expired
+            .into_iter()
+            .filter(|ip| self.active.remove(ip).is_some())
+            .collect()
+    }
+
+    /// 清除 IP 的窗口与缓存 (管理员解除封禁时调用)
+    pub fn forget(&self, ip: &str) {
+        self.windows.remove(ip);
+        self.active.remove(ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_marked_rejections_carry_signal() {
+        use axum::{http::StatusCode, response::IntoResponse};
+        // 上游透传的 401 没有标记
+        assert_eq!(rejection_signal(&StatusCode::UNAUTHORIZED.into_response()), None);
+        let marked = mark_rejection(StatusCode::UNAUTHORIZED.into_response(), AbuseSignal::AuthFailure);
+        assert_eq!(rejection_signal(&marked), Some(AbuseSignal::AuthFailure));
+    }
+
+    #[test]
+    fn test_sliding_window_threshold() {
+        let mut window = VecDeque::new();
+        assert!(!push_and_check(&mut window, 0, 1000, 3));
+        assert!(!push_and_check(&mut window, 500, 1000, 3));
+        // 第一次事件已滑出窗口
+        assert!(!push_and_check(&mut window, 1600, 1000, 3));
+        assert!(!push_and_check(&mut window, 1700, 1000, 3));
+        assert!(push_and_check(&mut window, 1800, 1000, 3));
+        assert!(!push_and_check(&mut VecDeque::new(), 0, 1000, 0));
+    }
+
+    #[test]
+    fn test_escalating_ban_duration() {
+        let config = AutoBanConfig::default();
+        assert_eq!(ban_duration_secs(&config, 1), 10 * 60);
+        assert_eq!(ban_duration_secs(&config, 2), 60 * 60);
+        assert_eq!(ban_duration_secs(&config, 4), 1440 * 60);
+        assert_eq!(ban_duration_secs(&config, 99), 1440 * 60);
+        let empty = AutoBanConfig { ban_durations_minutes: Vec::new(), ..Default::default() };
+        assert_eq!(ban_duration_secs(&empty, 3), 10 * 60);
+    }
+
+    #[test]
+    fn test_probe_path_patterns() {
+        let config = AutoBanConfig::default();
+        assert!(is_probe_path("/.env", &config.probe_patterns));
+        assert!(is_probe_path("/WP-Login.php", &config.probe_patterns));
+        assert!(!is_probe_path("/v1/messages", &config.probe_patterns));
+    }
+
+    #[test]
+    fn test_observe_triggers_once_per_window() {
+        let engine = AutoBanEngine::default();
+        let config = AutoBanConfig { auth_failure_threshold: 3, ..Default::default() };
+        assert!(engine.observe("10.0.0.1", AbuseSignal::AuthFailure, &config).is_none());
+        assert!(engine.observe("10.0.0.1", AbuseSignal::AuthFailure, &config).is_none());
+        assert!(engine.observe("10.0.0.2", AbuseSignal::AuthFailure, &config).is_none());
+        let reason = engine.observe("10.0.0.1", AbuseSignal::AuthFailure, &config);
+        assert!(reason.unwrap().starts_with("Auth failures"));
+        // 触发后窗口重置
+        assert!(engine.observe("10.0.0.1", AbuseSignal::AuthFailure, &config).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+// 客户端 IP 解析 (可信代理感知) 与 IPv4/IPv6 CIDR 匹配
+//
+// 只有当直连对端 (socket peer) 属于可信代理时才读取转发头，否则一律使用对端地址，
+// 防止客户端伪造 X-Forwarded-For 绕过黑/白名单。
+
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::proxy::config::TrustedProxyConfig;
+
+/// Cloudflare 边缘节点 IP 段 (https://www.cloudflare.com/ips/)
+pub const CLOUDFLARE_RANGES: &[&str] = &[
+    "173.245.48.0/20",
+    "103.21.244.0/22",
+    "103.22.200.0/22",
+    "103.31.4.0/22",
+    "141.101.64.0/18",
+    "108.162.192.0/18",
+    "190.93.240.0/20",
+    "188.114.96.0/20",
+    "197.234.240.0/22",
+    "198.41.128.0/17",
+    "162.158.0.0/15",
+    "104.16.0.0/13",
+    "104.24.0.0/14",
+    "172.64.0.0/13",
+    "131.0.72.0/22",
+    "2400:cb00::/32",
+    "2606:4700::/32",
+    "2803:f800::/32",
+    "2405:b500::/32",
+    "2405:8100::/32",
+    "2a06:98c0::/29",
+    "2c0f:f248::/32",
+];
+
+/// 将 IPv4-mapped IPv6 (::ffff:a.b.c.d) 还原为 IPv4，便于与 IPv4 规则比较
+fn normalize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+/// 解析 IP 或 CIDR 规则，返回 (网络地址, 前缀长度)；单个 IP 视为全长前缀
+pub fn parse_ip_pattern(pattern: &str) -> Option<(IpAddr, u8)> {
+    let pattern = pattern.trim();
+    let (addr, prefix) = match pattern.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+        None => (pattern, None),
+    };
+    let ip = normalize(addr.parse::<IpAddr>().ok()?);
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max);
+    (prefix <= max).then_some((ip, prefix))
+}
+
+/// IP 是否落在 CIDR (或等于单个 IP) 内
+pub fn cidr_contains(pattern: &str, ip: &IpAddr) -> bool {
+    let Some((network, prefix)) = parse_ip_pattern(pattern) else {
+        return false;
+    };
+    match (network, normalize(*ip)) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// 字符串形式的 IP 是否匹配规则
+pub fn ip_matches(pattern: &str, ip: &str) -> bool {
+    ip.trim()
+        .parse::<IpAddr>()
+        .map(|ip| cidr_contains(pattern, &ip))
+        .unwrap_or(false)
+}
+
+/// 对端是否为可信代理
+pub fn is_trusted(ip: &IpAddr, config: &TrustedProxyConfig) -> bool {
+    config.proxies.iter().any(|p| cidr_contains(p, ip))
+        || (config.cloudflare && CLOUDFLARE_RANGES.iter().any(|p| cidr_contains(p, ip)))
+}
+
+fn header_ip(headers: &HeaderMap, name: &str) -> Option<IpAddr> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<IpAddr>().ok())
+        .map(normalize)
+}
+
+/// 解析真实客户端 IP
+///
+/// 1. 对端不可信 (或未知) 时直接使用对端地址，忽略所有转发头
+/// 2. 启用 Cloudflare 预设时优先使用 `CF-Connecting-IP`
+/// 3. 从右向左遍历 `X-Forwarded-For`，跳过可信代理，第一个不可信地址即客户端
+/// 4. 其次使用 `X-Real-IP`，都没有时回退到对端地址
+pub fn resolve_client_ip(peer: Option<IpAddr>, headers: &HeaderMap, config: &TrustedProxyConfig) -> Option<IpAddr> {
+    let peer = normalize(peer?);
+    if !is_trusted(&peer, config) {
+        return Some(peer);
+    }
+
+    if config.cloudflare {
+        if let Some(ip) = header_ip(headers, "cf-connecting-ip") {
+            return Some(ip);
+        }
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+    if !forwarded.is_empty() {
+        let mut client = peer;
+        for hop in forwarded.iter().rev() {
+            match hop.parse::<IpAddr>().map(normalize) {
+                Ok(ip) => {
+                    client = ip;
+                    if !is_trusted(&ip, config) {
+                        break;
+                    }
+                }
+                // 无法解析的条目之后的内容不可信，停在最后一个有效的可信跳
+                Err(_) => break,
+            }
+        }
+        return Some(client);
+    }
+
+    header_ip(headers, "x-real-ip").or(Some(peer))
+}
+
+/// 从 axum 请求中解析客户端 IP (依赖 server 注入的 ConnectInfo)
+pub fn client_ip_from_request<B>(request: &axum::http::Request<B>, config: &TrustedProxyConfig) -> Option<String> {
+    let peer = request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    resolve_client_ip(peer, request.headers(), config).map(|ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (k, v) in pairs {
+            map.append(*k, v.parse().unwrap());
+        }
+        map
+    }
+
+    #[test]
+    fn test_cidr_ipv4_and_ipv6() {
+        assert!(cidr_contains("192.168.1.0/24", &ip("192.168.1.200")));
+        assert!(!cidr_contains("192.168.1.0/24", &ip("192.168.2.1")));
+        assert!(cidr_contains("0.0.0.0/0", &ip("8.8.8.8")));
+        assert!(cidr_contains("8.8.8.8", &ip("8.8.8.8")));
+        assert!(cidr_contains("10.0.0.0/8", &ip("::ffff:10.1.2.3")));
+        assert!(cidr_contains("2606:4700::/32", &ip("2606:4700:10::6816:1")));
+        assert!(!cidr_contains("2606:4700::/32", &ip("2606:4701::1")));
+        assert!(cidr_contains("::/0", &ip("2001:db8::1")));
+        assert!(cidr_contains("::1", &ip("::1")));
+        assert!(!cidr_contains("10.0.0.0/8", &ip("2001:db8::1")));
+        assert!(parse_ip_pattern("192.168.1.1/33").is_none());
+        assert!(parse_ip_pattern("2001:db8::/129").is_none());
+        assert!(parse_ip_pattern("192.168.1.1/").is_none());
+        assert!(parse_ip_pattern("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn test_untrusted_peer_ignores_forwarded_headers() {
+        let config = TrustedProxyConfig::default();
+        let h = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "5.6.7.8"), ("cf-connecting-ip", "9.9.9.9")]);
+        assert_eq!(resolve_client_ip(Some(ip("203.0.113.7")), &h, &config), Some(ip("203.0.113.7")));
+        assert_eq!(resolve_client_ip(None, &h, &config), None);
+    }
+
+    #[test]
+    fn test_trusted_chain_walks_from_right() {
+        let config = TrustedProxyConfig {
+            proxies: vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()],
+            cloudflare: false,
+        };
+        // 客户端伪造的最左侧条目被忽略，取第一个不可信跳
+        let h = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.9, 10.0.0.2")]);
+        assert_eq!(resolve_client_ip(Some(ip("127.0.0.1")), &h, &config), Some(ip("198.51.100.9")));
+
+        // 多个 X-Forwarded-For 头按顺序合并
+        let h = headers(&[("x-forwarded-for", "198.51.100.9"), ("x-forwarded-for", "10.0.0.2")]);
+        assert_eq!(resolve_client_ip(Some(ip("127.0.0.1")), &h, &config), Some(ip("198.51.100.9")));
+
+        // 全部为可信跳时取最左侧
+        let h = headers(&[("x-forwarded-for", "10.0.0.5, 10.0.0.2")]);
+        assert_eq!(resolve_client_ip(Some(ip("127.0.0.1")), &h, &config), Some(ip("10.0.0.5")));
+
+        // 无效条目: 停在最后一个有效跳
+        let h = headers(&[("x-forwarded-for", "garbage, 10.0.0.2")]);
+        assert_eq!(resolve_client_ip(Some(ip("127.0.0.1")), &h, &config), Some(ip("10.0.0.2")));
+
+        // 无 XFF 时使用 X-Real-IP，再回退到对端
+        let h = headers(&[("x-real-ip", "2001:db8::5")]);
+        assert_eq!(resolve_client_ip(Some(ip("::ffff:127.0.0.1")), &h, &config), Some(ip("2001:db8::5")));
+        assert_eq!(resolve_client_ip(Some(ip("10.1.1.1")), &HeaderMap::new(), &config), Some(ip("10.1.1.1")));
+    }
+
+    #[test]
+    fn test_cloudflare_preset() {
+        let config = TrustedProxyConfig { proxies: vec!["127.0.0.1".to_string()], cloudflare: true };
+        let h = headers(&[("cf-connecting-ip", "203.0.113.50"), ("x-forwarded-for", "6.6.6.6")]);
+        // cloudflared 隧道 (本机对端) 与 Cloudflare 边缘直连均信任 CF-Connecting-IP
+        assert_eq!(resolve_client_ip(Some(ip("127.0.0.1")), &h, &config), Some(ip("203.0.113.50")));
+        assert_eq!(resolve_client_ip(Some(ip("172.64.1.1")), &h, &config), Some(ip("203.0.113.50")));
+        // 非 Cloudflare 对端伪造 CF-Connecting-IP 无效
+        assert_eq!(resolve_client_ip(Some(ip("198.51.100.1")), &h, &config), Some(ip("198.51.100.1")));
+    }
+}
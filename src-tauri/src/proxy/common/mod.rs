@@ -3,8 +3,12 @@
 // pub mod error;
 // pub mod rate_limiter;
 pub mod model_mapping;
+pub mod model_registry;
+pub mod thinking_policy;
 pub mod utils;
 pub mod json_schema;
 pub mod tool_adapter;
 pub mod tool_adapters;
+pub mod tool_result_strategy;
+pub mod tool_result_strategies;
 pub mod schema_cache;
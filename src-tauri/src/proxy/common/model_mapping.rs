@@ -1,74 +1,14 @@
 // 模型名称映射
+// 内置模型与别名由 model_registry 统一维护
+#[cfg(test)]
 use std::collections::HashMap;
-use once_cell::sync::Lazy;
-
-static CLAUDE_TO_GEMINI: Lazy<HashMap<&'static str, &'static str>> = Lazy::new(|| {
-    let mut m = HashMap::new();
-
-    // 直接支持的模型
-    m.insert("claude-opus-4-5-thinking", "claude-opus-4-5-thinking");
-    m.insert("claude-sonnet-4-5", "claude-sonnet-4-5");
-    m.insert("claude-sonnet-4-5-thinking", "claude-sonnet-4-5-thinking");
-
-    // 别名映射
-    m.insert("claude-sonnet-4-5-20250929", "claude-sonnet-4-5-thinking");
-    m.insert("claude-3-5-sonnet-20241022", "claude-sonnet-4-5");
-    m.insert("claude-3-5-sonnet-20240620", "claude-sonnet-4-5");
-    m.insert("claude-opus-4", "claude-opus-4-5-thinking");
-    m.insert("claude-opus-4-5-20251101", "claude-opus-4-5-thinking");
-    m.insert("claude-haiku-4", "claude-sonnet-4-5");
-    m.insert("claude-3-haiku-20240307", "claude-sonnet-4-5");
-    m.insert("claude-haiku-4-5-20251001", "claude-sonnet-4-5");
-    // OpenAI 协议映射表
-    m.insert("gpt-4", "gemini-2.5-flash");
-    m.insert("gpt-4-turbo", "gemini-2.5-flash");
-    m.insert("gpt-4-turbo-preview", "gemini-2.5-flash");
-    m.insert("gpt-4-0125-preview", "gemini-2.5-flash");
-    m.insert("gpt-4-1106-preview", "gemini-2.5-flash");
-    m.insert("gpt-4-0613", "gemini-2.5-flash");
-
-    m.insert("gpt-4o", "gemini-2.5-flash");
-    m.insert("gpt-4o-2024-05-13", "gemini-2.5-flash");
-    m.insert("gpt-4o-2024-08-06", "gemini-2.5-flash");
-
-    m.insert("gpt-4o-mini", "gemini-2.5-flash");
-    m.insert("gpt-4o-mini-2024-07-18", "gemini-2.5-flash");
-
-    m.insert("gpt-3.5-turbo", "gemini-2.5-flash");
-    m.insert("gpt-3.5-turbo-16k", "gemini-2.5-flash");
-    m.insert("gpt-3.5-turbo-0125", "gemini-2.5-flash");
-    m.insert("gpt-3.5-turbo-1106", "gemini-2.5-flash");
-    m.insert("gpt-3.5-turbo-0613", "gemini-2.5-flash");
-
-    // Codex models
-    m.insert("gpt-5.2-codex", "gpt-5.2-codex");
-    m.insert("gpt-5.1-codex-max", "gpt-5.1-codex-max");
-    m.insert("gpt-5.1-codex-mini", "gpt-5.1-codex-mini");
-
-    // Gemini 协议映射表
-    m.insert("gemini-2.5-flash-lite", "gemini-2.5-flash");
-    m.insert("gemini-2.5-flash-thinking", "gemini-2.5-flash-thinking");
-    m.insert("gemini-3-pro-low", "gemini-3-pro-preview");
-    m.insert("gemini-3-pro-high", "gemini-3-pro-preview");
-    m.insert("gemini-3-pro-preview", "gemini-3-pro-preview");
-    m.insert("gemini-3-pro", "gemini-3-pro-preview");  // 统一映射到 preview
-    m.insert("gemini-2.5-flash", "gemini-2.5-flash");
-    m.insert("gemini-2.5-pro", "gemini-2.5-pro");
-    m.insert("gemini-3-flash", "gemini-3-flash");
-    m.insert("gemini-3-pro-image", "gemini-3-pro-image");
-
-    // [New] Unified Virtual ID for Background Tasks (Title, Summary, etc.)
-    // Allows users to override all background tasks via custom_mapping
-    m.insert("internal-background-task", "gemini-2.5-flash");
-
-
-    m
-});
+
+use super::model_registry;
 
 pub fn map_claude_model_to_gemini(input: &str) -> String {
-    // 1. Check exact match in map
-    if let Some(mapped) = CLAUDE_TO_GEMINI.get(input) {
-        return mapped.to_string();
+    // 1. Check registry (model id or alias)
+    if let Some(mapped) = model_registry::upstream_model(input) {
+        return mapped;
     }
 
     // 2. Pass-through known prefixes (gemini-, -thinking) to support dynamic suffixes
@@ -87,41 +27,44 @@ pub fn map_claude_model_to_gemini(input: &str) -> String {
 }
 
 pub fn is_codex_model(input: &str) -> bool {
-    matches!(
-        input,
-        "gpt-5.2-codex" | "gpt-5.1-codex-max" | "gpt-5.1-codex-mini"
-    )
+    model_registry::is_codex_model(input)
 }
 
 /// Codex 요청 시 OpenAI로 보낼 공식 모델명으로 정규화 (old codex handler 호환)
-const CODEX_OFFICIAL_MODELS: &[&str] = &["gpt-5.2-codex", "gpt-5.1-codex-max", "gpt-5.1-codex-mini"];
-const CODEX_DEFAULT_MODEL: &str = "gpt-5.2-codex";
-
-pub fn resolve_codex_model(request_model: &str) -> &'static str {
-    if let Some(m) = CODEX_OFFICIAL_MODELS.iter().find(|&&m| m == request_model) {
-        return *m;
+/// 레지스트리의 Codex 모델 중 첫 번째가 기본값
+pub fn resolve_codex_model(request_model: &str) -> String {
+    let codex_models = model_registry::codex_models();
+    if let Some(m) = codex_models.iter().find(|m| m.as_str() == request_model) {
+        return m.clone();
     }
     let lower = request_model.to_lowercase();
-    if lower.contains("codex-max") || lower.contains("max") {
-        return "gpt-5.1-codex-max";
-    }
-    if lower.contains("codex-mini") || lower.contains("mini") {
-        return "gpt-5.1-codex-mini";
+    for keyword in ["max", "mini"] {
+        if lower.contains(keyword) {
+            if let Some(m) = codex_models.iter().find(|m| m.contains(keyword)) {
+                return m.clone();
+            }
+        }
     }
-    CODEX_DEFAULT_MODEL
+    codex_models
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "gpt-5.2-codex".to_string())
 }
 
 /// 获取所有内置支持的模型列表关键字
 pub fn get_supported_models() -> Vec<String> {
-    CLAUDE_TO_GEMINI.keys().map(|s| s.to_string()).collect()
+    model_registry::known_model_names()
 }
 
-/// 动态获取所有可用模型列表 (包含内置与用户自定义)
+/// 动态获取所有可用模型列表 (包含内置、用户自定义与账号实际可用的上游模型)
+/// 发现数据就绪后，附带可服务账号数并隐藏没有任何账号可服务的模型
 pub async fn get_all_dynamic_models(
     custom_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
-) -> Vec<String> {
-    use std::collections::HashSet;
-    let mut model_ids = HashSet::new();
+    discovery: &crate::proxy::model_discovery::ModelDiscovery,
+) -> Vec<crate::proxy::model_discovery::ListedModel> {
+    use crate::proxy::model_discovery::ListedModel;
+    use std::collections::BTreeSet;
+    let mut model_ids = BTreeSet::new();
 
     // 1. 获取所有内置映射模型
     for m in get_supported_models() {
@@ -129,16 +72,17 @@ pub async fn get_all_dynamic_models(
     }
 
     // 2. 获取所有自定义映射模型 (Custom)
-    {
-        let mapping = custom_mapping.read().await;
-        for key in mapping.keys() {
-            model_ids.insert(key.clone());
-        }
+    let mapping = custom_mapping.read().await.clone();
+    for key in mapping.keys() {
+        model_ids.insert(key.clone());
     }
 
-    // 5. 确保包含常用的 Gemini/画画模型 ID
-    model_ids.insert("gemini-3-pro-low".to_string());
-    
+    // 3. [NEW] 账号配额模型表中发现的上游模型 (新模型无需发版即可出现)
+    for name in discovery.models.keys() {
+        model_ids.insert(name.clone());
+    }
+
+    // 5. 画画模型的分辨率/比例组合
     // [NEW] Issue #247: Dynamically generate all Image Gen Combinations
     let base = "gemini-3-pro-image";
     let resolutions = vec!["", "-2k", "-4k"];
@@ -153,17 +97,17 @@ pub async fn get_all_dynamic_models(
         }
     }
 
-    model_ids.insert("gemini-2.0-flash-exp".to_string());
-    model_ids.insert("gemini-2.5-flash".to_string());
-    model_ids.insert("gemini-2.5-pro".to_string());
-    model_ids.insert("gemini-3-flash".to_string());
-    model_ids.insert("gemini-3-pro-high".to_string());
-    model_ids.insert("gemini-3-pro-low".to_string());
-
-
-    let mut sorted_ids: Vec<_> = model_ids.into_iter().collect();
-    sorted_ids.sort();
-    sorted_ids
+    model_ids
+        .into_iter()
+        .filter_map(|id| {
+            let accounts = discovery.accounts_for(&id, &mapping);
+            // 用户自定义映射始终展示
+            if accounts == Some(0) && !mapping.contains_key(&id) {
+                return None;
+            }
+            Some(ListedModel { id, accounts })
+        })
+        .collect()
 }
 
 /// Wildcard matching - supports multiple wildcards
@@ -175,7 +119,7 @@ pub async fn get_all_dynamic_models(
 /// - `claude-*-sonnet-*` matches `claude-3-5-sonnet-20241022` ✓
 /// - `*-thinking` matches `claude-opus-4-5-thinking` ✓
 /// - `a*b*c` matches `a123b456c` ✓
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     // No wildcard - exact match
@@ -262,30 +206,19 @@ pub fn resolve_model_route(
     result
 }
 
-/// Normalize any physical model name to one of the 3 standard protection IDs.
+/// Normalize any physical model name to its standard protection ID (`quota_group` in the model registry).
 /// This ensures quota protection works consistently regardless of API versioning or request variations.
 /// 
-/// Standard IDs:
-/// - `gemini-3-flash`: All Flash variants (1.5-flash, 2.5-flash, 3-flash, etc.)
-/// - `gemini-3-pro-high`: All Pro variants (1.5-pro, 2.5-pro, etc.)
-/// - `claude-sonnet-4-5`: All Claude Sonnet variants (3-5-sonnet, sonnet-4-5, etc.)
+/// Built-in standard IDs:
+/// - `gemini-3-flash`: Gemini 3 Flash
+/// - `gemini-3-pro-high`: gemini-3-pro-high / gemini-3-pro-low / gemini-2.5-pro
+/// - `claude-sonnet-4-5`: claude-sonnet-4-5 / -thinking / claude-opus-4-5-thinking
+/// - `gemini-3-pro-image`: Gemini 3 Pro Image
 /// 
-/// Returns `None` if the model doesn't match any of the 3 protected categories.
+/// Returns `None` if the model doesn't belong to any quota group.
 pub fn normalize_to_standard_id(model_name: &str) -> Option<String> {
-    // [FIX] Strict matching based on user-defined groups (Case Insensitive)
-    let lower = model_name.to_lowercase();
-    match lower.as_str() {
-        // Gemini 3 Flash Group
-        "gemini-3-flash" => Some("gemini-3-flash".to_string()),
-
-        // Gemini 3 Pro High Group
-        "gemini-3-pro-high" | "gemini-3-pro-low" | "gemini-2.5-pro" => Some("gemini-3-pro-high".to_string()),
-
-        // Claude 4.5 Sonnet Group
-        "claude-sonnet-4-5" | "claude-sonnet-4-5-thinking" | "claude-opus-4-5-thinking" => Some("claude-sonnet-4-5".to_string()),
-
-        _ => None
-    }
+    // 分组由模型注册表的 quota_group 决定 (Case Insensitive)
+    model_registry::quota_group(model_name)
 }
 
 #[cfg(test)]
@@ -0,0 +1,509 @@
+// 模型能力注册表
+// 集中维护模型的上游提供方、上下文窗口、输出上限、thinking 支持与预算上限、
+// 多模态输入、配额分组与别名。内置默认值 + 配置中的用户覆盖 (proxy.model_overrides)，
+// 新上游模型只需改配置即可生效，无需发版。
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::proxy::config::ModelCapabilityOverride;
+
+/// 上游提供方 (决定由哪类账号池服务)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelProvider {
+    /// Antigravity 账号 - Gemini 系列
+    Gemini,
+    /// Antigravity 账号 - Claude 系列
+    Claude,
+    /// OpenAI Codex 账号 (Responses API)
+    Codex,
+}
+
+/// 单个模型的能力描述
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    pub id: String,
+    pub provider: ModelProvider,
+    /// 实际发往上游的模型名，None 表示与 id 相同
+    #[serde(default)]
+    pub upstream: Option<String>,
+    pub context_window: u32,
+    pub max_output_tokens: u32,
+    pub thinking: bool,
+    /// Auto 模式下的 thinkingBudget 上限，None 表示不限制
+    #[serde(default)]
+    pub thinking_budget_cap: Option<u32>,
+    pub image_input: bool,
+    pub audio_input: bool,
+    /// 配额保护/预热使用的标准分组 ID
+    #[serde(default)]
+    pub quota_group: Option<String>,
+    /// 映射到本模型的其他请求名称
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// 是否作为 PM Router 的候选模型
+    #[serde(default)]
+    pub router: bool,
+}
+
+impl ModelCapabilities {
+    /// 实际发往上游的模型名
+    pub fn upstream_model(&self) -> &str {
+        self.upstream.as_deref().unwrap_or(&self.id)
+    }
+
+    fn apply(&mut self, o: &ModelCapabilityOverride) {
+        if let Some(v) = o.provider {
+            self.provider = v;
+        }
+        if let Some(v) = &o.upstream {
+            self.upstream = (!v.is_empty()).then(|| v.clone());
+        }
+        if let Some(v) = o.context_window {
+            self.context_window = v;
+        }
+        if let Some(v) = o.max_output_tokens {
+            self.max_output_tokens = v;
+        }
+        if let Some(v) = o.thinking {
+            self.thinking = v;
+        }
+        if let Some(v) = o.thinking_budget_cap {
+            // 0 表示取消上限
+            self.thinking_budget_cap = (v > 0).then_some(v);
+        }
+        if let Some(v) = o.image_input {
+            self.image_input = v;
+        }
+        if let Some(v) = o.audio_input {
+            self.audio_input = v;
+        }
+        if let Some(v) = &o.quota_group {
+            self.quota_group = (!v.is_empty()).then(|| v.clone());
+        }
+        if let Some(v) = &o.aliases {
+            self.aliases = v.clone();
+        }
+        if let Some(v) = o.router {
+            self.router = v;
+        }
+    }
+
+    /// 内置表与覆盖项共用的模板: 上下文窗口按名称推断，默认支持图片输入并参与 PM Router
+    fn base(id: &str, provider: ModelProvider) -> Self {
+        Self {
+            id: id.to_string(),
+            provider,
+            upstream: None,
+            context_window: heuristic_context_window(id),
+            max_output_tokens: 65_536,
+            thinking: false,
+            thinking_budget_cap: None,
+            image_input: true,
+            audio_input: provider == ModelProvider::Gemini,
+            quota_group: None,
+            aliases: Vec::new(),
+            router: true,
+        }
+    }
+
+    /// 覆盖项中的新模型：未指定的字段按 id 推断合理默认值
+    fn from_override(o: &ModelCapabilityOverride) -> Self {
+        let lower = o.id.to_lowercase();
+        let provider = if lower.contains("codex") {
+            ModelProvider::Codex
+        } else if lower.starts_with("claude") {
+            ModelProvider::Claude
+        } else {
+            ModelProvider::Gemini
+        };
+        let mut spec = Self {
+            thinking: lower.contains("thinking"),
+            thinking_budget_cap: heuristic_thinking_budget_cap(&lower),
+            router: false,
+            ..Self::base(&o.id, provider)
+        };
+        spec.apply(o);
+        spec
+    }
+}
+
+/// Flash 系列与经由 Gemini 转发的 thinking 模型的预算上限 (Auto 模式)
+const FLASH_THINKING_BUDGET_CAP: u32 = 24576;
+
+fn names(list: &[&str]) -> Vec<String> {
+    list.iter().map(|s| s.to_string()).collect()
+}
+
+/// 内置模型表 (顺序即 PM Router 候选列表与默认监控列表的顺序)
+pub fn builtin_models() -> Vec<ModelCapabilities> {
+    use ModelProvider::*;
+    let base = ModelCapabilities::base;
+    let cap = Some(FLASH_THINKING_BUDGET_CAP);
+    let group = |g: &str| Some(g.to_string());
+    let codex = |id: &str| ModelCapabilities { max_output_tokens: 128_000, thinking: true, ..base(id, Codex) };
+    vec![
+        // Codex models
+        codex("gpt-5.2-codex"),
+        codex("gpt-5.1-codex-max"),
+        codex("gpt-5.1-codex-mini"),
+        // Claude (Antigravity)
+        ModelCapabilities {
+            max_output_tokens: 64_000,
+            quota_group: group("claude-sonnet-4-5"),
+            aliases: names(&[
+                "claude-3-5-sonnet-20241022",
+                "claude-3-5-sonnet-20240620",
+                "claude-haiku-4",
+                "claude-3-haiku-20240307",
+                "claude-haiku-4-5-20251001",
+            ]),
+            ..base("claude-sonnet-4-5", Claude)
+        },
+        ModelCapabilities {
+            max_output_tokens: 64_000,
+            thinking: true,
+            thinking_budget_cap: cap,
+            quota_group: group("claude-sonnet-4-5"),
+            aliases: names(&["claude-sonnet-4-5-20250929"]),
+            ..base("claude-sonnet-4-5-thinking", Claude)
+        },
+        ModelCapabilities {
+            max_output_tokens: 64_000,
+            thinking: true,
+            thinking_budget_cap: cap,
+            quota_group: group("claude-sonnet-4-5"),
+            aliases: names(&["claude-opus-4", "claude-opus-4-5-20251101"]),
+            ..base("claude-opus-4-5-thinking", Claude)
+        },
+        // Gemini
+        ModelCapabilities {
+            thinking: true,
+            quota_group: group("gemini-3-pro-high"),
+            ..base("gemini-2.5-pro", Gemini)
+        },
+        ModelCapabilities {
+            thinking: true,
+            thinking_budget_cap: cap,
+            aliases: names(&[
+                // OpenAI 协议映射表
+                "gpt-4",
+                "gpt-4-turbo",
+                "gpt-4-turbo-preview",
+                "gpt-4-0125-preview",
+                "gpt-4-1106-preview",
+                "gpt-4-0613",
+                "gpt-4o",
+                "gpt-4o-2024-05-13",
+                "gpt-4o-2024-08-06",
+                "gpt-4o-mini",
+                "gpt-4o-mini-2024-07-18",
+                "gpt-3.5-turbo",
+                "gpt-3.5-turbo-16k",
+                "gpt-3.5-turbo-0125",
+                "gpt-3.5-turbo-1106",
+                "gpt-3.5-turbo-0613",
+                // [New] Unified Virtual ID for Background Tasks (Title, Summary, etc.)
+                "internal-background-task",
+            ]),
+            ..base("gemini-2.5-flash", Gemini)
+        },
+        ModelCapabilities { thinking: true, thinking_budget_cap: cap, ..base("gemini-2.5-flash-thinking", Gemini) },
+        ModelCapabilities {
+            upstream: Some("gemini-2.5-flash".to_string()),
+            thinking: true,
+            thinking_budget_cap: cap,
+            ..base("gemini-2.5-flash-lite", Gemini)
+        },
+        ModelCapabilities {
+            thinking: true,
+            thinking_budget_cap: cap,
+            quota_group: group("gemini-3-flash"),
+            ..base("gemini-3-flash", Gemini)
+        },
+        ModelCapabilities {
+            upstream: Some("gemini-3-pro-preview".to_string()),
+            thinking: true,
+            quota_group: group("gemini-3-pro-high"),
+            ..base("gemini-3-pro-high", Gemini)
+        },
+        ModelCapabilities {
+            upstream: Some("gemini-3-pro-preview".to_string()),
+            thinking: true,
+            quota_group: group("gemini-3-pro-high"),
+            ..base("gemini-3-pro-low", Gemini)
+        },
+        ModelCapabilities {
+            thinking: true,
+            aliases: names(&["gemini-3-pro"]),
+            router: false,
+            ..base("gemini-3-pro-preview", Gemini)
+        },
+        // 图片模型自成配额分组: 默认监控/预热列表一直包含该分组，注册表化后账号级配额保护也会覆盖它
+        ModelCapabilities {
+            max_output_tokens: 32_768,
+            audio_input: false,
+            quota_group: group("gemini-3-pro-image"),
+            ..base("gemini-3-pro-image", Gemini)
+        },
+        ModelCapabilities {
+            max_output_tokens: 8_192,
+            thinking_budget_cap: cap,
+            router: false,
+            ..base("gemini-2.0-flash-exp", Gemini)
+        },
+    ]
+}
+
+/// 合并内置表与用户覆盖后的注册表
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    models: Vec<ModelCapabilities>,
+    /// 小写 id/别名 → models 下标
+    index: HashMap<String, usize>,
+}
+
+impl ModelRegistry {
+    pub fn new(overrides: &[ModelCapabilityOverride]) -> Self {
+        let mut models = builtin_models();
+        for o in overrides {
+            let id = o.id.trim();
+            if id.is_empty() {
+                continue;
+            }
+            match models.iter_mut().find(|m| m.id.eq_ignore_ascii_case(id)) {
+                Some(existing) => existing.apply(o),
+                None => models.push(ModelCapabilities::from_override(o)),
+            }
+        }
+
+        let mut index = HashMap::new();
+        // 先登记全部 id，别名不得遮蔽真实模型
+        for (i, m) in models.iter().enumerate() {
+            index.insert(m.id.to_lowercase(), i);
+        }
+        for (i, m) in models.iter().enumerate() {
+            for alias in &m.aliases {
+                index.entry(alias.to_lowercase()).or_insert(i);
+            }
+        }
+
+        Self { models, index }
+    }
+
+    /// 按 id 或别名查找 (大小写不敏感)
+    pub fn get(&self, model: &str) -> Option<&ModelCapabilities> {
+        self.index.get(&model.to_lowercase()).map(|&i| &self.models[i])
+    }
+
+    /// 仅按 id 精确查找 (大小写不敏感)
+    pub fn get_by_id(&self, model: &str) -> Option<&ModelCapabilities> {
+        self.models.iter().find(|m| m.id.eq_ignore_ascii_case(model))
+    }
+
+    pub fn models(&self) -> &[ModelCapabilities] {
+        &self.models
+    }
+
+    /// Auto 模式下的 thinkingBudget 上限 (仅按 id 匹配，别名不继承上限；未登记名称按名称推断)
+    pub fn thinking_budget_cap(&self, model: &str) -> Option<u32> {
+        match self.get_by_id(model) {
+            Some(m) => m.thinking_budget_cap,
+            None => heuristic_thinking_budget_cap(&model.to_lowercase()),
+        }
+    }
+}
+
+static REGISTRY: Lazy<RwLock<Arc<ModelRegistry>>> =
+    Lazy::new(|| RwLock::new(Arc::new(ModelRegistry::new(&[]))));
+
+/// 当前生效的注册表快照
+pub fn registry() -> Arc<ModelRegistry> {
+    REGISTRY
+        .read()
+        .map(|r| r.clone())
+        .unwrap_or_else(|_| Arc::new(ModelRegistry::new(&[])))
+}
+
+/// 以新的用户覆盖重建注册表 (启动与配置热更新时调用)
+pub fn update_model_registry(overrides: &[ModelCapabilityOverride]) {
+    let rebuilt = Arc::new(ModelRegistry::new(overrides));
+    if let Ok(mut r) = REGISTRY.write() {
+        *r = rebuilt;
+        tracing::info!(
+            "[Model-Registry] Updated: {} models ({} overrides)",
+            r.models().len(),
+            overrides.len()
+        );
+    }
+}
+
+/// 请求名 (id 或别名) → 上游模型名
+pub fn upstream_model(model: &str) -> Option<String> {
+    registry().get(model).map(|m| m.upstream_model().to_string())
+}
+
+/// 所有可请求的名称 (id + 别名)
+pub fn known_model_names() -> Vec<String> {
+    registry()
+        .models()
+        .iter()
+        .flat_map(|m| std::iter::once(m.id.clone()).chain(m.aliases.iter().cloned()))
+        .collect()
+}
+
+pub fn is_codex_model(model: &str) -> bool {
+    registry()
+        .get(model)
+        .map(|m| m.provider == ModelProvider::Codex)
+        .unwrap_or(false)
+}
+
+/// Codex 模型 id 列表 (第一项为默认模型)
+pub fn codex_models() -> Vec<String> {
+    ids_where(|m| m.provider == ModelProvider::Codex)
+}
+
+/// PM Router 候选模型
+pub fn router_models() -> Vec<String> {
+    ids_where(|m| m.router)
+}
+
+fn ids_where(pred: impl Fn(&ModelCapabilities) -> bool) -> Vec<String> {
+    registry()
+        .models()
+        .iter()
+        .filter(|m| pred(m))
+        .map(|m| m.id.clone())
+        .collect()
+}
+
+/// 配额分组 (仅按 id 匹配，别名不参与配额归并)
+pub fn quota_group(model: &str) -> Option<String> {
+    registry().get_by_id(model).and_then(|m| m.quota_group.clone())
+}
+
+/// 默认监控/预热的配额分组 (按注册表顺序去重)
+pub fn default_quota_groups() -> Vec<String> {
+    let mut groups: Vec<String> = Vec::new();
+    for group in registry().models().iter().filter_map(|m| m.quota_group.as_ref()) {
+        if !groups.contains(group) {
+            groups.push(group.clone());
+        }
+    }
+    groups
+}
+
+/// 未登记模型按名称推断：Flash / Gemini 1.5 / 经由 Gemini 转发的 -thinking 模型
+fn heuristic_thinking_budget_cap(lower: &str) -> Option<u32> {
+    (lower.contains("flash") || lower.contains("gemini-1.5") || lower.ends_with("-thinking"))
+        .then_some(FLASH_THINKING_BUDGET_CAP)
+}
+
+/// Auto 模式下该模型的 thinkingBudget 上限
+pub fn thinking_budget_cap(model: &str) -> Option<u32> {
+    registry().thinking_budget_cap(model)
+}
+
+/// 按名称推断上下文窗口: Pro 系列 2M，其余 1M
+fn heuristic_context_window(model: &str) -> u32 {
+    if model.contains("pro") {
+        2_097_152 // 2M for Pro
+    } else {
+        1_048_576 // 1M for Flash / Default
+    }
+}
+
+/// 上下文窗口，未登记模型按名称推断
+pub fn context_window(model: &str) -> u32 {
+    registry()
+        .get(model)
+        .map(|m| m.context_window)
+        .unwrap_or_else(|| heuristic_context_window(model))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn override_of(id: &str) -> ModelCapabilityOverride {
+        ModelCapabilityOverride {
+            id: id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_builtin_lookup_by_alias() {
+        let r = ModelRegistry::new(&[]);
+        assert_eq!(r.get("claude-opus-4").unwrap().id, "claude-opus-4-5-thinking");
+        assert_eq!(r.get("GPT-4O").unwrap().id, "gemini-2.5-flash");
+        assert_eq!(r.get("gemini-3-pro-low").unwrap().upstream_model(), "gemini-3-pro-preview");
+        assert!(r.get_by_id("gpt-4").is_none());
+        assert!(r.get("unknown-model").is_none());
+    }
+
+    #[test]
+    fn test_overrides_patch_and_extend() {
+        let mut patch = override_of("gemini-3-flash");
+        patch.thinking_budget_cap = Some(0);
+        patch.aliases = Some(vec!["flash-latest".to_string()]);
+        let mut new_model = override_of("gpt-5.3-codex");
+        new_model.router = Some(true);
+        // 别名不能遮蔽已有 id
+        new_model.aliases = Some(vec!["gemini-2.5-pro".to_string()]);
+
+        let r = ModelRegistry::new(&[patch, new_model]);
+        let flash = r.get("flash-latest").unwrap();
+        assert_eq!(flash.id, "gemini-3-flash");
+        assert_eq!(flash.thinking_budget_cap, None);
+        assert_eq!(flash.quota_group.as_deref(), Some("gemini-3-flash"));
+
+        let codex = r.get("gpt-5.3-codex").unwrap();
+        assert_eq!(codex.provider, ModelProvider::Codex);
+        assert!(codex.router);
+        assert_eq!(r.get("gemini-2.5-pro").unwrap().id, "gemini-2.5-pro");
+    }
+
+    #[test]
+    fn test_builtin_context_windows_match_name_heuristic() {
+        let r = ModelRegistry::new(&[]);
+        assert_eq!(r.get("claude-sonnet-4-5").unwrap().context_window, 1_048_576);
+        assert_eq!(r.get("claude-opus-4").unwrap().context_window, 1_048_576);
+        assert_eq!(r.get("gemini-3-pro-high").unwrap().context_window, 2_097_152);
+        assert_eq!(r.get("gemini-2.5-pro").unwrap().context_window, 2_097_152);
+        assert_eq!(r.get("gemini-3-flash").unwrap().context_window, 1_048_576);
+        for m in r.models() {
+            assert_eq!(m.context_window, heuristic_context_window(&m.id), "{}", m.id);
+        }
+    }
+
+    #[test]
+    fn test_heuristic_thinking_cap() {
+        assert_eq!(heuristic_thinking_budget_cap("gemini-2.0-flash-thinking-exp"), Some(24576));
+        assert_eq!(heuristic_thinking_budget_cap("gemini-2.0-pro-exp"), None);
+    }
+
+    #[test]
+    fn test_thinking_cap_ignores_aliases() {
+        let r = ModelRegistry::new(&[]);
+        // 与原先按名称判断一致: 映射后的 id 受限，别名本身不受限
+        assert_eq!(r.thinking_budget_cap("claude-opus-4-5-thinking"), Some(24576));
+        assert_eq!(r.thinking_budget_cap("claude-opus-4"), None);
+        assert_eq!(r.thinking_budget_cap("claude-sonnet-4-5-20250929"), None);
+        assert_eq!(r.thinking_budget_cap("gemini-2.5-flash"), Some(24576));
+        assert_eq!(r.thinking_budget_cap("gemini-2.5-pro"), None);
+        assert_eq!(r.thinking_budget_cap("gemini-2.0-flash-thinking-exp"), Some(24576));
+    }
+
+    #[test]
+    fn test_image_model_has_own_quota_group() {
+        let r = ModelRegistry::new(&[]);
+        let image = r.get_by_id("gemini-3-pro-image").unwrap();
+        assert_eq!(image.quota_group.as_deref(), Some("gemini-3-pro-image"));
+    }
+}
@@ -0,0 +1,177 @@
+// Thinking Budget 策略解析
+//
+// 优先级: 请求头 x-antigravity-thinking-budget > 调用方 API Key 的 thinking_policies
+//         > 全局 thinking_budget.policies > 全局 mode (Auto/Passthrough/Custom)
+// 规则按顺序匹配，模型名支持 `*` 通配符且不区分大小写。
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::config::{ThinkingBudgetConfig, ThinkingBudgetMode, ThinkingPolicy, ThinkingPolicyRule};
+
+/// 策略计算结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThinkingDecision {
+    Budget(u32),
+    Disabled,
+}
+
+impl ThinkingDecision {
+    /// 记录到请求日志的值 (Disabled 记为 0)
+    pub fn as_recorded(&self) -> u32 {
+        match self {
+            Self::Budget(b) => *b,
+            Self::Disabled => 0,
+        }
+    }
+}
+
+fn find_rule<'a>(rules: &'a [ThinkingPolicyRule], model: &str) -> Option<&'a ThinkingPolicyRule> {
+    let model = model.to_lowercase();
+    rules
+        .iter()
+        .find(|r| wildcard_match(&r.model.to_lowercase(), &model))
+}
+
+/// 查找对当前请求生效的显式策略 (请求头覆盖 > API Key 覆盖 > 全局规则)
+pub fn find_policy(model: &str, config: &ThinkingBudgetConfig) -> Option<ThinkingPolicy> {
+    match crate::proxy::request_context::current_overrides().thinking_budget {
+        Some(0) => return Some(ThinkingPolicy::Disable),
+        Some(value) => return Some(ThinkingPolicy::Fixed { value }),
+        None => {}
+    }
+    if let Some(key) = crate::proxy::request_context::current_api_key() {
+        if let Some(rule) = find_rule(&key.thinking_policies, model) {
+            return Some(rule.policy.clone());
+        }
+    }
+    find_rule(&config.policies, model).map(|r| r.policy.clone())
+}
+
+/// 应用单条策略
+///
+/// # Arguments
+/// * `requested` - 调用方请求的 budget
+/// * `prompt_tokens` - prompt 估算 token 数 (仅 Scale 策略调用)
+pub fn apply_policy(policy: &ThinkingPolicy, requested: u32, prompt_tokens: impl FnOnce() -> u32) -> ThinkingDecision {
+    let clamp = |value: u32, min: Option<u32>, max: Option<u32>| {
+        let value = min.map_or(value, |m| value.max(m));
+        max.map_or(value, |m| value.min(m))
+    };
+    match policy {
+        ThinkingPolicy::Passthrough => ThinkingDecision::Budget(requested),
+        ThinkingPolicy::Clamp { min, max } => ThinkingDecision::Budget(clamp(requested, *min, *max)),
+        ThinkingPolicy::Fixed { value } => ThinkingDecision::Budget(*value),
+        ThinkingPolicy::Disable => ThinkingDecision::Disabled,
+        ThinkingPolicy::Scale { ratio, min, max } => {
+            let scaled = (prompt_tokens() as f64 * ratio.max(0.0) as f64) as u32;
+            ThinkingDecision::Budget(clamp(scaled, *min, *max))
+        }
+    }
+}
+
+/// 仅按显式策略计算 (未命中任何规则时返回 None，由调用方沿用原有逻辑)
+pub fn policy_decision(model: &str, requested: u32, prompt_tokens: impl FnOnce() -> u32) -> Option<ThinkingDecision> {
+    let config = crate::proxy::config::get_thinking_budget_config();
+    let policy = find_policy(model, &config)?;
+    let decision = apply_policy(&policy, requested, prompt_tokens);
+    tracing::debug!(
+        "[Thinking-Budget] Policy {:?} for model {}: requested {} -> {:?}",
+        policy,
+        model,
+        requested,
+        decision
+    );
+    Some(decision)
+}
+
+/// 计算最终 thinking budget: 显式策略优先，否则按全局 mode
+pub fn resolve_thinking_budget(model: &str, requested: u32, prompt_tokens: impl FnOnce() -> u32) -> ThinkingDecision {
+    if let Some(decision) = policy_decision(model, requested, prompt_tokens) {
+        return decision;
+    }
+
+    let config = crate::proxy::config::get_thinking_budget_config();
+    let budget = match config.mode {
+        ThinkingBudgetMode::Passthrough => requested,
+        ThinkingBudgetMode::Custom => config.custom_value,
+        ThinkingBudgetMode::Auto => match crate::proxy::common::model_registry::thinking_budget_cap(model) {
+            Some(cap) if requested > cap => {
+                tracing::debug!(
+                    "[Thinking-Budget] Auto mode: capping {} to {} for model {}",
+                    requested,
+                    cap,
+                    model
+                );
+                cap
+            }
+            _ => requested,
+        },
+    };
+    ThinkingDecision::Budget(budget)
+}
+
+/// 当前请求对该模型是否禁用 thinking (用于在构建历史消息前提前降级)
+pub fn is_disabled(model: &str) -> bool {
+    let config = crate::proxy::config::get_thinking_budget_config();
+    matches!(find_policy(model, &config), Some(ThinkingPolicy::Disable))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(model: &str, policy: ThinkingPolicy) -> ThinkingPolicyRule {
+        ThinkingPolicyRule { model: model.to_string(), policy }
+    }
+
+    #[test]
+    fn test_apply_policies() {
+        let no_prompt = || unreachable!();
+        assert_eq!(apply_policy(&ThinkingPolicy::Passthrough, 50_000, no_prompt), ThinkingDecision::Budget(50_000));
+        assert_eq!(
+            apply_policy(&ThinkingPolicy::Clamp { min: Some(2048), max: Some(24576) }, 50_000, no_prompt),
+            ThinkingDecision::Budget(24576)
+        );
+        assert_eq!(
+            apply_policy(&ThinkingPolicy::Clamp { min: Some(2048), max: None }, 100, no_prompt),
+            ThinkingDecision::Budget(2048)
+        );
+        assert_eq!(apply_policy(&ThinkingPolicy::Fixed { value: 8192 }, 1, no_prompt), ThinkingDecision::Budget(8192));
+        assert_eq!(apply_policy(&ThinkingPolicy::Disable, 1, no_prompt), ThinkingDecision::Disabled);
+        assert_eq!(
+            apply_policy(&ThinkingPolicy::Scale { ratio: 0.5, min: Some(1024), max: Some(16000) }, 0, || 10_000),
+            ThinkingDecision::Budget(5000)
+        );
+        assert_eq!(
+            apply_policy(&ThinkingPolicy::Scale { ratio: 0.5, min: Some(1024), max: Some(16000) }, 0, || 100),
+            ThinkingDecision::Budget(1024)
+        );
+    }
+
+    #[test]
+    fn test_find_policy_wildcard_order() {
+        let config = ThinkingBudgetConfig {
+            policies: vec![
+                rule("claude-opus-*", ThinkingPolicy::Passthrough),
+                rule("*flash*", ThinkingPolicy::Clamp { min: None, max: Some(8192) }),
+                rule("*", ThinkingPolicy::Fixed { value: 4096 }),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(find_policy("claude-opus-4-5-thinking", &config), Some(ThinkingPolicy::Passthrough));
+        assert_eq!(
+            find_policy("Gemini-2.5-Flash", &config),
+            Some(ThinkingPolicy::Clamp { min: None, max: Some(8192) })
+        );
+        assert_eq!(find_policy("gemini-3-pro-high", &config), Some(ThinkingPolicy::Fixed { value: 4096 }));
+        assert_eq!(find_policy("gemini-3-pro-high", &ThinkingBudgetConfig::default()), None);
+    }
+
+    #[test]
+    fn test_policy_rule_serde() {
+        let rule: ThinkingPolicyRule =
+            serde_json::from_str(r#"{"model": "*flash*", "action": "clamp", "max": 8192}"#).unwrap();
+        assert_eq!(rule.policy, ThinkingPolicy::Clamp { min: None, max: Some(8192) });
+        let rule: ThinkingPolicyRule = serde_json::from_str(r#"{"model": "gpt-*", "action": "disable"}"#).unwrap();
+        assert_eq!(rule.policy, ThinkingPolicy::Disable);
+    }
+}
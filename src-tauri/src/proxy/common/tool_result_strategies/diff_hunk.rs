@@ -0,0 +1,146 @@
+use super::super::tool_result_strategy::{clip_line, ToolResultStrategy};
+
+/// unified diff hunk 裁剪策略
+///
+/// 保留所有文件头与 hunk 头，逐级收紧:
+/// 1. 缩减变更行周围的上下文行数
+/// 2. 限制每个 hunk 保留的变更行数，并注明省略数量
+pub struct DiffHunkStrategy;
+
+/// (上下文行数, 每个 hunk 保留的变更行数)，由宽到严
+const TRIM_LEVELS: &[(usize, usize)] = &[
+    (3, usize::MAX),
+    (1, usize::MAX),
+    (0, 200),
+    (0, 50),
+    (0, 10),
+    (0, 0),
+];
+
+/// 单行最大长度
+const MAX_LINE_CHARS: usize = 300;
+
+impl ToolResultStrategy for DiffHunkStrategy {
+    fn name(&self) -> &'static str {
+        "diff"
+    }
+
+    fn matches(&self, tool_name: &str) -> bool {
+        tool_name.contains("diff") || tool_name.contains("patch")
+    }
+
+    fn compact(&self, text: &str, budget: usize) -> Option<String> {
+        let sections = parse_diff(text)?;
+        for &(context, max_changes) in TRIM_LEVELS {
+            let mut out = vec![format!(
+                "[diff trimmed to reduce prompt size; original {} chars, context={} lines]",
+                text.len(),
+                context
+            )];
+            for section in &sections {
+                match section {
+                    Section::Header(line) => out.push(clip_line(line, MAX_LINE_CHARS)),
+                    Section::Hunk { header, lines } => {
+                        out.push(header.to_string());
+                        render_hunk(&mut out, lines, context, max_changes);
+                    }
+                }
+            }
+            let result = out.join("\n");
+            if result.len() <= budget {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+enum Section<'a> {
+    /// diff --git / index / --- / +++ 以及 hunk 之外的其他行
+    Header(&'a str),
+    Hunk { header: &'a str, lines: Vec<&'a str> },
+}
+
+fn parse_diff(text: &str) -> Option<Vec<Section<'_>>> {
+    let is_diff = text.contains("\n@@ ") || text.starts_with("@@ ");
+    if !is_diff || !(text.contains("diff --git") || text.contains("\n+++ ") || text.starts_with("--- ")) {
+        return None;
+    }
+
+    let mut sections = Vec::new();
+    let mut current: Option<(&str, Vec<&str>)> = None;
+    for line in text.lines() {
+        if line.starts_with("@@") {
+            if let Some((header, lines)) = current.take() {
+                sections.push(Section::Hunk { header, lines });
+            }
+            current = Some((line, Vec::new()));
+            continue;
+        }
+        let in_hunk = matches!(line.chars().next(), Some(' ' | '+' | '-' | '\\') | None)
+            && !line.starts_with("--- ")
+            && !line.starts_with("+++ ");
+        match current.as_mut() {
+            Some((_, lines)) if in_hunk => lines.push(line),
+            _ => {
+                if let Some((header, lines)) = current.take() {
+                    sections.push(Section::Hunk { header, lines });
+                }
+                sections.push(Section::Header(line));
+            }
+        }
+    }
+    if let Some((header, lines)) = current {
+        sections.push(Section::Hunk { header, lines });
+    }
+    Some(sections)
+}
+
+fn is_change(line: &str) -> bool {
+    line.starts_with('+') || line.starts_with('-')
+}
+
+/// 每行到最近变更行的距离 (两次扫描)
+fn distance_to_change(lines: &[&str]) -> Vec<usize> {
+    let mut dist = vec![usize::MAX; lines.len()];
+    let mut last: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if is_change(line) {
+            last = Some(i);
+        }
+        if let Some(c) = last {
+            dist[i] = i - c;
+        }
+    }
+    last = None;
+    for (i, line) in lines.iter().enumerate().rev() {
+        if is_change(line) {
+            last = Some(i);
+        }
+        if let Some(c) = last {
+            dist[i] = dist[i].min(c - i);
+        }
+    }
+    dist
+}
+
+fn render_hunk(out: &mut Vec<String>, lines: &[&str], context: usize, max_changes: usize) {
+    let dist = distance_to_change(lines);
+    let mut kept_changes = 0usize;
+    let mut omitted_changes = 0usize;
+    for (i, line) in lines.iter().enumerate() {
+        if is_change(line) {
+            if kept_changes < max_changes {
+                kept_changes += 1;
+                out.push(clip_line(line, MAX_LINE_CHARS));
+            } else {
+                omitted_changes += 1;
+            }
+        } else if dist[i] <= context && kept_changes < max_changes {
+            out.push(clip_line(line, MAX_LINE_CHARS));
+        }
+    }
+    if omitted_changes > 0 {
+        out.push(format!("… {} more changed lines in this hunk", omitted_changes));
+    }
+}
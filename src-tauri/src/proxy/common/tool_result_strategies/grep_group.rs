@@ -0,0 +1,116 @@
+use super::super::tool_result_strategy::{clip_line, ToolResultStrategy};
+
+/// grep / ripgrep 结果分组策略
+///
+/// 将 `path:line:content` / `path:content` / 纯路径列表按文件分组，
+/// 逐级减少每个文件保留的匹配行数，直到落入预算 (每个文件始终保留文件名与匹配数)。
+pub struct GrepGroupStrategy;
+
+/// 每个文件保留的匹配行数，由宽到严
+const LINES_PER_FILE_LEVELS: &[usize] = &[20, 10, 5, 3, 1, 0];
+
+/// 单条匹配内容最大长度
+const MAX_MATCH_CHARS: usize = 200;
+
+/// 可解析为 grep 格式的行占比下限
+const MIN_PARSED_RATIO: f64 = 0.6;
+
+impl ToolResultStrategy for GrepGroupStrategy {
+    fn name(&self) -> &'static str {
+        "grep"
+    }
+
+    fn matches(&self, tool_name: &str) -> bool {
+        matches!(tool_name, "grep" | "rg" | "ripgrep" | "glob" | "search_files" | "find_files")
+            || tool_name.contains("grep")
+            || tool_name.contains("search_code")
+    }
+
+    fn compact(&self, text: &str, budget: usize) -> Option<String> {
+        let groups = group_by_file(text)?;
+        let total: usize = groups.iter().map(|g| g.matches.len().max(1)).sum();
+
+        for &per_file in LINES_PER_FILE_LEVELS {
+            let mut out = vec![format!(
+                "[search results grouped by file to reduce prompt size; {} files, {} matches]",
+                groups.len(),
+                total
+            )];
+            for group in &groups {
+                if group.matches.is_empty() {
+                    out.push(group.path.to_string());
+                    continue;
+                }
+                out.push(format!("{} ({} matches)", group.path, group.matches.len()));
+                for (line_no, content) in group.matches.iter().take(per_file) {
+                    let content = clip_line(content.trim(), MAX_MATCH_CHARS);
+                    match line_no {
+                        Some(n) => out.push(format!("  {}: {}", n, content)),
+                        None => out.push(format!("  {}", content)),
+                    }
+                }
+                if group.matches.len() > per_file && per_file > 0 {
+                    out.push(format!("  … {} more", group.matches.len() - per_file));
+                }
+            }
+            let result = out.join("\n");
+            if result.len() <= budget {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+struct FileGroup<'a> {
+    path: &'a str,
+    matches: Vec<(Option<&'a str>, &'a str)>,
+}
+
+fn looks_like_path(s: &str) -> bool {
+    !s.is_empty() && !s.contains(char::is_whitespace) && (s.contains('/') || s.contains('\\') || s.contains('.'))
+}
+
+/// 解析一行: path:line:content | path-line-content (上下文行) | path:content | path
+fn parse_line(line: &str) -> Option<(&str, Option<&str>, &str)> {
+    if let Some((path, rest)) = line.split_once(':') {
+        // Windows 盘符 (C:\...) 不拆分
+        if path.len() > 1 && looks_like_path(path) {
+            if let Some((num, content)) = rest.split_once(':') {
+                if !num.is_empty() && num.chars().all(|c| c.is_ascii_digit()) {
+                    return Some((path, Some(num), content));
+                }
+            }
+            return Some((path, None, rest));
+        }
+    }
+    looks_like_path(line.trim()).then(|| (line.trim(), None, ""))
+}
+
+fn group_by_file(text: &str) -> Option<Vec<FileGroup<'_>>> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty() && *l != "--").collect();
+    if lines.len() < 2 {
+        return None;
+    }
+
+    let mut groups: Vec<FileGroup> = Vec::new();
+    let mut parsed = 0usize;
+    for line in &lines {
+        let Some((path, line_no, content)) = parse_line(line) else {
+            continue;
+        };
+        parsed += 1;
+        let idx = match groups.iter().position(|g| g.path == path) {
+            Some(i) => i,
+            None => {
+                groups.push(FileGroup { path, matches: Vec::new() });
+                groups.len() - 1
+            }
+        };
+        if !content.is_empty() {
+            groups[idx].matches.push((line_no, content));
+        }
+    }
+
+    ((parsed as f64 / lines.len() as f64) >= MIN_PARSED_RATIO && !groups.is_empty()).then_some(groups)
+}
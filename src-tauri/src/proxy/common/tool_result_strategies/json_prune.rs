@@ -0,0 +1,100 @@
+use serde_json::{json, Map, Value};
+use super::super::tool_result_strategy::ToolResultStrategy;
+
+/// JSON 感知裁剪策略
+///
+/// 解析工具输出为 JSON 后逐级收紧裁剪参数:
+/// 1. 超长数组只保留前 N 项，并注明省略数量
+/// 2. 超过深度上限的对象保留全部键名，值替换为类型摘要
+/// 3. 超长字符串截断
+pub struct JsonPruneStrategy;
+
+/// (最大深度, 数组保留项数, 字符串最大长度)，由宽到严
+const PRUNE_LEVELS: &[(usize, usize, usize)] = &[
+    (8, 50, 2000),
+    (6, 20, 500),
+    (4, 10, 200),
+    (3, 5, 100),
+    (2, 3, 60),
+    (1, 2, 40),
+];
+
+impl ToolResultStrategy for JsonPruneStrategy {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn matches(&self, tool_name: &str) -> bool {
+        // MCP 工具与 Web 请求类工具多返回 JSON
+        tool_name.starts_with("mcp__") || tool_name.contains("fetch")
+    }
+
+    fn compact(&self, text: &str, budget: usize) -> Option<String> {
+        let trimmed = text.trim();
+        if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+            return None;
+        }
+        let value: Value = serde_json::from_str(trimmed).ok()?;
+
+        for &(max_depth, max_items, max_str) in PRUNE_LEVELS {
+            let pruned = prune(&value, 0, max_depth, max_items, max_str);
+            let body = serde_json::to_string(&pruned).ok()?;
+            let out = format!(
+                "[json pruned to reduce prompt size; original {} chars, depth<={}, arrays<={} items]\n{}",
+                text.len(),
+                max_depth,
+                max_items,
+                body
+            );
+            if out.len() <= budget {
+                return Some(out);
+            }
+        }
+        None
+    }
+}
+
+fn summarize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => json!(format!("{{…{} keys}}", map.len())),
+        Value::Array(arr) => json!(format!("[…{} items]", arr.len())),
+        other => other.clone(),
+    }
+}
+
+fn prune(value: &Value, depth: usize, max_depth: usize, max_items: usize, max_str: usize) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut out = Map::with_capacity(map.len());
+            for (k, v) in map {
+                // 达到深度上限: 保留键名，值替换为摘要
+                let pruned = if depth >= max_depth {
+                    summarize(v)
+                } else {
+                    prune(v, depth + 1, max_depth, max_items, max_str)
+                };
+                out.insert(k.clone(), pruned);
+            }
+            Value::Object(out)
+        }
+        Value::Array(arr) => {
+            if depth >= max_depth {
+                return summarize(value);
+            }
+            let mut out: Vec<Value> = arr
+                .iter()
+                .take(max_items)
+                .map(|v| prune(v, depth + 1, max_depth, max_items, max_str))
+                .collect();
+            if arr.len() > max_items {
+                out.push(json!(format!("…{} more items", arr.len() - max_items)));
+            }
+            Value::Array(out)
+        }
+        Value::String(s) if s.len() > max_str => {
+            let clipped = super::super::tool_result_strategy::clip_str(s, max_str);
+            json!(format!("{}…[{} chars]", clipped, s.len()))
+        }
+        other => other.clone(),
+    }
+}
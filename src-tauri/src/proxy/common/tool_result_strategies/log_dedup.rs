@@ -0,0 +1,133 @@
+use super::super::tool_result_strategy::{clip_line, ToolResultStrategy};
+
+/// 日志感知去重策略
+///
+/// 1. 折叠连续重复行 (忽略数字差异，如时间戳/计数器/进度)
+/// 2. 仍超出预算时保留头部 + 尾部，并优先保留中间的错误/警告行
+pub struct LogDedupStrategy;
+
+/// 单行最大长度
+const MAX_LINE_CHARS: usize = 500;
+
+/// 头部预算占比 (日志通常尾部更重要)
+const HEAD_RATIO: f64 = 0.25;
+
+/// 中间错误行预算占比
+const SIGNAL_RATIO: f64 = 0.15;
+
+const SIGNAL_KEYWORDS: &[&str] = &["error", "warn", "fail", "panic", "exception", "fatal", "traceback"];
+
+impl ToolResultStrategy for LogDedupStrategy {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn matches(&self, tool_name: &str) -> bool {
+        matches!(tool_name, "bash" | "bashoutput" | "run_command" | "shell" | "terminal")
+            || tool_name.contains("shell")
+            || tool_name.contains("exec")
+            || tool_name.contains("log")
+    }
+
+    fn compact(&self, text: &str, budget: usize) -> Option<String> {
+        let lines = dedup_lines(text);
+        let deduped = lines.join("\n");
+        if deduped.len() <= budget {
+            return Some(deduped);
+        }
+        head_tail_with_signals(&lines, budget, text.len())
+    }
+}
+
+/// 将数字统一替换为 '#'，用于判断两行是否"同构"
+fn normalize(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_digits = false;
+    for c in line.trim().chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                out.push('#');
+            }
+            in_digits = true;
+        } else {
+            out.push(c);
+            in_digits = false;
+        }
+    }
+    out
+}
+
+fn dedup_lines(text: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    let mut last_key: Option<String> = None;
+    let mut repeats = 0usize;
+
+    let flush = |out: &mut Vec<String>, repeats: usize| {
+        if repeats > 0 {
+            out.push(format!("  [… previous line repeated {} more times]", repeats));
+        }
+    };
+
+    for line in text.lines() {
+        let key = normalize(line);
+        if last_key.as_deref() == Some(key.as_str()) && !key.is_empty() {
+            repeats += 1;
+            continue;
+        }
+        flush(&mut out, repeats);
+        repeats = 0;
+        out.push(clip_line(line, MAX_LINE_CHARS));
+        last_key = Some(key);
+    }
+    flush(&mut out, repeats);
+    out
+}
+
+fn is_signal(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    SIGNAL_KEYWORDS.iter().any(|k| lower.contains(k))
+}
+
+/// 从 lines 中按顺序取行，总长度不超过 budget
+fn take_lines<'a>(lines: impl Iterator<Item = &'a String>, budget: usize) -> (Vec<&'a String>, usize) {
+    let mut taken = Vec::new();
+    let mut used = 0;
+    for line in lines {
+        if used + line.len() + 1 > budget {
+            break;
+        }
+        used += line.len() + 1;
+        taken.push(line);
+    }
+    (taken, used)
+}
+
+fn head_tail_with_signals(lines: &[String], budget: usize, original_len: usize) -> Option<String> {
+    let meta = format!("[log deduplicated and trimmed to reduce prompt size; original {} chars]", original_len);
+    let budget = budget.checked_sub(meta.len() + 200)?;
+    if budget < 200 {
+        return None;
+    }
+
+    let (head, _) = take_lines(lines.iter(), (budget as f64 * HEAD_RATIO) as usize);
+    let (mut tail, _) = take_lines(
+        lines[head.len()..].iter().rev(),
+        (budget as f64 * (1.0 - HEAD_RATIO - SIGNAL_RATIO)) as usize,
+    );
+    tail.reverse();
+
+    let middle_end = lines.len() - tail.len();
+    let middle = &lines[head.len()..middle_end];
+    let (signals, _) = take_lines(middle.iter().filter(|l| is_signal(l)), (budget as f64 * SIGNAL_RATIO) as usize);
+    let omitted = middle.len() - signals.len();
+
+    let mut out = vec![meta];
+    out.extend(head.into_iter().cloned());
+    if !signals.is_empty() {
+        out.push(format!("---[{} error/warning lines from omitted section]---", signals.len()));
+        out.extend(signals.into_iter().cloned());
+    }
+    out.push(format!("---[...omitted {} lines]---", omitted));
+    out.extend(tail.into_iter().cloned());
+    Some(out.join("\n"))
+}
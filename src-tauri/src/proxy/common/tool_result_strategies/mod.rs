@@ -0,0 +1,182 @@
+pub mod diff_hunk;
+pub mod grep_group;
+pub mod json_prune;
+pub mod log_dedup;
+
+pub use diff_hunk::DiffHunkStrategy;
+pub use grep_group::GrepGroupStrategy;
+pub use json_prune::JsonPruneStrategy;
+pub use log_dedup::LogDedupStrategy;
+
+use once_cell::sync::Lazy;
+
+use super::tool_result_strategy::ToolResultStrategy;
+use crate::proxy::config::ToolResultCompressionConfig;
+
+/// 全局工具结果压缩策略注册表
+///
+/// 未配置规则时按注册顺序匹配 `matches()`，第一个命中的策略生效
+static TOOL_RESULT_STRATEGIES: Lazy<Vec<Box<dyn ToolResultStrategy>>> = Lazy::new(|| {
+    vec![
+        Box::new(DiffHunkStrategy),
+        Box::new(GrepGroupStrategy),
+        Box::new(JsonPruneStrategy),
+        Box::new(LogDedupStrategy),
+    ]
+});
+
+/// 按名称查找策略
+pub fn strategy_by_name(name: &str) -> Option<&'static dyn ToolResultStrategy> {
+    TOOL_RESULT_STRATEGIES
+        .iter()
+        .find(|s| s.name().eq_ignore_ascii_case(name))
+        .map(|s| s.as_ref())
+}
+
+/// 为工具解析压缩策略与字符预算
+///
+/// 优先级: 配置规则 (按顺序，支持通配符) > 内置策略默认匹配 > 仅通用压缩
+///
+/// # Returns
+/// (策略, 字符预算)；策略为 None 表示只使用通用压缩级联
+pub fn resolve(
+    tool_name: &str,
+    config: &ToolResultCompressionConfig,
+) -> (Option<&'static dyn ToolResultStrategy>, usize) {
+    let default_budget = config.default_max_chars;
+    if !config.enabled {
+        return (None, default_budget);
+    }
+
+    let lower = tool_name.to_lowercase();
+    for rule in &config.rules {
+        if !crate::proxy::common::model_mapping::wildcard_match(&rule.tool.to_lowercase(), &lower) {
+            continue;
+        }
+        let budget = rule.max_chars.unwrap_or(default_budget);
+        let strategy = strategy_by_name(&rule.strategy);
+        if strategy.is_none() && !rule.strategy.eq_ignore_ascii_case("default") {
+            tracing::warn!(
+                "[Tool-Compression] Unknown strategy '{}' for tool '{}', using default compaction",
+                rule.strategy,
+                tool_name
+            );
+        }
+        return (strategy, budget);
+    }
+
+    let strategy = TOOL_RESULT_STRATEGIES
+        .iter()
+        .find(|s| s.matches(&lower))
+        .map(|s| s.as_ref());
+    (strategy, default_budget)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::config::ToolResultCompressionRule;
+
+    #[test]
+    fn test_resolve_builtin_and_rules() {
+        let mut config = ToolResultCompressionConfig::default();
+        assert_eq!(resolve("Bash", &config).0.map(|s| s.name()), Some("log"));
+        assert_eq!(resolve("Grep", &config).0.map(|s| s.name()), Some("grep"));
+        assert_eq!(resolve("mcp__github__list_issues", &config).0.map(|s| s.name()), Some("json"));
+        assert_eq!(resolve("git_diff", &config).0.map(|s| s.name()), Some("diff"));
+        assert!(resolve("Read", &config).0.is_none());
+
+        config.rules.push(ToolResultCompressionRule {
+            tool: "mcp__*".to_string(),
+            strategy: "default".to_string(),
+            max_chars: Some(5_000),
+        });
+        config.rules.push(ToolResultCompressionRule {
+            tool: "Read".to_string(),
+            strategy: "log".to_string(),
+            max_chars: None,
+        });
+        let (strategy, budget) = resolve("mcp__github__list_issues", &config);
+        assert!(strategy.is_none());
+        assert_eq!(budget, 5_000);
+        assert_eq!(resolve("read", &config).0.map(|s| s.name()), Some("log"));
+
+        config.enabled = false;
+        assert!(resolve("Bash", &config).0.is_none());
+    }
+
+    #[test]
+    fn test_json_prune_keeps_structure() {
+        let items: Vec<_> = (0..500)
+            .map(|i| serde_json::json!({"id": i, "title": "x".repeat(300), "labels": [{"name": "bug"}]}))
+            .collect();
+        let text = serde_json::to_string(&serde_json::json!({"total": 500, "items": items})).unwrap();
+        let result = JsonPruneStrategy.compact(&text, 8_000).unwrap();
+        assert!(result.len() <= 8_000);
+        assert!(result.starts_with("[json pruned"));
+        assert!(result.contains("\"total\":500"));
+        assert!(result.contains("more items"));
+        assert!(JsonPruneStrategy.compact("not json", 100).is_none());
+    }
+
+    #[test]
+    fn test_log_dedup_collapses_repeats() {
+        let mut text = String::new();
+        for i in 0..1000 {
+            text.push_str(&format!("[{}] Downloading chunk {} of 1000\n", i, i));
+        }
+        text.push_str("Done\n");
+        let result = LogDedupStrategy.compact(&text, 5_000).unwrap();
+        assert!(result.contains("previous line repeated 999 more times"));
+        assert!(result.ends_with("Done"));
+    }
+
+    #[test]
+    fn test_log_dedup_keeps_errors_from_middle() {
+        let mut text = String::new();
+        for i in 0..5000 {
+            if i == 2500 {
+                text.push_str("ERROR: connection refused\n");
+            }
+            text.push_str(&format!("step {} {}\n", i, "abcdefgh".repeat(i % 7 + 1)));
+        }
+        let result = LogDedupStrategy.compact(&text, 4_000).unwrap();
+        assert!(result.len() <= 4_000);
+        assert!(result.contains("ERROR: connection refused"));
+        assert!(result.contains("omitted"));
+    }
+
+    #[test]
+    fn test_grep_group_by_file() {
+        let mut text = String::new();
+        for f in 0..20 {
+            for l in 0..100 {
+                text.push_str(&format!("src/module_{}.rs:{}:    let value = compute({});\n", f, l + 1, l));
+            }
+        }
+        let result = GrepGroupStrategy.compact(&text, 6_000).unwrap();
+        assert!(result.len() <= 6_000);
+        assert!(result.contains("20 files, 2000 matches"));
+        assert!(result.contains("src/module_19.rs (100 matches)"));
+        assert!(GrepGroupStrategy.compact("plain sentence one\nplain sentence two", 10).is_none());
+    }
+
+    #[test]
+    fn test_diff_hunk_trimming() {
+        let mut text = String::from("diff --git a/a.rs b/a.rs\nindex 1..2 100644\n--- a/a.rs\n+++ b/a.rs\n");
+        for h in 0..50 {
+            text.push_str(&format!("@@ -{},40 +{},40 @@ fn f{}()\n", h * 100, h * 100, h));
+            for i in 0..20 {
+                text.push_str(&format!(" context line {}\n", i));
+            }
+            for i in 0..20 {
+                text.push_str(&format!("-old {}\n+new {}\n", i, i));
+            }
+        }
+        let result = DiffHunkStrategy.compact(&text, 8_000).unwrap();
+        assert!(result.len() <= 8_000);
+        assert!(result.contains("+++ b/a.rs"));
+        assert!(result.contains("@@ -4900,40 +4900,40 @@ fn f49()"));
+        assert!(DiffHunkStrategy.compact("no diff here", 10).is_none());
+    }
+}
@@ -0,0 +1,54 @@
+/// 工具结果压缩策略 trait
+///
+/// 与 `ToolAdapter` 类似，按工具名为不同工具的输出提供定制化的压缩方式
+/// (JSON 裁剪、日志去重、grep 分组、diff hunk 裁剪等)。
+/// 策略无法处理该内容时返回 None，交由通用压缩级联处理。
+pub trait ToolResultStrategy: Send + Sync {
+    /// 策略名称 (配置中通过该名称引用)
+    fn name(&self) -> &'static str;
+
+    /// 未配置规则时，该策略默认接管的工具
+    ///
+    /// # Arguments
+    /// * `tool_name` - 工具名称 (已转为小写)
+    fn matches(&self, tool_name: &str) -> bool;
+
+    /// 将工具输出压缩到 `budget` 字符以内
+    ///
+    /// # Returns
+    /// 压缩结果 (长度不超过 budget)；内容形态不符或无法压缩到预算内时返回 None
+    fn compact(&self, text: &str, budget: usize) -> Option<String>;
+}
+
+/// 辅助函数: 按字节预算截取字符串 (不切断 UTF-8 字符)
+pub fn clip_str(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut end = max;
+    while end > 0 && !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// 辅助函数: 过长的单行截断并标注
+pub fn clip_line(line: &str, max: usize) -> String {
+    if line.len() <= max {
+        line.to_string()
+    } else {
+        format!("{}…", clip_str(line, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_str_char_boundary() {
+        assert_eq!(clip_str("héllo", 2), "h");
+        assert_eq!(clip_str("hello", 10), "hello");
+        assert_eq!(clip_line("abcdef", 3), "abc…");
+    }
+}
@@ -40,6 +40,37 @@ pub fn update_thinking_budget_config(config: ThinkingBudgetConfig) {
     }
 }
 
+// ============================================================================
+// 全局工具结果压缩配置存储
+// ============================================================================
+static GLOBAL_TOOL_RESULT_COMPRESSION_CONFIG: OnceLock<RwLock<ToolResultCompressionConfig>> = OnceLock::new();
+
+/// 获取当前工具结果压缩配置
+pub fn get_tool_result_compression_config() -> ToolResultCompressionConfig {
+    GLOBAL_TOOL_RESULT_COMPRESSION_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局工具结果压缩配置
+pub fn update_tool_result_compression_config(config: ToolResultCompressionConfig) {
+    if let Some(lock) = GLOBAL_TOOL_RESULT_COMPRESSION_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+        }
+    } else {
+        let _ = GLOBAL_TOOL_RESULT_COMPRESSION_CONFIG.set(RwLock::new(config.clone()));
+    }
+    tracing::info!(
+        "[Tool-Compression] Global config updated: enabled={}, rules={}, default_max_chars={}",
+        config.enabled,
+        config.rules.len(),
+        config.default_max_chars
+    );
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyAuthMode {
@@ -209,7 +240,7 @@ impl Default for ExperimentalConfig {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum PmRouterScope {
-    /// Only apply PM routing to known CLI clients (e.g., Claude Code CLI).
+    /// Only apply PM routing to known CLI clients (e.g., Claude Code, Codex CLI, Gemini CLI).
     CliOnly,
     /// Apply PM routing to all proxy requests.
     AllRequests,
@@ -221,6 +252,61 @@ impl Default for PmRouterScope {
     }
 }
 
+/// How the PM router picks a model.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PmRoutingMode {
+    /// Always ask the PM-lite/PM-pro model.
+    Llm,
+    /// Evaluate deterministic rules first; ask the LLM only when no rule fires.
+    RulesThenLlm,
+    /// Rules only; keep the requested model when no rule fires.
+    RulesOnly,
+}
+
+impl Default for PmRoutingMode {
+    fn default() -> Self {
+        Self::RulesThenLlm
+    }
+}
+
+/// Request features a routing rule can match on. Every set field must match (AND);
+/// list fields match when any entry matches. Empty rule = match everything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PmRuleConditions {
+    pub has_images: Option<bool>,
+    pub has_tools: Option<bool>,
+    /// Tool names (case-insensitive, trailing `*` = prefix match).
+    pub tool_names: Vec<String>,
+    /// Token estimate bucket (inclusive lower / exclusive upper bound).
+    pub min_tokens: Option<u32>,
+    pub max_tokens: Option<u32>,
+    /// Whether the client requested thinking / reasoning.
+    pub thinking: Option<bool>,
+    /// Keywords searched in the latest user turn.
+    pub keywords: Vec<String>,
+    /// User-Agent substrings (case-insensitive).
+    pub user_agents: Vec<String>,
+    /// Exact API keys the request was made with.
+    pub api_keys: Vec<String>,
+    /// Restrict to protocols: "claude" | "openai" | "gemini".
+    pub protocols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PmRoutingRule {
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub conditions: PmRuleConditions,
+    pub target_model: String,
+    /// Tried in order when the target has no available account / is unsupported by the protocol.
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PmRouterConfig {
     /// Enable PM router (model selection before routing).
@@ -254,6 +340,26 @@ pub struct PmRouterConfig {
     /// Default fallback model when routing fails.
     #[serde(default = "default_pm_fallback_model")]
     pub fallback_model: String,
+
+    /// Reuse the routing decision for the same session (SessionManager fingerprint).
+    #[serde(default = "default_true")]
+    pub decision_cache_enabled: bool,
+
+    /// Force re-routing after this many new user turns in a session.
+    #[serde(default = "default_pm_cache_max_turns")]
+    pub decision_cache_max_turns: usize,
+
+    /// Cached decisions older than this (seconds) are re-routed.
+    #[serde(default = "default_pm_cache_ttl_secs")]
+    pub decision_cache_ttl_secs: u64,
+
+    /// LLM only / rules first / rules only.
+    #[serde(default)]
+    pub routing_mode: PmRoutingMode,
+
+    /// Deterministic routing rules, evaluated top to bottom (first match wins).
+    #[serde(default)]
+    pub rules: Vec<PmRoutingRule>,
 }
 
 impl Default for PmRouterConfig {
@@ -280,9 +386,17 @@ impl Default for PmRouterConfig {
                 "claude-code".to_string(),
                 "claude-cli".to_string(),
                 "claude".to_string(),
+                "codex".to_string(),
+                "geminicli".to_string(),
+                "gemini-cli".to_string(),
             ],
             max_context_chars: default_pm_context_limit(),
             fallback_model: default_pm_fallback_model(),
+            decision_cache_enabled: true,
+            decision_cache_max_turns: default_pm_cache_max_turns(),
+            decision_cache_ttl_secs: default_pm_cache_ttl_secs(),
+            routing_mode: PmRoutingMode::default(),
+            rules: Vec::new(),
         }
     }
 }
@@ -303,6 +417,14 @@ fn default_pm_context_limit() -> usize {
     4000
 }
 
+fn default_pm_cache_max_turns() -> usize {
+    6
+}
+
+fn default_pm_cache_ttl_secs() -> u64 {
+    1800
+}
+
 fn default_threshold_l1() -> f32 {
     0.4
 }
@@ -341,6 +463,9 @@ pub struct ThinkingBudgetConfig {
     /// 自定义固定值（仅在 mode=Custom 时生效）
     #[serde(default = "default_thinking_budget_custom_value")]
     pub custom_value: u32,
+    /// [NEW] 按模型的策略 (按顺序匹配，支持 `*` 通配符)；未命中时使用 mode
+    #[serde(default)]
+    pub policies: Vec<ThinkingPolicyRule>,
 }
 
 impl Default for ThinkingBudgetConfig {
@@ -348,10 +473,139 @@ impl Default for ThinkingBudgetConfig {
         Self {
             mode: ThinkingBudgetMode::Auto,
             custom_value: default_thinking_budget_custom_value(),
+            policies: Vec::new(),
+        }
+    }
+}
+
+/// 单个模型的 Thinking Budget 策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ThinkingPolicy {
+    /// 透传调用方传入的值
+    Passthrough,
+    /// 限制在 [min, max] 范围内
+    Clamp {
+        #[serde(default)]
+        min: Option<u32>,
+        #[serde(default)]
+        max: Option<u32>,
+    },
+    /// 固定值
+    Fixed { value: u32 },
+    /// 关闭 thinking
+    Disable,
+    /// 按 prompt 估算 token 数 × ratio 计算，再限制在 [min, max] 范围内
+    Scale {
+        ratio: f32,
+        #[serde(default)]
+        min: Option<u32>,
+        #[serde(default)]
+        max: Option<u32>,
+    },
+}
+
+/// 模型 → 策略规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ThinkingPolicyRule {
+    /// 模型名或通配符模式 (如 `claude-opus-*`、`*flash*`)，匹配实际发往上游的模型
+    pub model: String,
+    #[serde(flatten)]
+    pub policy: ThinkingPolicy,
+}
+
+/// 附加 API Key (与主 api_key 一样可访问代理接口，可携带独立策略)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    /// 显示名称 (日志中用于标识调用方)
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 该 Key 的 Thinking Budget 策略，优先于全局 policies
+    #[serde(default)]
+    pub thinking_policies: Vec<ThinkingPolicyRule>,
+    /// 是否允许该 Key 通过 `x-antigravity-*` 请求头覆盖路由 (账号/调度/Router/Thinking/Provider)
+    #[serde(default)]
+    pub allow_request_overrides: bool,
+    /// 不记录该 Key 的请求/响应体 (仅保留元数据)
+    #[serde(default)]
+    pub skip_body_logging: bool,
+}
+
+/// 模型能力覆盖项 (未填写的字段保持内置值；新模型按 id 推断默认值)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCapabilityOverride {
+    pub id: String,
+    #[serde(default)]
+    pub provider: Option<crate::proxy::common::model_registry::ModelProvider>,
+    /// 实际发往上游的模型名 (空字符串表示与 id 相同)
+    #[serde(default)]
+    pub upstream: Option<String>,
+    #[serde(default)]
+    pub context_window: Option<u32>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default)]
+    pub thinking: Option<bool>,
+    /// Auto 模式下的 thinkingBudget 上限 (0 表示不限制)
+    #[serde(default)]
+    pub thinking_budget_cap: Option<u32>,
+    #[serde(default)]
+    pub image_input: Option<bool>,
+    #[serde(default)]
+    pub audio_input: Option<bool>,
+    #[serde(default)]
+    pub quota_group: Option<String>,
+    #[serde(default)]
+    pub aliases: Option<Vec<String>>,
+    /// 是否作为 PM Router 候选模型
+    #[serde(default)]
+    pub router: Option<bool>,
+}
+
+/// 工具结果压缩规则 (按工具名匹配，支持 `*` 通配符，不区分大小写)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResultCompressionRule {
+    /// 工具名或通配符模式，如 `mcp__github__*`
+    pub tool: String,
+    /// 策略名: json / log / grep / diff / default (仅使用通用压缩)
+    pub strategy: String,
+    /// 该工具的字符预算 (未设置时使用 default_max_chars)
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+}
+
+/// 工具结果压缩配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResultCompressionConfig {
+    /// 是否启用按工具的压缩策略 (关闭时仅使用通用压缩)
+    /// 启用时字符串形式的工具结果 content 也会按字符预算压缩，关闭时原样透传
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 自定义规则 (按顺序匹配，优先于内置策略的默认匹配)
+    #[serde(default)]
+    pub rules: Vec<ToolResultCompressionRule>,
+    /// 默认字符预算
+    #[serde(default = "default_tool_result_max_chars")]
+    pub default_max_chars: usize,
+}
+
+impl Default for ToolResultCompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: Vec::new(),
+            default_max_chars: default_tool_result_max_chars(),
         }
     }
 }
 
+fn default_tool_result_max_chars() -> usize {
+    200_000
+}
+
 fn default_thinking_budget_custom_value() -> u32 {
     24576
 }
@@ -437,6 +691,18 @@ pub struct SecurityMonitorConfig {
     /// IP 白名单配置
     #[serde(default)]
     pub whitelist: IpWhitelistConfig,
+
+    /// [NEW] 自动封禁配置
+    #[serde(default)]
+    pub auto_ban: AutoBanConfig,
+
+    /// [NEW] 可信代理 (仅信任来自这些对端的转发头)
+    #[serde(default)]
+    pub trusted_proxies: TrustedProxyConfig,
+
+    /// [NEW] 安全事件记录与导出 (SIEM)
+    #[serde(default)]
+    pub event_export: SecurityEventExportConfig,
 }
 
 impl Default for SecurityMonitorConfig {
@@ -444,6 +710,136 @@ impl Default for SecurityMonitorConfig {
         Self {
             blacklist: IpBlacklistConfig::default(),
             whitelist: IpWhitelistConfig::default(),
+            auto_ban: AutoBanConfig::default(),
+            trusted_proxies: TrustedProxyConfig::default(),
+            event_export: SecurityEventExportConfig::default(),
+        }
+    }
+}
+
+/// 安全事件输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventFormat {
+    /// 每行一个 JSON 对象
+    #[default]
+    Jsonl,
+    /// ArcSight Common Event Format
+    Cef,
+    /// RFC 5424 syslog (结构化数据携带事件字段)
+    Syslog,
+}
+
+/// syslog 传输协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyslogTransport {
+    #[default]
+    Udp,
+    /// TCP (RFC 6587 octet-counting 分帧)
+    Tcp,
+}
+
+/// 安全事件记录与导出配置
+///
+/// 事件统一写入 security.db 的 security_events 表 (供 `/api/security/events` 游标拉取)，
+/// 并可同时追加到本地文件或转发到 syslog 采集端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecurityEventExportConfig {
+    /// 是否记录普通访问事件 (量大，关闭后仅记录拦截、鉴权失败、封禁与配置变更)
+    pub include_access: bool,
+    /// 事件保留天数 (0 表示永久保留)
+    pub retention_days: u32,
+    /// 追加写入的本地文件路径 (留空表示不写文件)
+    pub file_path: Option<String>,
+    pub file_format: SecurityEventFormat,
+    pub syslog_enabled: bool,
+    pub syslog_transport: SyslogTransport,
+    pub syslog_host: String,
+    pub syslog_port: u16,
+    /// syslog 消息体格式 (syslog 为 RFC 5424 结构化数据，cef / jsonl 作为 MSG 部分)
+    pub syslog_format: SecurityEventFormat,
+}
+
+impl Default for SecurityEventExportConfig {
+    fn default() -> Self {
+        Self {
+            include_access: true,
+            retention_days: 30,
+            file_path: None,
+            file_format: SecurityEventFormat::Jsonl,
+            syslog_enabled: false,
+            syslog_transport: SyslogTransport::Udp,
+            syslog_host: "127.0.0.1".to_string(),
+            syslog_port: 514,
+            syslog_format: SecurityEventFormat::Syslog,
+        }
+    }
+}
+
+/// 可信代理配置
+///
+/// 只有直连对端命中该列表时才读取 X-Forwarded-For / X-Real-IP，否则使用 socket 对端地址
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustedProxyConfig {
+    /// 可信代理 IP / CIDR (IPv4 与 IPv6)，默认仅本机 (同机反代、cloudflared 隧道)
+    pub proxies: Vec<String>,
+    /// Cloudflare 预设: 信任 Cloudflare 边缘 IP 段并优先使用 CF-Connecting-IP
+    pub cloudflare: bool,
+}
+
+impl Default for TrustedProxyConfig {
+    fn default() -> Self {
+        Self {
+            proxies: vec!["127.0.0.1".to_string(), "::1".to_string()],
+            cloudflare: false,
+        }
+    }
+}
+
+/// 自动封禁配置 (fail2ban 风格，阈值为 0 表示关闭该规则)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoBanConfig {
+    pub enabled: bool,
+    /// 时间窗口内鉴权失败 (401) 次数上限
+    pub auth_failure_threshold: u32,
+    pub auth_failure_window_minutes: u64,
+    /// 时间窗口内请求数上限 (请求洪泛)
+    pub flood_threshold: u32,
+    pub flood_window_seconds: u64,
+    /// 时间窗口内探测未知路径 (404/405 或命中 probe_patterns) 次数上限
+    pub probe_threshold: u32,
+    pub probe_window_minutes: u64,
+    /// 扫描器常见路径特征 (不区分大小写的子串匹配)
+    pub probe_patterns: Vec<String>,
+    /// 阶梯式封禁时长 (分钟)，第 N 次违规使用第 N 项，超出后使用最后一项
+    pub ban_durations_minutes: Vec<u64>,
+    /// 距上次违规超过该时长后累犯次数清零 (小时)
+    pub offense_reset_hours: u64,
+    /// 豁免 IP / CIDR (白名单中的 IP 同样豁免)
+    pub exempt_ips: Vec<String>,
+}
+
+impl Default for AutoBanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auth_failure_threshold: 10,
+            auth_failure_window_minutes: 5,
+            flood_threshold: 600,
+            flood_window_seconds: 60,
+            probe_threshold: 20,
+            probe_window_minutes: 10,
+            probe_patterns: [".env", ".git/", "wp-", ".php", "phpmyadmin", "cgi-bin", "/actuator", "/.aws"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            ban_durations_minutes: vec![10, 60, 360, 1440],
+            offense_reset_hours: 24,
+            exempt_ips: vec!["127.0.0.1".to_string(), "::1".to_string()],
         }
     }
 }
@@ -538,6 +934,198 @@ pub struct ProxyConfig {
     /// 控制如何处理 AI 深度思考时的 Token 预算
     #[serde(default)]
     pub thinking_budget: ThinkingBudgetConfig,
+
+    /// [NEW] 请求日志脱敏 / 仅元数据模式
+    #[serde(default)]
+    pub log_redaction: LogRedactionConfig,
+
+    /// [NEW] 内容策略守卫 (入站提示词 / 出站补全的拦截、脱敏与标记)
+    #[serde(default)]
+    pub guardrails: GuardrailConfig,
+
+    /// [NEW] HTTPS 监听 (自定义证书或自签名证书，支持热重载)
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// [NEW] 管理后台用户与会话鉴权
+    #[serde(default)]
+    pub admin_auth: AdminAuthConfig,
+
+    /// [NEW] 附加 API Key (多调用方区分 / 按 Key 覆盖策略)
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+
+    /// [NEW] 是否允许主 api_key (或鉴权关闭时的本地调用方) 使用 `x-antigravity-*` 路由覆盖请求头
+    /// 附加 API Key 由各自的 `allow_request_overrides` 控制
+    #[serde(default)]
+    pub allow_request_overrides: bool,
+
+    /// [NEW] 模型能力覆盖 (与内置模型注册表合并，同 id 覆盖指定字段，新 id 追加)
+    #[serde(default)]
+    pub model_overrides: Vec<ModelCapabilityOverride>,
+
+    /// [NEW] 工具结果压缩配置 (按工具选择压缩策略与字符预算)
+    #[serde(default)]
+    pub tool_result_compression: ToolResultCompressionConfig,
+}
+
+/// 管理后台用户鉴权配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminAuthConfig {
+    /// 登录会话有效期 (小时)
+    pub session_ttl_hours: u64,
+    /// 已创建管理用户后是否仍允许使用 admin_password / api_key 访问管理接口 (视为 admin 角色)
+    pub allow_legacy_password: bool,
+}
+
+impl Default for AdminAuthConfig {
+    fn default() -> Self {
+        Self {
+            session_ttl_hours: 12,
+            allow_legacy_password: true,
+        }
+    }
+}
+
+/// 请求日志中请求/响应体的记录方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogBodyMode {
+    /// 记录 (脱敏后的) 完整请求/响应体
+    #[default]
+    Full,
+    /// 仅记录元数据 (模型、账号、状态、耗时、Token)，不保存请求/响应体
+    MetadataOnly,
+}
+
+/// 自定义脱敏规则
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// 规则名称 (替换为 `[REDACTED:<name>]`)
+    pub name: String,
+    /// 正则表达式
+    pub pattern: String,
+}
+
+/// 日志脱敏配置 (写入 proxy_db / 调试日志前执行)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRedactionConfig {
+    /// 是否启用脱敏 (内置检测器 + 自定义规则)
+    pub enabled: bool,
+    pub body_mode: LogBodyMode,
+    /// 关闭的内置检测器 (api_key / bearer_token / jwt / email / private_key / aws_key)
+    pub disabled_detectors: Vec<String>,
+    pub custom_rules: Vec<RedactionRule>,
+}
+
+impl Default for LogRedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            body_mode: LogBodyMode::Full,
+            disabled_detectors: Vec::new(),
+            custom_rules: Vec::new(),
+        }
+    }
+}
+
+/// 守卫规则命中后的动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardAction {
+    /// 拒绝请求 (入站) 或终止响应 (出站)
+    Block,
+    /// 将命中内容替换为 `[FILTERED:<name>]` 后放行
+    Redact,
+    /// 仅标记并记录到请求日志
+    #[default]
+    Tag,
+}
+
+/// 守卫规则作用阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardStage {
+    /// 入站提示词
+    Input,
+    /// 出站补全 (含 SSE 流)
+    Output,
+    #[default]
+    Both,
+}
+
+/// 内容守卫规则 (正则与关键词任一命中即视为命中)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardRule {
+    pub name: String,
+    #[serde(default)]
+    pub stage: GuardStage,
+    #[serde(default)]
+    pub action: GuardAction,
+    /// 正则表达式
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// 关键词列表 (不区分大小写)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+/// 内容策略守卫配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuardrailConfig {
+    pub enabled: bool,
+    pub rules: Vec<GuardRule>,
+    /// 单个请求允许的最大图片数量 (0 表示不限制，超出时拒绝)
+    pub max_images: u32,
+    /// 单张内联图片最大字节数 (0 表示不限制，超出时拒绝)
+    pub max_image_bytes: u64,
+    /// 禁止声明的工具名称 (不区分大小写)
+    pub banned_tools: Vec<String>,
+    /// 请求声明禁用工具时的动作 (block 拒绝 / redact 移除该工具 / tag 仅标记)
+    pub banned_tool_action: GuardAction,
+}
+
+impl Default for GuardrailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            max_images: 0,
+            max_image_bytes: 0,
+            banned_tools: Vec::new(),
+            banned_tool_action: GuardAction::Block,
+        }
+    }
+}
+
+/// HTTPS 监听配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    /// PEM 证书链路径 (与 key_path 同时留空时自动生成自签名证书)
+    pub cert_path: Option<String>,
+    /// PEM 私钥路径
+    pub key_path: Option<String>,
+    /// 自签名证书额外的 SAN (域名或局域网 IP)，默认已包含 localhost / 127.0.0.1 / ::1
+    pub self_signed_hosts: Vec<String>,
+    /// 证书文件变更检查间隔 (秒)，0 表示仅手动重载
+    pub reload_interval_secs: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            self_signed_hosts: Vec::new(),
+            reload_interval_secs: 30,
+        }
+    }
 }
 
 /// 上游代理配置
@@ -573,6 +1161,14 @@ impl Default for ProxyConfig {
             user_agent_override: None,
             saved_user_agent: None,
             thinking_budget: ThinkingBudgetConfig::default(),
+            log_redaction: LogRedactionConfig::default(),
+            guardrails: GuardrailConfig::default(),
+            tls: TlsConfig::default(),
+            admin_auth: AdminAuthConfig::default(),
+            api_keys: Vec::new(),
+            allow_request_overrides: false,
+            model_overrides: Vec::new(),
+            tool_result_compression: ToolResultCompressionConfig::default(),
         }
     }
 }
@@ -47,15 +47,26 @@ pub async fn write_debug_payload(
     let filename = build_filename(prefix, trace_id);
     let path = output_dir.join(filename);
 
-    match serde_json::to_vec_pretty(payload) {
-        Ok(bytes) => {
-            if let Err(e) = fs::write(&path, bytes).await {
-                tracing::warn!("[Debug-Log] Failed to write file: {}", e);
-            }
-        }
+    let text = match serde_json::to_string_pretty(payload) {
+        Ok(text) => text,
         Err(e) => {
             tracing::warn!("[Debug-Log] Failed to serialize payload: {}", e);
+            return;
         }
+    };
+
+    // [NEW] 调试日志同样在落盘前脱敏 (正则扫描放到阻塞线程池)
+    let redactor = crate::proxy::redaction::current();
+    let text = match tokio::task::spawn_blocking(move || redactor.redact(&text).0).await {
+        Ok(text) => text,
+        Err(e) => {
+            // 脱敏失败时不落盘，避免写入未脱敏内容
+            tracing::warn!("[Debug-Log] Failed to redact payload: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = fs::write(&path, text).await {
+        tracing::warn!("[Debug-Log] Failed to write file: {}", e);
     }
 }
 
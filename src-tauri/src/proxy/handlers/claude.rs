@@ -25,6 +25,7 @@ use crate::proxy::handlers::codex;
 use crate::proxy::mappers::context_manager::ContextManager;
 use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 use crate::proxy::debug_logger;
+use crate::proxy::request_context::ProviderOverride;
 use axum::http::HeaderMap;
 use std::sync::{atomic::Ordering, Arc};
 
@@ -135,6 +136,8 @@ pub async fn handle_messages(
     let zai = state.zai.read().await.clone();
     let zai_enabled = zai.enabled && !matches!(zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
     let google_accounts = state.token_manager.len();
+    // [NEW] 请求头 x-antigravity-provider (需授权) 强制指定上游
+    let provider_override = crate::proxy::request_context::current_overrides().provider;
 
     // [CRITICAL REFACTOR] 优先解析请求以获取模型信息(用于智能兜底判断)
     let mut request: crate::proxy::mappers::claude::models::ClaudeRequest = match serde_json::from_value(body) {
@@ -165,111 +168,31 @@ pub async fn handle_messages(
         debug_logger::write_debug_payload(&debug_cfg, Some(&trace_id), "original_request", &original_payload).await;
     }
 
-    // [Codex] Anthropic 프로토콜에서 gpt-5.x-codex 요청이 오면 Codex API로 라우팅
-    if crate::proxy::handlers::codex::should_use_codex(&request.model) {
-        info!("[Auto-Route] 🔀 {} → Codex (Anthropic /v1/messages)", request.model);
-        let openai_messages: Vec<Value> = request
-            .messages
-            .iter()
-            .map(|m| {
-                let content = match &m.content {
-                    MessageContent::String(s) => s.clone(),
-                    MessageContent::Array(blocks) => {
-                        let texts: Vec<String> = blocks
-                            .iter()
-                            .filter_map(|b| {
-                                if let ContentBlock::Text { text } = b {
-                                    Some(text.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect();
-                        texts.join("\n")
-                    }
-                };
-                json!({ "role": m.role, "content": content })
-            })
-            .collect();
-        let openai_body = json!({
-            "model": request.model,
-            "messages": openai_messages,
-            "stream": false
-        });
-        match codex::call_codex_chat_api(openai_body).await {
-            Ok((status, openai_resp, model_used)) => {
-                if !status.is_success() {
-                    let err_obj = openai_resp.get("error").and_then(|e| e.as_object());
-                    let err_msg = err_obj
-                        .and_then(|o| o.get("message"))
-                        .and_then(|m| m.as_str())
-                        .unwrap_or_else(|| openai_resp.get("error").and_then(|e| e.as_str()).unwrap_or("Unknown Codex API error"));
-                    let err_code = err_obj.and_then(|o| o.get("code")).and_then(|c| c.as_str());
-                    let full_msg = match err_code {
-                        Some(c) => format!("{} (code: {})", err_msg, c),
-                        None => err_msg.to_string(),
-                    };
-                    return (
-                        status,
-                        Json(json!({
-                            "type": "error",
-                            "error": { "type": "api_error", "message": full_msg }
-                        })),
-                    )
-                        .into_response();
-                }
-                let content_str = openai_resp
-                    .get("choices")
-                    .and_then(|c| c.as_array())
-                    .and_then(|a| a.first())
-                    .and_then(|c| c.get("message"))
-                    .and_then(|m| m.get("content"))
-                    .and_then(|c| c.as_str())
-                    .unwrap_or("");
-                let input_tokens = openai_resp
-                    .get("usage")
-                    .and_then(|u| u.get("prompt_tokens"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as u32;
-                let output_tokens = openai_resp
-                    .get("usage")
-                    .and_then(|u| u.get("completion_tokens"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as u32;
-                let anthropic_body = json!({
-                    "id": openai_resp.get("id").unwrap_or(&json!("")),
-                    "type": "message",
-                    "role": "assistant",
-                    "model": model_used,
-                    "content": [{ "type": "text", "text": content_str }],
-                    "stop_reason": "end_turn",
-                    "usage": {
-                        "input_tokens": input_tokens,
-                        "output_tokens": output_tokens,
-                        "cache_read_input_tokens": 0,
-                        "cache_creation_input_tokens": 0
-                    }
-                });
-                return (status, Json(anthropic_body)).into_response();
-            }
-            Err((status, msg)) => {
-                return (
-                    status,
-                    Json(json!({
-                        "type": "error",
-                        "error": { "type": "api_error", "message": msg }
-                    })),
-                )
-                    .into_response();
-            }
-        }
+    // [Codex] Anthropic 프로토콜에서 gpt-5.x-codex (직접 지정 또는 custom_mapping) 요청이 오면
+    // Claude Messages ↔ Responses 변환을 거쳐 Codex 계정으로 라우팅
+    let codex_target = crate::proxy::common::model_mapping::resolve_model_route(
+        &request.model,
+        &*state.custom_mapping.read().await,
+    );
+    if provider_override != Some(ProviderOverride::Google) && codex::should_use_codex(&codex_target) {
+        info!("[Auto-Route] 🔀 {} → Codex {} (Anthropic /v1/messages)", request.model, codex_target);
+        merge_consecutive_messages(&mut request.messages);
+        let codex_session_id = crate::proxy::session_manager::SessionManager::extract_session_id(&request);
+        return codex::handle_claude_via_codex(&state, &request, &codex_target, &codex_session_id, &trace_id).await;
     }
 
     // [Issue #703 Fix] 智能兜底判断:需要归一化模型名用于配额保护检查
     let normalized_model = crate::proxy::common::model_mapping::normalize_to_standard_id(&request.model)
         .unwrap_or_else(|| request.model.clone());
 
-    let use_zai = if !zai_enabled {
+    if provider_override == Some(ProviderOverride::Zai) && !zai.enabled {
+        warn!("[{}] Provider override 'zai' ignored: z.ai is not enabled", trace_id);
+    }
+    let use_zai = if provider_override == Some(ProviderOverride::Google) {
+        false
+    } else if provider_override == Some(ProviderOverride::Zai) && zai.enabled {
+        true
+    } else if !zai_enabled {
         false
     } else {
         match zai.dispatch_mode {
@@ -493,11 +416,12 @@ pub async fn handle_messages(
         {
             Ok(decision) => {
                 info!(
-                    "[{}][PM-Router] {} -> {} via {} | task={} | reason={}",
+                    "[{}][PM-Router] {} -> {} via {}{} | task={} | reason={}",
                     trace_id,
                     original_model,
                     decision.selected_model,
                     decision.used_router_model,
+                    if decision.from_cache { " (cached)" } else { "" },
                     decision.task_type,
                     decision.reason
                 );
@@ -526,6 +450,27 @@ pub async fn handle_messages(
             );
         }
     }
+    // [NEW] PM Router 选中 Codex 模型时，同样经 Responses 转换由 Codex 账号处理
+    if let Some(ref selected) = pm_selected_model {
+        let codex_target = crate::proxy::common::model_mapping::resolve_model_route(
+            selected,
+            &*state.custom_mapping.read().await,
+        );
+        if provider_override != Some(ProviderOverride::Google) && codex::should_use_codex(&codex_target) {
+            info!("[{}][PM-Router] 🔀 {} → Codex {}", trace_id, original_model, codex_target);
+            let codex_session_id = crate::proxy::session_manager::SessionManager::extract_session_id(&request_for_body);
+            let response = codex::handle_claude_via_codex(
+                &state,
+                &request_for_body,
+                &codex_target,
+                &codex_session_id,
+                &trace_id,
+            )
+            .await;
+            return pm_router::with_selected_model_header(response, pm_selected_model);
+        }
+    }
+
     let token_manager = state.token_manager;
     
     let pool_size = token_manager.len();
@@ -675,6 +620,9 @@ pub async fn handle_messages(
                 trace_id, usage_ratio * 100.0, raw_estimated, estimated_usage, context_limit, calibrator.get_factor()
             );
 
+            // [NEW] Layer 3 基于原始历史计算前缀哈希 (L1 裁剪会随对话增长改变前缀，无法跨轮复用摘要)
+            let l3_source = (usage_ratio > threshold_l3).then(|| request_with_mapped.clone());
+
             // ===== Layer 1: Tool Message Trimming (L1 threshold) =====
             // Borrowed from Practical-Guide-to-Context-Engineering
             // Advantage: Completely cache-friendly (only removes messages, doesn't modify content)
@@ -751,7 +699,9 @@ pub async fn handle_messages(
                 // Clone token_manager Arc to avoid borrow issues
                 let token_manager_clone = token_manager.clone();
                 
-                match try_compress_with_summary(&request_with_mapped, &trace_id, &token_manager_clone).await {
+                let l3_source = l3_source.as_ref().unwrap_or(&request_with_mapped);
+                let token_budget = (context_limit as f32 * threshold_l3) as u32;
+                match try_compress_with_summary(l3_source, &session_id_str, token_budget, &trace_id, &token_manager_clone).await {
                     Ok(forked_request) => {
                         info!(
                             "[{}] [Layer-3] Fork successful: {} → {} messages",
@@ -1361,17 +1311,21 @@ pub async fn handle_messages(
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
-    let model_ids = get_all_dynamic_models(
-        &state.custom_mapping,
-    ).await;
+    let discovery = state.token_manager.get_model_discovery().await;
+    let models = get_all_dynamic_models(&state.custom_mapping, &discovery).await;
 
-    let data: Vec<_> = model_ids.into_iter().map(|id| {
-        json!({
-            "id": id,
+    let data: Vec<_> = models.into_iter().map(|m| {
+        let mut item = json!({
+            "id": m.id,
             "object": "model",
             "created": 1706745600,
             "owned_by": "antigravity"
-        })
+        });
+        // [NEW] 可服务该模型的账号数 (模型发现)
+        if let Some(accounts) = m.accounts {
+            item["accounts"] = json!(accounts);
+        }
+        item
     }).collect();
 
     Json(json!({
@@ -1759,33 +1713,104 @@ async fn call_gemini_sync(
 // ===== [Layer 3] Fork Conversation + XML Summary =====
 // This is the ultimate context compression strategy
 // Borrowed from Practical-Guide-to-Context-Engineering + Claude Code official practice
+// [NEW] 摘要按 (session, 消息前缀哈希) 持久化，后续轮次复用并只对增量消息做摘要
 
 /// Try to compress context by generating an XML summary and forking the conversation
 /// 
 /// This function:
-/// 1. Extracts the last valid thinking signature
-/// 2. Calls a cheap model (gemini-2.5-flash-lite) to generate XML summary
-/// 3. Creates a new message sequence with summary as prefix
-/// 4. Preserves the signature in the summary
-/// 5. Returns the forked request
+/// 1. Looks up a persisted summary covering a prefix of this conversation (same session)
+/// 2. Reuses it as-is when summary + remaining messages fit within `token_budget`
+/// 3. Otherwise calls a cheap model to summarise (previous summary + delta messages, or full history)
+/// 4. Preserves the last thinking signature in the summary
+/// 5. Persists the new summary and returns the forked request
 /// 
 /// Returns Ok(forked_request) on success, Err(error_message) on failure
 async fn try_compress_with_summary(
     original_request: &ClaudeRequest,
+    session_id: &str,
+    token_budget: u32,
     trace_id: &str,
     token_manager: &Arc<crate::proxy::TokenManager>,
 ) -> Result<ClaudeRequest, String> {
     info!("[{}] [Layer-3] Starting context compression with XML summary", trace_id);
-    
-    // 1. Extract last valid signature
-    let last_signature = ContextManager::extract_last_valid_signature(&original_request.messages);
+
+    let messages = &original_request.messages;
+    // 最后一条用户消息原样保留，不纳入摘要
+    let cut = match messages.last() {
+        Some(last) if last.role == "user" => messages.len() - 1,
+        _ => messages.len(),
+    };
+    if cut == 0 {
+        return Err("No history to summarize".to_string());
+    }
+    let prefix_hashes = ContextManager::prefix_hashes(&messages[..cut]);
+
+    // 1. 查找可复用的摘要
+    let lookup_session = session_id.to_string();
+    let lookup_hashes = prefix_hashes.clone();
+    let previous = tokio::task::spawn_blocking(move || {
+        crate::modules::summary_db::find_reusable(&lookup_session, &lookup_hashes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .unwrap_or_else(|e| {
+        warn!("[{}] [Layer-3] Failed to query persisted summaries: {}", trace_id, e);
+        None
+    });
+
+    if let Some(prev) = &previous {
+        let forked = build_forked_request(original_request, &prev.summary, prev.covered_messages);
+        let estimated = get_calibrator().calibrate(ContextManager::estimate_token_usage(&forked));
+        if prev.covered_messages == cut || estimated <= token_budget {
+            info!(
+                "[{}] [Layer-3] Reusing persisted summary #{} (covers {}/{} messages, ~{} tokens)",
+                trace_id, prev.id, prev.covered_messages, messages.len(), estimated
+            );
+            let id = prev.id;
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = crate::modules::summary_db::mark_used(id) {
+                    tracing::warn!("[Layer-3] Failed to update summary usage: {}", e);
+                }
+            });
+            return Ok(forked);
+        }
+    }
+
+    // 2. Extract last valid signature
+    let last_signature = ContextManager::extract_last_valid_signature(messages);
     
     if let Some(ref sig) = last_signature {
         debug!("[{}] [Layer-3] Extracted signature (len: {})", trace_id, sig.len());
     }
     
-    // 2. Build summary request
-    let mut summary_messages = original_request.messages.clone();
+    // 3. Build summary request (增量: 上一份摘要 + 其后的消息；否则完整历史)
+    let mut summary_messages = match &previous {
+        Some(prev) => {
+            info!(
+                "[{}] [Layer-3] Summarizing delta: messages {}..{} on top of summary #{}",
+                trace_id, prev.covered_messages, cut, prev.id
+            );
+            let delta = &messages[prev.covered_messages..cut];
+            let mut seeded = vec![Message {
+                role: "user".to_string(),
+                content: MessageContent::String(format!(
+                    "Here is the structured summary of our earlier conversation history:\n\n{}",
+                    prev.summary
+                )),
+            }];
+            if delta.first().map(|m| m.role == "user").unwrap_or(true) {
+                seeded.push(Message {
+                    role: "assistant".to_string(),
+                    content: MessageContent::String(
+                        "I have reviewed the earlier summary. Please continue.".to_string(),
+                    ),
+                });
+            }
+            seeded.extend_from_slice(delta);
+            seeded
+        }
+        None => messages[..cut].to_vec(),
+    };
     
     // Add instruction to include signature in summary
     let signature_instruction = if let Some(ref sig) = last_signature {
@@ -1823,7 +1848,7 @@ async fn try_compress_with_summary(
     
     debug!("[{}] [Layer-3] Calling {} for summary generation", trace_id, INTERNAL_BACKGROUND_TASK);
     
-    // 3. Call upstream using helper function (reuse existing infrastructure)
+    // 4. Call upstream using helper function (reuse existing infrastructure)
     let xml_summary = call_gemini_sync(
         INTERNAL_BACKGROUND_TASK,
         &summary_request,
@@ -1832,43 +1857,64 @@ async fn try_compress_with_summary(
     ).await?;
     
     info!("[{}] [Layer-3] Generated XML summary (len: {} chars)", trace_id, xml_summary.len());
-    
-    // 4. Create forked conversation with summary as prefix
-    let mut forked_messages = vec![
-        Message {
-            role: "user".to_string(),
-            content: MessageContent::String(format!(
-                "Context has been compressed. Here is the structured summary of our conversation history:\n\n{}",
-                xml_summary
-            )),
-        },
-        Message {
-            role: "assistant".to_string(),
-            content: MessageContent::String(
-                "I have reviewed the compressed context summary. I understand the current state and will continue from here.".to_string()
-            ),
-        },
-    ];
-    
-    // 5. Append the user's latest message (if exists and is not the summary request)
-    if let Some(last_msg) = original_request.messages.last() {
-        if last_msg.role == "user" {
-            // Check if it's not the summary instruction we just added
-            if !matches!(&last_msg.content, MessageContent::String(s) if s.contains(CONTEXT_SUMMARY_PROMPT)) {
-                forked_messages.push(last_msg.clone());
+
+    // 5. 持久化摘要 (失败不影响本次请求)
+    {
+        let session_id = session_id.to_string();
+        let prefix_hash = prefix_hashes[cut - 1].clone();
+        let parent_id = previous.as_ref().map(|p| p.id);
+        let summary = xml_summary.clone();
+        let trace_id = trace_id.to_string();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = crate::modules::summary_db::save_summary(
+                &session_id,
+                &prefix_hash,
+                cut,
+                parent_id,
+                &summary,
+                INTERNAL_BACKGROUND_TASK,
+            ) {
+                tracing::warn!("[{}] [Layer-3] Failed to persist summary: {}", trace_id, e);
             }
-        }
+        });
     }
-    
+
+    // 6. Create forked conversation with summary as prefix
+    let forked = build_forked_request(original_request, &xml_summary, cut);
+
     info!(
         "[{}] [Layer-3] Fork successful: {} messages → {} messages",
         trace_id,
         original_request.messages.len(),
-        forked_messages.len()
+        forked.messages.len()
     );
-    
-    // 6. Return forked request
-    Ok(ClaudeRequest {
+
+    Ok(forked)
+}
+
+/// Build the forked request: summary prefix + messages not covered by the summary
+fn build_forked_request(original_request: &ClaudeRequest, summary: &str, covered_messages: usize) -> ClaudeRequest {
+    let mut forked_messages = vec![Message {
+        role: "user".to_string(),
+        content: MessageContent::String(format!(
+            "Context has been compressed. Here is the structured summary of our conversation history:\n\n{}",
+            summary
+        )),
+    }];
+
+    let remaining = &original_request.messages[covered_messages.min(original_request.messages.len())..];
+    // 保持 user/assistant 交替
+    if remaining.first().map(|m| m.role != "assistant").unwrap_or(true) {
+        forked_messages.push(Message {
+            role: "assistant".to_string(),
+            content: MessageContent::String(
+                "I have reviewed the compressed context summary. I understand the current state and will continue from here.".to_string()
+            ),
+        });
+    }
+    forked_messages.extend_from_slice(remaining);
+
+    ClaudeRequest {
         model: original_request.model.clone(),
         messages: forked_messages,
         system: original_request.system.clone(),
@@ -1883,5 +1929,5 @@ async fn try_compress_with_summary(
         output_config: original_request.output_config.clone(),
         size: original_request.size.clone(),
         quality: original_request.quality.clone(),
-    })
+    }
 }
@@ -10,22 +10,20 @@ use axum::{
 };
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
 use serde_json::{json, Value};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-use crate::modules::codex::{refresh_codex_account_tokens, storage, types::CodexAuthData, CodexAccount};
+use crate::proxy::common::model_mapping::{is_codex_model, resolve_codex_model};
+use crate::proxy::mappers::claude::models::ClaudeRequest;
+use crate::proxy::mappers::responses;
 use crate::proxy::server::AppState;
 
 /// OpenAI API 베이스 URL
 const OPENAI_API_BASE: &str = "https://api.openai.com/v1";
 const CODEX_USER_AGENT: &str = "codex-cli/1.0.0";
 
-/// Codex 공식 모델: 요청 모델이 이 중 하나면 그대로 전달, 아니면 기본값 사용
-const CODEX_MODELS: &[&str] = &["gpt-5.2-codex", "gpt-5.1-codex-max", "gpt-5.1-codex-mini"];
-const CODEX_DEFAULT_MODEL: &str = "gpt-5.2-codex";
-
 /// 모델명으로 Codex 사용 여부 판단
 pub fn should_use_codex(model: &str) -> bool {
-    if CODEX_MODELS.contains(&model) {
+    if is_codex_model(model) {
         return true;
     }
     let model_lower = model.to_lowercase();
@@ -38,24 +36,25 @@ pub fn should_use_codex(model: &str) -> bool {
     false
 }
 
-fn resolve_codex_model(request_model: &str) -> &'static str {
-    for m in CODEX_MODELS {
-        if *m == request_model {
-            return m;
-        }
-    }
-    CODEX_DEFAULT_MODEL
+/// 풀 선택 시 최대 재시도 횟수 (계정 교체 포함)
+const MAX_RETRY_ATTEMPTS: usize = 3;
+
+/// 계정 풀 요청 결과
+enum CodexPoolResult {
+    /// 성공 응답, 사용된 account_id / email
+    Success(reqwest::Response, String, String),
+    /// 업스트림 에러 (상태 코드, OpenAI 형식 에러 본문)
+    Upstream(StatusCode, Value),
 }
 
-/// Codex API 호출 (비스트리밍). Anthropic 핸들러에서 재사용.
-/// Returns (status, response_body_json, model_used).
-pub async fn call_codex_chat_api(
-    body: Value,
-) -> Result<(StatusCode, Value, String), (StatusCode, String)> {
-    let _trace_id = format!("codex_{}", chrono::Utc::now().timestamp_subsec_millis());
-    let mut account = get_active_codex_account()?;
-    let (mut access_token, refresh_token, mut chatgpt_account_id) =
-        extract_codex_auth(&account)?;
+/// Codex 채팅 요청 처리 (OpenAI API 방식)
+pub async fn handle_codex_chat(
+    State(state): State<AppState>,
+    Json(mut body): Json<Value>,
+    session_id: String,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let trace_id = format!("codex_{}", chrono::Utc::now().timestamp_subsec_millis());
+    info!("[{}] Codex API Request", trace_id);
 
     let original_model = body
         .get("model")
@@ -63,214 +62,281 @@ pub async fn call_codex_chat_api(
         .unwrap_or("unknown")
         .to_string();
     let model_to_send = resolve_codex_model(&original_model);
-    let mut body = body;
+    let model_to_send = model_to_send.as_str();
     body["model"] = json!(model_to_send);
-    body["stream"] = json!(false);
-
-    let client = reqwest::Client::new();
-    let mut response = send_codex_request(
-        &client,
-        "/chat/completions",
-        &body,
-        &access_token,
-        chatgpt_account_id.as_deref(),
-    )
-    .await?;
 
-    if should_refresh_codex_token(response.status(), refresh_token.as_deref()) {
-        if let Ok((updated, refresh_result)) =
-            refresh_codex_account_tokens(&account.id).await
-        {
-            account = updated;
-            access_token = refresh_result.access_token;
-            chatgpt_account_id = extract_codex_auth(&account)?.2;
-            response = send_codex_request(
-                &client,
-                "/chat/completions",
-                &body,
-                &access_token,
-                chatgpt_account_id.as_deref(),
-            )
-            .await?;
-        }
-    }
+    debug!(
+        "[{}] Model: {} → {}, session: {}",
+        trace_id, original_model, model_to_send, session_id
+    );
 
-    let status = response.status();
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        let error_body: Value = if let Ok(parsed) = serde_json::from_str::<Value>(&error_text) {
-            if parsed.get("error").is_some() {
-                parsed
-            } else {
-                json!({ "error": { "message": error_text, "type": "api_error", "code": "internal_error" } })
+    let stream = body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let (response, account_id) =
+        match send_with_codex_pool(&state, "/chat/completions", &body, model_to_send, &session_id).await? {
+            CodexPoolResult::Success(response, account_id, _email) => (response, account_id),
+            CodexPoolResult::Upstream(status, error_body) => {
+                return Ok((status, Json(error_body)).into_response());
             }
-        } else {
-            json!({ "error": { "message": error_text, "type": "api_error", "code": "internal_error" } })
         };
-        return Ok((
-            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
-            error_body,
-            model_to_send.to_string(),
-        ));
+
+    if stream {
+        let body = Body::from_stream(response.bytes_stream());
+        let response = Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .header("Connection", "keep-alive")
+            .header("X-Codex-Account", &account_id)
+            .header("X-Model", model_to_send)
+            .body(body)
+            .unwrap();
+        return Ok(response.into_response());
     }
 
     let response_body: Value = response
         .json()
         .await
         .map_err(|e| (StatusCode::BAD_GATEWAY, format!("응답 파싱 실패: {}", e)))?;
-    Ok((StatusCode::OK, response_body, model_to_send.to_string()))
+    Ok((
+        StatusCode::OK,
+        [("X-Codex-Account", account_id.as_str()), ("X-Model", model_to_send)],
+        Json(response_body),
+    )
+        .into_response())
 }
 
-/// Codex 채팅 요청 처리 (OpenAI API 방식)
-pub async fn handle_codex_chat(
-    State(_state): State<AppState>,
-    Json(mut body): Json<Value>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let trace_id = format!("codex_{}", chrono::Utc::now().timestamp_subsec_millis());
-    info!("[{}] Codex API Request", trace_id);
+/// Claude Messages 요청을 Responses API 로 변환해 Codex 계정으로 처리 (/v1/messages)
+///
+/// 클라이언트 모델 / PM Router / custom_mapping 이 gpt-5.x-codex 를 가리킬 때 사용.
+/// tool_use/tool_result, thinking ↔ reasoning, 이미지, 스트리밍 이벤트를 모두 변환한다.
+pub async fn handle_claude_via_codex(
+    state: &AppState,
+    request: &ClaudeRequest,
+    target_model: &str,
+    session_id: &str,
+    trace_id: &str,
+) -> Response {
+    let model_to_send = resolve_codex_model(target_model);
+    let model_to_send = model_to_send.as_str();
+    let include_thinking = request
+        .thinking
+        .as_ref()
+        .map(|t| t.type_ == "enabled")
+        .unwrap_or(false);
+    let body = responses::transform_claude_request_to_responses(request, model_to_send);
 
-    let mut account = get_active_codex_account()?;
-    let (mut access_token, refresh_token, mut chatgpt_account_id) =
-        extract_codex_auth(&account)?;
+    info!(
+        "[{}][Codex] Claude → Responses | {} → {} | stream: {} | messages: {}",
+        trace_id,
+        request.model,
+        model_to_send,
+        request.stream,
+        request.messages.len()
+    );
 
-    let original_model = body
-        .get("model")
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string();
-    let model_to_send = resolve_codex_model(&original_model);
-    body["model"] = json!(model_to_send);
+    let (response, email) =
+        match send_with_codex_pool(state, "/responses", &body, model_to_send, session_id).await {
+            Ok(CodexPoolResult::Success(response, _account_id, email)) => (response, email),
+            Ok(CodexPoolResult::Upstream(status, error_body)) => {
+                let message = error_body
+                    .pointer("/error/message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown Codex API error")
+                    .to_string();
+                return claude_error_response(status, &message, model_to_send);
+            }
+            Err((status, message)) => return claude_error_response(status, &message, model_to_send),
+        };
 
-    debug!(
-        "[{}] Model: {} → {}, account: {}",
-        trace_id, original_model, model_to_send, account.id
-    );
+    if request.stream {
+        let stream = responses::create_claude_sse_stream_from_responses(
+            Box::pin(response.bytes_stream()),
+            model_to_send.to_string(),
+            include_thinking,
+            trace_id.to_string(),
+        );
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "text/event-stream")
+            .header("Cache-Control", "no-cache")
+            .header("Connection", "keep-alive")
+            .header("X-Account-Email", &email)
+            .header("X-Mapped-Model", model_to_send)
+            .body(Body::from_stream(stream))
+            .unwrap();
+    }
 
-    let stream = body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false);
+    match response.json::<Value>().await {
+        Ok(responses_body) => {
+            let claude_body =
+                responses::transform_responses_to_claude(&responses_body, model_to_send, include_thinking);
+            (
+                StatusCode::OK,
+                [
+                    ("X-Account-Email", email.as_str()),
+                    ("X-Mapped-Model", model_to_send),
+                ],
+                Json(claude_body),
+            )
+                .into_response()
+        }
+        Err(e) => claude_error_response(
+            StatusCode::BAD_GATEWAY,
+            &format!("응답 파싱 실패: {}", e),
+            model_to_send,
+        ),
+    }
+}
 
-    if stream {
-        let client = reqwest::Client::new();
-        let mut response = send_codex_request(
+fn claude_error_response(status: StatusCode, message: &str, model: &str) -> Response {
+    (
+        status,
+        [("X-Mapped-Model", model)],
+        Json(json!({
+            "type": "error",
+            "error": { "type": "api_error", "message": message }
+        })),
+    )
+        .into_response()
+}
+
+/// TokenManager 의 codex 풀에서 계정을 골라 `endpoint` (`/chat/completions` / `/responses`) 호출
+///
+/// - 세션 ID 로 같은 계정에 고정 (Prompt Caching)
+/// - 401/403: 토큰 갱신 후 같은 계정으로 1회 재시도
+/// - 429: 사용량 리셋 시각까지 잠그고 다음 계정으로 교체
+/// - 5xx / 네트워크 오류: 다음 계정으로 교체
+async fn send_with_codex_pool(
+    state: &AppState,
+    endpoint: &str,
+    body: &Value,
+    model: &str,
+    session_id: &str,
+) -> Result<CodexPoolResult, (StatusCode, String)> {
+    let token_manager = state.token_manager.clone();
+    let max_attempts = MAX_RETRY_ATTEMPTS
+        .min(token_manager.len().saturating_add(1))
+        .max(1);
+    let client = crate::utils::http::get_long_client();
+    let mut last_error = String::new();
+
+    for attempt in 0..max_attempts {
+        let (mut access_token, _project_id, email, _wait) = token_manager
+            .get_token("codex", attempt > 0, Some(session_id), model)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    format!("사용 가능한 Codex 계정이 없습니다: {}", e),
+                )
+            })?;
+        let account_id = token_manager
+            .get_account_id_by_email(&email)
+            .unwrap_or_else(|| email.clone());
+        let mut chatgpt_account_id = token_manager.find_codex_chatgpt_account_id(&access_token);
+
+        let mut response = match send_codex_request(
             &client,
-            "/chat/completions",
-            &body,
+            endpoint,
+            body,
             &access_token,
             chatgpt_account_id.as_deref(),
         )
-        .await?;
+        .await
+        {
+            Ok(r) => r,
+            Err((_, e)) => {
+                warn!("[Codex] {} 요청 실패, 다음 계정 시도: {}", email, e);
+                token_manager.record_failure(&account_id);
+                last_error = e;
+                continue;
+            }
+        };
 
-        if should_refresh_codex_token(response.status(), refresh_token.as_deref()) {
-            if let Ok((updated, refresh_result)) =
-                refresh_codex_account_tokens(&account.id).await
+        if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+            if let Ok(Some(refreshed)) = token_manager
+                .refresh_codex_token_by_access_token(&access_token)
+                .await
             {
-                account = updated;
-                access_token = refresh_result.access_token;
-                chatgpt_account_id = extract_codex_auth(&account)?.2;
-                response = send_codex_request(
+                access_token = refreshed.access_token;
+                chatgpt_account_id = refreshed.chatgpt_account_id;
+                response = match send_codex_request(
                     &client,
-                    "/chat/completions",
-                    &body,
+                    endpoint,
+                    body,
                     &access_token,
                     chatgpt_account_id.as_deref(),
                 )
-                .await?;
+                .await
+                {
+                    Ok(r) => r,
+                    Err((_, e)) => {
+                        token_manager.record_failure(&account_id);
+                        last_error = e;
+                        continue;
+                    }
+                };
             }
         }
 
         let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            let error_body: Value = if let Ok(parsed) = serde_json::from_str::<Value>(&error_text) {
-                if parsed.get("error").is_some() {
-                    parsed
-                } else {
-                    json!({ "error": { "message": error_text, "type": "api_error", "code": "internal_error" } })
-                }
-            } else {
-                json!({ "error": { "message": error_text, "type": "api_error", "code": "internal_error" } })
-            };
-            return Ok((
-                StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
-                Json(error_body),
-            )
-            .into_response());
+        if status.is_success() {
+            token_manager.record_success(&account_id);
+            token_manager.mark_account_success(&account_id);
+            return Ok(CodexPoolResult::Success(response, account_id, email));
         }
 
-        let body = Body::from_stream(response.bytes_stream());
-        let response = Response::builder()
-            .status(StatusCode::OK)
-            .header("Content-Type", "text/event-stream")
-            .header("Cache-Control", "no-cache")
-            .header("Connection", "keep-alive")
-            .header("X-Codex-Account", &account.id)
-            .header("X-Model", model_to_send)
-            .body(body)
-            .unwrap();
-        return Ok(response.into_response());
-    }
-
-    // 비스트리밍: 공통 API 호출 후 응답만 래핑
-    match call_codex_chat_api(body).await {
-        Ok((status, response_body, model_used)) => Ok((
-            status,
-            [("X-Codex-Account", account.id.as_str()), ("X-Model", model_used.as_str())],
-            Json(response_body),
-        )
-            .into_response()),
-        Err(e) => Err(e),
-    }
-}
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+        let error_text = response.text().await.unwrap_or_default();
 
-fn get_active_codex_account() -> Result<CodexAccount, (StatusCode, String)> {
-    let active = storage::get_codex_active_account().map_err(|e| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            format!("활성 계정 없음: {}", e),
-        )
-    })?;
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            token_manager
+                .mark_codex_rate_limited(&email, status.as_u16(), retry_after.as_deref(), &error_text)
+                .await;
+        }
 
-    if let Some(account) = active {
-        return Ok(account);
-    }
+        let should_rotate = matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) || status.is_server_error();
 
-    let store = storage::load_codex_accounts().map_err(|e| {
-        (
-            StatusCode::SERVICE_UNAVAILABLE,
-            format!("계정 로드 실패: {}", e),
-        )
-    })?;
+        if !should_rotate || attempt + 1 == max_attempts {
+            return Ok(CodexPoolResult::Upstream(
+                StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+                codex_error_body(error_text),
+            ));
+        }
 
-    if store.accounts.is_empty() {
-        return Err((
-            StatusCode::SERVICE_UNAVAILABLE,
-            "등록된 Codex 계정이 없습니다".to_string(),
-        ));
+        warn!(
+            "[Codex] {} 응답 {} (attempt {}/{}), 다음 계정으로 교체",
+            email,
+            status,
+            attempt + 1,
+            max_attempts
+        );
+        token_manager.record_failure(&account_id);
+        last_error = format!("{}: {}", status, error_text);
     }
 
-    Ok(store.accounts[0].clone())
+    Err((
+        StatusCode::BAD_GATEWAY,
+        format!("Codex 업스트림 요청 실패: {}", last_error),
+    ))
 }
 
-fn extract_codex_auth(
-    account: &CodexAccount,
-) -> Result<(String, Option<String>, Option<String>), (StatusCode, String)> {
-    match &account.auth_data {
-        CodexAuthData::ChatGPT {
-            access_token,
-            refresh_token,
-            account_id: cg_id,
-            ..
-        } => Ok((access_token.clone(), Some(refresh_token.clone()), cg_id.clone())),
-        CodexAuthData::ApiKey { key } => Ok((key.clone(), None, None)),
+/// 업스트림 에러 텍스트를 OpenAI 에러 형식으로 정규화
+fn codex_error_body(error_text: String) -> Value {
+    match serde_json::from_str::<Value>(&error_text) {
+        Ok(parsed) if parsed.get("error").is_some() => parsed,
+        _ => json!({ "error": { "message": error_text, "type": "api_error", "code": "internal_error" } }),
     }
 }
 
-fn should_refresh_codex_token(status: StatusCode, refresh_token: Option<&str>) -> bool {
-    matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
-        && refresh_token
-            .map(|t| !t.is_empty())
-            .unwrap_or(false)
-}
-
 async fn send_codex_request(
     client: &reqwest::Client,
     endpoint: &str,
@@ -1,13 +1,15 @@
 // Gemini Handler
-use axum::{extract::State, extract::{Json, Path}, http::StatusCode, response::IntoResponse};
+use axum::{extract::State, extract::{Json, Path}, http::{HeaderMap, StatusCode}, response::{IntoResponse, Response}};
 use serde_json::{json, Value};
 use tracing::{debug, error, info};
 
 use crate::proxy::mappers::gemini::{wrap_request, unwrap_response};
+use crate::proxy::pm_router::{self, RouterInput, RouterProtocol};
 use crate::proxy::server::AppState;
 use crate::proxy::session_manager::SessionManager;
 use crate::proxy::handlers::common::{determine_retry_strategy, apply_retry_strategy, should_rotate_account, RetryStrategy};
 use crate::proxy::debug_logger;
+use crate::proxy::request_context::ProviderOverride;
 use tokio::time::Duration;
  
 const MAX_RETRY_ATTEMPTS: usize = 3;
@@ -15,6 +17,38 @@ const MAX_RETRY_ATTEMPTS: usize = 3;
 /// 处理 generateContent 和 streamGenerateContent
 /// 路径参数: model_name, method (e.g. "gemini-pro", "generateContent")
 pub async fn handle_generate(
+    State(state): State<AppState>,
+    Path(model_action): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    // PM Router: 路由结果替换路径中的模型名
+    let (model_name, action) = match model_action.rsplit_once(':') {
+        Some((m, a)) => (m.to_string(), Some(a.to_string())),
+        None => (model_action.clone(), None),
+    };
+    let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+    let pm_selected_model = pm_router::route_request(
+        &state,
+        &headers,
+        &RouterInput::from_gemini(&model_name, &body),
+        RouterProtocol::Gemini,
+        &trace_id,
+    )
+    .await;
+    let model_action = match (&pm_selected_model, action) {
+        (Some(selected), Some(action)) => format!("{}:{}", selected, action),
+        (Some(selected), None) => selected.clone(),
+        (None, _) => model_action,
+    };
+
+    let response = generate(State(state), Path(model_action), Json(body))
+        .await
+        .into_response();
+    pm_router::with_selected_model_header(response, pm_selected_model)
+}
+
+async fn generate(
     State(state): State<AppState>,
     Path(model_action): Path<String>,
     Json(mut body): Json<Value>  // 改为 mut 以支持修复提示词注入
@@ -34,6 +68,9 @@ pub async fn handle_generate(
     if method != "generateContent" && method != "streamGenerateContent" {
         return Err((StatusCode::BAD_REQUEST, format!("Unsupported method: {}", method)));
     }
+    // [FIX] Gemini 协议只走 Google 账号池: google 即默认行为，其它 Provider 直接拒绝
+    crate::proxy::request_context::check_provider_override("Gemini", &[ProviderOverride::Google])
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
     if debug_logger::is_enabled(&debug_cfg) {
         let original_payload = json!({
             "kind": "original_request",
@@ -423,12 +460,12 @@ pub async fn handle_list_models(State(state): State<AppState>) -> Result<impl In
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
     // 获取所有动态模型列表（与 /v1/models 一致）
-    let model_ids = get_all_dynamic_models(
-        &state.custom_mapping,
-    ).await;
+    let discovery = state.token_manager.get_model_discovery().await;
+    let listed = get_all_dynamic_models(&state.custom_mapping, &discovery).await;
 
     // 转换为 Gemini API 格式
-    let models: Vec<_> = model_ids.into_iter().map(|id| {
+    let models: Vec<_> = listed.into_iter().map(|m| {
+        let id = m.id;
         json!({
             "name": format!("models/{}", id),
             "version": "001",
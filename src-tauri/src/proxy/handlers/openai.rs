@@ -1,6 +1,7 @@
 // OpenAI Handler
 use axum::{
-    extract::Json, extract::State, http::StatusCode, response::IntoResponse, response::Response,
+    extract::Json, extract::State, http::HeaderMap, http::StatusCode, response::IntoResponse,
+    response::Response,
 };
 use base64::Engine as _;
 use bytes::Bytes;
@@ -10,12 +11,14 @@ use tracing::{debug, error, info}; // Import Engine trait for encode method
 
 use crate::proxy::common::model_mapping::resolve_codex_model;
 use crate::proxy::handlers::codex; // [NEW] Codex handler
+use crate::proxy::pm_router::{self, RouterInput, RouterProtocol};
 use crate::proxy::mappers::openai::{
     transform_openai_request, transform_openai_response, OpenAIRequest,
 };
 // use crate::proxy::upstream::client::UpstreamClient; // 通过 state 获取
 use crate::proxy::server::AppState;
 use crate::proxy::debug_logger;
+use crate::proxy::request_context::ProviderOverride;
 use reqwest::header::USER_AGENT;
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
@@ -36,6 +39,7 @@ async fn handle_codex_passthrough(
     endpoint: &str,
 ) -> Result<Response, (StatusCode, String)> {
     let model_to_send = resolve_codex_model(model);
+    let model_to_send = model_to_send.as_str();
     body["model"] = serde_json::json!(model_to_send);
 
     let token_manager = state.token_manager.clone();
@@ -152,7 +156,34 @@ async fn handle_codex_passthrough(
     ))
 }
 
+/// PM Router: 按请求内容改写 body.model，返回路由选中的模型
+async fn apply_pm_router(state: &AppState, headers: &HeaderMap, body: &mut Value) -> Option<String> {
+    let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+    let selected = pm_router::route_request(
+        state,
+        headers,
+        &RouterInput::from_openai(body),
+        RouterProtocol::OpenAI,
+        &trace_id,
+    )
+    .await?;
+    body["model"] = Value::String(selected.clone());
+    Some(selected)
+}
+
 pub async fn handle_chat_completions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut body): Json<Value>,
+) -> Response {
+    let pm_selected_model = apply_pm_router(&state, &headers, &mut body).await;
+    let response = chat_completions(State(state), Json(body))
+        .await
+        .into_response();
+    pm_router::with_selected_model_header(response, pm_selected_model)
+}
+
+async fn chat_completions(
     State(state): State<AppState>,
     Json(mut body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
@@ -212,15 +243,22 @@ pub async fn handle_chat_completions(
     let mut openai_req: OpenAIRequest = serde_json::from_value(body.clone())
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))?;
 
+    // [NEW] 请求头 x-antigravity-provider: google (需授权) 跳过 Codex 自动路由；zai 仅支持 Anthropic 协议
+    crate::proxy::request_context::check_provider_override("OpenAI", &[ProviderOverride::Google])
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let force_google = crate::proxy::request_context::current_overrides().provider
+        == Some(ProviderOverride::Google);
+
     // [Restored] Auto-Route Codex models to Codex handler
-    if codex::should_use_codex(&openai_req.model) {
+    if !force_google && codex::should_use_codex(&openai_req.model) {
         info!("[Auto-Route] 🔀 {} → Codex (ChatGPT)", openai_req.model);
-        return codex::handle_codex_chat(State(state), Json(body))
+        let session_id = SessionManager::extract_openai_session_id(&openai_req);
+        return codex::handle_codex_chat(State(state), Json(body), session_id)
             .await
             .map(|r| r.into_response());
     }
 
-    if crate::proxy::common::model_mapping::is_codex_model(&openai_req.model) {
+    if !force_google && crate::proxy::common::model_mapping::is_codex_model(&openai_req.model) {
         let session_id = SessionManager::extract_openai_session_id(&openai_req);
         return handle_codex_passthrough(
             &state,
@@ -761,8 +799,15 @@ pub async fn handle_chat_completions(
 /// 将 Prompt 转换为 Chat Message 格式，复用 handle_chat_completions
 pub async fn handle_completions(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(mut body): Json<Value>,
 ) -> Response {
+    let pm_selected_model = apply_pm_router(&state, &headers, &mut body).await;
+    let response = completions(State(state), Json(body)).await;
+    pm_router::with_selected_model_header(response, pm_selected_model)
+}
+
+async fn completions(State(state): State<AppState>, Json(mut body): Json<Value>) -> Response {
     debug!(
         "Received /v1/completions or /v1/responses payload: {:?}",
         body
@@ -770,8 +815,13 @@ pub async fn handle_completions(
 
     let is_codex_style = body.get("input").is_some() || body.get("instructions").is_some();
 
+    if let Err(e) = crate::proxy::request_context::check_provider_override("OpenAI", &[ProviderOverride::Google]) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    let force_google = crate::proxy::request_context::current_overrides().provider
+        == Some(ProviderOverride::Google);
     if let Some(model) = body.get("model").and_then(|v| v.as_str()).map(String::from) {
-        if crate::proxy::common::model_mapping::is_codex_model(&model) {
+        if !force_google && crate::proxy::common::model_mapping::is_codex_model(&model) {
             let openai_req: OpenAIRequest = match serde_json::from_value(body.clone()) {
                 Ok(req) => req,
                 Err(e) => {
@@ -1586,17 +1636,23 @@ pub async fn handle_completions(
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
-    let model_ids = get_all_dynamic_models(&state.custom_mapping).await;
+    let discovery = state.token_manager.get_model_discovery().await;
+    let models = get_all_dynamic_models(&state.custom_mapping, &discovery).await;
 
-    let data: Vec<_> = model_ids
+    let data: Vec<_> = models
         .into_iter()
-        .map(|id| {
-            json!({
-                "id": id,
+        .map(|m| {
+            let mut item = json!({
+                "id": m.id,
                 "object": "model",
                 "created": 1706745600,
                 "owned_by": "antigravity"
-            })
+            });
+            // [NEW] 可服务该模型的账号数 (模型发现)
+            if let Some(accounts) = m.accounts {
+                item["accounts"] = json!(accounts);
+            }
+            item
         })
         .collect();
 
@@ -221,6 +221,7 @@ pub async fn handle_warmup(
                 model: Some(req.model.clone()),
                 mapped_model: Some(req.model.clone()),
                 pm_selected_model: None,
+                thinking_budget: None,
                 account_email: Some(req.email.clone()),
                 client_ip: Some("127.0.0.1".to_string()),
                 error: if status.is_success() { None } else { Some(format!("HTTP {}", status_code)) },
@@ -229,6 +230,8 @@ pub async fn handle_warmup(
                 input_tokens: Some(0),
                 output_tokens: Some(0),
                 protocol: Some("warmup".to_string()),
+                redaction_count: None,
+                guard_result: None,
             };
             state.monitor.log_request(log).await;
 
@@ -286,6 +289,7 @@ pub async fn handle_warmup(
                 model: Some(req.model.clone()),
                 mapped_model: Some(req.model.clone()),
                 pm_selected_model: None,
+                thinking_budget: None,
                 account_email: Some(req.email.clone()),
                 client_ip: Some("127.0.0.1".to_string()),
                 error: Some(e.clone()),
@@ -294,6 +298,8 @@ pub async fn handle_warmup(
                 input_tokens: None,
                 output_tokens: None,
                 protocol: Some("warmup".to_string()),
+                redaction_count: None,
+                guard_result: None,
             };
             state.monitor.log_request(log).await;
 
@@ -404,9 +404,6 @@ pub fn transform_claude_request_in(
         build_system_instruction(&claude_req.system, &claude_req.model, has_mcp_tools);
 
     //  Map model name (Use standard mapping)
-    // [IMPROVED] 提取 web search 模型为常量，便于维护
-    const WEB_SEARCH_FALLBACK_MODEL: &str = "gemini-2.5-flash";
-
     let mapped_model = if has_web_search_tool {
         tracing::debug!(
             "[Claude-Request] Web search tool detected, using fallback model: {}",
@@ -529,6 +526,20 @@ pub fn transform_claude_request_in(
         }
     }
 
+    // [NEW] 按模型 / API Key 策略禁用 thinking (需在构建历史消息前决定，以便一并降级)
+    if is_thinking_enabled {
+        let policy_model = if has_web_search_tool {
+            WEB_SEARCH_FALLBACK_MODEL
+        } else {
+            claude_req.model.as_str()
+        };
+        if crate::proxy::common::thinking_policy::is_disabled(policy_model) {
+            tracing::info!("[Thinking-Mode] Thinking disabled by policy for model {}", policy_model);
+            crate::proxy::request_context::record_thinking_budget(0);
+            is_thinking_enabled = false;
+        }
+    }
+
     // 4. Generation Config & Thinking (Pass final is_thinking_enabled)
     let generation_config =
         build_generation_config(claude_req, has_web_search_tool, is_thinking_enabled);
@@ -1223,9 +1234,28 @@ fn build_contents(
 
                         // [FIX #593] 工具输出压缩: 处理超大工具输出
                         // 使用智能压缩策略(浏览器快照、大文件提示等)
+                        // [NEW] 按工具名选择压缩策略 (JSON / 日志 / grep / diff) 与字符预算
                         let mut compacted_content = content.clone();
-                        if let Some(blocks) = compacted_content.as_array_mut() {
-                            tool_result_compressor::sanitize_tool_result_blocks(blocks);
+                        match &mut compacted_content {
+                            serde_json::Value::Array(blocks) => {
+                                tool_result_compressor::sanitize_tool_result_blocks_for_tool(
+                                    blocks, &func_name,
+                                );
+                            }
+                            // 字符串形式的 content 原先不做处理；仅在启用按工具压缩时
+                            // 按同一字符预算压缩，关闭 tool_result_compression.enabled 即恢复原样透传
+                            serde_json::Value::String(text) => {
+                                let config = crate::proxy::config::get_tool_result_compression_config();
+                                let (_, max_chars) = crate::proxy::common::tool_result_strategies::resolve(
+                                    &func_name, &config,
+                                );
+                                if config.enabled && text.len() > max_chars {
+                                    *text = tool_result_compressor::compact_tool_result_for(
+                                        &func_name, text, max_chars,
+                                    );
+                                }
+                            }
+                            _ => {}
                         }
 
                         // Smart Truncation: strict image removal
@@ -1672,6 +1702,9 @@ fn build_tools(tools: &Option<Vec<Tool>>, has_web_search: bool) -> Result<Option
     Ok(None)
 }
 
+// [IMPROVED] 提取 web search 模型为常量，便于维护
+const WEB_SEARCH_FALLBACK_MODEL: &str = "gemini-2.5-flash";
+
 /// 构建 Generation Config
 fn build_generation_config(
     claude_req: &ClaudeRequest,
@@ -1687,53 +1720,40 @@ fn build_generation_config(
             let mut thinking_config = json!({"includeThoughts": true});
 
             if let Some(budget_tokens) = thinking.budget_tokens {
-                // [CONFIGURABLE] 根据用户配置决定 thinking_budget 处理方式
-                let tb_config = crate::proxy::config::get_thinking_budget_config();
-                let budget = match tb_config.mode {
-                    crate::proxy::config::ThinkingBudgetMode::Passthrough => {
-                        // 直接透传调用方传入的值
-                        tracing::debug!(
-                            "[Thinking-Budget] Passthrough mode: using caller's budget {}",
-                            budget_tokens
-                        );
-                        budget_tokens
-                    }
-                    crate::proxy::config::ThinkingBudgetMode::Custom => {
-                        // 使用用户设定的固定值
-                        let custom_value = tb_config.custom_value;
-                        tracing::debug!(
-                            "[Thinking-Budget] Custom mode: overriding {} with fixed value {}",
-                            budget_tokens,
-                            custom_value
-                        );
-                        custom_value
-                    }
-                    crate::proxy::config::ThinkingBudgetMode::Auto => {
-                        // 保留原有的自动限制逻辑 (向后兼容)
-                        let model_lower = claude_req.model.to_lowercase();
-                        let is_gemini_limited = has_web_search
-                            || model_lower.contains("flash")
-                            || model_lower.ends_with("-thinking");
-                        if is_gemini_limited {
-                            let capped = budget_tokens.min(24576);
-                            if capped < budget_tokens {
-                                tracing::debug!(
-                                    "[Thinking-Budget] Auto mode: capping {} to {} for model {}",
-                                    budget_tokens,
-                                    capped,
-                                    claude_req.model
-                                );
-                            }
-                            capped
-                        } else {
-                            budget_tokens
+                // [CONFIGURABLE] 按模型 / API Key 策略决定 thinking_budget (未命中策略时沿用全局 mode)
+                // 联网搜索走 Flash 回退模型
+                let policy_model = if has_web_search {
+                    WEB_SEARCH_FALLBACK_MODEL
+                } else {
+                    claude_req.model.as_str()
+                };
+                let decision = crate::proxy::common::thinking_policy::resolve_thinking_budget(
+                    policy_model,
+                    budget_tokens,
+                    || crate::proxy::mappers::context_manager::ContextManager::estimate_token_usage(claude_req),
+                );
+                crate::proxy::request_context::record_thinking_budget(decision.as_recorded());
+                match decision {
+                    crate::proxy::common::thinking_policy::ThinkingDecision::Budget(budget) => {
+                        if budget != budget_tokens {
+                            tracing::debug!(
+                                "[Thinking-Budget] Adjusted {} -> {} for model {}",
+                                budget_tokens,
+                                budget,
+                                policy_model
+                            );
                         }
+                        thinking_config["thinkingBudget"] = json!(budget);
                     }
-                };
-                thinking_config["thinkingBudget"] = json!(budget);
+                    crate::proxy::common::thinking_policy::ThinkingDecision::Disabled => {
+                        thinking_config = Value::Null;
+                    }
+                }
             }
 
-            config["thinkingConfig"] = thinking_config;
+            if !thinking_config.is_null() {
+                config["thinkingConfig"] = thinking_config;
+            }
         }
     }
 
@@ -101,6 +101,11 @@ impl ContextManager {
 }
 
 impl ContextManager {
+    /// Estimate token usage of plain text (OpenAI / Gemini bodies flattened to text)
+    pub fn estimate_text_usage(text: &str) -> u32 {
+        estimate_tokens_from_str(text)
+    }
+
     /// Estimate token usage for a Claude Request
     ///
     /// This is a lightweight estimation, not a precise count.
@@ -308,6 +313,29 @@ impl ContextManager {
         None
     }
 
+    // ===== [Layer 3 Helper] Message Prefix Hashes =====
+    // Used by Layer 3 to find a persisted summary that covers a prefix of this conversation
+
+    /// Compute chained SHA-256 hashes for every message prefix
+    ///
+    /// `hashes[i]` identifies `messages[..=i]`; appending messages never changes earlier entries,
+    /// so a summary stored for an older turn can be matched against the current history.
+    pub fn prefix_hashes(messages: &[Message]) -> Vec<String> {
+        use sha2::{Digest, Sha256};
+
+        let mut hashes = Vec::with_capacity(messages.len());
+        let mut prev = String::new();
+        for msg in messages {
+            let mut hasher = Sha256::new();
+            hasher.update(prev.as_bytes());
+            hasher.update(msg.role.as_bytes());
+            hasher.update(serde_json::to_vec(&msg.content).unwrap_or_default());
+            prev = format!("{:x}", hasher.finalize());
+            hashes.push(prev.clone());
+        }
+        hashes
+    }
+
     // ===== [Layer 1] Tool Message Intelligent Trimming =====
     // Borrowed from Practical-Guide-to-Context-Engineering
     // This layer removes old tool call/result pairs while preserving recent ones
@@ -474,6 +502,34 @@ mod tests {
         assert!(tokens < 50);
     }
 
+    #[test]
+    fn test_prefix_hashes_stable_on_append() {
+        let mut messages = vec![
+            Message {
+                role: "user".into(),
+                content: MessageContent::String("Refactor the parser".into()),
+            },
+            Message {
+                role: "assistant".into(),
+                content: MessageContent::String("Done".into()),
+            },
+        ];
+        let before = ContextManager::prefix_hashes(&messages);
+        messages.push(Message {
+            role: "user".into(),
+            content: MessageContent::String("Now add tests".into()),
+        });
+        let after = ContextManager::prefix_hashes(&messages);
+
+        assert_eq!(after.len(), 3);
+        assert_eq!(&after[..2], &before[..]);
+
+        // 修改历史消息后，其后的所有前缀哈希都会变化
+        messages[0].content = MessageContent::String("Rewrite the parser".into());
+        let edited = ContextManager::prefix_hashes(&messages);
+        assert!(edited.iter().zip(after.iter()).all(|(a, b)| a != b));
+    }
+
     #[test]
     fn test_purify_history_soft() {
         // Construct history of 6 messages (indices 0-5)
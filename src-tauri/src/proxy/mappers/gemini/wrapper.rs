@@ -55,9 +55,46 @@ pub fn wrap_request(
         }
     }
 
+    // [NEW] 按模型 / API Key 的显式 Thinking 策略优先 (未命中时走下方全局配置逻辑)
+    let mut policy_applied = false;
+    let requested_budget = inner_request
+        .pointer("/generationConfig/thinkingConfig/thinkingBudget")
+        .and_then(|v| v.as_u64());
+    if let Some(requested) = requested_budget {
+        crate::proxy::request_context::record_thinking_budget(requested as u32);
+        let contents_len = inner_request
+            .get("contents")
+            .map(|c| c.to_string().len())
+            .unwrap_or(0);
+        if let Some(decision) = crate::proxy::common::thinking_policy::policy_decision(
+            final_model_name,
+            requested as u32,
+            || (contents_len / 4) as u32,
+        ) {
+            policy_applied = true;
+            crate::proxy::request_context::record_thinking_budget(decision.as_recorded());
+            if let Some(gen_config) = inner_request
+                .get_mut("generationConfig")
+                .and_then(|g| g.as_object_mut())
+            {
+                match decision {
+                    crate::proxy::common::thinking_policy::ThinkingDecision::Budget(budget) => {
+                        gen_config["thinkingConfig"]["thinkingBudget"] = json!(budget);
+                    }
+                    crate::proxy::common::thinking_policy::ThinkingDecision::Disabled => {
+                        gen_config.remove("thinkingConfig");
+                    }
+                }
+            }
+        }
+    }
+
     // [FIX Issue #1355] Gemini Flash thinking budget capping
     // [CONFIGURABLE] 现在改为遵循全局 Thinking Budget 配置
-    if final_model_name.to_lowercase().contains("flash") {
+    // 上限由模型注册表的 thinking_budget_cap 决定
+    if let Some(budget_cap) = crate::proxy::common::model_registry::thinking_budget_cap(final_model_name)
+        .filter(|_| !policy_applied)
+    {
         if let Some(gen_config) = inner_request.get_mut("generationConfig") {
             if let Some(thinking_config) = gen_config.get_mut("thinkingConfig") {
                 if let Some(budget_val) = thinking_config.get("thinkingBudget") {
@@ -84,13 +121,14 @@ pub fn wrap_request(
                                 custom_value
                             }
                             crate::proxy::config::ThinkingBudgetMode::Auto => {
-                                // 自动模式：应用 24576 capping (向后兼容)
-                                if budget > 24576 {
+                                // 自动模式：应用注册表上限
+                                let budget_cap = budget_cap as u64;
+                                if budget > budget_cap {
                                     tracing::info!(
-                                        "[Gemini-Wrap] Auto mode: capping thinking_budget from {} to 24576 for model {}", 
-                                        budget, final_model_name
+                                        "[Gemini-Wrap] Auto mode: capping thinking_budget from {} to {} for model {}", 
+                                        budget, budget_cap, final_model_name
                                     );
-                                    24576
+                                    budget_cap
                                 } else {
                                     budget
                                 }
@@ -100,6 +138,7 @@ pub fn wrap_request(
                         if final_budget != budget {
                             thinking_config["thinkingBudget"] = json!(final_budget);
                         }
+                        crate::proxy::request_context::record_thinking_budget(final_budget as u32);
                     }
                 }
             }
@@ -8,5 +8,6 @@ pub mod error_classifier;
 pub mod estimation_calibrator;
 pub mod gemini;
 pub mod openai;
+pub mod responses;
 pub mod signature_store;
 pub mod tool_result_compressor;
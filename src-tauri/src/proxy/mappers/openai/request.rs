@@ -63,6 +63,13 @@ pub fn transform_openai_request(
         actual_include_thinking = false;
     }
     
+    // [NEW] 按模型 / API Key 策略禁用 thinking
+    if actual_include_thinking && crate::proxy::common::thinking_policy::is_disabled(mapped_model) {
+        tracing::info!("[OpenAI-Thinking] Thinking disabled by policy for model {}", mapped_model);
+        crate::proxy::request_context::record_thinking_budget(0);
+        actual_include_thinking = false;
+    }
+
     // [NEW] 日志：用户显式设置 thinking
     if user_enabled_thinking {
         tracing::info!(
@@ -393,46 +400,22 @@ pub fn transform_openai_request(
 
     // 为 thinking 模型注入 thinkingConfig (使用 thinkingBudget 而非 thinkingLevel)
     if actual_include_thinking {
-        // [CONFIGURABLE] 根据用户配置决定 thinking_budget 处理方式
-        let tb_config = crate::proxy::config::get_thinking_budget_config();
-        let user_budget: i64 = user_thinking_budget.map(|b| b as i64).unwrap_or(32000);
-        
-        let budget = match tb_config.mode {
-            crate::proxy::config::ThinkingBudgetMode::Passthrough => {
-                // 透传模式：使用用户传入的值，不做任何限制
-                tracing::debug!(
-                    "[OpenAI-Request] Passthrough mode: using caller's budget {}",
-                    user_budget
-                );
-                user_budget
-            }
-            crate::proxy::config::ThinkingBudgetMode::Custom => {
-                // 自定义模式：使用全局配置的固定值
-                let custom_value = tb_config.custom_value as i64;
-                tracing::debug!(
-                    "[OpenAI-Request] Custom mode: overriding {} with fixed value {}",
-                    user_budget,
-                    custom_value
-                );
-                custom_value
-            }
-            crate::proxy::config::ThinkingBudgetMode::Auto => {
-                // 自动模式：保持原有 Flash capping 逻辑 (向后兼容)
-                let is_gemini_limited = mapped_model_lower.contains("flash") 
-                    || mapped_model_lower.contains("gemini-1.5")
-                    || is_claude_thinking;  // Claude thinking 模型转发到 Gemini，同样需要限流
-                
-                if is_gemini_limited && user_budget > 24576 {
-                    tracing::info!(
-                        "[OpenAI-Request] Auto mode: capping thinking budget from {} to 24576 for model: {}", 
-                        user_budget, mapped_model
-                    );
-                    24576
-                } else {
-                    user_budget
-                }
-            }
-        };
+        // [CONFIGURABLE] 按模型 / API Key 策略决定 thinking_budget (未命中策略时沿用全局 mode)
+        let user_budget = user_thinking_budget.unwrap_or(32000);
+        let decision = crate::proxy::common::thinking_policy::resolve_thinking_budget(
+            mapped_model,
+            user_budget,
+            || (serde_json::to_string(&contents).map(|s| s.len()).unwrap_or(0) / 4) as u32,
+        );
+        crate::proxy::request_context::record_thinking_budget(decision.as_recorded());
+        // Disabled 已在构建历史消息前处理，这里只会得到具体数值
+        let budget = decision.as_recorded() as i64;
+        if budget != user_budget as i64 {
+            tracing::info!(
+                "[OpenAI-Request] Thinking budget adjusted from {} to {} for model: {}",
+                user_budget, budget, mapped_model
+            );
+        }
 
         gen_config["thinkingConfig"] = json!({
             "includeThoughts": true,
@@ -452,8 +435,8 @@ pub fn transform_openai_request(
         }
         
         tracing::debug!(
-            "[OpenAI-Request] Injected thinkingConfig for model {}: thinkingBudget={} (mode={:?})",
-            mapped_model, budget, tb_config.mode
+            "[OpenAI-Request] Injected thinkingConfig for model {}: thinkingBudget={}",
+            mapped_model, budget
         );
     }
 
@@ -0,0 +1,124 @@
+// Responses mapper 模块
+// 负责 Claude Messages ↔ OpenAI Responses 协议转换 (Codex 账号服务 /v1/messages)
+
+pub mod request;
+pub mod response;
+pub mod streaming;
+
+pub use request::transform_claude_request_to_responses;
+pub use response::transform_responses_to_claude;
+pub use streaming::create_claude_sse_stream_from_responses;
+
+/// Codex 推理块签名前缀：`openai-reasoning:<item_id>:<encrypted_content>`
+/// 客户端把 thinking.signature 原样带回时据此还原为 Responses 的 reasoning item
+const REASONING_SIGNATURE_PREFIX: &str = "openai-reasoning:";
+
+pub(crate) fn encode_reasoning_signature(item_id: &str, encrypted_content: &str) -> String {
+    format!("{}{}:{}", REASONING_SIGNATURE_PREFIX, item_id, encrypted_content)
+}
+
+pub(crate) fn decode_reasoning_signature(signature: &str) -> Option<(&str, &str)> {
+    let rest = signature.strip_prefix(REASONING_SIGNATURE_PREFIX)?;
+    let (id, encrypted) = rest.split_once(':')?;
+    (!id.is_empty() && !encrypted.is_empty()).then_some((id, encrypted))
+}
+
+fn image_data_url(media_type: &str, data: &str) -> String {
+    format!("data:{};base64,{}", media_type, data)
+}
+
+/// Responses 的 usage → Claude usage (input_tokens 不含缓存命中部分)
+fn to_claude_usage(usage: Option<&serde_json::Value>) -> serde_json::Value {
+    let get = |key: &str| {
+        usage
+            .and_then(|u| u.get(key))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0)
+    };
+    let cached = usage
+        .and_then(|u| u.get("input_tokens_details"))
+        .and_then(|d| d.get("cached_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+    serde_json::json!({
+        "input_tokens": get("input_tokens").saturating_sub(cached),
+        "output_tokens": get("output_tokens"),
+        "cache_read_input_tokens": cached,
+        "cache_creation_input_tokens": 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::mappers::claude::models::ClaudeRequest;
+    use serde_json::json;
+
+    #[test]
+    fn test_reasoning_signature_roundtrip() {
+        let sig = encode_reasoning_signature("rs_123", "gAAAAB:xyz");
+        assert_eq!(decode_reasoning_signature(&sig), Some(("rs_123", "gAAAAB:xyz")));
+        assert_eq!(decode_reasoning_signature("EqQBCkgIARABGAIiQ..."), None);
+    }
+
+    #[test]
+    fn test_claude_request_to_responses_items() {
+        let request: ClaudeRequest = serde_json::from_value(json!({
+            "model": "claude-sonnet-4-5",
+            "system": [{ "type": "text", "text": "You are Claude Code." }],
+            "max_tokens": 2048,
+            "stream": true,
+            "thinking": { "type": "enabled", "budget_tokens": 20000 },
+            "tools": [{ "name": "Bash", "description": "run", "input_schema": { "type": "object" } }],
+            "messages": [
+                { "role": "user", "content": [
+                    { "type": "text", "text": "what is in this image?" },
+                    { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "AAAA" } }
+                ]},
+                { "role": "assistant", "content": [
+                    { "type": "thinking", "thinking": "plan", "signature": encode_reasoning_signature("rs_1", "enc") },
+                    { "type": "thinking", "thinking": "gemini", "signature": "not-ours" },
+                    { "type": "text", "text": "Let me check." },
+                    { "type": "tool_use", "id": "call_1", "name": "Bash", "input": { "command": "ls" } }
+                ]},
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "call_1", "content": [
+                        { "type": "text", "text": "a.png" },
+                        { "type": "image", "source": { "type": "base64", "media_type": "image/jpeg", "data": "BBBB" } }
+                    ]},
+                    { "type": "text", "text": "continue" }
+                ]}
+            ]
+        }))
+        .unwrap();
+
+        let body = transform_claude_request_to_responses(&request, "gpt-5.2-codex");
+        assert_eq!(body["model"], "gpt-5.2-codex");
+        assert_eq!(body["instructions"], "You are Claude Code.");
+        assert_eq!(body["max_output_tokens"], 2048);
+        assert_eq!(body["reasoning"]["effort"], "high");
+        assert_eq!(body["tools"][0]["name"], "Bash");
+        assert_eq!(body["store"], false);
+
+        let types: Vec<&str> = body["input"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            vec!["message", "reasoning", "message", "function_call", "function_call_output", "message"]
+        );
+
+        let input = &body["input"];
+        assert_eq!(input[0]["content"][1]["image_url"], "data:image/png;base64,AAAA");
+        assert_eq!(input[1]["encrypted_content"], "enc");
+        assert_eq!(input[2]["content"][0]["type"], "output_text");
+        assert_eq!(input[3]["arguments"], r#"{"command":"ls"}"#);
+        assert_eq!(input[4]["output"], "a.png");
+        // 工具返回的图片 + 后续文本合并为一条用户消息
+        assert_eq!(input[5]["content"][0]["type"], "input_image");
+        assert_eq!(input[5]["content"][1]["text"], "continue");
+    }
+}
@@ -0,0 +1,235 @@
+// Claude Messages → OpenAI Responses 请求转换
+// 供 /v1/messages 经 Codex (gpt-5.x-codex) 账号处理
+
+use serde_json::{json, Value};
+
+use super::{decode_reasoning_signature, image_data_url};
+use crate::proxy::mappers::claude::models::{
+    ClaudeRequest, ContentBlock, Message, MessageContent, SystemPrompt,
+};
+
+/// 将 Claude 请求转换为 Responses API 请求体
+pub fn transform_claude_request_to_responses(request: &ClaudeRequest, model: &str) -> Value {
+    let mut input = Vec::new();
+    for message in &request.messages {
+        push_message_items(&mut input, message);
+    }
+
+    let mut body = json!({
+        "model": model,
+        "input": input,
+        "stream": request.stream,
+        // Codex 账号不保存会话状态：推理上下文通过 encrypted_content 在客户端往返
+        "store": false,
+        "parallel_tool_calls": true,
+    });
+
+    if let Some(instructions) = system_text(request) {
+        body["instructions"] = json!(instructions);
+    }
+
+    let tools: Vec<Value> = request
+        .tools
+        .iter()
+        .flatten()
+        .filter(|t| !t.is_web_search())
+        .filter_map(|t| {
+            let name = t.name.as_ref()?;
+            Some(json!({
+                "type": "function",
+                "name": name,
+                "description": t.description.clone().unwrap_or_default(),
+                "parameters": t.input_schema.clone().unwrap_or_else(|| json!({ "type": "object", "properties": {} })),
+                "strict": false,
+            }))
+        })
+        .collect();
+    if !tools.is_empty() {
+        body["tools"] = json!(tools);
+        body["tool_choice"] = json!("auto");
+    }
+
+    if let Some(effort) = reasoning_effort(request) {
+        body["reasoning"] = json!({ "effort": effort, "summary": "auto" });
+        body["include"] = json!(["reasoning.encrypted_content"]);
+    }
+
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_output_tokens"] = json!(max_tokens);
+    }
+
+    body
+}
+
+fn system_text(request: &ClaudeRequest) -> Option<String> {
+    let text = match request.system.as_ref()? {
+        SystemPrompt::String(s) => s.clone(),
+        SystemPrompt::Array(blocks) => blocks
+            .iter()
+            .map(|b| b.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// thinking.budget_tokens / output_config.effort → reasoning.effort
+fn reasoning_effort(request: &ClaudeRequest) -> Option<&'static str> {
+    if let Some(effort) = request.output_config.as_ref().and_then(|c| c.effort.as_deref()) {
+        return match effort {
+            "low" => Some("low"),
+            "high" => Some("high"),
+            _ => Some("medium"),
+        };
+    }
+
+    let thinking = request.thinking.as_ref().filter(|t| t.type_ == "enabled")?;
+    Some(match thinking.budget_tokens.unwrap_or(0) {
+        0 => "medium",
+        b if b < 4096 => "low",
+        b if b < 16384 => "medium",
+        _ => "high",
+    })
+}
+
+/// 累积连续的 message content，遇到 function_call / reasoning 等独立 item 时先落盘
+struct MessageBuffer<'a> {
+    role: &'a str,
+    parts: Vec<Value>,
+}
+
+impl<'a> MessageBuffer<'a> {
+    fn flush(&mut self, input: &mut Vec<Value>) {
+        if self.parts.is_empty() {
+            return;
+        }
+        input.push(json!({
+            "type": "message",
+            "role": self.role,
+            "content": std::mem::take(&mut self.parts),
+        }));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let part_type = if self.role == "assistant" { "output_text" } else { "input_text" };
+        self.parts.push(json!({ "type": part_type, "text": text }));
+    }
+}
+
+fn push_message_items(input: &mut Vec<Value>, message: &Message) {
+    let role = if message.role == "assistant" { "assistant" } else { "user" };
+    let mut buffer = MessageBuffer { role, parts: Vec::new() };
+
+    let blocks = match &message.content {
+        MessageContent::String(s) => {
+            buffer.push_text(s);
+            buffer.flush(input);
+            return;
+        }
+        MessageContent::Array(blocks) => blocks,
+    };
+
+    for block in blocks {
+        match block {
+            ContentBlock::Text { text } => buffer.push_text(text),
+            ContentBlock::Image { source, .. } if role == "user" => {
+                buffer.parts.push(json!({
+                    "type": "input_image",
+                    "image_url": image_data_url(&source.media_type, &source.data),
+                }));
+            }
+            ContentBlock::Document { source, .. } if role == "user" => {
+                buffer.parts.push(json!({
+                    "type": "input_file",
+                    "filename": "document.pdf",
+                    "file_data": image_data_url(&source.media_type, &source.data),
+                }));
+            }
+            ContentBlock::Thinking { thinking, signature, .. } => {
+                // 只有来自 Codex 的推理块 (带 encrypted_content) 才能回传
+                if let Some((id, encrypted)) = signature.as_deref().and_then(decode_reasoning_signature) {
+                    buffer.flush(input);
+                    let summary = if thinking.is_empty() {
+                        json!([])
+                    } else {
+                        json!([{ "type": "summary_text", "text": thinking }])
+                    };
+                    input.push(json!({
+                        "type": "reasoning",
+                        "id": id,
+                        "summary": summary,
+                        "encrypted_content": encrypted,
+                    }));
+                }
+            }
+            ContentBlock::ToolUse { id, name, input: args, .. } => {
+                buffer.flush(input);
+                input.push(json!({
+                    "type": "function_call",
+                    "call_id": id,
+                    "name": name,
+                    "arguments": serde_json::to_string(args).unwrap_or_else(|_| "{}".to_string()),
+                }));
+            }
+            ContentBlock::ToolResult { tool_use_id, content, is_error } => {
+                buffer.flush(input);
+                let (text, images) = tool_result_output(content);
+                let output = if is_error.unwrap_or(false) && !text.is_empty() {
+                    format!("Error: {}", text)
+                } else {
+                    text
+                };
+                input.push(json!({
+                    "type": "function_call_output",
+                    "call_id": tool_use_id,
+                    "output": output,
+                }));
+                // function_call_output 只接受文本，工具返回的图片作为紧随其后的用户消息
+                buffer.parts.extend(images);
+            }
+            // 其他块 (redacted_thinking / server tool / 非用户图片) 无法在 Responses 中表达
+            _ => {}
+        }
+    }
+    buffer.flush(input);
+}
+
+/// tool_result.content: 字符串或 [{type:text}, {type:image}] 数组
+fn tool_result_output(content: &Value) -> (String, Vec<Value>) {
+    match content {
+        Value::String(s) => (s.clone(), Vec::new()),
+        Value::Array(blocks) => {
+            let mut texts = Vec::new();
+            let mut images = Vec::new();
+            for block in blocks {
+                match block.get("type").and_then(|v| v.as_str()) {
+                    Some("text") => {
+                        if let Some(t) = block.get("text").and_then(|v| v.as_str()) {
+                            texts.push(t.to_string());
+                        }
+                    }
+                    Some("image") => {
+                        let source = block.get("source");
+                        let media_type = source
+                            .and_then(|s| s.get("media_type"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("image/png");
+                        if let Some(data) = source.and_then(|s| s.get("data")).and_then(|v| v.as_str()) {
+                            images.push(json!({
+                                "type": "input_image",
+                                "image_url": image_data_url(media_type, data),
+                            }));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            (texts.join("\n"), images)
+        }
+        Value::Null => (String::new(), Vec::new()),
+        other => (other.to_string(), Vec::new()),
+    }
+}
@@ -0,0 +1,141 @@
+// OpenAI Responses → Claude Messages 非流式响应转换
+
+use serde_json::{json, Value};
+
+use super::{encode_reasoning_signature, to_claude_usage};
+
+/// 将 Responses API 的响应体转换为 Claude message
+/// `include_thinking`: 客户端未开启 thinking 时丢弃 reasoning item
+pub fn transform_responses_to_claude(response: &Value, model: &str, include_thinking: bool) -> Value {
+    let mut content = Vec::new();
+    let mut used_tool = false;
+
+    for item in response
+        .get("output")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        match item.get("type").and_then(|v| v.as_str()) {
+            Some("reasoning") if include_thinking => {
+                let thinking = item
+                    .get("summary")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.get("text").and_then(|v| v.as_str()))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+                let mut block = json!({ "type": "thinking", "thinking": thinking });
+                if let (Some(id), Some(encrypted)) = (
+                    item.get("id").and_then(|v| v.as_str()),
+                    item.get("encrypted_content").and_then(|v| v.as_str()),
+                ) {
+                    block["signature"] = json!(encode_reasoning_signature(id, encrypted));
+                }
+                content.push(block);
+            }
+            Some("message") => {
+                for part in item
+                    .get("content")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let text = match part.get("type").and_then(|v| v.as_str()) {
+                        Some("output_text") => part.get("text"),
+                        Some("refusal") => part.get("refusal"),
+                        _ => None,
+                    };
+                    if let Some(text) = text.and_then(|v| v.as_str()) {
+                        content.push(json!({ "type": "text", "text": text }));
+                    }
+                }
+            }
+            Some("function_call") => {
+                used_tool = true;
+                let arguments = item.get("arguments").and_then(|v| v.as_str()).unwrap_or("{}");
+                content.push(json!({
+                    "type": "tool_use",
+                    "id": item.get("call_id").or_else(|| item.get("id")).and_then(|v| v.as_str()).unwrap_or_default(),
+                    "name": item.get("name").and_then(|v| v.as_str()).unwrap_or_default(),
+                    "input": serde_json::from_str::<Value>(arguments).unwrap_or_else(|_| json!({})),
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    json!({
+        "id": response.get("id").and_then(|v| v.as_str()).unwrap_or("msg_unknown"),
+        "type": "message",
+        "role": "assistant",
+        "model": model,
+        "content": content,
+        "stop_reason": stop_reason(response, used_tool),
+        "stop_sequence": null,
+        "usage": to_claude_usage(response.get("usage")),
+    })
+}
+
+/// status=incomplete (max_output_tokens) → max_tokens；有工具调用 → tool_use
+pub(crate) fn stop_reason(response: &Value, used_tool: bool) -> &'static str {
+    let incomplete_reason = response
+        .get("incomplete_details")
+        .and_then(|d| d.get("reason"))
+        .and_then(|v| v.as_str());
+    if response.get("status").and_then(|v| v.as_str()) == Some("incomplete")
+        && incomplete_reason == Some("max_output_tokens")
+    {
+        "max_tokens"
+    } else if used_tool {
+        "tool_use"
+    } else {
+        "end_turn"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_responses_output_to_claude_content() {
+        let response = json!({
+            "id": "resp_1",
+            "status": "completed",
+            "output": [
+                { "type": "reasoning", "id": "rs_1", "summary": [{ "type": "summary_text", "text": "Thinking..." }], "encrypted_content": "enc" },
+                { "type": "message", "role": "assistant", "content": [{ "type": "output_text", "text": "Running ls" }] },
+                { "type": "function_call", "call_id": "call_9", "name": "Bash", "arguments": "{\"command\":\"ls\"}" }
+            ],
+            "usage": { "input_tokens": 100, "output_tokens": 20, "input_tokens_details": { "cached_tokens": 40 } }
+        });
+
+        let claude = transform_responses_to_claude(&response, "gpt-5.2-codex", true);
+        assert_eq!(claude["stop_reason"], "tool_use");
+        assert_eq!(claude["content"][0]["type"], "thinking");
+        assert_eq!(claude["content"][0]["signature"], "openai-reasoning:rs_1:enc");
+        assert_eq!(claude["content"][1]["text"], "Running ls");
+        assert_eq!(claude["content"][2]["id"], "call_9");
+        assert_eq!(claude["content"][2]["input"]["command"], "ls");
+        assert_eq!(claude["usage"]["input_tokens"], 60);
+        assert_eq!(claude["usage"]["cache_read_input_tokens"], 40);
+
+        let without_thinking = transform_responses_to_claude(&response, "gpt-5.2-codex", false);
+        assert_eq!(without_thinking["content"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_incomplete_maps_to_max_tokens() {
+        let response = json!({
+            "status": "incomplete",
+            "incomplete_details": { "reason": "max_output_tokens" },
+            "output": []
+        });
+        assert_eq!(
+            transform_responses_to_claude(&response, "m", false)["stop_reason"],
+            "max_tokens"
+        );
+    }
+}
@@ -0,0 +1,418 @@
+// OpenAI Responses SSE → Claude SSE 流式转换
+
+use bytes::Bytes;
+use futures::Stream;
+use serde_json::{json, Value};
+use std::pin::Pin;
+
+use super::response::stop_reason;
+use super::{encode_reasoning_signature, to_claude_usage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenBlock {
+    None,
+    Text,
+    Thinking,
+    ToolUse,
+}
+
+/// Responses 事件 → Claude 事件的状态机
+/// Responses 的 output item 是顺序产出的，一次只映射到一个打开的 Claude content block
+pub struct ResponsesStreamState {
+    model: String,
+    include_thinking: bool,
+    message_start_sent: bool,
+    pub message_stop_sent: bool,
+    block_index: usize,
+    open: OpenBlock,
+    open_item_id: Option<String>,
+    tool_args_streamed: bool,
+    used_tool: bool,
+    last_summary_index: Option<u64>,
+    /// 上游最终 usage (response.completed)
+    pub usage: Option<Value>,
+}
+
+impl ResponsesStreamState {
+    pub fn new(model: &str, include_thinking: bool) -> Self {
+        Self {
+            model: model.to_string(),
+            include_thinking,
+            message_start_sent: false,
+            message_stop_sent: false,
+            block_index: 0,
+            open: OpenBlock::None,
+            open_item_id: None,
+            tool_args_streamed: false,
+            used_tool: false,
+            last_summary_index: None,
+            usage: None,
+        }
+    }
+
+    fn emit(&self, event_type: &str, data: Value) -> Bytes {
+        Bytes::from(format!(
+            "event: {}\ndata: {}\n\n",
+            event_type,
+            serde_json::to_string(&data).unwrap_or_default()
+        ))
+    }
+
+    fn ensure_message_start(&mut self, response: Option<&Value>) -> Vec<Bytes> {
+        if self.message_start_sent {
+            return Vec::new();
+        }
+        self.message_start_sent = true;
+        let id = response
+            .and_then(|r| r.get("id"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("msg_unknown");
+        vec![self.emit(
+            "message_start",
+            json!({
+                "type": "message_start",
+                "message": {
+                    "id": id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": self.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": { "input_tokens": 0, "output_tokens": 0 }
+                }
+            }),
+        )]
+    }
+
+    fn start_block(&mut self, kind: OpenBlock, content_block: Value, item_id: Option<&str>) -> Vec<Bytes> {
+        let mut chunks = self.ensure_message_start(None);
+        chunks.extend(self.end_block());
+        chunks.push(self.emit(
+            "content_block_start",
+            json!({
+                "type": "content_block_start",
+                "index": self.block_index,
+                "content_block": content_block
+            }),
+        ));
+        self.open = kind;
+        self.open_item_id = item_id.map(String::from);
+        chunks
+    }
+
+    fn end_block(&mut self) -> Vec<Bytes> {
+        if self.open == OpenBlock::None {
+            return Vec::new();
+        }
+        let chunk = self.emit(
+            "content_block_stop",
+            json!({ "type": "content_block_stop", "index": self.block_index }),
+        );
+        self.block_index += 1;
+        self.open = OpenBlock::None;
+        self.open_item_id = None;
+        vec![chunk]
+    }
+
+    fn delta(&self, delta: Value) -> Bytes {
+        self.emit(
+            "content_block_delta",
+            json!({ "type": "content_block_delta", "index": self.block_index, "delta": delta }),
+        )
+    }
+
+    fn is_open(&self, kind: OpenBlock, item_id: Option<&str>) -> bool {
+        self.open == kind && (item_id.is_none() || self.open_item_id.as_deref() == item_id)
+    }
+
+    /// 处理一条 Responses 事件 (data JSON)，返回要发送给客户端的 Claude SSE 片段
+    pub fn process_event(&mut self, event: &Value) -> Vec<Bytes> {
+        let event_type = event.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let item_id = event.get("item_id").and_then(|v| v.as_str());
+        let text_delta = event.get("delta").and_then(|v| v.as_str()).unwrap_or("");
+        let mut chunks = Vec::new();
+
+        match event_type {
+            "response.created" | "response.in_progress" => {
+                chunks.extend(self.ensure_message_start(event.get("response")));
+            }
+            "response.output_item.added" => {
+                let item = event.get("item").cloned().unwrap_or(Value::Null);
+                let id = item.get("id").and_then(|v| v.as_str());
+                match item.get("type").and_then(|v| v.as_str()) {
+                    Some("reasoning") if self.include_thinking => {
+                        self.last_summary_index = None;
+                        chunks.extend(self.start_block(
+                            OpenBlock::Thinking,
+                            json!({ "type": "thinking", "thinking": "" }),
+                            id,
+                        ));
+                    }
+                    Some("function_call") => {
+                        self.used_tool = true;
+                        self.tool_args_streamed = false;
+                        let call_id = item
+                            .get("call_id")
+                            .and_then(|v| v.as_str())
+                            .or(id)
+                            .unwrap_or_default();
+                        chunks.extend(self.start_block(
+                            OpenBlock::ToolUse,
+                            json!({
+                                "type": "tool_use",
+                                "id": call_id,
+                                "name": item.get("name").and_then(|v| v.as_str()).unwrap_or_default(),
+                                "input": {}
+                            }),
+                            id,
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+            "response.output_text.delta" | "response.refusal.delta" => {
+                if text_delta.is_empty() {
+                    return chunks;
+                }
+                if !self.is_open(OpenBlock::Text, item_id) {
+                    chunks.extend(self.start_block(
+                        OpenBlock::Text,
+                        json!({ "type": "text", "text": "" }),
+                        item_id,
+                    ));
+                }
+                chunks.push(self.delta(json!({ "type": "text_delta", "text": text_delta })));
+            }
+            "response.reasoning_summary_text.delta" if self.include_thinking => {
+                if !self.is_open(OpenBlock::Thinking, item_id) {
+                    self.last_summary_index = None;
+                    chunks.extend(self.start_block(
+                        OpenBlock::Thinking,
+                        json!({ "type": "thinking", "thinking": "" }),
+                        item_id,
+                    ));
+                }
+                let summary_index = event.get("summary_index").and_then(|v| v.as_u64());
+                let mut thinking = String::new();
+                if self.last_summary_index.is_some() && summary_index != self.last_summary_index {
+                    thinking.push_str("\n\n");
+                }
+                self.last_summary_index = summary_index;
+                thinking.push_str(text_delta);
+                chunks.push(self.delta(json!({ "type": "thinking_delta", "thinking": thinking })));
+            }
+            "response.function_call_arguments.delta" => {
+                if self.open == OpenBlock::ToolUse && !text_delta.is_empty() {
+                    self.tool_args_streamed = true;
+                    chunks.push(self.delta(json!({ "type": "input_json_delta", "partial_json": text_delta })));
+                }
+            }
+            "response.output_item.done" => {
+                let item = event.get("item").cloned().unwrap_or(Value::Null);
+                match item.get("type").and_then(|v| v.as_str()) {
+                    Some("reasoning") if self.open == OpenBlock::Thinking => {
+                        if let (Some(id), Some(encrypted)) = (
+                            item.get("id").and_then(|v| v.as_str()),
+                            item.get("encrypted_content").and_then(|v| v.as_str()),
+                        ) {
+                            chunks.push(self.delta(json!({
+                                "type": "signature_delta",
+                                "signature": encode_reasoning_signature(id, encrypted)
+                            })));
+                        }
+                        chunks.extend(self.end_block());
+                    }
+                    Some("function_call") if self.open == OpenBlock::ToolUse => {
+                        if !self.tool_args_streamed {
+                            let arguments = item.get("arguments").and_then(|v| v.as_str()).unwrap_or("{}");
+                            chunks.push(self.delta(json!({ "type": "input_json_delta", "partial_json": arguments })));
+                        }
+                        chunks.extend(self.end_block());
+                    }
+                    Some("message") if self.open == OpenBlock::Text => {
+                        chunks.extend(self.end_block());
+                    }
+                    _ => {}
+                }
+            }
+            "response.completed" | "response.incomplete" => {
+                chunks.extend(self.finish(event.get("response")));
+            }
+            "response.failed" | "error" => {
+                let message = event
+                    .pointer("/response/error/message")
+                    .or_else(|| event.get("message"))
+                    .or_else(|| event.pointer("/error/message"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Codex upstream error");
+                chunks.extend(self.ensure_message_start(None));
+                chunks.extend(self.end_block());
+                chunks.push(self.emit(
+                    "error",
+                    json!({ "type": "error", "error": { "type": "api_error", "message": message } }),
+                ));
+                self.message_stop_sent = true;
+            }
+            _ => {}
+        }
+        chunks
+    }
+
+    /// 关闭打开的块并发送 message_delta + message_stop
+    pub fn finish(&mut self, response: Option<&Value>) -> Vec<Bytes> {
+        if self.message_stop_sent {
+            return Vec::new();
+        }
+        let mut chunks = self.ensure_message_start(response);
+        chunks.extend(self.end_block());
+
+        let empty = json!({});
+        let response = response.unwrap_or(&empty);
+        self.usage = response.get("usage").cloned();
+        chunks.push(self.emit(
+            "message_delta",
+            json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": stop_reason(response, self.used_tool), "stop_sequence": null },
+                "usage": to_claude_usage(response.get("usage"))
+            }),
+        ));
+        chunks.push(self.emit("message_stop", json!({ "type": "message_stop" })));
+        self.message_stop_sent = true;
+        chunks
+    }
+}
+
+/// 创建从 Responses SSE 流到 Claude SSE 流的转换
+pub fn create_claude_sse_stream_from_responses(
+    mut upstream: Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>,
+    model: String,
+    include_thinking: bool,
+    trace_id: String,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>> {
+    use async_stream::stream;
+    use bytes::BytesMut;
+    use futures::StreamExt;
+
+    Box::pin(stream! {
+        let mut state = ResponsesStreamState::new(&model, include_thinking);
+        let mut buffer = BytesMut::new();
+
+        loop {
+            let next_chunk = tokio::time::timeout(
+                std::time::Duration::from_secs(30),
+                upstream.next()
+            ).await;
+
+            match next_chunk {
+                Ok(Some(Ok(chunk))) => {
+                    buffer.extend_from_slice(&chunk);
+                    while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                        let line_raw = buffer.split_to(pos + 1);
+                        let Ok(line) = std::str::from_utf8(&line_raw) else { continue };
+                        // event: 行可忽略，data JSON 自带 type
+                        let Some(data) = line.trim().strip_prefix("data:") else { continue };
+                        let data = data.trim();
+                        if data.is_empty() || data == "[DONE]" { continue; }
+
+                        match serde_json::from_str::<Value>(data) {
+                            Ok(event) => {
+                                for chunk in state.process_event(&event) {
+                                    yield Ok(chunk);
+                                }
+                            }
+                            Err(e) => {
+                                tracing::warn!("[{}][Codex] Failed to parse Responses SSE event: {}", trace_id, e);
+                            }
+                        }
+                    }
+                }
+                Ok(Some(Err(e))) => {
+                    yield Err(format!("Stream error: {}", e));
+                    break;
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    yield Ok(Bytes::from(": ping\n\n"));
+                }
+            }
+        }
+
+        // 上游未发送 response.completed 时补齐收尾事件
+        if !state.message_stop_sent {
+            tracing::warn!("[{}][Codex] Responses stream ended without completion event", trace_id);
+            for chunk in state.finish(None) {
+                yield Ok(chunk);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(state: &mut ResponsesStreamState, events: Vec<Value>) -> String {
+        events
+            .iter()
+            .flat_map(|e| state.process_event(e))
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_stream_text_reasoning_and_tool_call() {
+        let mut state = ResponsesStreamState::new("gpt-5.2-codex", true);
+        let out = run(&mut state, vec![
+            json!({ "type": "response.created", "response": { "id": "resp_1" } }),
+            json!({ "type": "response.output_item.added", "item": { "type": "reasoning", "id": "rs_1" } }),
+            json!({ "type": "response.reasoning_summary_text.delta", "item_id": "rs_1", "summary_index": 0, "delta": "Plan" }),
+            json!({ "type": "response.reasoning_summary_text.delta", "item_id": "rs_1", "summary_index": 1, "delta": "Act" }),
+            json!({ "type": "response.output_item.done", "item": { "type": "reasoning", "id": "rs_1", "encrypted_content": "enc" } }),
+            json!({ "type": "response.output_item.added", "item": { "type": "message", "id": "msg_1" } }),
+            json!({ "type": "response.output_text.delta", "item_id": "msg_1", "delta": "Hello" }),
+            json!({ "type": "response.output_item.done", "item": { "type": "message", "id": "msg_1" } }),
+            json!({ "type": "response.output_item.added", "item": { "type": "function_call", "id": "fc_1", "call_id": "call_1", "name": "Bash" } }),
+            json!({ "type": "response.function_call_arguments.delta", "item_id": "fc_1", "delta": "{\"command\":" }),
+            json!({ "type": "response.function_call_arguments.delta", "item_id": "fc_1", "delta": "\"ls\"}" }),
+            json!({ "type": "response.output_item.done", "item": { "type": "function_call", "id": "fc_1", "call_id": "call_1", "arguments": "{\"command\":\"ls\"}" } }),
+            json!({ "type": "response.completed", "response": { "id": "resp_1", "status": "completed", "usage": { "input_tokens": 10, "output_tokens": 5 } } }),
+        ]);
+
+        assert!(out.starts_with("event: message_start"));
+        assert!(out.contains(r#""type":"thinking""#));
+        assert!(out.contains(r#""thinking":"\n\nAct""#));
+        assert!(out.contains("openai-reasoning:rs_1:enc"));
+        assert!(out.contains(r#""text":"Hello""#));
+        assert!(out.contains(r#""partial_json":"\"ls\"}""#));
+        assert!(out.contains(r#""stop_reason":"tool_use""#));
+        assert!(out.trim_end().ends_with(r#"data: {"type":"message_stop"}"#));
+        // 三个块: thinking(0) / text(1) / tool_use(2)
+        assert_eq!(out.matches("event: content_block_start").count(), 3);
+        assert_eq!(out.matches("event: content_block_stop").count(), 3);
+        assert!(out.contains(r#""index":2"#));
+        assert!(state.message_stop_sent);
+    }
+
+    #[test]
+    fn test_stream_drops_reasoning_without_thinking() {
+        let mut state = ResponsesStreamState::new("gpt-5.2-codex", false);
+        let out = run(&mut state, vec![
+            json!({ "type": "response.output_item.added", "item": { "type": "reasoning", "id": "rs_1" } }),
+            json!({ "type": "response.reasoning_summary_text.delta", "item_id": "rs_1", "delta": "secret" }),
+            json!({ "type": "response.output_item.done", "item": { "type": "reasoning", "id": "rs_1", "encrypted_content": "enc" } }),
+            json!({ "type": "response.output_text.delta", "item_id": "msg_1", "delta": "Hi" }),
+        ]);
+        assert!(!out.contains("secret"));
+        assert!(out.contains(r#""index":0"#));
+
+        let tail: String = state
+            .finish(None)
+            .iter()
+            .map(|b| String::from_utf8(b.to_vec()).unwrap())
+            .collect();
+        assert!(tail.contains(r#""stop_reason":"end_turn""#));
+        assert!(state.finish(None).is_empty());
+    }
+}
@@ -4,11 +4,14 @@
 //! - 浏览器快照压缩 (头+尾保留)
 //! - 大文件提示压缩 (提取关键信息)
 //! - 通用截断 (200,000 字符限制)
+//! - [NEW] 按工具选择的压缩策略 (JSON 裁剪 / 日志去重 / grep 分组 / diff hunk 裁剪)
 
 use regex::Regex;
 use serde_json::Value;
 use tracing::{debug, info};
 
+use crate::proxy::common::tool_result_strategies;
+
 /// 最大工具结果字符数 (约 20 万,防止 prompt 超长)
 const MAX_TOOL_RESULT_CHARS: usize = 200_000;
 
@@ -68,6 +71,38 @@ pub fn compact_tool_result_text(text: &str, max_chars: usize) -> String {
     truncate_text_safe(&cleaned_text, max_chars)
 }
 
+/// 按工具名压缩工具结果文本
+///
+/// 先尝试该工具对应的压缩策略 (见 `tool_result_strategies::resolve`)，
+/// 策略不适用或无法压缩到预算内时回退到 `compact_tool_result_text`
+pub fn compact_tool_result_for(tool_name: &str, text: &str, max_chars: usize) -> String {
+    if text.len() <= max_chars {
+        return text.to_string();
+    }
+
+    let config = crate::proxy::config::get_tool_result_compression_config();
+    let (strategy, _) = tool_result_strategies::resolve(tool_name, &config);
+    if let Some(strategy) = strategy {
+        if let Some(compacted) = strategy.compact(text, max_chars) {
+            debug!(
+                "[ToolCompressor] Strategy '{}' compacted {} output: {} -> {} chars",
+                strategy.name(),
+                tool_name,
+                text.len(),
+                compacted.len()
+            );
+            return compacted;
+        }
+        debug!(
+            "[ToolCompressor] Strategy '{}' not applicable to {} output, falling back",
+            strategy.name(),
+            tool_name
+        );
+    }
+
+    compact_tool_result_text(text, max_chars)
+}
+
 /// 压缩"输出已保存到文件"类型的提示
 /// 
 /// 检测模式: "result (N characters) exceeds maximum allowed tokens. Output saved to <path>"
@@ -244,15 +279,26 @@ fn deep_clean_html(html: &str) -> String {
     result
 }
 
+/// 清理并截断工具调用结果内容块 (通用压缩，固定字符预算)
+#[allow(dead_code)]
+pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
+    sanitize_blocks(blocks, None, MAX_TOOL_RESULT_CHARS);
+}
+
+/// 按工具名清理工具调用结果内容块 (字符预算与压缩策略取自 tool_result_compression 配置)
+pub fn sanitize_tool_result_blocks_for_tool(blocks: &mut Vec<Value>, tool_name: &str) {
+    let config = crate::proxy::config::get_tool_result_compression_config();
+    let (_, max_chars) = tool_result_strategies::resolve(tool_name, &config);
+    sanitize_blocks(blocks, Some(tool_name), max_chars);
+}
+
 /// 清理工具结果 content blocks
 /// 
 /// 处理逻辑:
 /// 1. 移除 base64 图片 (避免体积过大)
 /// 2. 压缩文本内容 (使用智能压缩策略)
 /// 3. 限制总字符数 (默认 200,000)
-/// 
-/// 清理并截断工具调用结果内容块
-pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
+fn sanitize_blocks(blocks: &mut Vec<Value>, tool_name: Option<&str>, max_chars: usize) {
     let mut used_chars = 0;
     let mut cleaned_blocks = Vec::new();
     let mut removed_image = false;
@@ -261,7 +307,7 @@ pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
         info!(
             "[ToolCompressor] Processing {} blocks for truncation (MAX: {} chars)",
             blocks.len(),
-            MAX_TOOL_RESULT_CHARS
+            max_chars
         );
     }
     
@@ -275,13 +321,16 @@ pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
         
         // 压缩文本内容
         if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
-            let remaining = MAX_TOOL_RESULT_CHARS.saturating_sub(used_chars);
+            let remaining = max_chars.saturating_sub(used_chars);
             if remaining == 0 {
                 debug!("[ToolCompressor] Reached character limit, stopping");
                 break;
             }
             
-            let compacted = compact_tool_result_text(text, remaining);
+            let compacted = match tool_name {
+                Some(name) => compact_tool_result_for(name, text, remaining),
+                None => compact_tool_result_text(text, remaining),
+            };
             let mut new_block = block.clone();
             new_block["text"] = Value::String(compacted.clone());
             cleaned_blocks.push(new_block);
@@ -297,7 +346,7 @@ pub fn sanitize_tool_result_blocks(blocks: &mut Vec<Value>) {
             used_chars += 100; // 估算非文本块大小
         }
         
-        if used_chars >= MAX_TOOL_RESULT_CHARS {
+        if used_chars >= max_chars {
             break;
         }
     }
@@ -420,6 +469,20 @@ Please read the file locally."#;
         assert!(blocks[1]["text"].as_str().unwrap().contains("[image omitted"));
     }
 
+    #[test]
+    fn test_compact_tool_result_for_uses_strategy() {
+        let text: String = (0..5000)
+            .map(|i| format!("src/lib_{}.rs:{}:fn item_{}() {{}}\n", i % 10, i, i))
+            .collect();
+        let result = compact_tool_result_for("Grep", &text, 4_000);
+        assert!(result.len() <= 4_000);
+        assert!(result.contains("grouped by file"));
+
+        // 无匹配策略时回退到通用截断
+        let result = compact_tool_result_for("Read", &text, 4_000);
+        assert!(result.contains("[truncated"));
+    }
+
     #[test]
     fn test_is_base64_image() {
         let image_block = serde_json::json!({
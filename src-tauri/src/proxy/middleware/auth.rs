@@ -2,22 +2,99 @@
 use axum::{
     extract::State,
     extract::Request,
-    http::{header, StatusCode},
+    http::{header, Method, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::modules::admin_db::{self, AdminRole};
+use crate::proxy::auto_ban::{self, AbuseSignal};
+use crate::proxy::client_ip::client_ip_from_request;
+use crate::proxy::share_token::{self, ShareTokenClaims};
+use crate::proxy::request_context::RequestOverrides;
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
+use crate::proxy::security::secret_matches;
+
+const MAX_SHARE_TOKEN_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB (与 monitor 一致)
+
+/// 已通过会话令牌鉴权的管理用户 (写入请求扩展，供 /session/* 与用户管理接口使用)
+#[derive(Debug, Clone)]
+pub struct AdminIdentity {
+    pub user_id: String,
+    pub username: String,
+    pub role: AdminRole,
+    pub token_hash: String,
+    pub expires_at: i64,
+}
+
+/// 管理接口所需的最低角色
+///
+/// viewer: 统计与日志只读；operator: 账号、OAuth、预热与调度状态；其余 (配置、安全、系统、用户) 需要 admin
+pub fn required_role(method: &Method, path: &str) -> AdminRole {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+    let under = |prefix: &str| path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'));
+
+    if path == "/session/me" || path == "/session/logout" {
+        return AdminRole::Viewer;
+    }
+
+    let read_only = *method == Method::GET || *method == Method::HEAD;
+    if read_only
+        && (under("/health")
+            || under("/stats")
+            || under("/logs")
+            || under("/proxy/models/registry")
+            || [
+                "/proxy/stats",
+                "/proxy/status",
+                "/proxy/pm-router/decisions",
+                "/security/logs",
+                "/security/stats",
+                "/security/token-stats",
+            ]
+            .contains(&path))
+    {
+        return AdminRole::Viewer;
+    }
+
+    // 导出包含 refresh_token，仅限 admin
+    if path == "/accounts/export" {
+        return AdminRole::Admin;
+    }
+
+    let operator_prefixes = [
+        "/accounts",
+        "/auth",
+        "/proxy/session-bindings",
+        "/proxy/rate-limits",
+        "/proxy/preferred-account",
+        "/proxy/pm-router",
+        "/proxy/models/discovery",
+        "/proxy/context-summaries",
+        "/zai/models",
+    ];
+    if operator_prefixes.iter().any(|p| under(p)) {
+        return AdminRole::Operator;
+    }
+
+    AdminRole::Admin
+}
+
+fn is_admin_login_path(path: &str) -> bool {
+    path == "/session/login" || path == "/api/session/login"
+}
 
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
 pub async fn auth_middleware(
     state: State<Arc<RwLock<ProxySecurityConfig>>>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    auth_middleware_internal(state, request, next, false).await
+) -> Response {
+    auth_middleware_internal(state, request, next, false)
+        .await
+        .unwrap_or_else(rejection_response)
 }
 
 /// 管理接口认证中间件 (管理接口使用，强制严格鉴权)
@@ -25,14 +102,26 @@ pub async fn admin_auth_middleware(
     state: State<Arc<RwLock<ProxySecurityConfig>>>,
     request: Request,
     next: Next,
-) -> Result<Response, StatusCode> {
-    auth_middleware_internal(state, request, next, true).await
+) -> Response {
+    auth_middleware_internal(state, request, next, true)
+        .await
+        .unwrap_or_else(rejection_response)
+}
+
+/// 将鉴权拒绝转为响应；401 标记为鉴权失败，供自动封禁统计
+fn rejection_response(status: StatusCode) -> Response {
+    let response = status.into_response();
+    if status == StatusCode::UNAUTHORIZED {
+        auto_ban::mark_rejection(response, AbuseSignal::AuthFailure)
+    } else {
+        response
+    }
 }
 
 /// 内部认证逻辑
 async fn auth_middleware_internal(
     State(security): State<Arc<RwLock<ProxySecurityConfig>>>,
-    request: Request,
+    mut request: Request,
     next: Next,
     force_strict: bool,
 ) -> Result<Response, StatusCode> {
@@ -55,6 +144,109 @@ async fn auth_middleware_internal(
     let security = security.read().await.clone();
     let effective_mode = security.effective_auth_mode();
 
+    // [NEW] 分享令牌: 仅校验签名 / 有效期 / 内存吊销列表 (不查库)，并按令牌声明限制 IP、模型与 max_tokens
+    if !force_strict {
+        let share_token = extract_api_key(request.headers())
+            .filter(|k| k.starts_with(share_token::SHARE_TOKEN_PREFIX))
+            .map(str::to_string);
+        if let Some(token) = share_token {
+            match share_token::verify(&token) {
+                Ok(claims) => {
+                    let request = authorize_share_token(&claims, request, &security).await?;
+                    return Ok(next.run(request).await);
+                }
+                // 鉴权关闭时无效令牌按匿名处理
+                Err(e) if matches!(effective_mode, ProxyAuthMode::Off) => {
+                    tracing::debug!("[Share-Token] Ignored invalid token with auth disabled: {}", e);
+                }
+                Err(e) => {
+                    tracing::warn!("[Share-Token] Rejected {} {}: {}", method, path, e);
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+            }
+        }
+    }
+
+    // [NEW] 识别附加 API Key 并写入请求上下文 (鉴权关闭时也生效，用于按 Key 的策略)
+    let matched_key = if force_strict {
+        None
+    } else {
+        extract_api_key(request.headers()).and_then(|k| security.find_api_key(k)).cloned()
+    };
+    if let (Some(entry), Some(ctx)) = (&matched_key, crate::proxy::request_context::current()) {
+        ctx.set_api_key(Some(entry.clone()));
+    }
+
+    // [NEW] 路由覆盖请求头: 附加 Key 按其 allow_request_overrides，主 Key / 未鉴权调用方按全局开关
+    // (主 Key 尚未校验，校验失败会直接 401，提前写入上下文无副作用)
+    if !force_strict && RequestOverrides::present_in(request.headers()) {
+        let permitted = match &matched_key {
+            Some(entry) => entry.allow_request_overrides,
+            None => security.allow_request_overrides,
+        };
+        if permitted {
+            let overrides = RequestOverrides::from_headers(request.headers());
+            if let (false, Some(ctx)) = (overrides.is_empty(), crate::proxy::request_context::current()) {
+                tracing::info!("[Request-Override] {} {}: {}", method, path, overrides.summary());
+                ctx.set_overrides(overrides);
+            }
+        } else {
+            tracing::warn!(
+                "[Request-Override] Override headers ignored for {} {}: caller is not permitted",
+                method,
+                path
+            );
+        }
+    }
+
+    // [NEW] 管理接口: 登录接口放行；携带会话令牌时按用户角色鉴权
+    if force_strict {
+        if is_admin_login_path(&path) {
+            return Ok(next.run(request).await);
+        }
+
+        let session_token = extract_api_key(request.headers())
+            .filter(|k| k.starts_with(admin_db::SESSION_TOKEN_PREFIX))
+            .map(str::to_string);
+        if let Some(token) = session_token {
+            let session = tokio::task::spawn_blocking(move || admin_db::validate_session(&token))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map_err(|e| {
+                    tracing::error!("[Admin-Auth] Failed to validate session: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+
+            match session {
+                Some(session) => {
+                    let required = required_role(&method, &path);
+                    if session.user.role < required {
+                        tracing::warn!(
+                            "[Admin-Auth] {} ({}) denied {} {}: requires {}",
+                            session.user.username,
+                            session.user.role.as_str(),
+                            method,
+                            path,
+                            required.as_str()
+                        );
+                        return Err(StatusCode::FORBIDDEN);
+                    }
+                    request.extensions_mut().insert(AdminIdentity {
+                        user_id: session.user.id,
+                        username: session.user.username,
+                        role: session.user.role,
+                        token_hash: session.token_hash,
+                        expires_at: session.expires_at,
+                    });
+                    return Ok(next.run(request).await);
+                }
+                // 鉴权关闭时无效会话按匿名处理
+                None if matches!(effective_mode, ProxyAuthMode::Off) => {}
+                None => return Err(StatusCode::UNAUTHORIZED),
+            }
+        }
+    }
+
     // 权限检查逻辑
     if !force_strict {
         // AI 代理接口 (v1/chat/completions 等)
@@ -79,23 +271,12 @@ async fn auth_middleware_internal(
     }
     
     // 从 header 中提取 API key
-    let api_key = request
-        .headers()
-        .get(header::AUTHORIZATION)
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-api-key")
-                .and_then(|h| h.to_str().ok())
-        })
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-goog-api-key")
-                .and_then(|h| h.to_str().ok())
-        });
+    let api_key = extract_api_key(request.headers());
+
+    // [NEW] 附加 API Key: 仅可访问代理接口
+    if !force_strict && matched_key.is_some() {
+        return Ok(next.run(request).await);
+    }
 
     if security.api_key.is_empty() && (security.admin_password.is_none() || security.admin_password.as_ref().unwrap().is_empty()) {
         if force_strict {
@@ -111,18 +292,31 @@ async fn auth_middleware_internal(
         // 管理接口：优先使用独立的 admin_password，如果没有则回退使用 api_key
         match &security.admin_password {
             Some(pwd) if !pwd.is_empty() => {
-                api_key.map(|k| k == pwd).unwrap_or(false)
+                api_key.map(|k| secret_matches(k, pwd)).unwrap_or(false)
             }
             _ => {
                 // 回退使用 api_key
-                api_key.map(|k| k == security.api_key).unwrap_or(false)
+                api_key.map(|k| secret_matches(k, &security.api_key)).unwrap_or(false)
             }
         }
     } else {
         // AI 代理接口：仅允许使用 api_key
-        api_key.map(|k| k == security.api_key).unwrap_or(false)
+        api_key.map(|k| secret_matches(k, &security.api_key)).unwrap_or(false)
     };
 
+    // [NEW] 已创建管理用户且禁用旧版共享密码时，共享密码不再可用于管理接口
+    if authorized && force_strict && !security.admin_auth.allow_legacy_password {
+        let has_users = tokio::task::spawn_blocking(admin_db::users_count)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .map(|count| count > 0)
+            .unwrap_or(false);
+        if has_users {
+            tracing::warn!("[Admin-Auth] Legacy admin password rejected for {} {}: user login required", method, path);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    }
+
     if authorized {
         Ok(next.run(request).await)
     } else {
@@ -130,6 +324,66 @@ async fn auth_middleware_internal(
     }
 }
 
+/// 按分享令牌声明校验客户端 IP 与模型，并收紧请求的 max_tokens
+async fn authorize_share_token(
+    claims: &ShareTokenClaims,
+    request: Request,
+    security: &ProxySecurityConfig,
+) -> Result<Request, StatusCode> {
+    let path = request.uri().path().to_string();
+    let client_ip = client_ip_from_request(&request, &security.security_monitor.trusted_proxies);
+    if !claims.allows_ip(client_ip.as_deref()) {
+        tracing::warn!(
+            "[Share-Token] Token {} used from {} outside bound {}",
+            claims.jti,
+            client_ip.as_deref().unwrap_or("unknown"),
+            claims.ip.as_deref().unwrap_or("-")
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if request.method() != Method::POST || (claims.models.is_empty() && claims.max_tokens.is_none()) {
+        return Ok(request);
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_SHARE_TOKEN_BODY_SIZE)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+    let mut json = serde_json::from_slice::<serde_json::Value>(&bytes).ok();
+
+    if !claims.models.is_empty() {
+        let model = share_token::request_model(&path, json.as_ref());
+        if !model.as_deref().is_some_and(|m| claims.allows_model(m)) {
+            tracing::warn!(
+                "[Share-Token] Token {} denied model {}",
+                claims.jti,
+                model.as_deref().unwrap_or("unknown")
+            );
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    if let (Some(cap), Some(body)) = (claims.max_tokens, json.as_mut()) {
+        if share_token::clamp_max_tokens(body, &path, cap) {
+            let rewritten = serde_json::to_vec(body).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            parts.headers.insert(header::CONTENT_LENGTH, rewritten.len().into());
+            return Ok(Request::from_parts(parts, axum::body::Body::from(rewritten)));
+        }
+    }
+    Ok(Request::from_parts(parts, axum::body::Body::from(bytes)))
+}
+
+/// 从 Authorization / x-api-key / x-goog-api-key 中提取 API key
+fn extract_api_key(headers: &axum::http::HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|h| h.to_str().ok()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,7 +394,10 @@ mod tests {
         let security = Arc::new(RwLock::new(ProxySecurityConfig {
             auth_mode: ProxyAuthMode::Strict,
             api_key: "sk-api".to_string(),
+            api_keys: Vec::new(),
+            allow_request_overrides: false,
             admin_password: Some("admin123".to_string()),
+            admin_auth: crate::proxy::config::AdminAuthConfig::default(),
             allow_lan_access: true,
             port: 8045,
             security_monitor: crate::proxy::config::SecurityMonitorConfig::default(),
@@ -157,6 +414,24 @@ mod tests {
         // 我们在 auth_middleware_internal 基础上做了逻辑校验即可
     }
 
+    #[test]
+    fn test_required_role_mapping() {
+        assert_eq!(required_role(&Method::GET, "/stats/summary"), AdminRole::Viewer);
+        assert_eq!(required_role(&Method::GET, "/api/logs/abc"), AdminRole::Viewer);
+        assert_eq!(required_role(&Method::POST, "/logs/clear"), AdminRole::Admin);
+        assert_eq!(required_role(&Method::POST, "/session/logout"), AdminRole::Viewer);
+        assert_eq!(required_role(&Method::GET, "/accounts"), AdminRole::Operator);
+        assert_eq!(required_role(&Method::POST, "/accounts/warmup"), AdminRole::Operator);
+        assert_eq!(required_role(&Method::POST, "/accounts/export"), AdminRole::Admin);
+        assert_eq!(required_role(&Method::DELETE, "/proxy/rate-limits/abc"), AdminRole::Operator);
+        assert_eq!(required_role(&Method::GET, "/config"), AdminRole::Admin);
+        assert_eq!(required_role(&Method::POST, "/security/config"), AdminRole::Admin);
+        assert_eq!(required_role(&Method::GET, "/security/blacklist"), AdminRole::Admin);
+        assert_eq!(required_role(&Method::GET, "/users"), AdminRole::Admin);
+        // 前缀需按路径段匹配
+        assert_eq!(required_role(&Method::GET, "/statsx"), AdminRole::Admin);
+    }
+
     #[test]
     fn test_auth_placeholder() {
         assert!(true);
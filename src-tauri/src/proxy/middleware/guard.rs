@@ -0,0 +1,171 @@
+// 内容策略守卫中间件
+//
+// 位于 auth_middleware 之内: 入站检查请求体 (拦截 / 改写后转发)，
+// 出站对非流式 JSON 整体检查，对 SSE 包装为 StreamGuard 逐事件处理。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::Value;
+
+use crate::proxy::guardrails::{self, GuardProtocol, StreamGuard};
+use crate::proxy::request_context::{self, RequestContext};
+
+const MAX_GUARD_BODY_SIZE: usize = 100 * 1024 * 1024; // 100MB (与 monitor 一致)
+const HEADER_GUARD: &str = "x-antigravity-guard";
+
+pub async fn guard_middleware(request: Request, next: Next) -> Response {
+    let guard = guardrails::current();
+    if !guard.is_enabled() || request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(protocol) = GuardProtocol::from_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let ctx = request_context::current();
+
+    // 1. 入站检查 (非 JSON 请求体直接放行)
+    let (mut parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_GUARD_BODY_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(protocol.error_body("Request body too large for content policy check")),
+            )
+                .into_response();
+        }
+    };
+    let request = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(mut json) => {
+            let outcome = guard.check_request(&mut json);
+            record_events(ctx.as_deref(), outcome.events);
+            if let Some(message) = outcome.blocked {
+                tracing::warn!("[Guardrails] Request blocked: {}", message);
+                let mut response = (StatusCode::BAD_REQUEST, Json(protocol.error_body(&message))).into_response();
+                attach_guard_header(&mut response, ctx.as_deref());
+                return response;
+            }
+            if outcome.modified {
+                let rewritten = serde_json::to_string(&json).unwrap_or_default();
+                if let Some(ctx) = ctx.as_deref() {
+                    ctx.set_guarded_request_body(rewritten.clone());
+                }
+                parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(rewritten.len()));
+                Request::from_parts(parts, Body::from(rewritten))
+            } else {
+                Request::from_parts(parts, Body::from(bytes))
+            }
+        }
+        Err(_) => Request::from_parts(parts, Body::from(bytes)),
+    };
+
+    let mut response = next.run(request).await;
+    if !guard.has_output_rules() || !response.status().is_success() {
+        attach_guard_header(&mut response, ctx.as_deref());
+        return response;
+    }
+
+    // 2. 出站检查
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    if content_type.contains("text/event-stream") {
+        // 流式响应头已确定，出站命中仅记录到日志
+        attach_guard_header(&mut response, ctx.as_deref());
+        let (parts, body) = response.into_parts();
+        let mut upstream = body.into_data_stream();
+        let stream = async_stream::stream! {
+            let mut stream_guard = StreamGuard::new(guard, protocol);
+            while let Some(chunk) = upstream.next().await {
+                match chunk {
+                    Ok(chunk) => {
+                        let output = stream_guard.push(&chunk);
+                        record_events(ctx.as_deref(), stream_guard.take_events());
+                        if !output.is_empty() {
+                            yield Ok::<Bytes, axum::Error>(Bytes::from(output));
+                        }
+                        if stream_guard.is_blocked() {
+                            tracing::warn!("[Guardrails] Stream terminated by output policy");
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        yield Err(e);
+                        break;
+                    }
+                }
+            }
+            let rest = stream_guard.finish();
+            record_events(ctx.as_deref(), stream_guard.take_events());
+            if !rest.is_empty() {
+                yield Ok(Bytes::from(rest));
+            }
+        };
+        Response::from_parts(parts, Body::from_stream(stream))
+    } else if content_type.contains("application/json") {
+        let (mut parts, body) = response.into_parts();
+        let bytes = match axum::body::to_bytes(body, MAX_GUARD_BODY_SIZE).await {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    Json(protocol.error_body("Response body too large for content policy check")),
+                )
+                    .into_response();
+            }
+        };
+        let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+            return Response::from_parts(parts, Body::from(bytes));
+        };
+        let outcome = guard.check_response(&mut json);
+        record_events(ctx.as_deref(), outcome.events);
+        if let Some(message) = outcome.blocked {
+            tracing::warn!("[Guardrails] Response blocked: {}", message);
+            let mut response = (StatusCode::BAD_REQUEST, Json(protocol.error_body(&message))).into_response();
+            attach_guard_header(&mut response, ctx.as_deref());
+            return response;
+        }
+        let body = if outcome.modified {
+            let rewritten = serde_json::to_vec(&json).unwrap_or_default();
+            parts.headers.insert(header::CONTENT_LENGTH, HeaderValue::from(rewritten.len()));
+            Body::from(rewritten)
+        } else {
+            Body::from(bytes)
+        };
+        let mut response = Response::from_parts(parts, body);
+        attach_guard_header(&mut response, ctx.as_deref());
+        response
+    } else {
+        attach_guard_header(&mut response, ctx.as_deref());
+        response
+    }
+}
+
+fn record_events(ctx: Option<&RequestContext>, events: Vec<String>) {
+    for event in events {
+        tracing::info!("[Guardrails] {}", event);
+        if let Some(ctx) = ctx {
+            ctx.record_guard_event(event);
+        }
+    }
+}
+
+/// 回显守卫命中摘要 (`x-antigravity-guard`)
+fn attach_guard_header(response: &mut Response, ctx: Option<&RequestContext>) {
+    if let Some(summary) = ctx.and_then(|c| c.guard_summary()) {
+        if let Ok(value) = HeaderValue::from_str(&summary) {
+            response.headers_mut().insert(HEADER_GUARD, value);
+        }
+    }
+}
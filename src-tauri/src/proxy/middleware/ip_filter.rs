@@ -6,7 +6,11 @@ use axum::{
     body::Body,
 };
 use crate::proxy::server::AppState;
-use crate::modules::security_db;
+use crate::proxy::auto_ban::{self, AbuseSignal};
+use crate::proxy::client_ip;
+use crate::proxy::config::TrustedProxyConfig;
+use crate::modules::security_db::{self, SecurityEvent, SecurityEventKind};
+use crate::proxy::security_events;
 
 /// IP 黑白名单过滤中间件
 pub async fn ip_filter_middleware(
@@ -14,15 +18,23 @@ pub async fn ip_filter_middleware(
     request: Request,
     next: Next,
 ) -> Response {
-    // 提取客户端 IP
-    let client_ip = extract_client_ip(&request);
+    // 读取安全配置 (复制所需字段后立即释放读锁，避免跨 next.run 持有到流式响应结束)
+    let (trusted_proxies, whitelist, blacklist_enabled) = {
+        let security = state.security.read().await;
+        (
+            security.security_monitor.trusted_proxies.clone(),
+            security.security_monitor.whitelist.clone(),
+            security.security_monitor.blacklist.enabled,
+        )
+    };
+
+    // 提取客户端 IP (仅信任可信代理的转发头)
+    let client_ip = extract_client_ip(&request, &trusted_proxies);
     
     if let Some(ip) = &client_ip {
-        // 读取安全配置
-        let security_config = state.security.read().await;
         
         // 1. 检查白名单 (如果启用白名单模式,只允许白名单 IP)
-        if security_config.security_monitor.whitelist.enabled {
+        if whitelist.enabled {
             match security_db::is_ip_in_whitelist(ip) {
                 Ok(true) => {
                     // 在白名单中,直接放行
@@ -32,6 +44,7 @@ pub async fn ip_filter_middleware(
                 Ok(false) => {
                     // 不在白名单中,且启用了白名单模式,拒绝访问
                     tracing::warn!("[IP Filter] IP {} not in whitelist, blocking", ip);
+                    emit_blocked(ip, &request, "IP not in whitelist");
                     return create_blocked_response(
                         ip,
                         "Access denied. Your IP is not in the whitelist.",
@@ -43,7 +56,7 @@ pub async fn ip_filter_middleware(
             }
         } else {
             // 白名单优先模式: 如果在白名单中,跳过黑名单检查
-            if security_config.security_monitor.whitelist.whitelist_priority {
+            if whitelist.whitelist_priority {
                 match security_db::is_ip_in_whitelist(ip) {
                     Ok(true) => {
                         tracing::debug!("[IP Filter] IP {} is in whitelist (priority mode), skipping blacklist check", ip);
@@ -60,41 +73,13 @@ pub async fn ip_filter_middleware(
         }
 
         // 2. 检查黑名单
-        if security_config.security_monitor.blacklist.enabled {
+        if blacklist_enabled {
             match security_db::get_blacklist_entry_for_ip(ip) {
                 Ok(Some(entry)) => {
                     tracing::warn!("[IP Filter] IP {} is in blacklist, blocking", ip);
                     
-                    // 构建详细的封禁消息
                     let reason = entry.reason.as_deref().unwrap_or("Malicious activity detected");
-                    let ban_type = if let Some(expires_at) = entry.expires_at {
-                        let now = chrono::Utc::now().timestamp();
-                        let remaining_seconds = expires_at - now;
-                        
-                        if remaining_seconds > 0 {
-                            let hours = remaining_seconds / 3600;
-                            let minutes = (remaining_seconds % 3600) / 60;
-                            
-                            if hours > 24 {
-                                let days = hours / 24;
-                                format!("Temporary ban. Please try again after {} day(s).", days)
-                            } else if hours > 0 {
-                                format!("Temporary ban. Please try again after {} hour(s) and {} minute(s).", hours, minutes)
-                            } else {
-                                format!("Temporary ban. Please try again after {} minute(s).", minutes)
-                            }
-                        } else {
-                            "Temporary ban (expired, will be removed soon).".to_string()
-                        }
-                    } else {
-                        "Permanent ban.".to_string()
-                    };
-                    
-                    let detailed_message = format!(
-                        "Access denied. Reason: {}. {}",
-                        reason,
-                        ban_type
-                    );
+                    let detailed_message = format_ban_message(&entry);
                     
                     // 记录被封禁的访问日志
                     let log = security_db::IpAccessLog {
@@ -115,6 +100,8 @@ pub async fn ip_filter_middleware(
                         block_reason: Some(format!("IP in blacklist: {}", reason)),
                     };
                     
+                    emit_blocked(ip, &request, &format!("IP in blacklist: {}", reason));
+
                     tokio::spawn(async move {
                         if let Err(e) = security_db::save_ip_access_log(&log) {
                             tracing::error!("[IP Filter] Failed to save blocked access log: {}", e);
@@ -143,30 +130,127 @@ pub async fn ip_filter_middleware(
     next.run(request).await
 }
 
+/// [NEW] 自动封禁中间件 (全局层，覆盖代理、管理接口与未知路径)
+///
+/// 拦截生效中的自动封禁，并将请求、401 与探测行为喂给自动封禁引擎
+pub async fn auto_ban_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (config, trusted_proxies) = {
+        let security = state.security.read().await;
+        (security.security_monitor.auto_ban.clone(), security.security_monitor.trusted_proxies.clone())
+    };
+    let Some(ip) = extract_client_ip(&request, &trusted_proxies) else {
+        return next.run(request).await;
+    };
+    let (method, path) = (request.method().to_string(), request.uri().path().to_string());
+
+    // [NEW] 鉴权失败事件不依赖自动封禁开关
+    if !config.enabled {
+        let response = next.run(request).await;
+        emit_auth_failure(&ip, &method, &path, &response);
+        return response;
+    }
+
+    let engine = auto_ban::engine();
+    if let Some(entry) = engine.active_ban(&ip) {
+        tracing::debug!("[Auto-Ban] Rejecting request from banned IP {}", ip);
+        emit_blocked(&ip, &request, entry.reason.as_deref().unwrap_or("Auto ban active"));
+        return create_blocked_response(&ip, &format_ban_message(&entry));
+    }
+
+    let probe_pattern = auto_ban::is_probe_path(request.uri().path(), &config.probe_patterns);
+    let response = next.run(request).await;
+    emit_auth_failure(&ip, &method, &path, &response);
+
+    // 仅统计本服务自身打标记的拒绝 (上游透传的 401/404 不算滥用)
+    let mut signals = vec![AbuseSignal::Request];
+    match auto_ban::rejection_signal(&response) {
+        Some(signal @ (AbuseSignal::AuthFailure | AbuseSignal::Probe)) => signals.push(signal),
+        _ if probe_pattern => signals.push(AbuseSignal::Probe),
+        _ => {}
+    }
+
+    for signal in signals {
+        if let Some(reason) = engine.observe(&ip, signal, &config) {
+            let ip = ip.clone();
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = auto_ban::engine().ban(&ip, &reason, &config) {
+                    tracing::error!("[Auto-Ban] Failed to ban {}: {}", ip, e);
+                }
+            });
+            break;
+        }
+    }
+
+    response
+}
+
+/// 记录拦截事件
+fn emit_blocked(ip: &str, request: &Request, reason: &str) {
+    security_events::emit(
+        SecurityEvent::new(SecurityEventKind::Blocked, reason)
+            .ip(ip)
+            .request(request.method().as_str(), request.uri().path())
+            .status(403),
+    );
+}
+
+/// 鉴权层拒绝请求时记录鉴权失败事件
+fn emit_auth_failure(ip: &str, method: &str, path: &str, response: &Response) {
+    // 管理登录失败由登录接口自行记录 (含用户名)
+    let rejected = auto_ban::rejection_signal(response) == Some(AbuseSignal::AuthFailure);
+    if rejected && path != "/api/session/login" {
+        security_events::emit(
+            SecurityEvent::new(SecurityEventKind::AuthFailure, "Unauthorized request")
+                .ip(ip)
+                .request(method, path)
+                .status(401),
+        );
+    }
+}
+
+/// 构建详细的封禁消息
+fn format_ban_message(entry: &security_db::IpBlacklistEntry) -> String {
+    let reason = entry.reason.as_deref().unwrap_or("Malicious activity detected");
+    let ban_type = if let Some(expires_at) = entry.expires_at {
+        let now = chrono::Utc::now().timestamp();
+        let remaining_seconds = expires_at - now;
+        
+        if remaining_seconds > 0 {
+            let hours = remaining_seconds / 3600;
+            let minutes = (remaining_seconds % 3600) / 60;
+            
+            if hours > 24 {
+                let days = hours / 24;
+                format!("Temporary ban. Please try again after {} day(s).", days)
+            } else if hours > 0 {
+                format!("Temporary ban. Please try again after {} hour(s) and {} minute(s).", hours, minutes)
+            } else {
+                format!("Temporary ban. Please try again after {} minute(s).", minutes)
+            }
+        } else {
+            "Temporary ban (expired, will be removed soon).".to_string()
+        }
+    } else {
+        "Permanent ban.".to_string()
+    };
+    
+    format!(
+        "Access denied. Reason: {}. {}",
+        reason,
+        ban_type
+    )
+}
+
 /// 从请求中提取客户端 IP
-fn extract_client_ip(request: &Request) -> Option<String> {
-    // 1. 优先从 X-Forwarded-For 提取 (取第一个 IP)
-    request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            // 2. 备选从 X-Real-IP 提取
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .or_else(|| {
-            // 3. 最后尝试从 ConnectInfo 获取 (TCP 连接 IP)
-            // 这可以解决本地开发/测试时没有代理头导致 IP 获取失败的问题
-            request
-                .extensions()
-                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-                .map(|info| info.0.ip().to_string())
-        })
+///
+/// 对端为可信代理时才使用 CF-Connecting-IP / X-Forwarded-For / X-Real-IP，
+/// 否则使用 ConnectInfo 中的 TCP 对端地址，防止伪造转发头绕过黑白名单
+fn extract_client_ip(request: &Request, trusted_proxies: &TrustedProxyConfig) -> Option<String> {
+    client_ip::client_ip_from_request(request, trusted_proxies)
 }
 
 /// 创建被封禁的响应
@@ -2,6 +2,7 @@
 
 pub mod auth;
 pub mod cors;
+pub mod guard;
 pub mod logging;
 pub mod monitor;
 pub mod ip_filter;
@@ -9,7 +10,8 @@ pub mod ip_filter;
 pub mod service_status;
 
 pub use cors::cors_layer;
+pub use guard::guard_middleware;
 pub use monitor::monitor_middleware;
 pub use service_status::service_status_middleware;
-pub use auth::{auth_middleware, admin_auth_middleware};
-pub use ip_filter::ip_filter_middleware;
+pub use auth::{auth_middleware, admin_auth_middleware, AdminIdentity};
+pub use ip_filter::{auto_ban_middleware, ip_filter_middleware};
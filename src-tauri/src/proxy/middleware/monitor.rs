@@ -29,21 +29,11 @@ pub async fn monitor_middleware(
     
     let start = Instant::now();
     
-    // Extract client IP from headers (X-Forwarded-For or X-Real-IP)
+    // Extract client IP (forwarded headers are only honoured from trusted proxies)
     // IMPORTANT: Extract from Request headers, not Response headers (since we want the client's IP)
     // Note: We need to do this BEFORE consuming the request body if possible, or extract it from the original request
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+    let trusted_proxies = state.security.read().await.security_monitor.trusted_proxies.clone();
+    let client_ip = crate::proxy::client_ip::client_ip_from_request(&request, &trusted_proxies);
 
     let mut model = if uri.contains("/v1beta/models/") {
         uri.split("/v1beta/models/")
@@ -81,7 +71,25 @@ pub async fn monitor_middleware(
         request
     };
     
-    let response = next.run(request).await;
+    // [NEW] 请求级上下文: 鉴权写入调用方 Key，mapper 回写实际生效的 thinking budget
+    let request_ctx = std::sync::Arc::new(crate::proxy::request_context::RequestContext::default());
+    let mut response = crate::proxy::request_context::scope(request_ctx.clone(), next.run(request)).await;
+
+    // [NEW] 路由覆盖生效时回显实际使用的账号与模型
+    let overrides = request_ctx.overrides();
+    if !overrides.is_empty() {
+        let headers = response.headers_mut();
+        let echoed = [
+            ("x-antigravity-account", headers.get("X-Account-Email").cloned()),
+            ("x-antigravity-mapped-model", headers.get("X-Mapped-Model").cloned()),
+            ("x-antigravity-overrides", overrides.summary().parse().ok()),
+        ];
+        for (name, value) in echoed {
+            if let Some(value) = value {
+                headers.insert(name, value);
+            }
+        }
+    }
     
     let duration = start.elapsed().as_millis() as u64;
     let status = response.status().as_u16();
@@ -136,21 +144,29 @@ pub async fn monitor_middleware(
         model,
         mapped_model,
         pm_selected_model,
+        thinking_budget: request_ctx.thinking_budget(),
         account_email,
         client_ip,
         error: None,
-        request_body: request_body_str,
+        // [NEW] 入站守卫改写过请求体时记录改写后的版本
+        request_body: request_ctx.guarded_request_body().or(request_body_str),
         response_body: None,
         input_tokens: None,
         output_tokens: None,
         protocol,
+        redaction_count: None,
+        guard_result: request_ctx.guard_summary(),
     };
 
+    // [NEW] 调用方 Key 关闭了请求体记录时仅保留元数据
+    let skip_bodies = request_ctx.api_key().is_some_and(|k| k.skip_body_logging);
+
 
     if content_type.contains("text/event-stream") {
         let (parts, body) = response.into_parts();
         let mut stream = body.into_data_stream();
         let (tx, rx) = tokio::sync::mpsc::channel(64);
+        let request_ctx = request_ctx.clone();
         
         tokio::spawn(async move {
             let mut all_stream_data = Vec::new();
@@ -309,7 +325,9 @@ pub async fn monitor_middleware(
             if log.status >= 400 {
                 log.error = Some("Stream Error or Failed".to_string());
             }
-            monitor.log_request(log).await;
+            // [NEW] 出站守卫在流结束前才能确定全部命中
+            log.guard_result = request_ctx.guard_summary();
+            monitor.log_request(apply_body_policy(log, skip_bodies)).await;
         });
 
         Response::from_parts(parts, Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)))
@@ -348,18 +366,26 @@ pub async fn monitor_middleware(
                 if log.status >= 400 {
                     log.error = log.response_body.clone();
                 }
-                monitor.log_request(log).await;
+                monitor.log_request(apply_body_policy(log, skip_bodies)).await;
                 Response::from_parts(parts, Body::from(bytes))
             }
             Err(_) => {
                 log.response_body = Some("[Response too large (>100MB)]".to_string());
-                monitor.log_request(log).await;
+                monitor.log_request(apply_body_policy(log, skip_bodies)).await;
                 Response::from_parts(parts, Body::empty())
             }
         }
     } else {
         log.response_body = Some(format!("[{}]", content_type));
-        monitor.log_request(log).await;
+        monitor.log_request(apply_body_policy(log, skip_bodies)).await;
         response
     }
 }
+
+/// 按调用方 Key 的日志策略去除请求/响应体 (脱敏在 ProxyMonitor::log_request 中统一执行)
+fn apply_body_policy(mut log: ProxyRequestLog, skip_bodies: bool) -> ProxyRequestLog {
+    if skip_bodies {
+        crate::proxy::redaction::strip_bodies(&mut log);
+    }
+    log
+}
@@ -9,25 +9,36 @@ pub mod token_manager;
 
 // 新架构模块
 pub mod audio; // 音频处理模块
+pub mod auto_ban; // 自动封禁引擎 (fail2ban 风格)
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
+pub mod client_ip; // 客户端 IP 解析 (可信代理 / CIDR)
 pub mod common; // 公共工具
 pub mod debug_logger;
+pub mod guardrails; // 内容策略守卫 (入站提示词 / 出站补全 拦截、脱敏、标记)
 pub mod handlers; // API 端点处理器
 pub mod mappers; // 协议转换器
 pub mod middleware; // Axum 中间件
+pub mod model_discovery; // 动态模型发现 (账号配额模型表)
 pub mod monitor; // 监控
 pub mod pm_router; // PM Router (multi-model orchestration)
+pub mod pm_rules; // PM Router 规则引擎 (无需 LLM 调用)
 pub mod providers; // Extra upstream providers (z.ai, etc.)
 pub mod rate_limit; // 限流跟踪
+pub mod redaction; // 日志脱敏 (内置检测器 + 自定义规则)
+pub mod request_context; // 请求级上下文 (调用方 API Key / 实际生效参数)
+pub mod security_events; // 安全事件记录与 SIEM 导出 (JSONL / CEF / syslog)
 pub mod session_manager; // 会话指纹管理
+pub mod share_token; // 分享令牌 (HMAC 签名的临时访问令牌)
 pub mod signature_cache; // Signature Cache (v3.3.16)
 pub mod sticky_config; // 粘性调度配置
+pub mod tls; // HTTPS 监听 (证书加载 / 自签名 / 热重载)
 pub mod upstream; // 上游客户端
 pub mod zai_vision_mcp; // Built-in Vision MCP server state
 pub mod zai_vision_tools; // Built-in Vision MCP tools (z.ai vision API) // 调试日志
 
 pub use config::get_thinking_budget_config;
 pub use config::update_thinking_budget_config;
+pub use config::update_tool_result_compression_config;
 pub use config::ProxyAuthMode;
 pub use config::ProxyConfig;
 pub use config::PmRouterConfig;
@@ -0,0 +1,165 @@
+//! 动态模型发现
+//! 从各账号的配额模型表 (fetchAvailableModels，含订阅等级限定的模型) 汇总实际可用的模型与账号数，
+//! 供 /v1/models、/v1beta/models 使用：新上游模型无需发版即可出现，无账号可服务的模型被隐藏。
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::proxy::common::model_registry;
+
+/// 模型发现快照
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelDiscovery {
+    /// 上游模型名 → 可服务的账号数
+    pub models: BTreeMap<String, usize>,
+    pub google_accounts: usize,
+    /// 已有配额模型表的 Google 账号数
+    pub google_accounts_with_quota: usize,
+    pub codex_accounts: usize,
+    pub refreshed_at: i64,
+}
+
+/// 列表中的模型及其可服务账号数 (None 表示无法判断，照常展示)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedModel {
+    pub id: String,
+    pub accounts: Option<usize>,
+}
+
+/// 读取账号文件中的配额模型名；无配额数据或账号被禁止 (403) 时返回 None
+pub fn quota_model_names(account: &Value) -> Option<Vec<String>> {
+    let quota = account.get("quota")?;
+    if quota.get("is_forbidden").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+    let names: Vec<String> = quota
+        .get("models")?
+        .as_array()?
+        .iter()
+        .filter_map(|m| m.get("name").and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+        .collect();
+    (!names.is_empty()).then_some(names)
+}
+
+impl ModelDiscovery {
+    pub fn record_google_account(&mut self, model_names: Option<Vec<String>>) {
+        self.google_accounts += 1;
+        if let Some(names) = model_names {
+            self.google_accounts_with_quota += 1;
+            for name in names {
+                *self.models.entry(name).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn record_codex_account(&mut self) {
+        self.codex_accounts += 1;
+    }
+
+    /// 至少有一个账号提供了可用模型信息时才据此过滤列表
+    pub fn is_ready(&self) -> bool {
+        self.google_accounts_with_quota > 0 || (self.google_accounts == 0 && self.codex_accounts > 0)
+    }
+
+    /// 可服务该模型的账号数
+    /// - Some(0): 注册表中已知但没有任何账号可用 → 隐藏
+    /// - None: 发现数据未就绪，或无法判断的自定义/透传模型 → 照常展示
+    pub fn accounts_for(&self, model: &str, custom_mapping: &HashMap<String, String>) -> Option<usize> {
+        if !self.is_ready() {
+            return None;
+        }
+        let registry = model_registry::registry();
+        let target = custom_mapping.get(model).map(String::as_str).unwrap_or(model);
+
+        let mut names: Vec<String> = vec![model.to_string(), target.to_string()];
+        let mut known = false;
+        for name in [model, target] {
+            if let Some(spec) = registry.get(name) {
+                known = true;
+                names.push(spec.id.clone());
+                names.push(spec.upstream_model().to_string());
+                names.extend(spec.quota_group.clone());
+            }
+        }
+        // 共享同一上游模型的条目 (如 gemini-3-pro-preview ← gemini-3-pro-high)
+        let siblings: Vec<String> = registry
+            .models()
+            .iter()
+            .filter(|m| names.iter().any(|n| n == m.upstream_model()))
+            .map(|m| m.id.clone())
+            .collect();
+        names.extend(siblings);
+        // 画图模型的分辨率/比例组合按基础模型统计
+        if model.starts_with("gemini-3-pro-image") {
+            names.push("gemini-3-pro-image".to_string());
+        }
+
+        if names.iter().any(|n| model_registry::is_codex_model(n)) {
+            return Some(self.codex_accounts);
+        }
+        if let Some(count) = names.iter().filter_map(|n| self.models.get(n)).max() {
+            return Some(*count);
+        }
+        known.then_some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn discovery() -> ModelDiscovery {
+        let mut d = ModelDiscovery::default();
+        d.record_google_account(Some(vec![
+            "gemini-3-flash".to_string(),
+            "gemini-3-pro-high".to_string(),
+            "gemini-2.5-flash".to_string(),
+        ]));
+        d.record_google_account(Some(vec![
+            "gemini-3-flash".to_string(),
+            "gemini-3.5-pro-exp".to_string(),
+        ]));
+        d.record_google_account(None);
+        d
+    }
+
+    #[test]
+    fn test_quota_model_names() {
+        let account = json!({ "quota": { "models": [{ "name": "gemini-3-flash" }, { "name": "claude-sonnet-4-5" }] } });
+        assert_eq!(
+            quota_model_names(&account),
+            Some(vec!["gemini-3-flash".to_string(), "claude-sonnet-4-5".to_string()])
+        );
+        let forbidden = json!({ "quota": { "is_forbidden": true, "models": [] } });
+        assert_eq!(quota_model_names(&forbidden), None);
+        assert_eq!(quota_model_names(&json!({})), None);
+    }
+
+    #[test]
+    fn test_accounts_for_resolves_aliases_and_hides_unserved() {
+        let d = discovery();
+        let custom = HashMap::from([("my-fast".to_string(), "gemini-3-flash".to_string())]);
+
+        assert!(d.is_ready());
+        assert_eq!(d.accounts_for("gemini-3-flash", &custom), Some(2));
+        // 别名 → 注册表条目
+        assert_eq!(d.accounts_for("gpt-4o", &custom), Some(1));
+        // 同一上游模型
+        assert_eq!(d.accounts_for("gemini-3-pro-preview", &custom), Some(1));
+        // 自定义映射
+        assert_eq!(d.accounts_for("my-fast", &custom), Some(2));
+        // 新上游模型
+        assert_eq!(d.accounts_for("gemini-3.5-pro-exp", &custom), Some(1));
+        // 已知但无账号可用
+        assert_eq!(d.accounts_for("claude-sonnet-4-5", &custom), Some(0));
+        assert_eq!(d.accounts_for("gpt-5.2-codex", &custom), Some(0));
+        // 无法判断
+        assert_eq!(d.accounts_for("some-passthrough", &custom), None);
+
+        assert_eq!(ModelDiscovery::default().accounts_for("gemini-3-flash", &custom), None);
+    }
+}
@@ -15,6 +15,7 @@ pub struct ProxyRequestLog {
     pub model: Option<String>,        // 客户端请求的模型名
     pub mapped_model: Option<String>, // 实际路由后使用的模型名
     pub pm_selected_model: Option<String>, // PM 라우터가 선택한 모델 (있을 때만)
+    pub thinking_budget: Option<u32>, // 实际生效的 thinking budget (0 表示策略禁用了 thinking)
     pub account_email: Option<String>,
     pub client_ip: Option<String>,    // 客户端 IP 地址
     pub error: Option<String>,
@@ -23,6 +24,10 @@ pub struct ProxyRequestLog {
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
     pub protocol: Option<String>,     // 协议类型: "openai", "anthropic", "gemini"
+    #[serde(default)]
+    pub redaction_count: Option<u32>, // 持久化前脱敏命中次数
+    #[serde(default)]
+    pub guard_result: Option<String>, // 内容守卫命中记录 (如 "input:block:pii; output:tag:acme")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -96,6 +101,22 @@ impl ProxyMonitor {
         if !self.is_enabled() {
             return;
         }
+        // [NEW] 写入内存/数据库前统一脱敏 (正则扫描大请求体较耗 CPU，放到阻塞线程池)
+        let redactor = crate::proxy::redaction::current();
+        let log = match tokio::task::spawn_blocking(move || {
+            let mut log = log;
+            redactor.scrub_log(&mut log);
+            log
+        })
+        .await
+        {
+            Ok(log) => log,
+            Err(e) => {
+                // 脱敏失败时丢弃该条日志，避免落盘未脱敏内容
+                tracing::error!("[Monitor] Failed to redact request log: {}", e);
+                return;
+            }
+        };
         tracing::info!("[Monitor] Logging request: {} {}", log.method, log.url);
         // Update stats
         {
@@ -143,6 +164,18 @@ impl ProxyMonitor {
                 if let Err(e) = crate::modules::security_db::save_ip_access_log(&security_log) {
                      tracing::error!("Failed to save security log: {}", e);
                 }
+
+                // [NEW] 安全事件流 (SIEM 导出)，仅记录路径: 查询串可能携带凭据 (如 `?key=`)
+                let path = log_to_save.url.split('?').next().unwrap_or_default();
+                crate::proxy::security_events::emit(
+                    crate::modules::security_db::SecurityEvent::new(
+                        crate::modules::security_db::SecurityEventKind::Access,
+                        format!("{} {} -> {}", log_to_save.method, path, log_to_save.status),
+                    )
+                    .ip(ip.clone())
+                    .request(log_to_save.method.clone(), path.to_string())
+                    .status(log_to_save.status),
+                );
             }
 
             // Record token stats if available
@@ -170,6 +203,7 @@ impl ProxyMonitor {
                 model: log.model.clone(),
                 mapped_model: log.mapped_model.clone(),
                 pm_selected_model: log.pm_selected_model.clone(),
+                thinking_budget: log.thinking_budget,
                 account_email: log.account_email.clone(),
                 client_ip: log.client_ip.clone(),
                 error: log.error.clone(),
@@ -178,6 +212,8 @@ impl ProxyMonitor {
                 input_tokens: log.input_tokens,
                 output_tokens: log.output_tokens,
                 protocol: log.protocol.clone(),
+                redaction_count: log.redaction_count,
+                guard_result: log.guard_result.clone(),
             };
             let _ = app.emit("proxy://request", &log_summary);
         }
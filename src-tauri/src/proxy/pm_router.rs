@@ -1,29 +1,20 @@
 use axum::http::HeaderMap;
-use serde::Deserialize;
-use serde_json::json;
-use tracing::{info, warn};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::{debug, info, warn};
 
 use crate::proxy::common::model_mapping::is_codex_model;
-use crate::proxy::config::{PmRouterConfig, PmRouterScope};
+use crate::proxy::common::model_registry;
+use crate::proxy::config::{PmRouterConfig, PmRouterScope, PmRoutingMode};
+use crate::proxy::pm_rules::{self, RuleEvaluation, RuleFeatures};
 use crate::proxy::mappers::claude::models::{ClaudeRequest, MessageContent, ContentBlock, SystemPrompt};
+use crate::proxy::mappers::context_manager::ContextManager;
+use crate::proxy::mappers::openai::models::OpenAIRequest;
 use crate::proxy::server::AppState;
-
-const ROUTER_ALLOWED_MODELS: &[&str] = &[
-    "gpt-5.2-codex",
-    "gpt-5.1-codex-max",
-    "gpt-5.1-codex-mini",
-    "claude-sonnet-4-5",
-    "claude-sonnet-4-5-thinking",
-    "claude-opus-4-5-thinking",
-    "gemini-2.5-pro",
-    "gemini-2.5-flash",
-    "gemini-2.5-flash-thinking",
-    "gemini-2.5-flash-lite",
-    "gemini-3-flash",
-    "gemini-3-pro-high",
-    "gemini-3-pro-low",
-    "gemini-3-pro-image",
-];
+use crate::proxy::session_manager::SessionManager;
 
 const ROUTER_PROMPT_TEMPLATE: &str = r#"You are the PM Router agent for Antigravity.
 Your job is to choose the BEST model for the task and return strict JSON.
@@ -70,13 +61,661 @@ struct RouterResponse {
     reason: String,
 }
 
+/// 请求所属协议，决定路由结果能否落到 Codex 模型上
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouterProtocol {
+    Claude,
+    OpenAI,
+    Gemini,
+}
+
+impl RouterProtocol {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Claude => "Claude",
+            Self::OpenAI => "OpenAI",
+            Self::Gemini => "Gemini",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "claude" | "anthropic" => Some(Self::Claude),
+            "openai" => Some(Self::OpenAI),
+            "gemini" => Some(Self::Gemini),
+            _ => None,
+        }
+    }
+
+    /// OpenAI 协议直接转发；Claude 协议经 Messages ↔ Responses 转换后由 Codex 账号处理
+    fn supports_codex(&self) -> bool {
+        matches!(self, Self::OpenAI | Self::Claude)
+    }
+}
+
 #[derive(Debug, Clone)]
+pub struct RouterMessage {
+    pub role: String,
+    pub text: String,
+}
+
+/// 与协议无关的路由上下文视图 (system prompt / 最近消息 / 图片 / 工具)
+#[derive(Debug, Clone, Default)]
+pub struct RouterInput {
+    pub requested_model: String,
+    pub system_prompt: Option<String>,
+    pub messages: Vec<RouterMessage>,
+    pub has_images: bool,
+    pub has_tools: bool,
+    /// SessionManager 会话指纹，用于复用路由决策 (None 时不缓存)
+    pub session_id: Option<String>,
+    /// 请求声明的工具名 (规则引擎匹配用)
+    pub tool_names: Vec<String>,
+    /// ContextManager 估算的输入 Token 数
+    pub estimated_tokens: u32,
+    /// 客户端是否请求了 thinking / reasoning
+    pub thinking_requested: bool,
+}
+
+impl RouterInput {
+    pub fn from_claude(request: &ClaudeRequest) -> Self {
+        let system_prompt = match &request.system {
+            Some(SystemPrompt::String(s)) => Some(s.clone()),
+            Some(SystemPrompt::Array(arr)) => Some(
+                arr.iter()
+                    .map(|b| b.text.clone())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            None => None,
+        };
+
+        Self {
+            requested_model: request.model.clone(),
+            system_prompt,
+            messages: request
+                .messages
+                .iter()
+                .map(|msg| RouterMessage {
+                    role: msg.role.clone(),
+                    text: extract_message_text(&msg.content),
+                })
+                .collect(),
+            has_images: request
+                .messages
+                .iter()
+                .any(|msg| message_has_image(&msg.content)),
+            has_tools: request.tools.as_ref().map(|t| !t.is_empty()).unwrap_or(false),
+            session_id: Some(SessionManager::extract_session_id(request)),
+            tool_names: request
+                .tools
+                .iter()
+                .flatten()
+                .filter_map(|t| t.name.clone())
+                .collect(),
+            estimated_tokens: ContextManager::estimate_token_usage(request),
+            thinking_requested: request
+                .thinking
+                .as_ref()
+                .map(|t| t.type_ == "enabled")
+                .unwrap_or(false)
+                || request.model.ends_with("-thinking"),
+        }
+    }
+
+    /// OpenAI Chat Completions / Responses / Legacy Completions 请求体
+    pub fn from_openai(body: &Value) -> Self {
+        let mut input = Self {
+            requested_model: body
+                .get("model")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            has_tools: body
+                .get("tools")
+                .and_then(|v| v.as_array())
+                .map(|t| !t.is_empty())
+                .unwrap_or(false),
+            ..Default::default()
+        };
+        let mut system_parts = Vec::new();
+
+        if let Some(instructions) = body.get("instructions").and_then(|v| v.as_str()) {
+            if !instructions.is_empty() {
+                system_parts.push(instructions.to_string());
+            }
+        }
+
+        if let Some(messages) = body.get("messages").and_then(|v| v.as_array()) {
+            for msg in messages {
+                let role = msg.get("role").and_then(|v| v.as_str()).unwrap_or("user");
+                let mut text = openai_content_text(msg.get("content"), &mut input.has_images);
+                if let Some(calls) = msg.get("tool_calls").and_then(|v| v.as_array()) {
+                    for call in calls {
+                        let name = call["function"]["name"].as_str().unwrap_or("unknown");
+                        push_part(&mut text, &format!("[tool_use:{}]", name));
+                    }
+                }
+                match role {
+                    "system" | "developer" => system_parts.push(text),
+                    "tool" => input.messages.push(RouterMessage {
+                        role: "user".to_string(),
+                        text: "[tool_result]".to_string(),
+                    }),
+                    _ => input.messages.push(RouterMessage {
+                        role: role.to_string(),
+                        text,
+                    }),
+                }
+            }
+        }
+
+        match body.get("input") {
+            Some(Value::String(s)) => input.messages.push(RouterMessage {
+                role: "user".to_string(),
+                text: s.clone(),
+            }),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let item_type = item.get("type").and_then(|v| v.as_str()).unwrap_or("message");
+                    let (role, text) = match item_type {
+                        "message" => (
+                            item.get("role").and_then(|v| v.as_str()).unwrap_or("user"),
+                            openai_content_text(item.get("content"), &mut input.has_images),
+                        ),
+                        "function_call" | "local_shell_call" | "web_search_call" => (
+                            "assistant",
+                            format!(
+                                "[tool_use:{}]",
+                                item.get("name").and_then(|v| v.as_str()).unwrap_or(item_type)
+                            ),
+                        ),
+                        "function_call_output" | "custom_tool_call_output" => {
+                            ("user", "[tool_result]".to_string())
+                        }
+                        _ => continue,
+                    };
+                    if role == "system" || role == "developer" {
+                        system_parts.push(text);
+                    } else {
+                        input.messages.push(RouterMessage {
+                            role: role.to_string(),
+                            text,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(prompt) = body.get("prompt") {
+            let text = match prompt {
+                Value::String(s) => s.clone(),
+                Value::Array(arr) => arr
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                _ => String::new(),
+            };
+            input.messages.push(RouterMessage {
+                role: "user".to_string(),
+                text,
+            });
+        }
+
+        if !system_parts.is_empty() {
+            input.system_prompt = Some(system_parts.join("\n"));
+        }
+
+        input.tool_names = openai_tool_names(body);
+        input.thinking_requested = body
+            .get("reasoning_effort")
+            .or_else(|| body.get("reasoning").and_then(|r| r.get("effort")))
+            .and_then(|v| v.as_str())
+            .map(|effort| !matches!(effort, "none" | "minimal"))
+            .unwrap_or(false);
+        input.estimated_tokens = input.estimate_text_tokens();
+
+        input.session_id = if body.get("messages").is_some() {
+            OpenAIRequest::deserialize(body)
+                .ok()
+                .map(|req| SessionManager::extract_openai_session_id(&req))
+        } else if body.get("input").is_some() {
+            Some(SessionManager::extract_responses_session_id(body))
+        } else {
+            None
+        };
+        input
+    }
+
+    /// Gemini 原生 generateContent 请求体 (模型名来自路径)
+    pub fn from_gemini(model: &str, body: &Value) -> Self {
+        let mut input = Self {
+            requested_model: model.to_string(),
+            session_id: Some(SessionManager::extract_gemini_session_id(body, model)),
+            has_tools: body
+                .get("tools")
+                .and_then(|v| v.as_array())
+                .map(|t| !t.is_empty())
+                .unwrap_or(false),
+            ..Default::default()
+        };
+
+        let system = body
+            .get("systemInstruction")
+            .or_else(|| body.get("system_instruction"));
+        if let Some(parts) = system.and_then(|s| s.get("parts")).and_then(|p| p.as_array()) {
+            let text = parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|v| v.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            if !text.is_empty() {
+                input.system_prompt = Some(text);
+            }
+        }
+
+        if let Some(contents) = body.get("contents").and_then(|v| v.as_array()) {
+            for content in contents {
+                let role = content.get("role").and_then(|v| v.as_str()).unwrap_or("user");
+                let mut text = String::new();
+                for part in content
+                    .get("parts")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(t) = part.get("text").and_then(|v| v.as_str()) {
+                        if part.get("thought").and_then(|v| v.as_bool()) != Some(true) {
+                            push_part(&mut text, t);
+                        }
+                    } else if let Some(call) = part.get("functionCall") {
+                        let name = call.get("name").and_then(|v| v.as_str()).unwrap_or("unknown");
+                        push_part(&mut text, &format!("[tool_use:{}]", name));
+                    } else if part.get("functionResponse").is_some() {
+                        push_part(&mut text, "[tool_result]");
+                    } else if let Some(data) = part.get("inlineData").or_else(|| part.get("fileData")) {
+                        let mime = data
+                            .get("mimeType")
+                            .or_else(|| data.get("mime_type"))
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("");
+                        if mime.starts_with("image/") {
+                            input.has_images = true;
+                            push_part(&mut text, "[image]");
+                        } else {
+                            push_part(&mut text, "[document]");
+                        }
+                    }
+                }
+                input.messages.push(RouterMessage {
+                    role: role.to_string(),
+                    text,
+                });
+            }
+        }
+
+        input.tool_names = body
+            .get("tools")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .flat_map(|tool| {
+                tool.get("functionDeclarations")
+                    .or_else(|| tool.get("function_declarations"))
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|f| f.get("name").and_then(|v| v.as_str()).map(String::from))
+            })
+            .collect();
+        input.thinking_requested = body
+            .get("generationConfig")
+            .and_then(|g| g.get("thinkingConfig"))
+            .map(|t| t.get("thinkingBudget").and_then(|v| v.as_i64()) != Some(0))
+            .unwrap_or(false);
+        input.estimated_tokens = input.estimate_text_tokens();
+        input
+    }
+
+    /// 非 Claude 协议：按已提取的文本估算 Token
+    fn estimate_text_tokens(&self) -> u32 {
+        let system = self
+            .system_prompt
+            .as_deref()
+            .map(ContextManager::estimate_text_usage)
+            .unwrap_or(0);
+        self.messages.iter().fold(system, |acc, m| {
+            acc + 4 + ContextManager::estimate_text_usage(&m.text)
+        })
+    }
+
+    /// 最近一条真实用户输入
+    pub fn latest_user_text(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|m| is_user_turn(m))
+            .map(|m| m.text.as_str())
+    }
+}
+
+/// OpenAI tools: Chat Completions `{function:{name}}` / Responses `{name}`
+fn openai_tool_names(body: &Value) -> Vec<String> {
+    body.get("tools")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| {
+            tool.get("function")
+                .and_then(|f| f.get("name"))
+                .or_else(|| tool.get("name"))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        })
+        .collect()
+}
+
+fn push_part(text: &mut String, part: &str) {
+    if part.is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push(' ');
+    }
+    text.push_str(part);
+}
+
+/// OpenAI 消息 content 可能是字符串或 parts 数组 (text / image_url / input_text / input_image ...)
+fn openai_content_text(content: Option<&Value>, has_images: &mut bool) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => {
+            let mut text = String::new();
+            for part in parts {
+                let part_type = part.get("type").and_then(|v| v.as_str()).unwrap_or("");
+                if matches!(part_type, "image_url" | "input_image") {
+                    *has_images = true;
+                    push_part(&mut text, "[image]");
+                } else if part_type == "input_file" {
+                    push_part(&mut text, "[document]");
+                } else if let Some(t) = part.get("text").and_then(|v| v.as_str()) {
+                    push_part(&mut text, t);
+                }
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct RouterDecision {
     pub selected_model: String,
     pub reason: String,
     pub task_type: String,
     pub used_router_model: String,
     pub used_pro: bool,
+    /// 是否来自会话决策缓存 (未调用 PM 模型)
+    #[serde(skip)]
+    pub from_cache: bool,
+}
+
+// ===== 会话级路由决策缓存 =====
+// 同一 Agent 会话内复用 PM 决策：省去每轮额外的 PM-lite/PM-pro 调用，
+// 并避免轮次间模型来回切换导致 thinking 签名失效与 Prompt Caching 失效。
+
+/// 缓存条目上限，超出时优先淘汰过期/最旧条目
+const MAX_CACHED_DECISIONS: usize = 2000;
+
+#[derive(Debug, Clone)]
+struct CachedDecision {
+    decision: RouterDecision,
+    protocol: RouterProtocol,
+    requested_model: String,
+    /// 做出决策时会话中的用户轮次数
+    user_turns: usize,
+    created_at: i64,
+    last_used_at: i64,
+    hits: u64,
+}
+
+/// 决策缓存条目快照 (Admin API)
+#[derive(Debug, Clone, Serialize)]
+pub struct CachedDecisionInfo {
+    pub session_key: String,
+    pub protocol: String,
+    pub requested_model: String,
+    pub decision: RouterDecision,
+    pub user_turns: usize,
+    pub created_at: i64,
+    pub last_used_at: i64,
+    pub age_secs: i64,
+    pub hits: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionCacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub reroutes: u64,
+    /// hits / (hits + misses + reroutes)
+    pub hit_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecisionCacheSnapshot {
+    pub stats: DecisionCacheStats,
+    pub decisions: Vec<CachedDecisionInfo>,
+}
+
+enum CacheLookup {
+    Hit(RouterDecision),
+    Miss,
+    Reroute(String),
+}
+
+#[derive(Default)]
+pub struct RouterDecisionCache {
+    entries: DashMap<String, CachedDecision>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    reroutes: AtomicU64,
+}
+
+static DECISION_CACHE: Lazy<RouterDecisionCache> = Lazy::new(RouterDecisionCache::default);
+
+/// 全局路由决策缓存
+pub fn decision_cache() -> &'static RouterDecisionCache {
+    &DECISION_CACHE
+}
+
+impl RouterDecisionCache {
+    fn lookup(&self, key: &str, input: &RouterInput, config: &PmRouterConfig, now: i64) -> CacheLookup {
+        let Some(mut entry) = self.entries.get_mut(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return CacheLookup::Miss;
+        };
+
+        if let Some(reason) = reroute_reason(&entry, input, config, now) {
+            drop(entry);
+            self.entries.remove(key);
+            self.reroutes.fetch_add(1, Ordering::Relaxed);
+            return CacheLookup::Reroute(reason);
+        }
+
+        entry.hits += 1;
+        entry.last_used_at = now;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let mut decision = entry.decision.clone();
+        decision.from_cache = true;
+        CacheLookup::Hit(decision)
+    }
+
+    fn insert(
+        &self,
+        key: String,
+        protocol: RouterProtocol,
+        input: &RouterInput,
+        decision: &RouterDecision,
+        config: &PmRouterConfig,
+        now: i64,
+    ) {
+        if self.entries.len() >= MAX_CACHED_DECISIONS {
+            let ttl = config.decision_cache_ttl_secs as i64;
+            self.entries.retain(|_, e| now - e.created_at < ttl);
+            if self.entries.len() >= MAX_CACHED_DECISIONS {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|e| e.last_used_at)
+                    .map(|e| e.key().clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+
+        self.entries.insert(
+            key,
+            CachedDecision {
+                decision: decision.clone(),
+                protocol,
+                requested_model: input.requested_model.clone(),
+                user_turns: count_user_turns(input),
+                created_at: now,
+                last_used_at: now,
+                hits: 0,
+            },
+        );
+    }
+
+    pub fn snapshot(&self) -> DecisionCacheSnapshot {
+        let now = chrono::Utc::now().timestamp();
+        let mut decisions: Vec<CachedDecisionInfo> = self
+            .entries
+            .iter()
+            .map(|e| CachedDecisionInfo {
+                session_key: e.key().clone(),
+                protocol: e.protocol.as_str().to_string(),
+                requested_model: e.requested_model.clone(),
+                decision: e.decision.clone(),
+                user_turns: e.user_turns,
+                created_at: e.created_at,
+                last_used_at: e.last_used_at,
+                age_secs: now - e.created_at,
+                hits: e.hits,
+            })
+            .collect();
+        decisions.sort_by(|a, b| b.last_used_at.cmp(&a.last_used_at));
+
+        DecisionCacheSnapshot {
+            stats: self.stats(),
+            decisions,
+        }
+    }
+
+    pub fn stats(&self) -> DecisionCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let reroutes = self.reroutes.load(Ordering::Relaxed);
+        let total = hits + misses + reroutes;
+        DecisionCacheStats {
+            entries: self.entries.len(),
+            hits,
+            misses,
+            reroutes,
+            hit_rate: if total == 0 { 0.0 } else { hits as f64 / total as f64 },
+        }
+    }
+
+    /// 清空缓存的决策与统计 (配置热更新 / Admin API)
+    pub fn clear(&self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        self.reroutes.store(0, Ordering::Relaxed);
+        count
+    }
+}
+
+/// 判断缓存决策是否需要重新路由，返回原因
+fn reroute_reason(
+    entry: &CachedDecision,
+    input: &RouterInput,
+    config: &PmRouterConfig,
+    now: i64,
+) -> Option<String> {
+    if entry.requested_model != input.requested_model {
+        return Some(format!(
+            "requested model changed ({} -> {})",
+            entry.requested_model, input.requested_model
+        ));
+    }
+    if now - entry.created_at >= config.decision_cache_ttl_secs as i64 {
+        return Some("ttl expired".to_string());
+    }
+
+    let turns = count_user_turns(input);
+    let new_turns = turns.saturating_sub(entry.user_turns);
+    if config.decision_cache_max_turns > 0 && new_turns >= config.decision_cache_max_turns {
+        return Some(format!("{} new user turns", new_turns));
+    }
+
+    // 只在出现新的用户轮次时检查任务类型；工具循环中的请求沿用原决策
+    if new_turns > 0 {
+        let latest = input.latest_user_text().unwrap_or_default();
+        if let Some(hint) = infer_task_hint(latest) {
+            if hint != entry.decision.task_type {
+                return Some(format!(
+                    "task type changed ({} -> {})",
+                    entry.decision.task_type, hint
+                ));
+            }
+        }
+        if !entry.decision.used_pro && should_escalate_to_pro(config, latest) {
+            return Some("escalation keyword in new turn".to_string());
+        }
+    }
+    None
+}
+
+/// 是否为真实的用户输入 (排除纯 tool_result 等占位内容)
+fn is_user_turn(msg: &RouterMessage) -> bool {
+    msg.role == "user"
+        && msg
+            .text
+            .split_whitespace()
+            .any(|w| !(w.starts_with('[') && w.ends_with(']')))
+}
+
+fn count_user_turns(input: &RouterInput) -> usize {
+    input.messages.iter().filter(|m| is_user_turn(m)).count()
+}
+
+/// 轻量级任务类型判别：仅在信号明确时返回 (coding/general 视为默认，不触发重路由)
+fn infer_task_hint(text: &str) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    let has_any = |keywords: &[&str]| keywords.iter().any(|k| lower.contains(k));
+
+    if lower.contains("[image]") {
+        Some("image")
+    } else if has_any(&["stack trace", "traceback", "panicked", "exception", "segfault", "root cause", "crash"]) {
+        Some("debugging")
+    } else if has_any(&["code review", "review this", "review the", "audit"]) {
+        Some("review")
+    } else if has_any(&["architecture", "system design", "design doc"]) {
+        Some("architecture")
+    } else if has_any(&["readme", "documentation", "changelog"]) {
+        Some("docs")
+    } else if has_any(&["research", "comparison", "alternatives"]) {
+        Some("research")
+    } else {
+        None
+    }
 }
 
 pub fn should_apply_router(config: &PmRouterConfig, headers: &HeaderMap) -> bool {
@@ -84,6 +723,11 @@ pub fn should_apply_router(config: &PmRouterConfig, headers: &HeaderMap) -> bool
         return false;
     }
 
+    // [NEW] 请求头 x-antigravity-no-router (需授权) 跳过路由
+    if crate::proxy::request_context::current_overrides().no_router {
+        return false;
+    }
+
     match config.scope {
         PmRouterScope::AllRequests => true,
         PmRouterScope::CliOnly => {
@@ -100,6 +744,19 @@ pub fn should_apply_router(config: &PmRouterConfig, headers: &HeaderMap) -> bool
     }
 }
 
+/// 在响应上附加 X-PM-Selected-Model，供监控中间件写入 pm_selected_model
+pub fn with_selected_model_header(
+    mut response: axum::response::Response,
+    selected_model: Option<String>,
+) -> axum::response::Response {
+    if let Some(model) = selected_model {
+        if let Ok(v) = axum::http::HeaderValue::from_str(&model) {
+            response.headers_mut().insert("X-PM-Selected-Model", v);
+        }
+    }
+    response
+}
+
 pub fn should_escalate_to_pro(config: &PmRouterConfig, context: &str) -> bool {
     if config.pro_keywords.is_empty() {
         return false;
@@ -147,8 +804,274 @@ pub async fn select_model_for_claude_request(
     headers: &HeaderMap,
     trace_id: &str,
 ) -> Result<RouterDecision, String> {
-    let context = build_router_context(request, config.max_context_chars);
-    let prompt = build_router_prompt(request, headers, &context);
+    select_model_cached(
+        state,
+        config,
+        &RouterInput::from_claude(request),
+        RouterProtocol::Claude,
+        headers,
+        trace_id,
+    )
+    .await
+}
+
+/// OpenAI / Gemini 处理器的统一入口：按 scope/cli_user_agents 判断是否路由，
+/// 返回路由选中的模型 (未路由或路由失败时返回 None，沿用请求模型)
+pub async fn route_request(
+    state: &AppState,
+    headers: &HeaderMap,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    trace_id: &str,
+) -> Option<String> {
+    let config = state.pm_router.read().await.clone();
+    if !should_apply_router(&config, headers) {
+        if config.enabled {
+            let ua = headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("-");
+            debug!(
+                "[{}][PM-Router] Skipped: scope=CLI-only and User-Agent not matched (UA={})",
+                trace_id, ua
+            );
+        }
+        return None;
+    }
+
+    match select_model_cached(state, &config, input, protocol, headers, trace_id).await {
+        Ok(decision) => {
+            info!(
+                "[{}][PM-Router][{}] {} -> {} via {}{} | task={} | reason={}",
+                trace_id,
+                protocol.as_str(),
+                input.requested_model,
+                decision.selected_model,
+                decision.used_router_model,
+                if decision.from_cache { " (cached)" } else { "" },
+                decision.task_type,
+                decision.reason
+            );
+            Some(decision.selected_model)
+        }
+        Err(err) => {
+            warn!(
+                "[{}][PM-Router] Routing failed: {} (fallback to request model {})",
+                trace_id, err, input.requested_model
+            );
+            None
+        }
+    }
+}
+
+/// 带会话决策缓存的模型选择：同一会话命中缓存时不再调用 PM 模型
+///
+/// 规则按每个请求的特征 (图片、API Key、User-Agent、Token 估算等) 评估，开销很小，
+/// 因此每次都先执行规则阶段，缓存只保存 PM 模型的决策
+pub async fn select_model_cached(
+    state: &AppState,
+    config: &PmRouterConfig,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    headers: &HeaderMap,
+    trace_id: &str,
+) -> Result<RouterDecision, String> {
+    if let Some(decision) =
+        select_model_by_rules_stage(state, config, input, protocol, headers, trace_id).await
+    {
+        return Ok(decision);
+    }
+
+    let session_key = match (&input.session_id, config.decision_cache_enabled) {
+        (Some(sid), true) => format!("{}:{}", protocol.as_str(), sid),
+        _ => return select_model_by_llm(state, config, input, protocol, headers, trace_id).await,
+    };
+
+    let cache = decision_cache();
+    match cache.lookup(&session_key, input, config, chrono::Utc::now().timestamp()) {
+        CacheLookup::Hit(decision) => {
+            debug!(
+                "[{}][PM-Router] Decision cache hit for {} -> {}",
+                trace_id, session_key, decision.selected_model
+            );
+            return Ok(decision);
+        }
+        CacheLookup::Reroute(reason) => {
+            info!(
+                "[{}][PM-Router] Re-routing session {}: {}",
+                trace_id, session_key, reason
+            );
+        }
+        CacheLookup::Miss => {}
+    }
+
+    let decision = select_model_by_llm(state, config, input, protocol, headers, trace_id).await?;
+    cache.insert(
+        session_key,
+        protocol,
+        input,
+        &decision,
+        config,
+        chrono::Utc::now().timestamp(),
+    );
+    Ok(decision)
+}
+
+// ===== 规则路由 =====
+
+/// 规则命中时 RouterDecision.used_router_model 的取值
+const RULES_ROUTER: &str = "rules";
+
+/// 规则候选模型的可用性检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CandidateCheck {
+    pub model: String,
+    pub usable: bool,
+    pub reason: Option<String>,
+}
+
+/// Dry-run 结果：哪条规则会命中、最终选中哪个模型
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleDryRun {
+    pub routing_mode: PmRoutingMode,
+    pub features: RuleFeatures,
+    pub evaluations: Vec<RuleEvaluation>,
+    pub matched_rule: Option<String>,
+    pub candidates: Vec<CandidateCheck>,
+    pub selected_model: Option<String>,
+    /// 规则未能给出模型时是否会继续询问 PM 模型
+    pub falls_back_to_llm: bool,
+}
+
+/// 依次检查候选模型，遇到第一个可用模型即停止
+async fn check_rule_candidates(
+    state: &AppState,
+    protocol: RouterProtocol,
+    models: &[String],
+) -> Vec<CandidateCheck> {
+    let mut checks = Vec::new();
+    for model in models {
+        let reason = if is_codex_model(model) && !protocol.supports_codex() {
+            Some(format!("Codex model not supported for {} protocol", protocol.as_str()))
+        } else if !state.token_manager.has_available_account("", model).await {
+            Some("no available account".to_string())
+        } else {
+            None
+        };
+        let usable = reason.is_none();
+        checks.push(CandidateCheck {
+            model: model.clone(),
+            usable,
+            reason,
+        });
+        if usable {
+            break;
+        }
+    }
+    checks
+}
+
+pub async fn dry_run_rules(
+    state: &AppState,
+    config: &PmRouterConfig,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    headers: &HeaderMap,
+) -> RuleDryRun {
+    let features = RuleFeatures::new(input, protocol.as_str(), headers);
+    let (matched, evaluations) = pm_rules::evaluate(&config.rules, &features);
+    let candidates = match matched {
+        Some(rule) => check_rule_candidates(state, protocol, &pm_rules::candidate_models(rule)).await,
+        None => Vec::new(),
+    };
+    let selected_model = candidates.iter().find(|c| c.usable).map(|c| c.model.clone());
+    let falls_back_to_llm = selected_model.is_none() && config.routing_mode != PmRoutingMode::RulesOnly;
+
+    RuleDryRun {
+        routing_mode: config.routing_mode,
+        features,
+        evaluations,
+        matched_rule: matched.map(|r| r.name.clone()),
+        candidates,
+        selected_model,
+        falls_back_to_llm,
+    }
+}
+
+/// 评估规则并返回决策；无规则命中或候选全部不可用时返回 None
+async fn select_model_by_rules(
+    state: &AppState,
+    config: &PmRouterConfig,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    headers: &HeaderMap,
+    trace_id: &str,
+) -> Option<RouterDecision> {
+    let features = RuleFeatures::new(input, protocol.as_str(), headers);
+    let (rule, _) = pm_rules::evaluate(&config.rules, &features);
+    let rule = rule?;
+
+    let checks = check_rule_candidates(state, protocol, &pm_rules::candidate_models(rule)).await;
+    let Some(selected) = checks.iter().find(|c| c.usable) else {
+        warn!(
+            "[{}][PM-Router] Rule '{}' matched but no candidate model is usable: {:?}",
+            trace_id, rule.name, checks
+        );
+        return None;
+    };
+
+    Some(RouterDecision {
+        selected_model: selected.model.clone(),
+        reason: if selected.model == rule.target_model.trim() {
+            format!("rule '{}'", rule.name)
+        } else {
+            format!("rule '{}' (fallback)", rule.name)
+        },
+        task_type: infer_task_hint(&features.latest_user_text)
+            .unwrap_or("general")
+            .to_string(),
+        used_router_model: RULES_ROUTER.to_string(),
+        used_pro: false,
+        from_cache: false,
+    })
+}
+
+/// 按 routing_mode 执行规则阶段；返回 Some 表示无需询问 PM 模型
+async fn select_model_by_rules_stage(
+    state: &AppState,
+    config: &PmRouterConfig,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    headers: &HeaderMap,
+    trace_id: &str,
+) -> Option<RouterDecision> {
+    if config.routing_mode == PmRoutingMode::Llm {
+        return None;
+    }
+    if let Some(decision) = select_model_by_rules(state, config, input, protocol, headers, trace_id).await {
+        return Some(decision);
+    }
+    (config.routing_mode == PmRoutingMode::RulesOnly).then(|| RouterDecision {
+        selected_model: input.requested_model.clone(),
+        reason: "no routing rule matched".to_string(),
+        task_type: "general".to_string(),
+        used_router_model: RULES_ROUTER.to_string(),
+        used_pro: false,
+        from_cache: false,
+    })
+}
+
+/// 询问 PM-lite (必要时升级到 PM-pro) 选择模型
+async fn select_model_by_llm(
+    state: &AppState,
+    config: &PmRouterConfig,
+    input: &RouterInput,
+    protocol: RouterProtocol,
+    headers: &HeaderMap,
+    trace_id: &str,
+) -> Result<RouterDecision, String> {
+    let context = build_router_context(input, config.max_context_chars);
+    let prompt = build_router_prompt(input, headers, &context);
 
     let (lite_response, used_lite_model) =
         call_router_with_fallback(
@@ -167,7 +1090,7 @@ pub async fn select_model_for_claude_request(
     let mut used_pro = false;
 
     if parsed_lite.needs_pro || should_escalate_to_pro(config, &context) {
-        let pro_prompt = build_router_prompt(request, headers, &context);
+        let pro_prompt = build_router_prompt(input, headers, &context);
         let (pro_response, used_pro_model_opt) =
             match call_router_with_fallback(
                 state,
@@ -201,11 +1124,12 @@ pub async fn select_model_for_claude_request(
         }
     }
 
-    if is_codex_model(&selected) {
+    if is_codex_model(&selected) && !protocol.supports_codex() {
         warn!(
-            "[{}][PM-Router] Selected Codex model ({}) is not supported for Claude protocol. Falling back to {}.",
+            "[{}][PM-Router] Selected Codex model ({}) is not supported for {} protocol. Falling back to {}.",
             trace_id,
             selected,
+            protocol.as_str(),
             config.fallback_model
         );
         selected = config.fallback_model.clone();
@@ -217,36 +1141,35 @@ pub async fn select_model_for_claude_request(
         task_type: parsed_lite.task_type,
         used_router_model,
         used_pro,
+        from_cache: false,
     })
 }
 
-fn build_router_prompt(request: &ClaudeRequest, headers: &HeaderMap, context: &str) -> String {
-    let model_list = ROUTER_ALLOWED_MODELS.join(", ");
+fn build_router_prompt(input: &RouterInput, headers: &HeaderMap, context: &str) -> String {
+    // 候选模型来自模型注册表 (router = true)
+    let model_list = model_registry::router_models().join(", ");
     let user_agent = headers
         .get(axum::http::header::USER_AGENT)
         .and_then(|v| v.to_str().ok())
         .unwrap_or("-");
-    let has_images = request.messages.iter().any(|msg| message_has_image(&msg.content));
-    let has_tools = request.tools.as_ref().map(|t| !t.is_empty()).unwrap_or(false);
-    let system_prompt = render_system_prompt(&request.system);
+    let system_prompt = input.system_prompt.as_deref().unwrap_or("-");
 
     ROUTER_PROMPT_TEMPLATE
         .replace("{{model_list}}", &model_list)
-        .replace("{{requested_model}}", &request.model)
+        .replace("{{requested_model}}", &input.requested_model)
         .replace("{{user_agent}}", user_agent)
-        .replace("{{has_images}}", &has_images.to_string())
-        .replace("{{has_tools}}", &has_tools.to_string())
-        .replace("{{system_prompt}}", &system_prompt)
+        .replace("{{has_images}}", &input.has_images.to_string())
+        .replace("{{has_tools}}", &input.has_tools.to_string())
+        .replace("{{system_prompt}}", system_prompt)
         .replace("{{recent_messages}}", context)
 }
 
-fn build_router_context(request: &ClaudeRequest, max_chars: usize) -> String {
+fn build_router_context(input: &RouterInput, max_chars: usize) -> String {
     let mut chunks = Vec::new();
 
-    for msg in request.messages.iter().rev().take(6).rev() {
-        let content = extract_message_text(&msg.content);
-        if !content.is_empty() {
-            chunks.push(format!("{}: {}", msg.role, content));
+    for msg in input.messages.iter().rev().take(6).rev() {
+        if !msg.text.is_empty() {
+            chunks.push(format!("{}: {}", msg.role, msg.text));
         }
     }
 
@@ -288,17 +1211,9 @@ fn message_has_image(content: &MessageContent) -> bool {
     }
 }
 
-fn render_system_prompt(system: &Option<SystemPrompt>) -> String {
-    match system {
-        Some(SystemPrompt::String(s)) => s.clone(),
-        Some(SystemPrompt::Array(arr)) => arr.iter().map(|b| b.text.clone()).collect::<Vec<_>>().join("\n"),
-        None => "-".to_string(),
-    }
-}
-
 fn validate_router_model(selected: &str, config: &PmRouterConfig) -> String {
     let trimmed = selected.trim();
-    if ROUTER_ALLOWED_MODELS.contains(&trimmed) {
+    if model_registry::router_models().iter().any(|m| m == trimmed) {
         trimmed.to_string()
     } else {
         config.fallback_model.clone()
@@ -488,12 +1403,10 @@ async fn call_anthropic_router_model(
     let upstream_proxy = state.upstream_proxy.read().await.clone();
     let mut builder = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(timeout_secs));
-    if let Some(config) = upstream_proxy {
-        if config.enabled && !config.url.is_empty() {
-            let proxy = reqwest::Proxy::all(&config.url)
-                .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
-            builder = builder.proxy(proxy);
-        }
+    if upstream_proxy.enabled && !upstream_proxy.url.is_empty() {
+        let proxy = reqwest::Proxy::all(&upstream_proxy.url)
+            .map_err(|e| format!("Invalid upstream proxy url: {}", e))?;
+        builder = builder.proxy(proxy);
     }
     let client = builder
         .build()
@@ -524,3 +1437,184 @@ async fn call_anthropic_router_model(
         .map(|s| s.to_string())
         .ok_or_else(|| "Anthropic router missing content".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_router_input_from_openai_responses() {
+        let body = json!({
+            "model": "gpt-5.2-codex",
+            "instructions": "You are Codex.",
+            "input": [
+                { "type": "message", "role": "user", "content": [
+                    { "type": "input_text", "text": "fix the login bug" },
+                    { "type": "input_image", "image_url": "data:image/png;base64,AAAA" }
+                ]},
+                { "type": "function_call", "name": "shell", "arguments": "{}", "call_id": "c1" },
+                { "type": "function_call_output", "call_id": "c1", "output": "ok" }
+            ],
+            "tools": [{ "type": "function", "name": "shell" }]
+        });
+
+        let input = RouterInput::from_openai(&body);
+        assert_eq!(input.requested_model, "gpt-5.2-codex");
+        assert_eq!(input.system_prompt.as_deref(), Some("You are Codex."));
+        assert!(input.has_images);
+        assert!(input.has_tools);
+        assert_eq!(input.messages.len(), 3);
+        assert_eq!(input.messages[0].text, "fix the login bug [image]");
+        assert_eq!(input.messages[1].text, "[tool_use:shell]");
+
+        let context = build_router_context(&input, 1000);
+        assert!(context.starts_with("user: fix the login bug"));
+    }
+
+    #[test]
+    fn test_responses_session_id_stable_without_user_text() {
+        // 首条输入过短时指纹取 instructions + 首个 input 项，后续轮次追加 input 不改变会话
+        let first_turn = json!({
+            "model": "gpt-5.2-codex",
+            "instructions": "You are Codex.",
+            "input": [{ "type": "message", "role": "user", "content": "hi" }]
+        });
+        let second_turn = json!({
+            "model": "gpt-5.2-codex",
+            "instructions": "You are Codex.",
+            "input": [
+                { "type": "message", "role": "user", "content": "hi" },
+                { "type": "message", "role": "assistant", "content": "Hello!" },
+                { "type": "message", "role": "user", "content": "ok" }
+            ]
+        });
+        let other_session = json!({
+            "model": "gpt-5.2-codex",
+            "instructions": "You are a reviewer.",
+            "input": [{ "type": "message", "role": "user", "content": "hi" }]
+        });
+
+        let sid = RouterInput::from_openai(&first_turn).session_id;
+        assert!(sid.is_some());
+        assert_eq!(sid, RouterInput::from_openai(&second_turn).session_id);
+        assert_ne!(sid, RouterInput::from_openai(&other_session).session_id);
+    }
+
+    #[test]
+    fn test_router_input_from_gemini() {
+        let body = json!({
+            "systemInstruction": { "parts": [{ "text": "Be brief." }] },
+            "contents": [
+                { "role": "user", "parts": [
+                    { "text": "describe this" },
+                    { "inlineData": { "mimeType": "image/png", "data": "AAAA" } }
+                ]},
+                { "role": "model", "parts": [{ "functionCall": { "name": "search", "args": {} } }] }
+            ]
+        });
+
+        let input = RouterInput::from_gemini("gemini-2.5-pro", &body);
+        assert_eq!(input.requested_model, "gemini-2.5-pro");
+        assert_eq!(input.system_prompt.as_deref(), Some("Be brief."));
+        assert!(input.has_images);
+        assert!(!input.has_tools);
+        assert_eq!(input.messages[0].text, "describe this [image]");
+        assert_eq!(input.messages[1].text, "[tool_use:search]");
+    }
+
+    fn user(text: &str) -> RouterMessage {
+        RouterMessage {
+            role: "user".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn decision(task_type: &str) -> RouterDecision {
+        RouterDecision {
+            selected_model: "gpt-5.2-codex".to_string(),
+            reason: "test".to_string(),
+            task_type: task_type.to_string(),
+            used_router_model: "gpt-5.1-codex-mini".to_string(),
+            used_pro: false,
+            from_cache: false,
+        }
+    }
+
+    fn session_input(messages: Vec<RouterMessage>) -> RouterInput {
+        RouterInput {
+            requested_model: "claude-sonnet-4-5".to_string(),
+            messages,
+            session_id: Some("sid-test".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decision_cache_reuses_within_tool_loop() {
+        let cache = RouterDecisionCache::default();
+        let config = PmRouterConfig::default();
+        let first = session_input(vec![user("implement the login form")]);
+
+        assert!(matches!(cache.lookup("k", &first, &config, 0), CacheLookup::Miss));
+        cache.insert("k".to_string(), RouterProtocol::Claude, &first, &decision("coding"), &config, 0);
+
+        // 工具循环：只追加 tool_result，不算新的用户轮次
+        let mut looped = first.clone();
+        looped.messages.push(user("[tool_result]"));
+        match cache.lookup("k", &looped, &config, 10) {
+            CacheLookup::Hit(d) => assert!(d.from_cache && d.selected_model == "gpt-5.2-codex"),
+            _ => panic!("expected cache hit"),
+        }
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (1, 1, 1));
+        assert!((stats.hit_rate - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_decision_cache_reroutes_on_material_change() {
+        let config = PmRouterConfig::default();
+        let base = session_input(vec![user("implement the login form")]);
+        let entry = CachedDecision {
+            decision: decision("coding"),
+            protocol: RouterProtocol::Claude,
+            requested_model: base.requested_model.clone(),
+            user_turns: count_user_turns(&base),
+            created_at: 0,
+            last_used_at: 0,
+            hits: 0,
+        };
+
+        // 同类任务的新轮次：沿用
+        let mut same = base.clone();
+        same.messages.push(user("now add a remember-me checkbox"));
+        assert!(reroute_reason(&entry, &same, &config, 10).is_none());
+
+        // 任务类型明显变化
+        let mut debug = base.clone();
+        debug.messages.push(user("it crashed, here is the stack trace"));
+        assert!(reroute_reason(&entry, &debug, &config, 10)
+            .unwrap()
+            .contains("task type changed"));
+
+        // 超过 N 轮
+        let mut long = base.clone();
+        for i in 0..config.decision_cache_max_turns {
+            long.messages.push(user(&format!("tweak the form {}", i)));
+        }
+        assert!(reroute_reason(&entry, &long, &config, 10).is_some());
+
+        // TTL 过期 / 客户端模型变化
+        assert!(reroute_reason(&entry, &base, &config, config.decision_cache_ttl_secs as i64).is_some());
+        let mut other_model = base.clone();
+        other_model.requested_model = "claude-opus-4-5".to_string();
+        assert!(reroute_reason(&entry, &other_model, &config, 10).is_some());
+    }
+
+    #[test]
+    fn test_codex_selection_not_for_gemini_protocol() {
+        assert!(RouterProtocol::OpenAI.supports_codex());
+        assert!(RouterProtocol::Claude.supports_codex());
+        assert!(!RouterProtocol::Gemini.supports_codex());
+    }
+}
@@ -0,0 +1,287 @@
+//! PM Router 规则引擎
+//! 按请求特征 (图片/工具/Token 档位/thinking/关键词/UA/API Key) 确定性地选择模型，
+//! 在调用 PM-lite/PM-pro 之前评估，命中即可省去一次 LLM 往返。
+
+use axum::http::{header, HeaderMap};
+use serde::Serialize;
+
+use crate::proxy::config::{PmRoutingRule, PmRuleConditions};
+use crate::proxy::pm_router::RouterInput;
+
+/// 规则匹配所用的请求特征
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleFeatures {
+    pub protocol: String,
+    pub requested_model: String,
+    pub has_images: bool,
+    pub has_tools: bool,
+    pub tool_names: Vec<String>,
+    pub estimated_tokens: u32,
+    pub thinking: bool,
+    pub latest_user_text: String,
+    pub user_agent: String,
+    #[serde(skip)]
+    pub api_key: Option<String>,
+}
+
+impl RuleFeatures {
+    pub fn new(input: &RouterInput, protocol: &str, headers: &HeaderMap) -> Self {
+        Self {
+            protocol: protocol.to_lowercase(),
+            requested_model: input.requested_model.clone(),
+            has_images: input.has_images,
+            has_tools: input.has_tools,
+            tool_names: input.tool_names.clone(),
+            estimated_tokens: input.estimated_tokens,
+            thinking: input.thinking_requested,
+            latest_user_text: input.latest_user_text().unwrap_or_default().to_string(),
+            user_agent: headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_string(),
+            api_key: extract_api_key(headers),
+        }
+    }
+}
+
+/// 与鉴权中间件一致：Authorization Bearer > x-api-key > x-goog-api-key
+fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.strip_prefix("Bearer ").unwrap_or(s))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .or_else(|| headers.get("x-goog-api-key").and_then(|h| h.to_str().ok()))
+        .map(|s| s.to_string())
+}
+
+/// 单条规则的评估结果 (dry-run 展示用)
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleEvaluation {
+    pub index: usize,
+    pub name: String,
+    pub enabled: bool,
+    pub matched: bool,
+    /// 未满足的条件
+    pub unmet: Vec<String>,
+}
+
+fn tool_name_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// 返回规则中未满足的条件，空表示命中
+pub fn unmet_conditions(c: &PmRuleConditions, f: &RuleFeatures) -> Vec<String> {
+    let mut unmet = Vec::new();
+
+    if let Some(expected) = c.has_images {
+        if f.has_images != expected {
+            unmet.push(format!("has_images={}", expected));
+        }
+    }
+    if let Some(expected) = c.has_tools {
+        if f.has_tools != expected {
+            unmet.push(format!("has_tools={}", expected));
+        }
+    }
+    if !c.tool_names.is_empty()
+        && !c
+            .tool_names
+            .iter()
+            .any(|p| f.tool_names.iter().any(|n| tool_name_matches(p, n)))
+    {
+        unmet.push(format!("tool_names in {:?}", c.tool_names));
+    }
+    if let Some(min) = c.min_tokens {
+        if f.estimated_tokens < min {
+            unmet.push(format!("tokens >= {}", min));
+        }
+    }
+    if let Some(max) = c.max_tokens {
+        if f.estimated_tokens >= max {
+            unmet.push(format!("tokens < {}", max));
+        }
+    }
+    if let Some(expected) = c.thinking {
+        if f.thinking != expected {
+            unmet.push(format!("thinking={}", expected));
+        }
+    }
+    if !c.keywords.is_empty() {
+        let text = f.latest_user_text.to_lowercase();
+        if !c.keywords.iter().any(|k| text.contains(&k.to_lowercase())) {
+            unmet.push(format!("keywords in {:?}", c.keywords));
+        }
+    }
+    if !c.user_agents.is_empty() {
+        let ua = f.user_agent.to_lowercase();
+        if !c.user_agents.iter().any(|u| ua.contains(&u.to_lowercase())) {
+            unmet.push(format!("user_agent contains {:?}", c.user_agents));
+        }
+    }
+    if !c.api_keys.is_empty()
+        && !f
+            .api_key
+            .as_ref()
+            .map(|k| c.api_keys.iter().any(|allowed| allowed == k))
+            .unwrap_or(false)
+    {
+        // 不回显 Key 本身
+        unmet.push("api_key".to_string());
+    }
+    if !c.protocols.is_empty() && !c.protocols.iter().any(|p| p.eq_ignore_ascii_case(&f.protocol)) {
+        unmet.push(format!("protocol in {:?}", c.protocols));
+    }
+
+    unmet
+}
+
+/// 自上而下评估规则，返回第一条命中的规则 (first match wins) 及全部评估明细
+pub fn evaluate<'a>(
+    rules: &'a [PmRoutingRule],
+    features: &RuleFeatures,
+) -> (Option<&'a PmRoutingRule>, Vec<RuleEvaluation>) {
+    let mut matched = None;
+    let mut evaluations = Vec::with_capacity(rules.len());
+
+    for (index, rule) in rules.iter().enumerate() {
+        let unmet = if rule.enabled {
+            unmet_conditions(&rule.conditions, features)
+        } else {
+            vec!["disabled".to_string()]
+        };
+        let is_match = rule.enabled && unmet.is_empty() && matched.is_none();
+        if is_match {
+            matched = Some(rule);
+        }
+        evaluations.push(RuleEvaluation {
+            index,
+            name: rule.name.clone(),
+            enabled: rule.enabled,
+            matched: is_match,
+            unmet,
+        });
+    }
+
+    (matched, evaluations)
+}
+
+/// 目标模型 + 备选模型 (去空、保序)
+pub fn candidate_models(rule: &PmRoutingRule) -> Vec<String> {
+    let mut models: Vec<String> = Vec::new();
+    for model in std::iter::once(&rule.target_model).chain(rule.fallback_models.iter()) {
+        let model = model.trim();
+        if !model.is_empty() && !models.iter().any(|m| m == model) {
+            models.push(model.to_string());
+        }
+    }
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features() -> RuleFeatures {
+        RuleFeatures {
+            protocol: "claude".to_string(),
+            requested_model: "claude-sonnet-4-5".to_string(),
+            has_images: false,
+            has_tools: true,
+            tool_names: vec!["Bash".to_string(), "mcp__github__create_pr".to_string()],
+            estimated_tokens: 12_000,
+            thinking: false,
+            latest_user_text: "Please review the auth middleware".to_string(),
+            user_agent: "claude-cli/2.0.1".to_string(),
+            api_key: Some("sk-team-a".to_string()),
+        }
+    }
+
+    fn rule(name: &str, conditions: PmRuleConditions, target: &str) -> PmRoutingRule {
+        PmRoutingRule {
+            name: name.to_string(),
+            enabled: true,
+            conditions,
+            target_model: target.to_string(),
+            fallback_models: vec![],
+        }
+    }
+
+    #[test]
+    fn test_conditions_all_must_match() {
+        let f = features();
+        let c = PmRuleConditions {
+            has_tools: Some(true),
+            tool_names: vec!["mcp__github__*".to_string()],
+            min_tokens: Some(8_000),
+            max_tokens: Some(32_000),
+            keywords: vec!["REVIEW".to_string()],
+            user_agents: vec!["claude-cli".to_string()],
+            api_keys: vec!["sk-team-a".to_string()],
+            protocols: vec!["Claude".to_string()],
+            ..Default::default()
+        };
+        assert!(unmet_conditions(&c, &f).is_empty());
+
+        let c = PmRuleConditions {
+            has_images: Some(true),
+            max_tokens: Some(12_000),
+            api_keys: vec!["sk-other".to_string()],
+            ..Default::default()
+        };
+        let unmet = unmet_conditions(&c, &f);
+        assert_eq!(unmet.len(), 3);
+        assert!(!unmet.iter().any(|u| u.contains("sk-")));
+    }
+
+    #[test]
+    fn test_first_enabled_match_wins() {
+        let f = features();
+        let mut disabled = rule("disabled", PmRuleConditions::default(), "gemini-2.5-flash");
+        disabled.enabled = false;
+        let rules = vec![
+            disabled,
+            rule(
+                "images",
+                PmRuleConditions {
+                    has_images: Some(true),
+                    ..Default::default()
+                },
+                "gemini-3-pro-image",
+            ),
+            rule(
+                "review",
+                PmRuleConditions {
+                    keywords: vec!["review".to_string()],
+                    ..Default::default()
+                },
+                "claude-sonnet-4-5",
+            ),
+            rule("catch-all", PmRuleConditions::default(), "gemini-2.5-flash"),
+        ];
+
+        let (matched, evaluations) = evaluate(&rules, &f);
+        assert_eq!(matched.unwrap().name, "review");
+        assert_eq!(evaluations.len(), 4);
+        assert!(evaluations.iter().filter(|e| e.matched).count() == 1);
+        assert_eq!(evaluations[0].unmet, vec!["disabled".to_string()]);
+        assert!(!evaluations[3].matched);
+    }
+
+    #[test]
+    fn test_candidate_models_dedup() {
+        let mut r = rule("r", PmRuleConditions::default(), "gpt-5.2-codex");
+        r.fallback_models = vec![
+            " claude-sonnet-4-5 ".to_string(),
+            "gpt-5.2-codex".to_string(),
+            "".to_string(),
+        ];
+        assert_eq!(candidate_models(&r), vec!["gpt-5.2-codex", "claude-sonnet-4-5"]);
+    }
+}
@@ -0,0 +1,282 @@
+// 请求级上下文 (task-local)
+//
+// monitor_middleware 为每个代理请求建立上下文，auth_middleware 写入调用方 API Key 与路由覆盖请求头，
+// mapper 等深层逻辑无需修改函数签名即可读取调用方信息并回写实际生效的参数，
+// 最终由 monitor_middleware 记录到 ProxyRequestLog。
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use axum::http::HeaderMap;
+
+use crate::proxy::config::ApiKeyConfig;
+use crate::proxy::sticky_config::SchedulingMode;
+
+pub const HEADER_ACCOUNT: &str = "x-antigravity-account";
+pub const HEADER_SCHEDULING: &str = "x-antigravity-scheduling";
+pub const HEADER_NO_ROUTER: &str = "x-antigravity-no-router";
+pub const HEADER_THINKING_BUDGET: &str = "x-antigravity-thinking-budget";
+pub const HEADER_PROVIDER: &str = "x-antigravity-provider";
+
+/// 强制指定的上游 Provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderOverride {
+    /// 仅使用 Google 账号池 (跳过 z.ai 分流与 Codex 自动路由)
+    Google,
+    /// Anthropic 协议请求强制走 z.ai
+    Zai,
+}
+
+impl ProviderOverride {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Google => "google",
+            Self::Zai => "zai",
+        }
+    }
+}
+
+/// 请求级路由覆盖 (`x-antigravity-*` 请求头，仅对被授权的 API Key 生效)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOverrides {
+    /// 固定账号 (account id 或邮箱)
+    pub account: Option<String>,
+    pub scheduling: Option<SchedulingMode>,
+    /// 跳过 PM Router
+    pub no_router: bool,
+    /// 固定 thinking budget (0 表示禁用 thinking)
+    pub thinking_budget: Option<u32>,
+    pub provider: Option<ProviderOverride>,
+}
+
+impl RequestOverrides {
+    /// 解析请求头，无法识别的值记录警告后忽略
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let get = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let mut overrides = Self::default();
+
+        overrides.account = get(HEADER_ACCOUNT).map(str::to_string);
+
+        if let Some(value) = get(HEADER_SCHEDULING) {
+            overrides.scheduling = match value.to_lowercase().replace(['-', '_'], "").as_str() {
+                "cachefirst" => Some(SchedulingMode::CacheFirst),
+                "balance" => Some(SchedulingMode::Balance),
+                "performancefirst" => Some(SchedulingMode::PerformanceFirst),
+                "useitorloseit" => Some(SchedulingMode::UseItOrLoseIt),
+                _ => {
+                    tracing::warn!("[Request-Override] Unknown scheduling mode '{}', ignored", value);
+                    None
+                }
+            };
+        }
+
+        overrides.no_router = get(HEADER_NO_ROUTER)
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+            .unwrap_or(false);
+
+        if let Some(value) = get(HEADER_THINKING_BUDGET) {
+            overrides.thinking_budget = match value.parse::<u32>() {
+                Ok(budget) => Some(budget),
+                Err(_) => {
+                    tracing::warn!("[Request-Override] Invalid thinking budget '{}', ignored", value);
+                    None
+                }
+            };
+        }
+
+        if let Some(value) = get(HEADER_PROVIDER) {
+            overrides.provider = match value.to_lowercase().as_str() {
+                "google" | "antigravity" => Some(ProviderOverride::Google),
+                "zai" | "z.ai" => Some(ProviderOverride::Zai),
+                _ => {
+                    tracing::warn!("[Request-Override] Unknown provider '{}', ignored", value);
+                    None
+                }
+            };
+        }
+
+        overrides
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 请求头中是否携带任何覆盖项 (用于未授权时提示)
+    pub fn present_in(headers: &HeaderMap) -> bool {
+        [HEADER_ACCOUNT, HEADER_SCHEDULING, HEADER_NO_ROUTER, HEADER_THINKING_BUDGET, HEADER_PROVIDER]
+            .iter()
+            .any(|h| headers.contains_key(*h))
+    }
+
+    /// 生效覆盖项摘要 (回显到响应头 `x-antigravity-overrides`)
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(account) = &self.account {
+            parts.push(format!("account={}", account));
+        }
+        if let Some(mode) = &self.scheduling {
+            parts.push(format!("scheduling={:?}", mode));
+        }
+        if self.no_router {
+            parts.push("no-router".to_string());
+        }
+        if let Some(budget) = self.thinking_budget {
+            parts.push(format!("thinking-budget={}", budget));
+        }
+        if let Some(provider) = &self.provider {
+            parts.push(format!("provider={}", provider.as_str()));
+        }
+        parts.join("; ")
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RequestContext {
+    /// 通过附加 API Key 鉴权时的 Key 配置 (主 api_key 或未鉴权时为 None)
+    api_key: Mutex<Option<ApiKeyConfig>>,
+    /// 实际生效的 thinking budget (0 表示被策略禁用)
+    thinking_budget: Mutex<Option<u32>>,
+    /// 已授权的路由覆盖
+    overrides: Mutex<RequestOverrides>,
+    /// 内容守卫命中记录 (如 `input:block:pii`)
+    guard_events: Mutex<Vec<String>>,
+    /// 入站守卫脱敏后的请求体 (用于替换日志中的原始请求体)
+    guarded_request_body: Mutex<Option<String>>,
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: Arc<RequestContext>;
+}
+
+/// 在指定上下文中执行 future
+pub async fn scope<F: Future>(ctx: Arc<RequestContext>, f: F) -> F::Output {
+    REQUEST_CONTEXT.scope(ctx, f).await
+}
+
+/// 当前请求上下文 (不在代理请求中时为 None，如后台任务、单元测试)
+pub fn current() -> Option<Arc<RequestContext>> {
+    REQUEST_CONTEXT.try_with(|ctx| ctx.clone()).ok()
+}
+
+/// 当前调用方的附加 API Key 配置
+pub fn current_api_key() -> Option<ApiKeyConfig> {
+    current().and_then(|ctx| ctx.api_key())
+}
+
+/// 当前请求的路由覆盖 (无上下文或未授权时为空)
+pub fn current_overrides() -> RequestOverrides {
+    current().map(|ctx| ctx.overrides()).unwrap_or_default()
+}
+
+/// 校验当前请求强制的 Provider 是否被该协议支持 (不支持时返回错误信息，由调用方返回 400)
+pub fn check_provider_override(protocol: &str, supported: &[ProviderOverride]) -> Result<(), String> {
+    match current_overrides().provider {
+        Some(provider) if !supported.contains(&provider) => Err(format!(
+            "{} '{}' is not supported for {} requests",
+            HEADER_PROVIDER,
+            provider.as_str(),
+            protocol
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// 记录实际生效的 thinking budget
+pub fn record_thinking_budget(budget: u32) {
+    if let Some(ctx) = current() {
+        if let Ok(mut slot) = ctx.thinking_budget.lock() {
+            *slot = Some(budget);
+        }
+    }
+}
+
+impl RequestContext {
+    pub fn set_api_key(&self, key: Option<ApiKeyConfig>) {
+        if let Ok(mut slot) = self.api_key.lock() {
+            *slot = key;
+        }
+    }
+
+    pub fn api_key(&self) -> Option<ApiKeyConfig> {
+        self.api_key.lock().ok().and_then(|k| k.clone())
+    }
+
+    pub fn thinking_budget(&self) -> Option<u32> {
+        self.thinking_budget.lock().ok().and_then(|b| *b)
+    }
+
+    pub fn set_overrides(&self, overrides: RequestOverrides) {
+        if let Ok(mut slot) = self.overrides.lock() {
+            *slot = overrides;
+        }
+    }
+
+    pub fn overrides(&self) -> RequestOverrides {
+        self.overrides.lock().map(|o| o.clone()).unwrap_or_default()
+    }
+
+    /// 记录守卫命中 (重复项忽略)
+    pub fn record_guard_event(&self, event: String) {
+        if let Ok(mut events) = self.guard_events.lock() {
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+    }
+
+    /// 守卫命中摘要 (无命中时为 None)
+    pub fn guard_summary(&self) -> Option<String> {
+        self.guard_events
+            .lock()
+            .ok()
+            .filter(|events| !events.is_empty())
+            .map(|events| events.join("; "))
+    }
+
+    pub fn set_guarded_request_body(&self, body: String) {
+        if let Ok(mut slot) = self.guarded_request_body.lock() {
+            *slot = Some(body);
+        }
+    }
+
+    pub fn guarded_request_body(&self) -> Option<String> {
+        self.guarded_request_body.lock().ok().and_then(|b| b.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_override_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_ACCOUNT, "a@example.com".parse().unwrap());
+        headers.insert(HEADER_SCHEDULING, "performance-first".parse().unwrap());
+        headers.insert(HEADER_NO_ROUTER, "true".parse().unwrap());
+        headers.insert(HEADER_THINKING_BUDGET, "0".parse().unwrap());
+        headers.insert(HEADER_PROVIDER, "Google".parse().unwrap());
+
+        let overrides = RequestOverrides::from_headers(&headers);
+        assert_eq!(overrides.account.as_deref(), Some("a@example.com"));
+        assert_eq!(overrides.scheduling, Some(SchedulingMode::PerformanceFirst));
+        assert!(overrides.no_router);
+        assert_eq!(overrides.thinking_budget, Some(0));
+        assert_eq!(overrides.provider, Some(ProviderOverride::Google));
+        assert!(RequestOverrides::present_in(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER_SCHEDULING, "fastest".parse().unwrap());
+        headers.insert(HEADER_THINKING_BUDGET, "lots".parse().unwrap());
+        let overrides = RequestOverrides::from_headers(&headers);
+        assert!(overrides.is_empty());
+        assert!(RequestOverrides::from_headers(&HeaderMap::new()).is_empty());
+    }
+}
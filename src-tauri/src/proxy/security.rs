@@ -1,10 +1,25 @@
-use crate::proxy::config::{ProxyAuthMode, ProxyConfig, SecurityMonitorConfig};
+use sha2::digest::CtOutput;
+use sha2::{Digest, Sha256};
+
+use crate::proxy::config::{AdminAuthConfig, ApiKeyConfig, ProxyAuthMode, ProxyConfig, SecurityMonitorConfig};
+
+/// 常量时间比较密钥: 比较两者的 SHA-256 摘要，避免按长度或公共前缀泄露时间差
+pub fn secret_matches(provided: &str, expected: &str) -> bool {
+    CtOutput::<Sha256>::new(Sha256::digest(provided.as_bytes()))
+        == CtOutput::new(Sha256::digest(expected.as_bytes()))
+}
 
 #[derive(Debug, Clone)]
 pub struct ProxySecurityConfig {
     pub auth_mode: ProxyAuthMode,
     pub api_key: String,
+    /// 附加 API Key (仅用于代理接口)
+    pub api_keys: Vec<ApiKeyConfig>,
+    /// 主 api_key / 未鉴权调用方是否可使用路由覆盖请求头
+    pub allow_request_overrides: bool,
     pub admin_password: Option<String>,
+    /// 管理后台用户会话配置
+    pub admin_auth: AdminAuthConfig,
     pub allow_lan_access: bool,
     pub port: u16,
     pub security_monitor: SecurityMonitorConfig,
@@ -15,13 +30,23 @@ impl ProxySecurityConfig {
         Self {
             auth_mode: config.auth_mode.clone(),
             api_key: config.api_key.clone(),
+            api_keys: config.api_keys.clone(),
+            allow_request_overrides: config.allow_request_overrides,
             admin_password: config.admin_password.clone(),
+            admin_auth: config.admin_auth.clone(),
             allow_lan_access: config.allow_lan_access,
             port: config.port,
             security_monitor: config.security_monitor.clone(),
         }
     }
 
+    /// 查找已启用的附加 API Key
+    pub fn find_api_key(&self, key: &str) -> Option<&ApiKeyConfig> {
+        self.api_keys
+            .iter()
+            .find(|k| k.enabled && !k.key.is_empty() && secret_matches(key, &k.key))
+    }
+
     pub fn effective_auth_mode(&self) -> ProxyAuthMode {
         match self.auth_mode {
             ProxyAuthMode::Auto => {
@@ -40,12 +65,22 @@ impl ProxySecurityConfig {
 mod tests {
     use super::*;
 
+    #[test]
+    fn secret_matches_requires_exact_value() {
+        assert!(secret_matches("sk-antigravity", "sk-antigravity"));
+        assert!(!secret_matches("sk-antigravity", "sk-antigravity2"));
+        assert!(!secret_matches("", "sk-antigravity"));
+    }
+
     #[test]
     fn auto_mode_resolves_off_for_local_only() {
         let s = ProxySecurityConfig {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
+            api_keys: Vec::new(),
+            allow_request_overrides: false,
             admin_password: None,
+            admin_auth: AdminAuthConfig::default(),
             allow_lan_access: false,
             port: 8080,
             security_monitor: crate::proxy::config::SecurityMonitorConfig::default(),
@@ -58,7 +93,10 @@ mod tests {
         let s = ProxySecurityConfig {
             auth_mode: ProxyAuthMode::Auto,
             api_key: "sk-test".to_string(),
+            api_keys: Vec::new(),
+            allow_request_overrides: false,
             admin_password: None,
+            admin_auth: AdminAuthConfig::default(),
             allow_lan_access: true,
             port: 8080,
             security_monitor: crate::proxy::config::SecurityMonitorConfig::default(),
@@ -0,0 +1,396 @@
+// 安全事件 (SIEM 导出)
+//
+// 访问、拦截、鉴权失败、封禁创建/到期与配置变更统一经 emit() 投递到后台线程:
+// 写入 security.db 的 security_events 表 (seq 自增作为 `/api/security/events` 的游标)，
+// 再按配置追加到本地文件 (JSON Lines / CEF / RFC 5424) 或转发到 syslog 采集端 (UDP / TCP)。
+
+use once_cell::sync::Lazy;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::modules::security_db::{self, SecurityEvent, SecurityEventKind};
+use crate::proxy::config::{SecurityEventExportConfig, SecurityEventFormat, SyslogTransport};
+
+/// 待处理事件队列上限 (满时丢弃并记录警告，避免阻塞请求路径)
+const EVENT_QUEUE_SIZE: usize = 10_000;
+/// 队列空闲时的唤醒间隔 (用于定期清理)
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+/// syslog 连接 / 发送超时
+const SYSLOG_TIMEOUT: Duration = Duration::from_secs(5);
+/// 过期事件清理间隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+/// syslog facility: authpriv (10)
+const SYSLOG_FACILITY: u8 = 10;
+/// RFC 5424 结构化数据 ID (32473 为文档保留的私有企业号)
+const SD_ID: &str = "antigravity@32473";
+const APP_NAME: &str = "antigravity";
+
+impl SecurityEvent {
+    pub fn new(kind: SecurityEventKind, message: impl Into<String>) -> Self {
+        Self {
+            seq: 0,
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            kind,
+            client_ip: None,
+            method: None,
+            path: None,
+            status: None,
+            actor: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn ip(mut self, ip: impl Into<String>) -> Self {
+        self.client_ip = Some(ip.into());
+        self
+    }
+
+    pub fn request(mut self, method: impl Into<String>, path: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub fn actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+}
+
+/// CEF 严重级别 (0-10)
+fn cef_severity(kind: SecurityEventKind) -> u8 {
+    match kind {
+        SecurityEventKind::Access => 1,
+        SecurityEventKind::BanExpired => 3,
+        SecurityEventKind::ConfigChange => 4,
+        SecurityEventKind::Blocked => 5,
+        SecurityEventKind::AuthFailure => 6,
+        SecurityEventKind::BanCreated => 7,
+    }
+}
+
+/// syslog 严重级别 (RFC 5424: 4 warning / 5 notice / 6 informational)
+fn syslog_severity(kind: SecurityEventKind) -> u8 {
+    match kind {
+        SecurityEventKind::Access => 6,
+        SecurityEventKind::BanExpired | SecurityEventKind::ConfigChange => 5,
+        SecurityEventKind::Blocked | SecurityEventKind::AuthFailure | SecurityEventKind::BanCreated => 4,
+    }
+}
+
+fn cef_escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn cef_escape_extension(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+fn sd_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace(']', "\\]")
+}
+
+/// 格式化为单行 CEF
+pub fn format_cef(event: &SecurityEvent) -> String {
+    let mut extensions = vec![
+        format!("rt={}", event.timestamp),
+        format!("externalId={}", event.seq),
+    ];
+    let optional = [
+        ("src", event.client_ip.as_deref()),
+        ("requestMethod", event.method.as_deref()),
+        ("request", event.path.as_deref()),
+        ("suser", event.actor.as_deref()),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            extensions.push(format!("{}={}", key, cef_escape_extension(value)));
+        }
+    }
+    if let Some(status) = event.status {
+        extensions.push(format!("outcome={}", status));
+    }
+    extensions.push(format!("msg={}", cef_escape_extension(&event.message)));
+
+    format!(
+        "CEF:0|Antigravity|Antigravity Tools|{}|{}|{}|{}|{}",
+        env!("CARGO_PKG_VERSION"),
+        event.kind.as_str(),
+        cef_escape_header(&event.message),
+        cef_severity(event.kind),
+        extensions.join(" ")
+    )
+}
+
+/// 格式化为 RFC 5424 syslog 消息，`body` 为空时使用事件描述
+pub fn format_syslog(event: &SecurityEvent, hostname: &str, body: Option<&str>) -> String {
+    let pri = SYSLOG_FACILITY * 8 + syslog_severity(event.kind);
+    let timestamp = chrono::DateTime::from_timestamp_millis(event.timestamp)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+    let mut params = vec![format!("seq=\"{}\"", event.seq)];
+    let optional = [
+        ("ip", event.client_ip.as_deref()),
+        ("method", event.method.as_deref()),
+        ("path", event.path.as_deref()),
+        ("actor", event.actor.as_deref()),
+    ];
+    for (key, value) in optional {
+        if let Some(value) = value {
+            params.push(format!("{}=\"{}\"", key, sd_escape(value)));
+        }
+    }
+    if let Some(status) = event.status {
+        params.push(format!("status=\"{}\"", status));
+    }
+
+    format!(
+        "<{}>1 {} {} {} {} {} [{} {}] {}",
+        pri,
+        timestamp,
+        if hostname.is_empty() { "-" } else { hostname },
+        APP_NAME,
+        std::process::id(),
+        event.kind.as_str(),
+        SD_ID,
+        params.join(" "),
+        body.unwrap_or(&event.message).replace(['\r', '\n'], " ")
+    )
+}
+
+/// 按格式渲染单行事件
+pub fn format_event(event: &SecurityEvent, format: SecurityEventFormat, hostname: &str) -> String {
+    match format {
+        SecurityEventFormat::Jsonl => serde_json::to_string(event).unwrap_or_default(),
+        SecurityEventFormat::Cef => format_cef(event),
+        SecurityEventFormat::Syslog => format_syslog(event, hostname, None),
+    }
+}
+
+/// 批量渲染为换行分隔的文本 (拉取接口使用)
+pub fn render_events(events: &[SecurityEvent], format: SecurityEventFormat) -> String {
+    let host = hostname();
+    events
+        .iter()
+        .map(|event| format_event(event, format, &host))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn hostname() -> String {
+    sysinfo::System::host_name().unwrap_or_default()
+}
+
+static CONFIG: Lazy<RwLock<SecurityEventExportConfig>> =
+    Lazy::new(|| RwLock::new(SecurityEventExportConfig::default()));
+
+/// 更新导出配置 (后台线程在下一条事件时生效)
+pub fn update_event_export_config(config: &SecurityEventExportConfig) {
+    tracing::info!(
+        "[Security-Events] Config updated: access={}, file={}, syslog={}",
+        config.include_access,
+        config.file_path.as_deref().unwrap_or("-"),
+        if config.syslog_enabled {
+            format!("{:?}://{}:{}", config.syslog_transport, config.syslog_host, config.syslog_port).to_lowercase()
+        } else {
+            "off".to_string()
+        }
+    );
+    if let Ok(mut slot) = CONFIG.write() {
+        *slot = config.clone();
+    }
+}
+
+fn current_config() -> SecurityEventExportConfig {
+    CONFIG.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+static SENDER: Lazy<Option<SyncSender<SecurityEvent>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::sync_channel(EVENT_QUEUE_SIZE);
+    match std::thread::Builder::new()
+        .name("security-events".to_string())
+        .spawn(move || worker(rx))
+    {
+        Ok(_) => Some(tx),
+        Err(e) => {
+            tracing::error!("[Security-Events] Failed to start worker: {}", e);
+            None
+        }
+    }
+});
+
+/// 投递安全事件 (非阻塞)
+pub fn emit(event: SecurityEvent) {
+    let Some(sender) = SENDER.as_ref() else {
+        return;
+    };
+    if let Err(TrySendError::Full(event)) = sender.try_send(event) {
+        tracing::warn!("[Security-Events] Queue full, dropped {} event", event.kind.as_str());
+    }
+}
+
+fn worker(rx: mpsc::Receiver<SecurityEvent>) {
+    let mut exporter = Exporter::default();
+    let mut last_cleanup: Option<Instant> = None;
+
+    loop {
+        match rx.recv_timeout(IDLE_INTERVAL) {
+            Ok(event) => exporter.handle(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if last_cleanup.map_or(true, |t| t.elapsed() >= CLEANUP_INTERVAL) {
+            last_cleanup = Some(Instant::now());
+            let retention_days = current_config().retention_days;
+            if retention_days > 0 {
+                if let Err(e) = security_db::cleanup_old_security_events(retention_days as i64) {
+                    tracing::error!("[Security-Events] Failed to clean up old events: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 文件与 syslog 输出 (TCP 连接在失败时重建)
+#[derive(Default)]
+struct Exporter {
+    hostname: Option<String>,
+    udp: Option<UdpSocket>,
+    tcp: Option<(String, TcpStream)>,
+}
+
+impl Exporter {
+    fn handle(&mut self, mut event: SecurityEvent) {
+        let config = current_config();
+        if event.kind == SecurityEventKind::Access && !config.include_access {
+            return;
+        }
+        match security_db::insert_security_event(&event) {
+            Ok(seq) => event.seq = seq,
+            Err(e) => tracing::error!("[Security-Events] Failed to save event: {}", e),
+        }
+
+        let hostname = self.hostname.get_or_insert_with(hostname).clone();
+        if let Some(path) = config.file_path.as_deref().filter(|p| !p.trim().is_empty()) {
+            let line = format_event(&event, config.file_format, &hostname);
+            let result = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+            if let Err(e) = result {
+                tracing::error!("[Security-Events] Failed to write {}: {}", path, e);
+            }
+        }
+
+        if config.syslog_enabled {
+            let message = match config.syslog_format {
+                SecurityEventFormat::Syslog => format_syslog(&event, &hostname, None),
+                format => format_syslog(&event, &hostname, Some(&format_event(&event, format, &hostname))),
+            };
+            if let Err(e) = self.send_syslog(&config, &message) {
+                tracing::warn!(
+                    "[Security-Events] Failed to send syslog to {}:{}: {}",
+                    config.syslog_host,
+                    config.syslog_port,
+                    e
+                );
+            }
+        }
+    }
+
+    fn send_syslog(&mut self, config: &SecurityEventExportConfig, message: &str) -> std::io::Result<()> {
+        let target = format!("{}:{}", config.syslog_host, config.syslog_port);
+        match config.syslog_transport {
+            SyslogTransport::Udp => {
+                if self.udp.is_none() {
+                    let socket = UdpSocket::bind(if config.syslog_host.contains(':') { "[::]:0" } else { "0.0.0.0:0" })?;
+                    socket.set_write_timeout(Some(SYSLOG_TIMEOUT))?;
+                    self.udp = Some(socket);
+                }
+                let socket = self.udp.as_ref().expect("udp socket initialized above");
+                socket.send_to(message.as_bytes(), target.as_str()).map(|_| ())
+            }
+            SyslogTransport::Tcp => {
+                // RFC 6587 octet-counting
+                let frame = format!("{} {}", message.len(), message);
+                if let Some((addr, stream)) = self.tcp.as_mut() {
+                    if *addr == target && stream.write_all(frame.as_bytes()).is_ok() {
+                        return Ok(());
+                    }
+                }
+                self.tcp = None;
+                let mut stream = connect_with_timeout(&target)?;
+                stream.set_write_timeout(Some(SYSLOG_TIMEOUT))?;
+                stream.write_all(frame.as_bytes())?;
+                self.tcp = Some((target, stream));
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 逐个尝试解析出的地址，单个地址连接超时为 SYSLOG_TIMEOUT
+fn connect_with_timeout(target: &str) -> std::io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in target.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, SYSLOG_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, format!("no address resolved for {}", target))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SecurityEvent {
+        let mut event = SecurityEvent::new(SecurityEventKind::Blocked, "IP in blacklist: scanner | bad=actor")
+            .ip("203.0.113.7")
+            .request("GET", "/.env")
+            .status(403);
+        event.seq = 42;
+        event.timestamp = 1_700_000_000_123;
+        event
+    }
+
+    #[test]
+    fn test_format_cef() {
+        let line = format_cef(&sample());
+        assert!(line.starts_with("CEF:0|Antigravity|Antigravity Tools|"));
+        assert!(line.contains("|blocked|IP in blacklist: scanner \\| bad=actor|5|"), "{}", line);
+        assert!(line.contains("rt=1700000000123 externalId=42 src=203.0.113.7 requestMethod=GET request=/.env outcome=403"));
+        assert!(line.ends_with("msg=IP in blacklist: scanner | bad\\=actor"));
+    }
+
+    #[test]
+    fn test_format_syslog_rfc5424() {
+        let line = format_syslog(&sample(), "gateway", None);
+        // authpriv (10) * 8 + warning (4)
+        assert!(line.starts_with("<84>1 2023-11-14T22:13:20.123Z gateway antigravity "), "{}", line);
+        assert!(line.contains(" blocked [antigravity@32473 seq=\"42\" ip=\"203.0.113.7\" method=\"GET\" path=\"/.env\" status=\"403\"] IP in blacklist"));
+
+        let json = format_event(&sample(), SecurityEventFormat::Jsonl, "");
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["kind"], "blocked");
+        assert_eq!(parsed["seq"], 42);
+    }
+}
@@ -1,5 +1,9 @@
 use crate::models::AppConfig;
-use crate::modules::{account, config, logger, migration, proxy_db, security_db, token_stats};
+use crate::modules::{
+    account, admin_db, audit_db, config, config_history, logger, migration, proxy_db, security_db, summary_db,
+    token_stats,
+};
+use crate::modules::config_history::ConfigSource;
 use crate::proxy::TokenManager;
 use axum::{
     extract::{DefaultBodyLimit, Path, Query, State},
@@ -110,6 +114,8 @@ struct AccountResponse {
     quota: Option<QuotaResponse>,
     device_bound: bool,
     last_used: i64,
+    usage_limits: crate::models::AccountUsageLimits,
+    availability_windows: Vec<crate::models::AvailabilityWindow>,
 }
 
 #[derive(Serialize)]
@@ -166,6 +172,117 @@ fn to_account_response(
         }),
         device_bound: account.device_profile.is_some(),
         last_used: account.last_used,
+        usage_limits: account.usage_limits.clone(),
+        availability_windows: account.availability_windows.clone(),
+    }
+}
+
+/// 可热更新的运行时配置句柄 (AxumServer 与 AppState 共享同一组 Arc)
+struct RuntimeConfigHandles {
+    custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    pm_router: Arc<RwLock<crate::proxy::PmRouterConfig>>,
+    upstream_proxy: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
+    upstream: Arc<crate::proxy::upstream::client::UpstreamClient>,
+    security: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
+    zai: Arc<RwLock<crate::proxy::ZaiConfig>>,
+    experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
+    debug_logging: Arc<RwLock<crate::proxy::config::DebugLoggingConfig>>,
+    token_manager: Arc<TokenManager>,
+}
+
+impl RuntimeConfigHandles {
+    fn from_state(state: &AppState) -> Self {
+        Self {
+            custom_mapping: state.custom_mapping.clone(),
+            pm_router: state.pm_router.clone(),
+            upstream_proxy: state.upstream_proxy.clone(),
+            upstream: state.upstream.clone(),
+            security: state.security.clone(),
+            zai: state.zai.clone(),
+            experimental: state.experimental.clone(),
+            debug_logging: state.debug_logging.clone(),
+            token_manager: state.token_manager.clone(),
+        }
+    }
+
+    /// 仅热更新发生变化的分区，未变化的运行时状态保持不动
+    async fn apply(&self, config: &AppConfig, commit: &crate::modules::config::ConfigCommit) {
+        let proxy = &config.proxy;
+
+        if commit.touches("proxy.custom_mapping") {
+            *self.custom_mapping.write().await = proxy.custom_mapping.clone();
+        }
+        if commit.touches("proxy.pm_router") {
+            *self.pm_router.write().await = proxy.pm_router.clone();
+            crate::proxy::pm_router::decision_cache().clear();
+        }
+        if commit.touches("proxy.upstream_proxy") {
+            *self.upstream_proxy.write().await = proxy.upstream_proxy.clone();
+        }
+        // ProxySecurityConfig 由这些字段组合而成
+        const SECURITY_SECTIONS: &[&str] = &[
+            "proxy.auth_mode",
+            "proxy.api_key",
+            "proxy.api_keys",
+            "proxy.allow_request_overrides",
+            "proxy.admin_password",
+            "proxy.admin_auth",
+            "proxy.allow_lan_access",
+            "proxy.port",
+            "proxy.security_monitor",
+        ];
+        if SECURITY_SECTIONS.iter().any(|s| commit.touches(s)) {
+            *self.security.write().await = crate::proxy::ProxySecurityConfig::from_proxy_config(proxy);
+        }
+        if commit.touches("proxy.security_monitor") {
+            crate::proxy::security_events::update_event_export_config(&proxy.security_monitor.event_export);
+        }
+        if commit.touches("proxy.zai") {
+            *self.zai.write().await = proxy.zai.clone();
+        }
+        if commit.touches("proxy.experimental") {
+            *self.experimental.write().await = proxy.experimental.clone();
+        }
+        if commit.touches("proxy.debug_logging") {
+            *self.debug_logging.write().await = proxy.debug_logging.clone();
+        }
+        if commit.touches("proxy.user_agent_override") {
+            self.upstream.set_user_agent_override(proxy.user_agent_override.clone()).await;
+        }
+        if commit.touches("proxy.scheduling") {
+            self.token_manager.update_sticky_config(proxy.scheduling.clone()).await;
+        }
+        if commit.touches("circuit_breaker") {
+            self.token_manager.update_circuit_breaker_config(config.circuit_breaker.clone()).await;
+        }
+        if commit.touches("proxy.thinking_budget") {
+            crate::proxy::update_thinking_budget_config(proxy.thinking_budget.clone());
+        }
+        if commit.touches("proxy.model_overrides") {
+            crate::proxy::common::model_registry::update_model_registry(&proxy.model_overrides);
+        }
+        if commit.touches("proxy.tool_result_compression") {
+            crate::proxy::update_tool_result_compression_config(proxy.tool_result_compression.clone());
+        }
+        if commit.touches("proxy.log_redaction") {
+            crate::proxy::redaction::update_redaction_config(&proxy.log_redaction);
+        }
+        if commit.touches("proxy.guardrails") {
+            crate::proxy::guardrails::update_guardrail_config(&proxy.guardrails);
+        }
+        if commit.touches("proxy.tls") {
+            crate::proxy::tls::manager().apply_config(&proxy.tls);
+        }
+
+        tracing::info!(
+            "[Config] Hot-applied sections: [{}]{}",
+            commit.changed_sections.join(", "),
+            if commit.restart_required.is_empty() {
+                String::new()
+            } else {
+                format!(", restart required for: [{}]", commit.restart_required.join(", "))
+            }
+        );
     }
 }
 
@@ -195,55 +312,27 @@ impl AxumServer {
         tracing::debug!("模型映射 (Custom) 已全量热更新");
     }
 
-    pub async fn update_pm_router(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut pm = self.pm_router.write().await;
-        *pm = config.pm_router.clone();
-        let scope = match pm.scope {
-            crate::proxy::config::PmRouterScope::AllRequests => "all_requests",
-            crate::proxy::config::PmRouterScope::CliOnly => "cli_only",
-        };
-        tracing::info!(
-            "PM Router 配置已热更新: enabled={}, scope={}",
-            pm.enabled, scope
-        );
-    }
-
-    /// 更新代理配置
-    pub async fn update_proxy(&self, new_config: crate::proxy::config::UpstreamProxyConfig) {
-        let mut proxy = self.proxy_state.write().await;
-        *proxy = new_config;
-        tracing::info!("上游代理配置已热更新");
-    }
-
     pub async fn update_security(&self, config: &crate::proxy::config::ProxyConfig) {
         let mut sec = self.security_state.write().await;
         *sec = crate::proxy::ProxySecurityConfig::from_proxy_config(config);
         tracing::info!("反代服务安全配置已热更新");
     }
 
-    pub async fn update_zai(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut zai = self.zai_state.write().await;
-        *zai = config.zai.clone();
-        tracing::info!("z.ai 配置已热更新");
-    }
-
-    pub async fn update_experimental(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut exp = self.experimental.write().await;
-        *exp = config.experimental.clone();
-        tracing::info!("实验性配置已热更新");
-    }
-
-    pub async fn update_debug_logging(&self, config: &crate::proxy::config::ProxyConfig) {
-        let mut dbg_cfg = self.debug_logging.write().await;
-        *dbg_cfg = config.debug_logging.clone();
-        tracing::info!("调试日志配置已热更新");
-    }
-
-    pub async fn update_user_agent(&self, config: &crate::proxy::config::ProxyConfig) {
-        self.upstream
-            .set_user_agent_override(config.user_agent_override.clone())
-            .await;
-        tracing::info!("User-Agent 配置已热更新: {:?}", config.user_agent_override);
+    /// [NEW] 按变更分区热更新 (Tauri save_config)
+    pub async fn apply_config_changes(&self, config: &AppConfig, commit: &crate::modules::config::ConfigCommit) {
+        RuntimeConfigHandles {
+            custom_mapping: self.custom_mapping.clone(),
+            pm_router: self.pm_router.clone(),
+            upstream_proxy: self.proxy_state.clone(),
+            upstream: self.upstream.clone(),
+            security: self.security_state.clone(),
+            zai: self.zai_state.clone(),
+            experimental: self.experimental.clone(),
+            debug_logging: self.debug_logging.clone(),
+            token_manager: self.token_manager.clone(),
+        }
+        .apply(config, commit)
+        .await;
     }
 
     pub async fn set_running(&self, running: bool) {
@@ -328,8 +417,8 @@ impl AxumServer {
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         use crate::proxy::middleware::{
-            admin_auth_middleware, auth_middleware, cors_layer, ip_filter_middleware,
-            monitor_middleware, service_status_middleware,
+            admin_auth_middleware, auth_middleware, auto_ban_middleware, cors_layer, guard_middleware,
+            ip_filter_middleware, monitor_middleware, service_status_middleware,
         };
 
         // 1. 构建主 AI 代理路由 (遵循 auth_mode 配置)
@@ -398,6 +487,7 @@ impl AxumServer {
             .route("/v1/api/event_logging/batch", post(silent_ok_handler))
             .route("/v1/api/event_logging", post(silent_ok_handler))
             // 应用 AI 服务特定的层
+            .layer(axum::middleware::from_fn(guard_middleware))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 auth_middleware,
@@ -414,6 +504,12 @@ impl AxumServer {
         // 2. 构建管理 API (强制鉴权)
         let admin_routes = Router::new()
             .route("/health", get(health_check_handler))
+            // 管理后台登录会话与用户 (登录接口免鉴权，其余按角色校验)
+            .route("/session/login", post(admin_session_login))
+            .route("/session/logout", post(admin_session_logout))
+            .route("/session/me", get(admin_session_me))
+            .route("/users", get(admin_list_users).post(admin_create_user))
+            .route("/users/:userId", post(admin_update_user).delete(admin_delete_user))
             .route(
                 "/accounts",
                 get(admin_list_accounts).post(admin_add_account),
@@ -462,6 +558,9 @@ impl AxumServer {
             .route("/stats/accounts", get(admin_get_token_stats_by_account))
             .route("/stats/models", get(admin_get_token_stats_by_model))
             .route("/config", get(admin_get_config).post(admin_save_config))
+            .route("/config/versions", get(admin_list_config_versions))
+            .route("/config/versions/:versionId/diff", get(admin_diff_config_version))
+            .route("/config/versions/:versionId/rollback", post(admin_rollback_config_version))
             .route("/proxy/cli/status", post(admin_get_cli_sync_status))
             .route("/proxy/cli/sync", post(admin_execute_cli_sync))
             .route("/proxy/cli/restore", post(admin_execute_cli_restore))
@@ -484,6 +583,22 @@ impl AxumServer {
                 "/proxy/preferred-account",
                 get(admin_get_preferred_account).post(admin_set_preferred_account),
             )
+            .route(
+                "/proxy/pm-router/decisions",
+                get(admin_get_pm_router_decisions).delete(admin_clear_pm_router_decisions),
+            )
+            .route("/proxy/pm-router/dry-run", post(admin_pm_router_dry_run))
+            .route("/proxy/models/registry", get(admin_get_model_registry))
+            .route(
+                "/proxy/models/discovery",
+                get(admin_get_model_discovery).post(admin_refresh_model_discovery),
+            )
+            // Layer-3 上下文摘要 (持久化，可跨轮复用)
+            .route(
+                "/proxy/context-summaries",
+                get(admin_get_context_summaries).delete(admin_clear_context_summaries),
+            )
+            .route("/proxy/context-summaries/:id", get(admin_get_context_summary))
             .route("/accounts/oauth/prepare", post(admin_prepare_oauth_url))
             .route("/accounts/oauth/start", post(admin_start_oauth_login))
             .route("/accounts/oauth/complete", post(admin_complete_oauth_login))
@@ -502,6 +617,9 @@ impl AxumServer {
                 "/proxy/cloudflared/install",
                 post(admin_cloudflared_install),
             )
+            // HTTPS 证书状态 / 手动重载
+            .route("/proxy/tls", get(admin_get_tls_status))
+            .route("/proxy/tls/reload", post(admin_reload_tls))
             .route("/proxy/cloudflared/start", post(admin_cloudflared_start))
             .route("/proxy/cloudflared/stop", post(admin_cloudflared_stop))
             .route("/system/open-folder", post(admin_open_folder))
@@ -544,6 +662,8 @@ impl AxumServer {
                 "/accounts/:accountId/toggle-proxy",
                 post(admin_toggle_proxy_status),
             )
+            .route("/accounts/:accountId/limits", post(admin_update_account_limits))
+            .route("/accounts/eligibility", get(admin_get_account_eligibility))
             .route("/accounts/warmup", post(admin_warm_up_all_accounts))
             .route("/accounts/:accountId/warmup", post(admin_warm_up_account))
             .route("/system/data-dir", get(admin_get_data_dir_path))
@@ -570,6 +690,7 @@ impl AxumServer {
             // Security / IP Monitoring
             .route("/security/logs", get(admin_get_ip_access_logs))
             .route("/security/logs/clear", post(admin_clear_ip_access_logs))
+            .route("/security/events", get(admin_get_security_events))
             .route("/security/stats", get(admin_get_ip_stats))
             .route("/security/token-stats", get(admin_get_ip_token_stats)) // For IP Token usage
             .route("/security/blacklist", get(admin_get_ip_blacklist).post(admin_add_ip_to_blacklist).delete(admin_remove_ip_from_blacklist))
@@ -578,9 +699,31 @@ impl AxumServer {
             .route("/security/whitelist", get(admin_get_ip_whitelist).post(admin_add_ip_to_whitelist).delete(admin_remove_ip_from_whitelist))
             .route("/security/whitelist/clear", post(admin_clear_ip_whitelist))
             .route("/security/whitelist/check", get(admin_check_ip_in_whitelist))
+            .route("/security/auto-bans", get(admin_get_auto_bans).delete(admin_lift_auto_ban))
+            .route("/security/login-failures", get(admin_get_login_failures))
+            .route(
+                "/share-tokens",
+                get(admin_list_share_tokens).post(admin_create_share_token),
+            )
+            .route("/share-tokens/:tokenId", delete(admin_revoke_share_token))
             .route("/security/config", get(admin_get_security_config).post(admin_update_security_config))
+            // Account Audit
+            .route("/audit", get(admin_get_audit_events))
+            .route("/audit/export", get(admin_export_audit_events))
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
+            // 批量授权 (Headless 批量导入)
+            .route(
+                "/auth/batch",
+                get(admin_list_oauth_batches).post(admin_create_oauth_batch),
+            )
+            .route(
+                "/auth/batch/:sessionId",
+                get(admin_get_oauth_batch).delete(admin_cancel_oauth_batch),
+            )
+            .route("/auth/batch/submit", post(admin_submit_oauth_batch_code))
+            // 管理接口发起的账号变更统一记为 admin_api 来源
+            .layer(axum::middleware::from_fn(admin_audit_scope_middleware))
             // 应用管理特定鉴权层 (强制校验)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
@@ -600,11 +743,18 @@ impl AxumServer {
             .merge(proxy_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
+            // [NEW] 未知路由 404 (标记为探测信号)
+            .fallback(handle_unknown_route)
             // 应用全局监控与状态层 (外层)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 service_status_middleware,
             ))
+            // [NEW] 自动封禁 (覆盖所有路径，未知路径探测同样计数)
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                auto_ban_middleware,
+            ))
             .layer(cors_layer())
             .layer(DefaultBodyLimit::max(max_body_size)) // 放宽 body 大小限制
             .with_state(state.clone());
@@ -626,7 +776,10 @@ impl AxumServer {
             .await
             .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        let scheme = if crate::proxy::tls::manager().is_active() { "https" } else { "http" };
+        tracing::info!("反代服务器启动在 {}://{}", scheme, addr);
+        // [NEW] 证书文件变更后自动热重载
+        crate::proxy::tls::manager().spawn_watcher();
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
@@ -657,8 +810,6 @@ impl AxumServer {
                     res = listener.accept() => {
                         match res {
                             Ok((stream, remote_addr)) => {
-                                let io = TokioIo::new(stream);
-                                
                                 // 注入 ConnectInfo (用于获取真实 IP)
                                 use tower::ServiceExt;
                                 use hyper::body::Incoming;
@@ -668,8 +819,33 @@ impl AxumServer {
                                 });
 
                                 let service = TowerToHyperService::new(app_with_info);
+                                // [NEW] 每个连接读取当前证书，证书热重载后新连接立即生效
+                                let tls_acceptor = crate::proxy::tls::manager().acceptor();
 
                                 tokio::task::spawn(async move {
+                                    let stream: Box<dyn crate::proxy::tls::ConnectionIo> = match tls_acceptor {
+                                        Some(acceptor) => {
+                                            match tokio::time::timeout(
+                                                crate::proxy::tls::HANDSHAKE_TIMEOUT,
+                                                acceptor.accept(stream),
+                                            )
+                                            .await
+                                            {
+                                                Ok(Ok(tls_stream)) => Box::new(tls_stream),
+                                                Ok(Err(err)) => {
+                                                    debug!("TLS 握手失败 ({}): {:?}", remote_addr, err);
+                                                    return;
+                                                }
+                                                Err(_) => {
+                                                    debug!("TLS 握手超时 ({})", remote_addr);
+                                                    return;
+                                                }
+                                            }
+                                        }
+                                        None => Box::new(stream),
+                                    };
+                                    let io = TokioIo::new(stream);
+
                                     if let Err(err) = http1::Builder::new()
                                         .serve_connection(io, service)
                                         .with_upgrades() // 支持 WebSocket (如果以后需要)
@@ -776,6 +952,8 @@ async fn admin_list_accounts(
                 quota,
                 device_bound: acc.device_profile.is_some(),
                 last_used: acc.last_used,
+                usage_limits: acc.usage_limits,
+                availability_windows: acc.availability_windows,
             }
         })
         .collect();
@@ -850,6 +1028,8 @@ async fn admin_get_current_account(
                 quota,
                 device_bound: acc.device_profile.is_some(),
                 last_used: acc.last_used,
+                usage_limits: acc.usage_limits,
+                availability_windows: acc.availability_windows,
             }
         })
     } else {
@@ -1174,56 +1354,124 @@ struct SaveConfigWrapper {
 
 async fn admin_save_config(
     State(state): State<AppState>,
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
     Json(payload): Json<SaveConfigWrapper>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, Response> {
     let new_config = payload.config;
-    // 1. 持久化
-    config::save_app_config(&new_config).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
-        )
-    })?;
-
-    // 2. 热更新内存状态
-    // 这里我们直接复用内部组件的 update 方法
-    // 注意：AppState 本身持有各个组件的 Arc<RwLock> 或直接持有引用
-
-    // 我们需要一个方式获取到当前的 AxumServer 实例来进行热更新，
-    // 或者直接操作 AppState 里的各状态。
-    // 在本重构中，各个状态已经在 AppState 中了。
+    let actor = admin_actor(identity);
+    // 1. 校验 + 持久化 + 记录版本 (文件与 SQLite 读写，持有同步锁，放到阻塞线程池)
+    let (new_config, commit) = {
+        let task_actor = actor.clone();
+        tokio::task::spawn_blocking(move || {
+            let commit = config::commit_app_config(&new_config, ConfigSource::AdminApi, Some(&task_actor))
+                .map_err(config_save_error_response)?;
+            Ok::<_, Response>((new_config, commit))
+        })
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())??
+    };
 
-    // 更新模型映射
-    {
-        let mut mapping = state.custom_mapping.write().await;
-        *mapping = new_config.clone().proxy.custom_mapping;
+    // 2. 仅热更新发生变化的分区
+    RuntimeConfigHandles::from_state(&state).apply(&new_config, &commit).await;
+
+    crate::proxy::security_events::emit(
+        security_db::SecurityEvent::new(security_db::SecurityEventKind::ConfigChange, "Configuration saved via admin API")
+            .request("POST", "/api/config")
+            .actor(actor),
+    );
+    Ok(Json(commit))
+}
+
+/// 校验失败返回 422 与字段级错误，其它失败返回 500
+fn config_save_error_response(err: config::ConfigSaveError) -> Response {
+    match err {
+        config::ConfigSaveError::Invalid(fields) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({
+                "error": config::ConfigSaveError::Invalid(fields.clone()).to_string(),
+                "fields": fields,
+            })),
+        )
+            .into_response(),
+        config::ConfigSaveError::Failed(e) => admin_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
+}
 
-    // 更新上游代理
-    {
-        let mut proxy = state.upstream_proxy.write().await;
-        *proxy = new_config.clone().proxy.upstream_proxy;
-    }
+async fn admin_list_config_versions() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let versions = tokio::task::spawn_blocking(config_history::list_versions)
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(versions))
+}
 
-    // 更新安全策略
-    {
-        let mut security = state.security.write().await;
-        *security = crate::proxy::ProxySecurityConfig::from_proxy_config(&new_config.proxy);
-    }
+#[derive(Deserialize)]
+struct ConfigDiffQuery {
+    /// 对比目标版本，缺省为当前配置
+    against: Option<i64>,
+}
 
-    // 更新 z.ai 配置
-    {
-        let mut zai = state.zai.write().await;
-        *zai = new_config.clone().proxy.zai;
-    }
+/// 对比历史版本与当前配置 (或另一历史版本)
+async fn admin_diff_config_version(
+    Path(version_id): Path<i64>,
+    Query(q): Query<ConfigDiffQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let diff = tokio::task::spawn_blocking(move || {
+        let base = config_history::get_version_config(version_id)?
+            .ok_or_else(|| format!("Config version {} not found", version_id))?;
+        let target = match q.against {
+            Some(id) => config_history::get_version_config(id)?
+                .ok_or_else(|| format!("Config version {} not found", id))?,
+            // 快照中的密钥为掩码，当前配置按同样规则掩码后再对比
+            None => config_history::mask_config(&config::load_app_config()?)?,
+        };
+        Ok::<_, String>(config::diff_app_configs(&base, &target))
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| {
+        let status = if e.ends_with("not found") { StatusCode::NOT_FOUND } else { StatusCode::INTERNAL_SERVER_ERROR };
+        admin_error(status, e)
+    })?;
+    Ok(Json(diff))
+}
 
-    // 更新实验性配置
-    {
-        let mut exp = state.experimental.write().await;
-        *exp = new_config.clone().proxy.experimental;
-    }
+/// 回滚到历史版本 (作为新版本记录，来源为 rollback)
+async fn admin_rollback_config_version(
+    State(state): State<AppState>,
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+    Path(version_id): Path<i64>,
+) -> Result<impl IntoResponse, Response> {
+    let actor = admin_actor(identity);
+    let task_actor = actor.clone();
+    let (target, commit) = tokio::task::spawn_blocking(move || {
+        let snapshot = config_history::get_version_config(version_id)
+            .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response())?
+            .ok_or_else(|| {
+                admin_error(StatusCode::NOT_FOUND, format!("Config version {} not found", version_id)).into_response()
+            })?;
+        let current = config::load_app_config()
+            .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response())?;
+        let target = config_history::restore_secrets(&snapshot, &current)
+            .map_err(|e| admin_error(StatusCode::CONFLICT, e).into_response())?;
+        let commit = config::commit_app_config(&target, ConfigSource::Rollback, Some(&task_actor))
+            .map_err(config_save_error_response)?;
+        Ok::<_, Response>((target, commit))
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())??;
 
-    Ok(StatusCode::OK)
+    RuntimeConfigHandles::from_state(&state).apply(&target, &commit).await;
+
+    crate::proxy::security_events::emit(
+        security_db::SecurityEvent::new(
+            security_db::SecurityEventKind::ConfigChange,
+            format!("Configuration rolled back to version {}", version_id),
+        )
+        .request("POST", format!("/api/config/versions/{}/rollback", version_id))
+        .actor(actor),
+    );
+    Ok(Json(commit))
 }
 
 async fn admin_get_proxy_status(
@@ -1236,7 +1484,8 @@ async fn admin_get_proxy_status(
     Ok(Json(serde_json::json!({
         "running": is_running,
         "port": state.port,
-        "base_url": format!("http://127.0.0.1:{}", state.port),
+        "base_url": crate::proxy::tls::local_base_url(state.port),
+        "tls": crate::proxy::tls::manager().is_active(),
         "active_accounts": active_accounts,
     })))
 }
@@ -1342,6 +1591,200 @@ async fn admin_clear_rate_limit(
     }
 }
 
+async fn admin_get_pm_router_decisions() -> impl IntoResponse {
+    Json(crate::proxy::pm_router::decision_cache().snapshot())
+}
+
+/// 当前生效的模型能力注册表 (内置 + proxy.model_overrides)
+async fn admin_get_model_registry() -> impl IntoResponse {
+    Json(crate::proxy::common::model_registry::registry().models().to_vec())
+}
+
+/// 动态模型发现快照 (上游模型 → 可服务账号数)
+async fn admin_get_model_discovery(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.token_manager.get_model_discovery().await)
+}
+
+async fn admin_refresh_model_discovery(State(state): State<AppState>) -> impl IntoResponse {
+    let snapshot = state.token_manager.refresh_model_discovery().await;
+    logger::log_info(&format!(
+        "[API] 模型发现已刷新: {} 个模型",
+        snapshot.models.len()
+    ));
+    Json(snapshot)
+}
+
+#[derive(Deserialize)]
+struct ContextSummaryQuery {
+    session_id: Option<String>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ContextSummariesResponse {
+    summaries: Vec<summary_db::ContextSummary>,
+    total: u64,
+}
+
+/// 已持久化的 Layer-3 摘要 (即模型实际看到的压缩上下文)
+async fn admin_get_context_summaries(
+    Query(q): Query<ContextSummaryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let page_size = q.page_size.unwrap_or(50).clamp(1, 500);
+    let offset = (q.page.unwrap_or(1).max(1) - 1) * page_size;
+    let res = tokio::task::spawn_blocking(move || {
+        let session_id = q.session_id.as_deref();
+        let summaries = summary_db::get_summaries(session_id, page_size, offset)?;
+        let total = summary_db::get_summaries_count(session_id)?;
+        Ok::<_, String>(ContextSummariesResponse { summaries, total })
+    })
+    .await;
+
+    match res {
+        Ok(Ok(data)) => Ok(Json(data)),
+        Ok(Err(e)) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() }),
+        )),
+    }
+}
+
+async fn admin_get_context_summary(
+    Path(id): Path<i64>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(move || summary_db::get_summary(id))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e.to_string() }),
+            )
+        })?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    match res {
+        Some(summary) => Ok(Json(summary)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Context summary {} not found", id),
+            }),
+        )),
+    }
+}
+
+async fn admin_clear_context_summaries(
+    Query(q): Query<ContextSummaryQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let session_id = q.session_id.clone();
+    let deleted = tokio::task::spawn_blocking(move || summary_db::clear_summaries(session_id.as_deref()))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e.to_string() }),
+            )
+        })?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    logger::log_info(&format!(
+        "[API] 已清除 {} 条上下文摘要 (session: {})",
+        deleted,
+        q.session_id.as_deref().unwrap_or("*")
+    ));
+    Ok(Json(serde_json::json!({ "deleted": deleted })))
+}
+
+async fn admin_clear_pm_router_decisions() -> impl IntoResponse {
+    let cleared = crate::proxy::pm_router::decision_cache().clear();
+    logger::log_info(&format!("[API] 已清除 {} 条 PM Router 会话决策缓存", cleared));
+    Json(serde_json::json!({ "cleared": cleared }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PmRouterDryRunRequest {
+    /// claude | openai | gemini
+    #[serde(default)]
+    protocol: Option<String>,
+    /// Gemini 请求的模型名 (原本来自路径)
+    #[serde(default)]
+    model: Option<String>,
+    /// 示例请求体
+    request: serde_json::Value,
+    #[serde(default)]
+    user_agent: Option<String>,
+    #[serde(default)]
+    api_key: Option<String>,
+    /// 可选：用未保存的规则进行测试
+    #[serde(default)]
+    rules: Option<Vec<crate::proxy::config::PmRoutingRule>>,
+}
+
+async fn admin_pm_router_dry_run(
+    State(state): State<AppState>,
+    Json(payload): Json<PmRouterDryRunRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    use crate::proxy::pm_router::{RouterInput, RouterProtocol};
+
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }));
+
+    let mut config = state.pm_router.read().await.clone();
+    if let Some(rules) = payload.rules {
+        config.rules = rules;
+    }
+
+    let protocol_str = payload.protocol.as_deref().unwrap_or("claude");
+    let protocol = RouterProtocol::parse(protocol_str)
+        .ok_or_else(|| bad_request(format!("Unknown protocol: {}", protocol_str)))?;
+
+    let input = match protocol {
+        RouterProtocol::Claude => {
+            let request: crate::proxy::mappers::claude::models::ClaudeRequest =
+                serde_json::from_value(payload.request)
+                    .map_err(|e| bad_request(format!("Invalid Claude request: {}", e)))?;
+            RouterInput::from_claude(&request)
+        }
+        RouterProtocol::OpenAI => RouterInput::from_openai(&payload.request),
+        RouterProtocol::Gemini => {
+            let model = payload
+                .model
+                .clone()
+                .or_else(|| {
+                    payload
+                        .request
+                        .get("model")
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                })
+                .unwrap_or_default();
+            RouterInput::from_gemini(&model, &payload.request)
+        }
+    };
+
+    let mut headers = axum::http::HeaderMap::new();
+    if let Some(ua) = payload
+        .user_agent
+        .as_deref()
+        .and_then(|v| axum::http::HeaderValue::from_str(v).ok())
+    {
+        headers.insert(axum::http::header::USER_AGENT, ua);
+    }
+    if let Some(key) = payload
+        .api_key
+        .as_deref()
+        .and_then(|v| axum::http::HeaderValue::from_str(&format!("Bearer {}", v)).ok())
+    {
+        headers.insert(axum::http::header::AUTHORIZATION, key);
+    }
+
+    let result =
+        crate::proxy::pm_router::dry_run_rules(&state, &config, &input, protocol, &headers).await;
+    Ok(Json(result))
+}
+
 async fn admin_get_preferred_account(State(state): State<AppState>) -> impl IntoResponse {
     let pref = state.token_manager.get_preferred_account().await;
     Json(pref)
@@ -2011,6 +2454,91 @@ async fn admin_toggle_proxy_status(
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateAccountLimitsRequest {
+    #[serde(default)]
+    usage_limits: crate::models::AccountUsageLimits,
+    #[serde(default)]
+    availability_windows: Vec<crate::models::AvailabilityWindow>,
+}
+
+async fn admin_update_account_limits(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+    Json(payload): Json<UpdateAccountLimitsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::modules::account::update_account_limits(
+        &account_id,
+        payload.usage_limits,
+        payload.availability_windows,
+    )
+    .map_err(|e| {
+        let status = match e {
+            crate::modules::account::AccountLimitsError::NotFound(_) => StatusCode::NOT_FOUND,
+            crate::modules::account::AccountLimitsError::Invalid(_) => StatusCode::BAD_REQUEST,
+            crate::modules::account::AccountLimitsError::Failed(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(ErrorResponse { error: e.to_string() }))
+    })?;
+
+    // 同步到运行中的反代服务
+    let _ = state.token_manager.reload_account(&account_id).await;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Serialize)]
+struct AccountEligibilityResponse {
+    account_id: String,
+    email: String,
+    provider: String,
+    eligible: bool,
+    reasons: Vec<crate::models::IneligibleReason>,
+    /// 人类可读的原因描述
+    reason_text: Option<String>,
+}
+
+async fn admin_get_account_eligibility(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token_manager = state.token_manager.clone();
+    let report = tokio::task::spawn_blocking(move || token_manager.get_eligibility_report())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    let items: Vec<AccountEligibilityResponse> = report
+        .into_iter()
+        .map(|(token, reasons)| AccountEligibilityResponse {
+            account_id: token.account_id,
+            email: token.email,
+            provider: token.provider,
+            eligible: reasons.is_empty(),
+            reason_text: if reasons.is_empty() {
+                None
+            } else {
+                Some(
+                    reasons
+                        .iter()
+                        .map(|r| r.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                )
+            },
+            reasons,
+        })
+        .collect();
+
+    Ok(Json(items))
+}
+
 async fn admin_warm_up_all_accounts() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)>
 {
     let result = crate::commands::warm_up_all_accounts().await.map_err(|e| {
@@ -2525,8 +3053,10 @@ async fn admin_get_cli_config_content(
 
 #[derive(Deserialize)]
 struct OAuthParams {
-    code: String,
+    code: Option<String>,
     state: Option<String>,
+    /// 用户拒绝授权等情况下 Google 回传的错误码 (此时没有 code)
+    error: Option<String>,
     #[allow(dead_code)]
     scope: Option<String>,
 }
@@ -2536,7 +3066,39 @@ async fn handle_oauth_callback(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Html<String>, StatusCode> {
-    let code = params.code;
+    let code = match (params.code, params.error) {
+        (Some(code), None) => code,
+        (_, error) => {
+            let error = error.unwrap_or_else(|| "missing authorization code".to_string());
+            return Ok(Html(format!(
+                r#"<html><body style="font-family: sans-serif; text-align: center; padding: 50px;"><h1 style="color: #dc2626;">❌ Authorization Failed</h1><p>{}</p></body></html>"#,
+                escape_html(&error)
+            )));
+        }
+    };
+
+    // 批量授权链接：按 state 路由到对应会话，各链接独立完成
+    if let Some(link_state) = params
+        .state
+        .as_deref()
+        .filter(|s| crate::modules::oauth_batch::is_batch_state(s))
+    {
+        let result = audit_db::scope(
+            audit_db::AuditActor::AdminApi,
+            crate::modules::oauth_batch::complete_link(&state.token_manager, link_state, &code),
+        )
+        .await;
+        return Ok(Html(match result {
+            Ok(email) => format!(
+                r#"<html><body style="font-family: sans-serif; text-align: center; padding: 50px;"><h1 style="color: #059669;">✅ Authorization Successful</h1><p>{} has been added to the account pool. You can close this window now.</p></body></html>"#,
+                escape_html(&email)
+            ),
+            Err(e) => format!(
+                r#"<html><body style="font-family: sans-serif; text-align: center; padding: 50px;"><h1 style="color: #dc2626;">❌ Authorization Failed</h1><p>{}</p></body></html>"#,
+                escape_html(&e)
+            ),
+        }));
+    }
 
     // Exchange token
     let port = state.security.read().await.port;
@@ -2563,7 +3125,7 @@ async fn handle_oauth_callback(
                         error!("Failed to add account: {}", e);
                         return Ok(Html(format!(
                             r#"<html><body><h1>Authorization Failed</h1><p>Failed to save account: {}</p></body></html>"#,
-                            e
+                            escape_html(&e)
                         )));
                     }
                 }
@@ -2571,7 +3133,7 @@ async fn handle_oauth_callback(
                     error!("Failed to get user info: {}", e);
                     return Ok(Html(format!(
                         r#"<html><body><h1>Authorization Failed</h1><p>Failed to get user info: {}</p></body></html>"#,
-                        e
+                        escape_html(&e)
                     )));
                 }
             }
@@ -2640,7 +3202,7 @@ async fn handle_oauth_callback(
             error!("OAuth exchange failed: {}", e);
             Ok(Html(format!(
                 r#"<html><body><h1>Authorization Failed</h1><p>Error: {}</p></body></html>"#,
-                e
+                escape_html(&e)
             )))
         }
     }
@@ -2738,6 +3300,158 @@ async fn admin_prepare_oauth_url_web(
     })))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateOAuthBatchRequest {
+    count: usize,
+    ttl_seconds: Option<i64>,
+}
+
+async fn admin_create_oauth_batch(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(payload): Json<CreateOAuthBatchRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let port = state.security.read().await.port;
+    let host = headers.get("host").and_then(|h| h.to_str().ok());
+    let proto = headers
+        .get("x-forwarded-proto")
+        .and_then(|h| h.to_str().ok());
+    let redirect_uri = batch_oauth_redirect_uri(port, host, proto)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    let session = crate::modules::oauth_batch::create_session(
+        payload.count,
+        &redirect_uri,
+        payload.ttl_seconds,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    Ok(Json(session))
+}
+
+async fn admin_list_oauth_batches() -> impl IntoResponse {
+    Json(crate::modules::oauth_batch::list_sessions())
+}
+
+async fn admin_get_oauth_batch(
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::modules::oauth_batch::get_session(&session_id)
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: format!("Batch session not found: {}", session_id),
+                }),
+            )
+        })
+}
+
+async fn admin_cancel_oauth_batch(
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::modules::oauth_batch::cancel_session(&session_id)
+        .map(Json)
+        .map_err(|e| (StatusCode::NOT_FOUND, Json(ErrorResponse { error: e })))
+}
+
+#[derive(Deserialize)]
+struct SubmitOAuthBatchCodeRequest {
+    /// 授权码，或浏览器地址栏中完整的回调 URL
+    code: String,
+    state: Option<String>,
+}
+
+/// 回调无法直接到达时 (例如 redirect 指向 localhost)，手动回填批量链接的回调地址
+async fn admin_submit_oauth_batch_code(
+    State(state): State<AppState>,
+    Json(payload): Json<SubmitOAuthBatchCodeRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let (code, link_state) = match url::Url::parse(&payload.code) {
+        Ok(url) => {
+            let find = |key: &str| {
+                url.query_pairs()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.to_string())
+            };
+            (
+                find("code").unwrap_or_else(|| payload.code.clone()),
+                payload.state.clone().or_else(|| find("state")),
+            )
+        }
+        Err(_) => (payload.code.clone(), payload.state.clone()),
+    };
+
+    let link_state = link_state.ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Missing OAuth state".to_string(),
+            }),
+        )
+    })?;
+
+    let email = crate::modules::oauth_batch::complete_link(&state.token_manager, &link_state, &code)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+
+    Ok(Json(serde_json::json!({ "email": email })))
+}
+
+/// 批量授权链接的重定向 URI
+///
+/// 桌面 OAuth 客户端仅接受回环地址，因此不能直接使用请求的 Host：
+/// - 设置了 ABV_PUBLIC_URL 时使用该地址 (必须是带主机名的 http(s) URL，否则拒绝创建)
+/// - 否则回调指向 localhost，远程管理员需将浏览器最终停留的地址提交到
+///   `POST /api/auth/batch/submit` 完成授权
+fn batch_oauth_redirect_uri(port: u16, host: Option<&str>, proto: Option<&str>) -> Result<String, String> {
+    if let Ok(public_url) = std::env::var("ABV_PUBLIC_URL") {
+        let valid = url::Url::parse(public_url.trim())
+            .map(|u| matches!(u.scheme(), "http" | "https") && u.host_str().is_some())
+            .unwrap_or(false);
+        if !valid {
+            return Err(format!(
+                "ABV_PUBLIC_URL must be an absolute http(s) URL, got '{}'",
+                public_url
+            ));
+        }
+    } else if let Some(host) = host.filter(|h| !is_loopback_host(h)) {
+        tracing::info!(
+            "[OAuth Batch] Admin host {} is not loopback and ABV_PUBLIC_URL is unset; links redirect to localhost and must be completed via /api/auth/batch/submit",
+            host
+        );
+    }
+    Ok(get_oauth_redirect_uri(port, host, proto))
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    // Host 头: name[:port] 或 [ipv6][:port]
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(rest),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<std::net::IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false)
+}
+
+/// 回调页面中的动态内容 (邮箱、上游错误) 必须转义，避免反射型 XSS
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#x27;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
 /// 辅助函数：获取 OAuth 重定向 URI
 /// 强制使用 localhost，以绕过 Google 2.0 政策对 IP 地址和非 HTTPS 环境的拦截。
 /// 只有在显式设置了 ABV_PUBLIC_URL (例如用户配置了 HTTPS 域名) 时才会使用外部地址。
@@ -2747,7 +3461,8 @@ fn get_oauth_redirect_uri(port: u16, _host: Option<&str>, _proto: Option<&str>)
         format!("{}/auth/callback", base)
     } else {
         // 强制返回 localhost。远程部署时，用户可通过回填功能完成授权。
-        format!("http://localhost:{}/auth/callback", port)
+        let scheme = if crate::proxy::tls::manager().is_active() { "https" } else { "http" };
+        format!("{}://localhost:{}/auth/callback", scheme, port)
     }
 }
 
@@ -2792,6 +3507,77 @@ async fn admin_clear_ip_access_logs() -> Result<impl IntoResponse, (StatusCode,
     Ok(StatusCode::OK)
 }
 
+#[derive(Deserialize)]
+struct SecurityEventsQuery {
+    #[serde(default)]
+    cursor: i64,
+    #[serde(default = "default_security_events_limit")]
+    limit: usize,
+    /// 逗号分隔的事件类型 (access,blocked,auth_failure,...)
+    kinds: Option<String>,
+    /// json (默认) / jsonl / cef / syslog
+    format: Option<String>,
+}
+
+fn default_security_events_limit() -> usize {
+    500
+}
+
+#[derive(Serialize)]
+struct SecurityEventsResponse {
+    events: Vec<security_db::SecurityEvent>,
+    next_cursor: i64,
+}
+
+/// 按游标增量拉取安全事件 (供 SIEM 轮询)
+async fn admin_get_security_events(
+    Query(q): Query<SecurityEventsQuery>,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let mut kinds = Vec::new();
+    for raw in q.kinds.as_deref().unwrap_or("").split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let kind = security_db::SecurityEventKind::parse(raw)
+            .ok_or_else(|| admin_error(StatusCode::BAD_REQUEST, format!("Unknown event kind: {}", raw)))?;
+        kinds.push(kind);
+    }
+    let format = match q.format.as_deref().unwrap_or("json") {
+        "json" => None,
+        "jsonl" => Some(crate::proxy::config::SecurityEventFormat::Jsonl),
+        "cef" => Some(crate::proxy::config::SecurityEventFormat::Cef),
+        "syslog" => Some(crate::proxy::config::SecurityEventFormat::Syslog),
+        other => {
+            return Err(admin_error(
+                StatusCode::BAD_REQUEST,
+                format!("Unsupported event format: {}", other),
+            ))
+        }
+    };
+
+    let cursor = q.cursor.max(0);
+    let limit = q.limit.clamp(1, 5000);
+    let events = tokio::task::spawn_blocking(move || security_db::get_security_events_after(cursor, &kinds, limit))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    let next_cursor = events.last().map(|e| e.seq).unwrap_or(cursor);
+
+    let Some(format) = format else {
+        return Ok(Json(SecurityEventsResponse { events, next_cursor }).into_response());
+    };
+    let content_type = match format {
+        crate::proxy::config::SecurityEventFormat::Jsonl => "application/x-ndjson",
+        _ => "text/plain; charset=utf-8",
+    };
+    let body = crate::proxy::security_events::render_events(&events, format);
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, content_type.to_string()),
+            (axum::http::HeaderName::from_static("x-next-cursor"), next_cursor.to_string()),
+        ],
+        body,
+    )
+        .into_response())
+}
+
 #[derive(Serialize)]
 struct IpStatsResponse {
     total_requests: usize,
@@ -2831,6 +3617,113 @@ async fn admin_get_ip_token_stats(
     Ok(Json(stats))
 }
 
+// ============================================================================
+// Account Audit Handlers
+// ============================================================================
+
+async fn admin_audit_scope_middleware(
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    audit_db::scope(audit_db::AuditActor::AdminApi, next.run(request)).await
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    account_id: Option<String>,
+    email: Option<String>,
+    action: Option<String>,
+    actor: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    page: Option<usize>,
+    page_size: Option<usize>,
+    format: Option<String>,
+}
+
+impl AuditQuery {
+    fn to_filter(&self) -> audit_db::AuditFilter {
+        let page_size = self.page_size.unwrap_or(100).clamp(1, 1000);
+        audit_db::AuditFilter {
+            account_id: self.account_id.clone(),
+            email: self.email.clone(),
+            action: self.action.clone(),
+            actor: self.actor.clone(),
+            since: self.since,
+            until: self.until,
+            limit: Some(page_size),
+            offset: Some((self.page.unwrap_or(1).max(1) - 1) * page_size),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AuditEventsResponse {
+    events: Vec<audit_db::AuditEvent>,
+    total: u64,
+}
+
+async fn admin_get_audit_events(
+    Query(q): Query<AuditQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let filter = q.to_filter();
+    let res = tokio::task::spawn_blocking(move || {
+        let events = audit_db::get_audit_events(&filter)?;
+        let total = audit_db::get_audit_events_count(&filter)?;
+        Ok::<_, String>(AuditEventsResponse { events, total })
+    })
+    .await;
+
+    match res {
+        Ok(Ok(data)) => Ok(Json(data)),
+        Ok(Err(e)) => Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e }))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e.to_string() }),
+        )),
+    }
+}
+
+async fn admin_export_audit_events(
+    Query(q): Query<AuditQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let format = q.format.clone().unwrap_or_else(|| "jsonl".to_string());
+    let (content_type, ext) = match format.as_str() {
+        "jsonl" => ("application/x-ndjson", "jsonl"),
+        "csv" => ("text/csv; charset=utf-8", "csv"),
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: format!("Unsupported export format: {}", other),
+                }),
+            ))
+        }
+    };
+
+    let filter = q.to_filter();
+    let body = tokio::task::spawn_blocking(move || audit_db::export_audit_events(&filter, &format))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e.to_string() }),
+            )
+        })?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    Ok((
+        [
+            (axum::http::header::CONTENT_TYPE, content_type.to_string()),
+            (
+                axum::http::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"account_audit.{}\"", ext),
+            ),
+        ],
+        body,
+    ))
+}
+
 async fn admin_get_ip_blacklist() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let list = security_db::get_blacklist()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
@@ -2845,6 +3738,7 @@ struct AddBlacklistRequest {
 }
 
 async fn admin_add_ip_to_blacklist(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
     Json(req): Json<AddBlacklistRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     security_db::add_to_blacklist(
@@ -2853,7 +3747,15 @@ async fn admin_add_ip_to_blacklist(
         req.expires_at,
         "manual",
     ).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
-    
+
+    crate::proxy::security_events::emit(
+        security_db::SecurityEvent::new(
+            security_db::SecurityEventKind::BanCreated,
+            req.reason.unwrap_or_else(|| "Manual blacklist entry".to_string()),
+        )
+        .ip(req.ip_pattern)
+        .actor(admin_actor(identity)),
+    );
     Ok(StatusCode::CREATED)
 }
 
@@ -2863,6 +3765,7 @@ struct RemoveIpRequest {
 }
 
 async fn admin_remove_ip_from_blacklist(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
     Query(q): Query<RemoveIpRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let entries = security_db::get_blacklist()
@@ -2871,6 +3774,7 @@ async fn admin_remove_ip_from_blacklist(
     if let Some(entry) = entries.iter().find(|e| e.ip_pattern == q.ip_pattern) {
         security_db::remove_from_blacklist(&entry.id)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        emit_ban_removed(&entry.ip_pattern, &admin_actor(identity));
     } else {
         return Err((StatusCode::NOT_FOUND, Json(ErrorResponse { error: format!("IP pattern {} not found", q.ip_pattern) })));
     }
@@ -2878,16 +3782,52 @@ async fn admin_remove_ip_from_blacklist(
     Ok(StatusCode::OK)
 }
 
-async fn admin_clear_ip_blacklist() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+async fn admin_clear_ip_blacklist(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let actor = admin_actor(identity);
     let entries = security_db::get_blacklist()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
     for entry in entries {
         security_db::remove_from_blacklist(&entry.ip_pattern)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+        emit_ban_removed(&entry.ip_pattern, &actor);
     }
     Ok(StatusCode::OK)
 }
 
+async fn admin_get_auto_bans() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let list = tokio::task::spawn_blocking(security_db::get_active_auto_bans)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() })))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(list))
+}
+
+/// 解除自动封禁并清空累犯记录 (下次违规重新从最短封禁开始)
+async fn admin_lift_auto_ban(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+    Query(q): Query<RemoveIpRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let ip = q.ip_pattern.clone();
+    tokio::task::spawn_blocking(move || security_db::lift_auto_ban(&ip))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() })))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    crate::proxy::auto_ban::engine().forget(&q.ip_pattern);
+    emit_ban_removed(&q.ip_pattern, &admin_actor(identity));
+    Ok(StatusCode::OK)
+}
+
+/// 管理员解除封禁 (记录为 ban_expired 事件)
+fn emit_ban_removed(ip_pattern: &str, actor: &str) {
+    crate::proxy::security_events::emit(
+        security_db::SecurityEvent::new(security_db::SecurityEventKind::BanExpired, "Ban removed by admin")
+            .ip(ip_pattern)
+            .actor(actor),
+    );
+}
+
 #[derive(Deserialize)]
 struct CheckIpQuery {
     ip: String,
@@ -2967,21 +3907,325 @@ async fn admin_get_security_config(
 
 async fn admin_update_security_config(
     State(state): State<AppState>,
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
     Json(config): Json<crate::proxy::config::SecurityMonitorConfig>,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let mut app_config = crate::modules::config::load_app_config()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() })))?;
-        
-    app_config.proxy.security_monitor = config.clone();
-    
-    crate::modules::config::save_app_config(&app_config)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() })))?;
+) -> Result<impl IntoResponse, Response> {
+    let actor = admin_actor(identity);
+    let task_actor = actor.clone();
+    let task_config = config.clone();
+    let app_config = tokio::task::spawn_blocking(move || {
+        let mut app_config = crate::modules::config::load_app_config()
+            .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response())?;
+        app_config.proxy.security_monitor = task_config;
+        crate::modules::config::commit_app_config(&app_config, ConfigSource::AdminApi, Some(&task_actor))
+            .map_err(config_save_error_response)?;
+        Ok::<_, Response>(app_config)
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())??;
 
     {
         let mut sec = state.security.write().await;
         *sec = crate::proxy::ProxySecurityConfig::from_proxy_config(&app_config.proxy);
         tracing::info!("[Security] Runtime security config hot-reloaded via Web API");
     }
+    crate::proxy::security_events::update_event_export_config(&config.event_export);
+    crate::proxy::security_events::emit(
+        security_db::SecurityEvent::new(security_db::SecurityEventKind::ConfigChange, "Security config updated via admin API")
+            .request("POST", "/api/security/config")
+            .actor(actor),
+    );
+
+    Ok(StatusCode::OK)
+}
+
+// ============================================================================
+// HTTPS 证书
+// ============================================================================
+
+async fn admin_get_tls_status() -> impl IntoResponse {
+    Json(crate::proxy::tls::manager().status())
+}
+
+/// 立即重载证书 (失败时保留当前证书并返回错误)
+async fn admin_reload_tls() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let status = tokio::task::spawn_blocking(|| crate::proxy::tls::manager().reload())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e.to_string() })))?
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(status))
+}
+
+// ============================================================================
+// 管理后台登录会话与用户管理
+// ============================================================================
+
+fn admin_error(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { error: error.into() }))
+}
+
+/// 操作者名称: 会话登录的管理用户名，旧版共享密码记为 admin
+fn admin_actor(identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>) -> String {
+    identity
+        .map(|axum::Extension(identity)| identity.username)
+        .unwrap_or_else(|| "admin".to_string())
+}
+
+#[derive(Deserialize)]
+struct AdminLoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Serialize)]
+struct AdminLoginResponse {
+    token: String,
+    expires_at: i64,
+    username: String,
+    role: admin_db::AdminRole,
+}
+
+/// 未知路由: 返回 404 并标记为探测信号 (上游透传的 404 不计入自动封禁)
+async fn handle_unknown_route() -> Response {
+    crate::proxy::auto_ban::mark_rejection(
+        StatusCode::NOT_FOUND.into_response(),
+        crate::proxy::auto_ban::AbuseSignal::Probe,
+    )
+}
+
+/// 用户名密码登录，签发会话令牌；失败记录到 security_db (401 同时计入自动封禁的鉴权失败)
+async fn admin_session_login(
+    State(state): State<AppState>,
+    connect_info: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: HeaderMap,
+    Json(req): Json<AdminLoginRequest>,
+) -> Result<impl IntoResponse, Response> {
+    let (ttl_secs, trusted_proxies) = {
+        let security = state.security.read().await;
+        (
+            security.admin_auth.session_ttl_hours.max(1) as i64 * 3600,
+            security.security_monitor.trusted_proxies.clone(),
+        )
+    };
+    let client_ip = crate::proxy::client_ip::resolve_client_ip(
+        connect_info.map(|info| info.0.ip()),
+        &headers,
+        &trusted_proxies,
+    )
+    .map(|ip| ip.to_string());
+
+    let username = req.username.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<Option<AdminLoginResponse>, String> {
+        let Some(user) = admin_db::verify_credentials(&req.username, &req.password)? else {
+            if let Err(e) =
+                security_db::record_admin_login_failure(&req.username, client_ip.as_deref(), "invalid_credentials")
+            {
+                error!("[Admin-Auth] Failed to record login failure: {}", e);
+            }
+            let mut event = security_db::SecurityEvent::new(
+                security_db::SecurityEventKind::AuthFailure,
+                "Admin login failed: invalid credentials",
+            )
+            .request("POST", "/api/session/login")
+            .status(401)
+            .actor(req.username.clone());
+            event.client_ip = client_ip.clone();
+            crate::proxy::security_events::emit(event);
+            return Ok(None);
+        };
+        let (token, expires_at) = admin_db::create_session(&user, ttl_secs, client_ip.as_deref())?;
+        Ok(Some(AdminLoginResponse {
+            token,
+            expires_at,
+            username: user.username,
+            role: user.role,
+        }))
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e).into_response())?;
+
+    match result {
+        Some(response) => {
+            tracing::info!("[Admin-Auth] {} logged in as {}", response.username, response.role.as_str());
+            Ok(Json(response))
+        }
+        None => {
+            tracing::warn!("[Admin-Auth] Failed login attempt for user '{}'", username);
+            Err(crate::proxy::auto_ban::mark_rejection(
+                admin_error(StatusCode::UNAUTHORIZED, "Invalid username or password").into_response(),
+                crate::proxy::auto_ban::AbuseSignal::AuthFailure,
+            ))
+        }
+    }
+}
 
+/// 登出 (吊销当前会话令牌；使用旧版共享密码时无操作)
+async fn admin_session_logout(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if let Some(axum::Extension(identity)) = identity {
+        tokio::task::spawn_blocking(move || admin_db::revoke_session(&identity.token_hash))
+            .await
+            .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    }
+    Ok(StatusCode::OK)
+}
+
+/// 当前登录身份 (旧版共享密码或鉴权关闭时视为 admin)
+async fn admin_session_me(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+) -> impl IntoResponse {
+    match identity {
+        Some(axum::Extension(identity)) => Json(serde_json::json!({
+            "user_id": identity.user_id,
+            "username": identity.username,
+            "role": identity.role,
+            "expires_at": identity.expires_at,
+            "legacy": false,
+        })),
+        None => Json(serde_json::json!({
+            "user_id": null,
+            "username": "admin",
+            "role": admin_db::AdminRole::Admin,
+            "expires_at": null,
+            "legacy": true,
+        })),
+    }
+}
+
+async fn admin_list_users() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let users = tokio::task::spawn_blocking(admin_db::list_users)
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(users))
+}
+
+#[derive(Deserialize)]
+struct CreateAdminUserRequest {
+    username: String,
+    password: String,
+    role: admin_db::AdminRole,
+}
+
+async fn admin_create_user(
+    Json(req): Json<CreateAdminUserRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = tokio::task::spawn_blocking(move || admin_db::create_user(&req.username, &req.password, req.role))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::BAD_REQUEST, e))?;
+    tracing::info!("[Admin-Auth] Created admin user {} ({})", user.username, user.role.as_str());
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+#[derive(Deserialize)]
+struct UpdateAdminUserRequest {
+    role: Option<admin_db::AdminRole>,
+    password: Option<String>,
+    enabled: Option<bool>,
+}
+
+async fn admin_update_user(
+    Path(user_id): Path<String>,
+    Json(req): Json<UpdateAdminUserRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = tokio::task::spawn_blocking(move || {
+        admin_db::update_user(&user_id, req.role, req.password.as_deref(), req.enabled)
+    })
+    .await
+    .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| {
+        let status = if e == admin_db::LAST_ADMIN_ERROR { StatusCode::CONFLICT } else { StatusCode::BAD_REQUEST };
+        admin_error(status, e)
+    })?;
+    Ok(Json(user))
+}
+
+async fn admin_delete_user(
+    Path(user_id): Path<String>,
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    if identity.is_some_and(|axum::Extension(identity)| identity.user_id == user_id) {
+        return Err(admin_error(StatusCode::BAD_REQUEST, "Cannot delete the currently logged-in user"));
+    }
+    tokio::task::spawn_blocking(move || admin_db::delete_user(&user_id))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| {
+            let status = if e == admin_db::LAST_ADMIN_ERROR { StatusCode::CONFLICT } else { StatusCode::NOT_FOUND };
+            admin_error(status, e)
+        })?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct LoginFailuresQuery {
+    limit: Option<usize>,
+}
+
+async fn admin_get_login_failures(
+    Query(q): Query<LoginFailuresQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let limit = q.limit.unwrap_or(100).clamp(1, 1000);
+    let list = tokio::task::spawn_blocking(move || security_db::get_admin_login_failures(limit))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(list))
+}
+
+// ============================================================================
+// 分享令牌 (临时代理访问)
+// ============================================================================
+
+#[derive(Serialize)]
+struct CreateShareTokenResponse {
+    /// 令牌明文 (仅在签发时返回一次)
+    token: String,
+    #[serde(flatten)]
+    record: security_db::ShareTokenRecord,
+}
+
+async fn admin_list_share_tokens() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let list = tokio::task::spawn_blocking(security_db::list_share_tokens)
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    Ok(Json(list))
+}
+
+async fn admin_create_share_token(
+    identity: Option<axum::Extension<crate::proxy::middleware::AdminIdentity>>,
+    Json(req): Json<crate::proxy::share_token::ShareTokenRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let created_by = admin_actor(identity);
+    let (token, record) = tokio::task::spawn_blocking(move || crate::proxy::share_token::mint(req, &created_by))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::BAD_REQUEST, e))?;
+    tracing::info!(
+        "[Share-Token] Issued token {} by {} (expires_at={})",
+        record.id,
+        record.created_by,
+        record.expires_at
+    );
+    Ok((StatusCode::CREATED, Json(CreateShareTokenResponse { token, record })))
+}
+
+async fn admin_revoke_share_token(
+    Path(token_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id = token_id.clone();
+    let found = tokio::task::spawn_blocking(move || crate::proxy::share_token::revoke(&id))
+        .await
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| admin_error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    if !found {
+        return Err(admin_error(StatusCode::NOT_FOUND, "Share token not found"));
+    }
+    tracing::info!("[Share-Token] Revoked token {}", token_id);
     Ok(StatusCode::OK)
 }
@@ -156,4 +156,56 @@ impl SessionManager {
         tracing::debug!("[SessionManager-Gemini] Generated fingerprint: {}", sid);
         sid
     }
+
+    /// 根据 OpenAI Responses API 请求 (input 字符串或数组) 生成稳定的会话指纹
+    pub fn extract_responses_session_id(request: &Value) -> String {
+        let mut hasher = Sha256::new();
+
+        let mut content_found = false;
+        match request.get("input") {
+            Some(Value::String(s)) => {
+                hasher.update(s.trim().as_bytes());
+                content_found = true;
+            }
+            Some(Value::Array(items)) => {
+                for item in items {
+                    if item.get("role").and_then(|v| v.as_str()) != Some("user") { continue; }
+
+                    let text = match item.get("content") {
+                        Some(Value::String(s)) => s.clone(),
+                        Some(Value::Array(parts)) => parts
+                            .iter()
+                            .filter_map(|p| p.get("text").and_then(|v| v.as_str()))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        _ => String::new(),
+                    };
+
+                    let clean_text = text.trim();
+                    if clean_text.len() > 10 && !clean_text.contains("<system-reminder>") {
+                        hasher.update(clean_text.as_bytes());
+                        content_found = true;
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if !content_found {
+            // 没有可用的用户文本时，退回到会话内稳定不变的前缀 (instructions + 首个 input 项)，
+            // 不能哈希整个请求体，否则每轮追加的 input 都会产生新的指纹
+            if let Some(instructions) = request.get("instructions").and_then(|v| v.as_str()) {
+                hasher.update(instructions.as_bytes());
+            }
+            if let Some(first) = request.get("input").and_then(|v| v.as_array()).and_then(|items| items.first()) {
+                hasher.update(first.to_string().as_bytes());
+            }
+        }
+
+        let hash = format!("{:x}", hasher.finalize());
+        let sid = format!("sid-{}", &hash[..16]);
+        tracing::debug!("[SessionManager-Responses] Generated fingerprint: {}", sid);
+        sid
+    }
 }
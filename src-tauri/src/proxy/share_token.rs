@@ -0,0 +1,327 @@
+// 分享令牌 (临时代理访问)
+//
+// 令牌格式: `agt_<base64url(claims)>.<base64url(HMAC-SHA256)>`，签名密钥保存在 security.db。
+// 鉴权时只校验签名、有效期与内存中的吊销列表，不查询数据库；
+// 签发记录仅用于管理端列出与吊销 (令牌明文不落库)。
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use crate::modules::security_db::{self, ShareTokenRecord};
+use crate::proxy::client_ip::{ip_matches, parse_ip_pattern};
+use crate::proxy::common::model_mapping::wildcard_match;
+
+/// 分享令牌前缀 (与 API Key / 管理会话令牌区分)
+pub const SHARE_TOKEN_PREFIX: &str = "agt_";
+
+/// 最长有效期: 90 天
+const MAX_TTL_SECS: u64 = 90 * 24 * 3600;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 令牌内携带的声明
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareTokenClaims {
+    /// 令牌 ID (对应签发记录，用于吊销)
+    pub jti: String,
+    /// 过期时间 (Unix 秒)
+    pub exp: i64,
+    /// 允许的模型 (支持 * 通配符，空表示不限)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    /// 单次请求最大输出 Token 数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// 绑定的客户端 IP / CIDR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<String>,
+}
+
+impl ShareTokenClaims {
+    pub fn allows_model(&self, model: &str) -> bool {
+        self.models.is_empty() || self.models.iter().any(|pattern| wildcard_match(pattern, model))
+    }
+
+    /// 未绑定 IP 时放行；绑定后无法解析客户端 IP 视为不匹配
+    pub fn allows_ip(&self, client_ip: Option<&str>) -> bool {
+        match &self.ip {
+            None => true,
+            Some(pattern) => client_ip.is_some_and(|ip| ip_matches(pattern, ip)),
+        }
+    }
+}
+
+fn mac(secret: &[u8], payload: &str) -> HmacSha256 {
+    // HMAC 接受任意长度的密钥
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// 签名并编码令牌
+pub fn encode(claims: &ShareTokenClaims, secret: &[u8]) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap_or_default());
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, &payload).finalize().into_bytes());
+    format!("{}{}.{}", SHARE_TOKEN_PREFIX, payload, signature)
+}
+
+/// 校验签名与有效期并解码声明
+pub fn decode(token: &str, secret: &[u8], now: i64) -> Result<ShareTokenClaims, String> {
+    let body = token
+        .strip_prefix(SHARE_TOKEN_PREFIX)
+        .ok_or_else(|| "Not a share token".to_string())?;
+    let (payload, signature) = body
+        .split_once('.')
+        .ok_or_else(|| "Malformed share token".to_string())?;
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|_| "Malformed share token signature".to_string())?;
+    mac(secret, payload)
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid share token signature".to_string())?;
+
+    let claims: ShareTokenClaims = URL_SAFE_NO_PAD
+        .decode(payload)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| "Malformed share token payload".to_string())?;
+    if claims.exp <= now {
+        return Err("Share token expired".to_string());
+    }
+    Ok(claims)
+}
+
+struct ShareTokenState {
+    secret: Vec<u8>,
+    revoked: HashSet<String>,
+}
+
+impl ShareTokenState {
+    /// 从 security.db 载入签名密钥与吊销列表 (失败时所有令牌校验失败)
+    fn load() -> Self {
+        let secret = security_db::get_or_create_share_token_secret()
+            .map(String::into_bytes)
+            .unwrap_or_else(|e| {
+                tracing::error!("[Share-Token] Failed to load signing secret: {}", e);
+                Vec::new()
+            });
+        let revoked = security_db::get_revoked_share_token_ids()
+            .map(|ids| ids.into_iter().collect())
+            .unwrap_or_else(|e| {
+                tracing::error!("[Share-Token] Failed to load revocation list: {}", e);
+                HashSet::new()
+            });
+        Self { secret, revoked }
+    }
+}
+
+static STATE: Lazy<RwLock<ShareTokenState>> = Lazy::new(|| RwLock::new(ShareTokenState::load()));
+
+/// 启动时预加载签名密钥与吊销列表
+pub fn init() {
+    Lazy::force(&STATE);
+}
+
+/// 校验令牌 (签名、有效期、吊销列表)
+pub fn verify(token: &str) -> Result<ShareTokenClaims, String> {
+    let state = STATE.read().map_err(|_| "Share token state unavailable".to_string())?;
+    if state.secret.is_empty() {
+        return Err("Share token signing secret unavailable".to_string());
+    }
+    let claims = decode(token, &state.secret, chrono::Utc::now().timestamp())?;
+    if state.revoked.contains(&claims.jti) {
+        return Err("Share token revoked".to_string());
+    }
+    Ok(claims)
+}
+
+/// 签发请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShareTokenRequest {
+    #[serde(default)]
+    pub label: Option<String>,
+    /// 有效期 (秒)，默认 24 小时
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub ip: Option<String>,
+}
+
+fn default_ttl_secs() -> u64 {
+    24 * 3600
+}
+
+/// 签发令牌，返回 (令牌明文, 签发记录)
+pub fn mint(request: ShareTokenRequest, created_by: &str) -> Result<(String, ShareTokenRecord), String> {
+    if request.ttl_secs == 0 || request.ttl_secs > MAX_TTL_SECS {
+        return Err(format!("ttl_secs must be between 1 and {}", MAX_TTL_SECS));
+    }
+    if request.max_tokens == Some(0) {
+        return Err("max_tokens must be greater than 0".to_string());
+    }
+    let ip = request.ip.map(|ip| ip.trim().to_string()).filter(|ip| !ip.is_empty());
+    if let Some(ip) = &ip {
+        if parse_ip_pattern(ip).is_none() {
+            return Err(format!("Invalid IP or CIDR: {}", ip));
+        }
+    }
+    let models: Vec<String> = request
+        .models
+        .into_iter()
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+
+    let now = chrono::Utc::now().timestamp();
+    let record = ShareTokenRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        label: request.label.filter(|l| !l.trim().is_empty()),
+        models: models.clone(),
+        max_tokens: request.max_tokens,
+        ip: ip.clone(),
+        created_at: now,
+        expires_at: now + request.ttl_secs as i64,
+        created_by: created_by.to_string(),
+        revoked_at: None,
+    };
+    let claims = ShareTokenClaims {
+        jti: record.id.clone(),
+        exp: record.expires_at,
+        models,
+        max_tokens: request.max_tokens,
+        ip,
+    };
+
+    let state = STATE.read().map_err(|_| "Share token state unavailable".to_string())?;
+    if state.secret.is_empty() {
+        return Err("Share token signing secret unavailable".to_string());
+    }
+    security_db::insert_share_token(&record)?;
+    Ok((encode(&claims, &state.secret), record))
+}
+
+/// 吊销令牌 (写入数据库并立即加入内存吊销列表)，返回是否存在
+pub fn revoke(id: &str) -> Result<bool, String> {
+    let found = security_db::revoke_share_token(id)?;
+    if found {
+        if let Ok(mut state) = STATE.write() {
+            state.revoked.insert(id.to_string());
+        }
+    }
+    Ok(found)
+}
+
+/// 从请求中取出目标模型 (Gemini 取自路径，其余取自请求体)
+pub fn request_model(path: &str, body: Option<&Value>) -> Option<String> {
+    if let Some(rest) = path.strip_prefix("/v1beta/models/") {
+        return rest.split(':').next().filter(|m| !m.is_empty()).map(str::to_string);
+    }
+    body.and_then(|b| b.get("model"))
+        .and_then(|m| m.as_str())
+        .map(str::to_string)
+}
+
+/// 按令牌上限收紧请求的最大输出 Token 数 (未指定时写入上限)，返回是否修改了请求体
+pub fn clamp_max_tokens(body: &mut Value, path: &str, cap: u32) -> bool {
+    let Some(obj) = body.as_object_mut() else {
+        return false;
+    };
+
+    if path.starts_with("/v1beta/") {
+        let generation = obj.entry("generationConfig").or_insert_with(|| json!({}));
+        let Some(generation) = generation.as_object_mut() else {
+            return false;
+        };
+        if generation.get("maxOutputTokens").and_then(|v| v.as_u64()).is_some_and(|n| n <= cap as u64) {
+            return false;
+        }
+        generation.insert("maxOutputTokens".to_string(), json!(cap));
+        return true;
+    }
+
+    let mut present = false;
+    let mut changed = false;
+    for field in ["max_tokens", "max_completion_tokens", "max_output_tokens"] {
+        if let Some(value) = obj.get(field) {
+            present = true;
+            if value.as_u64().map_or(true, |n| n > cap as u64) {
+                obj.insert(field.to_string(), json!(cap));
+                changed = true;
+            }
+        }
+    }
+    if !present {
+        let field = if path == "/v1/responses" { "max_output_tokens" } else { "max_tokens" };
+        obj.insert(field.to_string(), json!(cap));
+        changed = true;
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims() -> ShareTokenClaims {
+        ShareTokenClaims {
+            jti: "t1".to_string(),
+            exp: 2_000,
+            models: vec!["gemini-*".to_string()],
+            max_tokens: Some(1024),
+            ip: Some("10.0.0.0/8".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_and_tampering() {
+        let token = encode(&claims(), b"secret");
+        assert!(token.starts_with(SHARE_TOKEN_PREFIX));
+        assert_eq!(decode(&token, b"secret", 1_000).unwrap(), claims());
+
+        assert_eq!(decode(&token, b"secret", 2_000).unwrap_err(), "Share token expired");
+        assert_eq!(decode(&token, b"other", 1_000).unwrap_err(), "Invalid share token signature");
+
+        // 篡改声明 (放宽模型限制) 后签名失效
+        let forged_payload = URL_SAFE_NO_PAD.encode(br#"{"jti":"t1","exp":2000}"#);
+        let signature = token.rsplit('.').next().unwrap();
+        let forged = format!("{}{}.{}", SHARE_TOKEN_PREFIX, forged_payload, signature);
+        assert_eq!(decode(&forged, b"secret", 1_000).unwrap_err(), "Invalid share token signature");
+        assert!(decode("sk-123", b"secret", 1_000).is_err());
+    }
+
+    #[test]
+    fn test_claim_restrictions() {
+        let claims = claims();
+        assert!(claims.allows_model("gemini-2.5-flash"));
+        assert!(!claims.allows_model("claude-opus-4-5"));
+        assert!(claims.allows_ip(Some("10.1.2.3")));
+        assert!(!claims.allows_ip(Some("192.168.1.1")));
+        assert!(!claims.allows_ip(None));
+
+        assert_eq!(request_model("/v1beta/models/gemini-2.5-pro:streamGenerateContent", None).as_deref(), Some("gemini-2.5-pro"));
+        assert_eq!(request_model("/v1/messages", Some(&json!({"model": "claude-sonnet-4-5"}))).as_deref(), Some("claude-sonnet-4-5"));
+
+        let mut body = json!({"model": "x", "max_tokens": 4096});
+        assert!(clamp_max_tokens(&mut body, "/v1/messages", 1024));
+        assert_eq!(body["max_tokens"], 1024);
+        assert!(!clamp_max_tokens(&mut body, "/v1/messages", 2048));
+
+        let mut body = json!({"model": "x"});
+        assert!(clamp_max_tokens(&mut body, "/v1/responses", 512));
+        assert_eq!(body["max_output_tokens"], 512);
+
+        let mut body = json!({"contents": []});
+        assert!(clamp_max_tokens(&mut body, "/v1beta/models/gemini-2.5-pro:generateContent", 256));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 256);
+    }
+}
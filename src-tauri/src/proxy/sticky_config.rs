@@ -9,6 +9,8 @@ pub enum SchedulingMode {
     Balance,
     /// 性能优先 (Performance-first): 纯轮询模式 (Round-robin)，账号负载最均衡，但不利用缓存
     PerformanceFirst,
+    /// 临期优先 (Use-it-or-lose-it): 按"刷新时将被浪费的配额"打分，优先消耗即将刷新的账号；会话绑定行为同 Balance
+    UseItOrLoseIt,
 }
 
 impl Default for SchedulingMode {
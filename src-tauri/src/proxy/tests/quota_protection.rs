@@ -39,6 +39,8 @@ mod tests {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: Default::default(),
+            availability_windows: Vec::new(),
         }
     }
 
@@ -80,8 +82,16 @@ mod tests {
             Some("gemini-3-pro-high".to_string())
         );
 
+        // 图片模型归入自身分组 (默认监控列表包含该分组)
+        assert_eq!(
+            normalize_to_standard_id("gemini-3-pro-image"),
+            Some("gemini-3-pro-image".to_string())
+        );
+
         // 不支持的模型应返回 None
         assert_eq!(normalize_to_standard_id("gpt-4"), None);
+        // 别名不参与配额归并
+        assert_eq!(normalize_to_standard_id("claude-opus-4"), None);
         assert_eq!(normalize_to_standard_id("unknown-model"), None);
     }
 
@@ -1115,6 +1125,123 @@ mod tests {
         let _ = std::fs::remove_file(&account_path);
     }
 
+    // ==================================================================================
+    // 测试 20: 临期优先 (UseItOrLoseIt) 打分
+    // 20% 且 10 分钟后刷新的账号应排在 60% 且 5 天后刷新的账号之前
+    // ==================================================================================
+
+    #[test]
+    fn test_use_it_or_lose_it_score_prefers_imminent_reset() {
+        use crate::proxy::token_manager::quota_waste_score;
+
+        let now = 1_700_000_000;
+        let soon = quota_waste_score(20, Some(now + 10 * 60), now);
+        let later = quota_waste_score(60, Some(now + 5 * 86400), now);
+
+        assert!(
+            soon > later,
+            "20%/10min ({}) 应该比 60%/5d ({}) 更优先",
+            soon,
+            later
+        );
+        assert!((soon - 120.0).abs() < 1e-9);
+        assert!((later - 0.5).abs() < 1e-9);
+    }
+
+    // ==================================================================================
+    // 测试 21: 临期优先打分的边界情况
+    // 刷新时间未知/已过去、配额为 0 时分数为 0；极短的刷新时间不会导致分数爆炸
+    // ==================================================================================
+
+    #[test]
+    fn test_use_it_or_lose_it_score_edge_cases() {
+        use crate::proxy::token_manager::quota_waste_score;
+
+        let now = 1_700_000_000;
+        assert_eq!(quota_waste_score(80, None, now), 0.0);
+        assert_eq!(quota_waste_score(80, Some(now - 10), now), 0.0);
+        assert_eq!(quota_waste_score(0, Some(now + 600), now), 0.0);
+
+        // 5 秒后刷新按 60 秒下限计算
+        assert!((quota_waste_score(10, Some(now + 5), now) - 600.0).abs() < 1e-9);
+    }
+
+    // ==================================================================================
+    // 测试 22: 临期优先排序读取目标模型自身的 reset_time
+    // ==================================================================================
+
+    #[test]
+    fn test_use_it_or_lose_it_sorting_uses_target_model_reset() {
+        use crate::proxy::sticky_config::SchedulingMode;
+        use crate::proxy::token_manager::TokenManager;
+
+        let temp_dir = std::env::temp_dir().join(format!("test_lose_it_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
+
+        let now = chrono::Utc::now().timestamp();
+        let rfc3339 = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .unwrap()
+                .to_rfc3339()
+        };
+
+        // 账号 A: sonnet 60%，5 天后刷新 (gemini 很快刷新，但与目标模型无关)
+        let account_a_json = serde_json::json!({
+            "email": "a@example.com",
+            "quota": {
+                "models": [
+                    { "name": "claude-sonnet-4-5", "percentage": 60, "reset_time": rfc3339(now + 5 * 86400) },
+                    { "name": "gemini-3-flash", "percentage": 100, "reset_time": rfc3339(now + 60) }
+                ]
+            }
+        });
+
+        // 账号 B: sonnet 20%，10 分钟后刷新
+        let account_b_json = serde_json::json!({
+            "email": "b@example.com",
+            "quota": {
+                "models": [
+                    { "name": "claude-sonnet-4-5", "percentage": 20, "reset_time": rfc3339(now + 600) }
+                ]
+            }
+        });
+
+        // 账号 C: sonnet 100%，没有 reset_time
+        let account_c_json = serde_json::json!({
+            "email": "c@example.com",
+            "quota": {
+                "models": [
+                    { "name": "claude-sonnet-4-5", "percentage": 100, "reset_time": "" }
+                ]
+            }
+        });
+
+        let path_a = temp_dir.join("account_a.json");
+        let path_b = temp_dir.join("account_b.json");
+        let path_c = temp_dir.join("account_c.json");
+        std::fs::write(&path_a, account_a_json.to_string()).unwrap();
+        std::fs::write(&path_b, account_b_json.to_string()).unwrap();
+        std::fs::write(&path_c, account_c_json.to_string()).unwrap();
+
+        let mut tokens = vec![
+            create_mock_token_with_path("a", "a@example.com", vec![], Some(100), path_a),
+            create_mock_token_with_path("b", "b@example.com", vec![], Some(100), path_b),
+            create_mock_token_with_path("c", "c@example.com", vec![], Some(100), path_c),
+        ];
+
+        // 常规模式: 目标模型配额高者优先
+        TokenManager::sort_candidates(&mut tokens, "claude-sonnet-4-5", SchedulingMode::Balance, now);
+        assert_eq!(tokens[0].email, "c@example.com");
+
+        TokenManager::sort_candidates(&mut tokens, "claude-sonnet-4-5", SchedulingMode::UseItOrLoseIt, now);
+
+        assert_eq!(tokens[0].email, "b@example.com", "即将刷新的账号应该排第一");
+        assert_eq!(tokens[1].email, "a@example.com");
+        assert_eq!(tokens[2].email, "c@example.com", "没有刷新时间的账号排在最后");
+
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+
     /// 辅助函数：创建带有自定义 account_path 的 mock token
     fn create_mock_token_with_path(
         account_id: &str,
@@ -1141,6 +1268,8 @@ mod tests {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: Default::default(),
+            availability_windows: Vec::new(),
         }
     }
 }
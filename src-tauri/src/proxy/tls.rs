@@ -0,0 +1,436 @@
+// TLS 终止 (HTTPS 监听)
+//
+// 启用后监听端口直接提供 HTTPS: 优先使用用户提供的 PEM 证书/私钥，未配置时在数据目录 tls/ 下
+// 生成并复用自签名证书。后台按间隔检查证书文件修改时间，变更后热重载: 新连接使用新证书，
+// 已建立的连接不受影响，无需重启反代服务。重载失败时保留上一份有效证书。
+
+use once_cell::sync::Lazy;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+
+use crate::proxy::config::TlsConfig;
+
+const SELF_SIGNED_CERT_FILE: &str = "self_signed_cert.pem";
+const SELF_SIGNED_KEY_FILE: &str = "self_signed_key.pem";
+/// 记录自签名证书生成时使用的 SAN 列表 (每行一个)，用于判断证书是否需要重新生成
+const SELF_SIGNED_HOSTS_FILE: &str = "self_signed_hosts.txt";
+
+/// TLS 握手超时，防止慢速连接长期占用任务
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 监听连接的统一 IO 类型 (明文 TCP 或 TLS 流)
+pub trait ConnectionIo: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send {}
+
+impl<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send> ConnectionIo for T {}
+
+/// 证书来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertSource {
+    File,
+    SelfSigned,
+}
+
+/// 当前生效的证书信息
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsCertInfo {
+    pub source: CertSource,
+    pub cert_path: String,
+    pub key_path: String,
+    /// 叶子证书 SHA-256 指纹 (AA:BB:...)，客户端可据此校验/固定自签名证书
+    pub fingerprint_sha256: String,
+    pub loaded_at: i64,
+}
+
+/// TLS 状态 (管理接口返回)
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsStatus {
+    pub enabled: bool,
+    /// 监听端口当前是否提供 HTTPS
+    pub active: bool,
+    pub cert: Option<TlsCertInfo>,
+    pub last_error: Option<String>,
+}
+
+struct LoadedCert {
+    acceptor: TlsAcceptor,
+    info: TlsCertInfo,
+}
+
+type FileStamps = (Option<SystemTime>, Option<SystemTime>);
+
+#[derive(Default)]
+pub struct TlsManager {
+    config: RwLock<TlsConfig>,
+    loaded: RwLock<Option<LoadedCert>>,
+    last_error: RwLock<Option<String>>,
+    /// 上次加载尝试时的 (证书, 私钥) 修改时间
+    last_stamps: RwLock<FileStamps>,
+    watcher_started: AtomicBool,
+}
+
+static MANAGER: Lazy<TlsManager> = Lazy::new(TlsManager::default);
+
+/// 全局 TLS 管理器
+pub fn manager() -> &'static TlsManager {
+    &MANAGER
+}
+
+/// 证书 SHA-256 指纹
+pub fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// 本机访问反代服务的 base URL (随 TLS 状态切换协议)
+pub fn local_base_url(port: u16) -> String {
+    let scheme = if manager().is_active() { "https" } else { "http" };
+    format!("{}://127.0.0.1:{}", scheme, port)
+}
+
+fn tls_dir() -> Result<PathBuf, String> {
+    let dir = crate::modules::account::get_data_dir()?.join("tls");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create TLS directory: {}", e))?;
+    Ok(dir)
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().map(str::trim).filter(|v| !v.is_empty())
+}
+
+fn file_stamp(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn write_private_file(path: &Path, content: &str) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(content.as_bytes()).map_err(|e| e.to_string())
+    }
+    #[cfg(not(unix))]
+    {
+        std::fs::write(path, content).map_err(|e| e.to_string())
+    }
+}
+
+/// 自签名证书的 SAN 列表: 默认 localhost / 127.0.0.1 / ::1，外加去重后的 `self_signed_hosts`
+fn self_signed_san(extra_hosts: &[String]) -> Vec<String> {
+    let mut hosts = vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()];
+    for host in extra_hosts.iter().map(|h| h.trim()).filter(|h| !h.is_empty()) {
+        if !hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) {
+            hosts.push(host.to_string());
+        }
+    }
+    hosts
+}
+
+/// 已有证书的 SAN 是否与期望一致 (早期生成的证书没有记录文件，视为仅含默认 SAN)
+fn san_matches(recorded: Option<&str>, hosts: &[String]) -> bool {
+    let recorded: Vec<String> = match recorded {
+        Some(content) => content.lines().map(|l| l.trim().to_lowercase()).filter(|l| !l.is_empty()).collect(),
+        None => self_signed_san(&[]),
+    };
+    recorded.len() == hosts.len() && hosts.iter().all(|h| recorded.contains(&h.to_lowercase()))
+}
+
+/// 生成自签名证书 (SAN 未变化时直接复用，保证指纹在重启后保持不变)
+///
+/// SAN 默认包含 localhost / 127.0.0.1 / ::1，外加 `self_signed_hosts` 中的域名或局域网 IP；
+/// `self_signed_hosts` 变更后重新生成证书
+pub fn ensure_self_signed(extra_hosts: &[String]) -> Result<(PathBuf, PathBuf), String> {
+    let dir = tls_dir()?;
+    let cert_path = dir.join(SELF_SIGNED_CERT_FILE);
+    let key_path = dir.join(SELF_SIGNED_KEY_FILE);
+    let hosts_path = dir.join(SELF_SIGNED_HOSTS_FILE);
+    let hosts = self_signed_san(extra_hosts);
+    if cert_path.exists() && key_path.exists() {
+        let recorded = std::fs::read_to_string(&hosts_path).ok();
+        if san_matches(recorded.as_deref(), &hosts) {
+            return Ok((cert_path, key_path));
+        }
+        tracing::info!("[TLS] self_signed_hosts changed, regenerating self-signed certificate");
+    }
+
+    let mut params = rcgen::CertificateParams::new(hosts.clone()).map_err(|e| e.to_string())?;
+    params
+        .distinguished_name
+        .push(rcgen::DnType::CommonName, "Antigravity Tools Proxy");
+    let now = chrono::Utc::now();
+    use chrono::Datelike;
+    params.not_before = rcgen::date_time_ymd(now.year(), now.month() as u8, 1);
+    params.not_after = rcgen::date_time_ymd(now.year() + 10, now.month() as u8, 1);
+
+    let key_pair = rcgen::KeyPair::generate().map_err(|e| e.to_string())?;
+    let cert = params.self_signed(&key_pair).map_err(|e| e.to_string())?;
+
+    write_private_file(&key_path, &key_pair.serialize_pem())
+        .map_err(|e| format!("Failed to write TLS private key: {}", e))?;
+    std::fs::write(&cert_path, cert.pem()).map_err(|e| format!("Failed to write TLS certificate: {}", e))?;
+    std::fs::write(&hosts_path, hosts.join("\n"))
+        .map_err(|e| format!("Failed to write TLS host list: {}", e))?;
+
+    tracing::info!(
+        "[TLS] Generated self-signed certificate for {:?}, fingerprint {}",
+        hosts,
+        fingerprint(cert.der())
+    );
+    Ok((cert_path, key_path))
+}
+
+/// 解析证书与私钥路径 (`generate` 为 false 时不生成自签名证书，仅返回其路径)
+fn resolve_paths(config: &TlsConfig, generate: bool) -> Result<(PathBuf, PathBuf, CertSource), String> {
+    match (non_empty(&config.cert_path), non_empty(&config.key_path)) {
+        (Some(cert), Some(key)) => Ok((PathBuf::from(cert), PathBuf::from(key), CertSource::File)),
+        (None, None) if generate => {
+            let (cert, key) = ensure_self_signed(&config.self_signed_hosts)?;
+            Ok((cert, key, CertSource::SelfSigned))
+        }
+        (None, None) => {
+            let dir = tls_dir()?;
+            Ok((dir.join(SELF_SIGNED_CERT_FILE), dir.join(SELF_SIGNED_KEY_FILE), CertSource::SelfSigned))
+        }
+        _ => Err("TLS cert_path and key_path must be set together".to_string()),
+    }
+}
+
+/// 读取 PEM 证书链与私钥，构建 TLS acceptor，返回 (acceptor, 叶子证书指纹)
+pub fn load_acceptor(cert_path: &Path, key_path: &Path) -> Result<(TlsAcceptor, String), String> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .map_err(|e| format!("Failed to read certificate {}: {}", cert_path.display(), e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate {}: {}", cert_path.display(), e))?;
+    let leaf = certs
+        .first()
+        .ok_or_else(|| format!("No certificate found in {}", cert_path.display()))?;
+    let fingerprint = fingerprint(leaf);
+
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| format!("Failed to read private key {}: {}", key_path.display(), e))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut server_config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Certificate and private key do not match: {}", e))?;
+    // 监听端仅支持 HTTP/1.1
+    server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok((TlsAcceptor::from(Arc::new(server_config)), fingerprint))
+}
+
+impl TlsManager {
+    /// 应用新配置并立即重载 (配置保存 / 服务启动时调用)
+    pub fn apply_config(&self, config: &TlsConfig) {
+        if let Ok(mut slot) = self.config.write() {
+            *slot = config.clone();
+        }
+        if let Err(e) = self.reload() {
+            tracing::error!("[TLS] {}", e);
+        }
+    }
+
+    /// 按当前配置重新加载证书 (失败时保留上一份有效证书)
+    pub fn reload(&self) -> Result<TlsStatus, String> {
+        let config = self.config.read().map(|c| c.clone()).unwrap_or_default();
+        if !config.enabled {
+            if let Ok(mut loaded) = self.loaded.write() {
+                if loaded.take().is_some() {
+                    tracing::info!("[TLS] HTTPS disabled, new connections use plain HTTP");
+                }
+            }
+            self.set_error(None);
+            return Ok(self.status());
+        }
+
+        let result = resolve_paths(&config, true).and_then(|(cert_path, key_path, source)| {
+            if let Ok(mut stamps) = self.last_stamps.write() {
+                *stamps = (file_stamp(&cert_path), file_stamp(&key_path));
+            }
+            let (acceptor, fingerprint) = load_acceptor(&cert_path, &key_path)?;
+            Ok(LoadedCert {
+                acceptor,
+                info: TlsCertInfo {
+                    source,
+                    cert_path: cert_path.display().to_string(),
+                    key_path: key_path.display().to_string(),
+                    fingerprint_sha256: fingerprint,
+                    loaded_at: chrono::Utc::now().timestamp(),
+                },
+            })
+        });
+
+        match result {
+            Ok(cert) => {
+                tracing::info!(
+                    "[TLS] Certificate loaded from {} (fingerprint {})",
+                    cert.info.cert_path,
+                    cert.info.fingerprint_sha256
+                );
+                if let Ok(mut loaded) = self.loaded.write() {
+                    *loaded = Some(cert);
+                }
+                self.set_error(None);
+                Ok(self.status())
+            }
+            Err(e) => {
+                let message = format!("Failed to load TLS certificate: {}", e);
+                self.set_error(Some(message.clone()));
+                Err(message)
+            }
+        }
+    }
+
+    /// 证书或私钥文件修改时间变化时重载 (后台轮询调用，阻塞)
+    pub fn reload_if_changed(&self) {
+        let config = self.config.read().map(|c| c.clone()).unwrap_or_default();
+        if !config.enabled {
+            return;
+        }
+        let Ok((cert_path, key_path, _)) = resolve_paths(&config, false) else {
+            return;
+        };
+        let stamps = (file_stamp(&cert_path), file_stamp(&key_path));
+        if self.last_stamps.read().map(|s| *s == stamps).unwrap_or(true) {
+            return;
+        }
+        tracing::info!("[TLS] Certificate files changed, reloading");
+        if let Err(e) = self.reload() {
+            tracing::error!("[TLS] {}", e);
+        }
+    }
+
+    /// 启动证书文件变更检查任务 (进程内只启动一次)
+    pub fn spawn_watcher(&'static self) {
+        if self.watcher_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tokio::spawn(async move {
+            loop {
+                let interval = self.config.read().map(|c| c.reload_interval_secs).unwrap_or(0);
+                // 间隔为 0 时仅支持手动重载，仍定期醒来以感知配置变化
+                tokio::time::sleep(Duration::from_secs(if interval == 0 { 30 } else { interval })).await;
+                if interval > 0 {
+                    let _ = tokio::task::spawn_blocking(move || self.reload_if_changed()).await;
+                }
+            }
+        });
+    }
+
+    /// 当前 TLS acceptor (未启用或证书不可用时为 None，连接按明文 HTTP 处理)
+    pub fn acceptor(&self) -> Option<TlsAcceptor> {
+        self.loaded
+            .read()
+            .ok()
+            .and_then(|l| l.as_ref().map(|c| c.acceptor.clone()))
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.loaded.read().map(|l| l.is_some()).unwrap_or(false)
+    }
+
+    /// 当前是否使用自动生成的自签名证书
+    pub fn is_self_signed(&self) -> bool {
+        self.loaded
+            .read()
+            .map(|l| l.as_ref().is_some_and(|c| c.info.source == CertSource::SelfSigned))
+            .unwrap_or(false)
+    }
+
+    pub fn status(&self) -> TlsStatus {
+        let cert = self
+            .loaded
+            .read()
+            .ok()
+            .and_then(|l| l.as_ref().map(|c| c.info.clone()));
+        TlsStatus {
+            enabled: self.config.read().map(|c| c.enabled).unwrap_or(false),
+            active: cert.is_some(),
+            cert,
+            last_error: self.last_error.read().ok().and_then(|e| e.clone()),
+        }
+    }
+
+    fn set_error(&self, error: Option<String>) {
+        if let Ok(mut slot) = self.last_error.write() {
+            *slot = error;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_format() {
+        let fp = fingerprint(b"hello");
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert!(fp.starts_with("2C:F2:4D:BA"));
+    }
+
+    #[test]
+    fn test_generated_cert_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("abv-tls-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let params = rcgen::CertificateParams::new(vec!["localhost".to_string()]).unwrap();
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let cert = params.self_signed(&key_pair).unwrap();
+        let cert_path = dir.join("cert.pem");
+        let key_path = dir.join("key.pem");
+        std::fs::write(&cert_path, cert.pem()).unwrap();
+        write_private_file(&key_path, &key_pair.serialize_pem()).unwrap();
+
+        let (_, fp) = load_acceptor(&cert_path, &key_path).unwrap();
+        assert_eq!(fp, fingerprint(cert.der()));
+
+        // 私钥与证书不匹配
+        let other_key = rcgen::KeyPair::generate().unwrap();
+        std::fs::write(&key_path, other_key.serialize_pem()).unwrap();
+        assert!(load_acceptor(&cert_path, &key_path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_self_signed_san_change_detected() {
+        let hosts = self_signed_san(&["nas.lan".to_string(), " LOCALHOST ".to_string()]);
+        assert_eq!(hosts, vec!["localhost", "127.0.0.1", "::1", "nas.lan"]);
+
+        assert!(san_matches(Some("localhost\n127.0.0.1\n::1\nNAS.lan"), &hosts));
+        assert!(!san_matches(Some("localhost\n127.0.0.1\n::1\n192.168.1.2"), &hosts));
+        // 早期证书没有记录文件: 仅默认 SAN 时复用，配置了额外主机则重新生成
+        assert!(san_matches(None, &self_signed_san(&[])));
+        assert!(!san_matches(None, &hosts));
+    }
+
+    #[test]
+    fn test_paths_must_be_paired() {
+        let config = TlsConfig {
+            enabled: true,
+            cert_path: Some("/tmp/cert.pem".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_paths(&config, false).is_err());
+    }
+}
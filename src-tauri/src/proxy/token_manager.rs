@@ -6,6 +6,9 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 
+use crate::models::{AccountUsageLimits, AccountUsageSnapshot, AvailabilityWindow, IneligibleReason};
+use crate::modules::codex::types::CodexUsageInfo;
+use crate::proxy::model_discovery::ModelDiscovery;
 use crate::proxy::rate_limit::RateLimitTracker;
 use crate::proxy::sticky_config::StickySessionConfig;
 
@@ -28,6 +31,8 @@ pub struct ProxyToken {
     pub reset_time: Option<i64>,           // [NEW] 配额刷新时间戳（用于排序优化）
     pub validation_blocked: bool,          // [NEW] Check for validation block (VALIDATION_REQUIRED temporary block)
     pub validation_blocked_until: i64,     // [NEW] Timestamp until which the account is blocked
+    pub usage_limits: AccountUsageLimits,  // [NEW] 每日/每月 Token 与请求上限
+    pub availability_windows: Vec<AvailabilityWindow>, // [NEW] 可用时间窗口 (为空表示全天可用)
 }
 
 pub struct TokenManager {
@@ -43,6 +48,14 @@ pub struct TokenManager {
     circuit_breaker_config: Arc<tokio::sync::RwLock<crate::models::CircuitBreakerConfig>>, // [NEW] 熔断配置缓存
     /// 支持优雅关闭时主动 abort 后台任务
     auto_cleanup_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// [NEW] Codex 계정별 사용량 캐시 (account_id -> primary/secondary 윈도우)
+    codex_usage: Arc<DashMap<String, CodexUsageInfo>>,
+    codex_usage_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// [NEW] 动态模型发现快照 (账号配额模型表汇总)
+    model_discovery: Arc<tokio::sync::RwLock<ModelDiscovery>>,
+    model_discovery_handle: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    /// [NEW] 账号用量快照缓存 (email -> (采集时间, 用量))，避免每次调度都查询 token_stats
+    usage_snapshots: Arc<DashMap<String, (i64, AccountUsageSnapshot)>>,
     cancel_token: CancellationToken,
 }
 
@@ -63,6 +76,11 @@ impl TokenManager {
                 crate::models::CircuitBreakerConfig::default(),
             )),
             auto_cleanup_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            codex_usage: Arc::new(DashMap::new()),
+            codex_usage_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            model_discovery: Arc::new(tokio::sync::RwLock::new(ModelDiscovery::default())),
+            model_discovery_handle: Arc::new(tokio::sync::Mutex::new(None)),
+            usage_snapshots: Arc::new(DashMap::new()),
             cancel_token: CancellationToken::new(),
         }
     }
@@ -104,6 +122,127 @@ impl TokenManager {
         tracing::info!("Rate limit auto-cleanup task started (interval: 15s)");
     }
 
+    /// [NEW] Codex 사용량 주기 갱신 백그라운드 태스크 (5분 간격)
+    /// primary/secondary 윈도우 잔여율을 remaining_quota 로 반영해 계정 선택 순서에 사용
+    pub async fn start_codex_usage_refresh(&self) {
+        let tokens = self.tokens.clone();
+        let usage_cache = self.codex_usage.clone();
+        let tracker = self.rate_limit_tracker.clone();
+        let cancel = self.cancel_token.child_token();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        tracing::info!("Codex usage refresh task received cancel signal");
+                        break;
+                    }
+                    _ = interval.tick() => {
+                        let updated = Self::refresh_codex_usage_into(&tokens, &usage_cache, &tracker, None).await;
+                        if updated > 0 {
+                            tracing::debug!("[Codex Usage] Refreshed usage for {} account(s)", updated);
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut guard = self.codex_usage_handle.lock().await;
+        if let Some(old) = guard.take() {
+            old.abort();
+            tracing::warn!("Aborted previous codex usage refresh task");
+        }
+        *guard = Some(handle);
+
+        tracing::info!("Codex usage refresh task started (interval: 300s)");
+    }
+
+    /// [NEW] 动态模型发现后台任务 (10分钟间隔)
+    /// 配额刷新会写回账号文件，这里定期重新汇总各账号的可用模型
+    pub async fn start_model_discovery_refresh(&self) {
+        let tokens = self.tokens.clone();
+        let discovery = self.model_discovery.clone();
+        let cancel = self.cancel_token.child_token();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(600));
+            loop {
+                tokio::select! {
+                    _ = cancel.cancelled() => {
+                        tracing::info!("Model discovery task received cancel signal");
+                        break;
+                    }
+                    _ = interval.tick() => {
+                        let Some(snapshot) = Self::collect_model_discovery(tokens.clone()).await else {
+                            continue;
+                        };
+                        tracing::debug!(
+                            "[Model-Discovery] {} model(s) across {} account(s)",
+                            snapshot.models.len(),
+                            snapshot.google_accounts + snapshot.codex_accounts
+                        );
+                        *discovery.write().await = snapshot;
+                    }
+                }
+            }
+        });
+
+        let mut guard = self.model_discovery_handle.lock().await;
+        if let Some(old) = guard.take() {
+            old.abort();
+            tracing::warn!("Aborted previous model discovery task");
+        }
+        *guard = Some(handle);
+
+        tracing::info!("Model discovery task started (interval: 600s)");
+    }
+
+    /// 在阻塞线程池中汇总可用模型 (逐个读取账号文件，不占用异步执行器)
+    async fn collect_model_discovery(tokens: Arc<DashMap<String, ProxyToken>>) -> Option<ModelDiscovery> {
+        match tokio::task::spawn_blocking(move || Self::scan_model_discovery(&tokens)).await {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                tracing::warn!("[Model-Discovery] Scan task failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 汇总当前账号池的可用模型 (读取各 Google 账号文件中的配额模型表，阻塞)
+    fn scan_model_discovery(tokens: &DashMap<String, ProxyToken>) -> ModelDiscovery {
+        let mut discovery = ModelDiscovery::default();
+        for entry in tokens.iter() {
+            let token = entry.value();
+            if token.provider == "codex" {
+                discovery.record_codex_account();
+                continue;
+            }
+            let model_names = std::fs::read_to_string(&token.account_path)
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .and_then(|account| crate::proxy::model_discovery::quota_model_names(&account));
+            discovery.record_google_account(model_names);
+        }
+        discovery.refreshed_at = chrono::Utc::now().timestamp();
+        discovery
+    }
+
+    /// 立即重新汇总可用模型
+    pub async fn refresh_model_discovery(&self) -> ModelDiscovery {
+        match Self::collect_model_discovery(self.tokens.clone()).await {
+            Some(snapshot) => {
+                *self.model_discovery.write().await = snapshot.clone();
+                snapshot
+            }
+            None => self.get_model_discovery().await,
+        }
+    }
+
+    pub async fn get_model_discovery(&self) -> ModelDiscovery {
+        self.model_discovery.read().await.clone()
+    }
+
     /// 从主应用账号目录加载所有账号（含 Antigravity accounts/*.json 与 Codex 独立存储）
     pub async fn load_accounts(&self) -> Result<usize, String> {
         let accounts_dir = self.data_dir.join("accounts");
@@ -157,6 +296,9 @@ impl TokenManager {
             }
         }
 
+        // [NEW] 账号池变化后同步刷新模型发现
+        self.refresh_model_discovery().await;
+
         Ok(count)
     }
 
@@ -167,28 +309,40 @@ impl TokenManager {
         codex_dir: &PathBuf,
     ) -> Option<ProxyToken> {
         use crate::modules::codex::types::CodexAuthData;
-        let (access_token, refresh_token, expires_in) = match &account.auth_data {
-            CodexAuthData::ApiKey { key } => (key.clone(), String::new(), 60 * 60 * 24 * 365),
+        let now = chrono::Utc::now().timestamp();
+        let (access_token, refresh_token, expires_in, expiry) = match &account.auth_data {
+            CodexAuthData::ApiKey { key } => {
+                let expires_in = 60 * 60 * 24 * 365;
+                (key.clone(), String::new(), expires_in, now + expires_in)
+            }
             CodexAuthData::ChatGPT {
                 access_token,
                 refresh_token,
                 ..
-            } => (access_token.clone(), refresh_token.clone(), 3600),
+            } => {
+                // [FIX] access token 의 exp 클레임을 만료 시각으로 사용 (없으면 즉시 갱신 대상)
+                let expiry = crate::modules::codex::parse_jwt_expiry(access_token).unwrap_or(now);
+                (access_token.clone(), refresh_token.clone(), (expiry - now).max(0), expiry)
+            }
         };
-        let now = chrono::Utc::now().timestamp();
         let email = account
             .email
             .clone()
             .unwrap_or_else(|| account.name.clone());
         let subscription_tier = account.plan_type.clone();
         let account_path = codex_dir.join("accounts.json");
+        let (remaining_quota, reset_time) = self
+            .codex_usage
+            .get(&account.id)
+            .map(|u| Self::codex_usage_to_quota(&u))
+            .unwrap_or((None, None));
 
         Some(ProxyToken {
             account_id: account.id.clone(),
             access_token,
             refresh_token,
             expires_in,
-            timestamp: now,
+            timestamp: expiry,
             email,
             provider: "codex".to_string(),
             chatgpt_account_id: match &account.auth_data {
@@ -198,12 +352,14 @@ impl TokenManager {
             account_path: account_path.clone(),
             project_id: None,
             subscription_tier,
-            remaining_quota: None,
+            remaining_quota,
             protected_models: HashSet::new(),
             health_score: self.health_scores.get(&account.id).map(|r| *r).unwrap_or(1.0),
-            reset_time: None,
+            reset_time,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: account.usage_limits.clone(),
+            availability_windows: account.availability_windows.clone(),
         })
     }
 
@@ -214,7 +370,8 @@ impl TokenManager {
             .join("accounts")
             .join(format!("{}.json", account_id));
         if !path.exists() {
-            return Err(format!("账号文件不存在: {:?}", path));
+            // Codex 账号保存在独立存储中
+            return self.reload_codex_account(account_id).await;
         }
 
         match self.load_single_account(&path).await {
@@ -229,6 +386,25 @@ impl TokenManager {
         }
     }
 
+    /// 重新加载指定 Codex 账号 (用量上限 / 可用时间窗口修改后同步)
+    async fn reload_codex_account(&self, account_id: &str) -> Result<(), String> {
+        let account = tokio::task::spawn_blocking(crate::modules::codex::storage::load_codex_accounts)
+            .await
+            .map_err(|e| e.to_string())??
+            .accounts
+            .into_iter()
+            .find(|a| a.id == account_id)
+            .ok_or_else(|| format!("账号不存在: {}", account_id))?;
+        let codex_path = crate::modules::codex::storage::get_codex_data_dir()
+            .unwrap_or_else(|_| self.data_dir.join("codex"));
+        let token = self
+            .codex_account_to_proxy_token(&account, &codex_path)
+            .ok_or_else(|| "账号加载失败".to_string())?;
+        self.tokens.insert(account_id.to_string(), token);
+        self.clear_rate_limit(account_id);
+        Ok(())
+    }
+
     /// 重新加载所有账号
     pub async fn reload_all_accounts(&self) -> Result<usize, String> {
         let count = self.load_accounts().await?;
@@ -445,6 +621,14 @@ impl TokenManager {
             reset_time,
             validation_blocked: account.get("validation_blocked").and_then(|v| v.as_bool()).unwrap_or(false),
             validation_blocked_until: account.get("validation_blocked_until").and_then(|v| v.as_i64()).unwrap_or(0),
+            usage_limits: account
+                .get("usage_limits")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            availability_windows: account
+                .get("availability_windows")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
         }))
     }
 
@@ -629,6 +813,117 @@ impl TokenManager {
         None
     }
 
+    /// 候选账号排序 (get_token 轮询顺序)
+    /// 优先级: [临期优先] 浪费分数 > 目标模型配额 > 健康分 > 订阅等级 > 刷新时间
+    pub(crate) fn sort_candidates(
+        tokens: &mut [ProxyToken],
+        normalized_target: &str,
+        mode: crate::proxy::sticky_config::SchedulingMode,
+        now: i64,
+    ) {
+        use crate::proxy::sticky_config::SchedulingMode;
+        const RESET_TIME_THRESHOLD_SECS: i64 = 600; // 10 分钟阈值，差异小于此值视为相同
+
+        // [NEW] 临期优先模式：预先计算每个账号目标模型的浪费分数
+        let waste_scores: std::collections::HashMap<String, f64> = if mode == SchedulingMode::UseItOrLoseIt {
+            tokens
+                .iter()
+                .map(|t| {
+                    let (quota, reset) = Self::get_model_quota_and_reset_from_json(
+                        &t.account_path,
+                        normalized_target,
+                    )
+                    .unwrap_or((t.remaining_quota.unwrap_or(0), None));
+                    let score = quota_waste_score(quota, reset.or(t.reset_time), now);
+                    (t.account_id.clone(), score)
+                })
+                .collect()
+        } else {
+            std::collections::HashMap::new()
+        };
+
+        tokens.sort_by(|a, b| {
+            // Priority 0 (UseItOrLoseIt): 刷新时将被浪费的配额越多越优先
+            if !waste_scores.is_empty() {
+                let score_a = waste_scores.get(&a.account_id).copied().unwrap_or(0.0);
+                let score_b = waste_scores.get(&b.account_id).copied().unwrap_or(0.0);
+                let waste_cmp = score_b
+                    .partial_cmp(&score_a)
+                    .unwrap_or(std::cmp::Ordering::Equal);
+                if waste_cmp != std::cmp::Ordering::Equal {
+                    return waste_cmp;
+                }
+            }
+
+            // Priority 1: 目标模型的 quota (higher is better) -> 保护低配额账号
+            let quota_a = Self::get_model_quota_from_json(&a.account_path, normalized_target)
+                .unwrap_or(a.remaining_quota.unwrap_or(0));
+            let quota_b = Self::get_model_quota_from_json(&b.account_path, normalized_target)
+                .unwrap_or(b.remaining_quota.unwrap_or(0));
+            let quota_cmp = quota_b.cmp(&quota_a);
+            if quota_cmp != std::cmp::Ordering::Equal {
+                return quota_cmp;
+            }
+
+            // Priority 2: Health score (higher is better)
+            let health_cmp = b.health_score.partial_cmp(&a.health_score)
+                .unwrap_or(std::cmp::Ordering::Equal);
+            if health_cmp != std::cmp::Ordering::Equal {
+                return health_cmp;
+            }
+
+            // Priority 3: Subscription tier (ULTRA > PRO > FREE) -> 平局时高级账号优先
+            let tier_priority = |tier: &Option<String>| {
+                let t = tier.as_deref().unwrap_or("").to_lowercase();
+                if t.contains("ultra") { 0 }
+                else if t.contains("pro") { 1 }
+                else if t.contains("free") { 2 }
+                else { 3 }
+            };
+            let tier_cmp = tier_priority(&a.subscription_tier)
+                .cmp(&tier_priority(&b.subscription_tier));
+            if tier_cmp != std::cmp::Ordering::Equal {
+                return tier_cmp;
+            }
+
+            // Priority 4: Reset time (earlier is better, but only if diff > 10 min)
+            let reset_a = a.reset_time.unwrap_or(i64::MAX);
+            let reset_b = b.reset_time.unwrap_or(i64::MAX);
+            if (reset_a - reset_b).abs() >= RESET_TIME_THRESHOLD_SECS {
+                reset_a.cmp(&reset_b)
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+    }
+
+    /// 读取目标模型的 quota 百分比及其刷新时间戳 (reset_time 为空或无法解析时为 None)
+    fn get_model_quota_and_reset_from_json(
+        account_path: &PathBuf,
+        model_name: &str,
+    ) -> Option<(i32, Option<i64>)> {
+        let content = std::fs::read_to_string(account_path).ok()?;
+        let account: serde_json::Value = serde_json::from_str(&content).ok()?;
+        let models = account.get("quota")?.get("models")?.as_array()?;
+
+        models.iter().find_map(|model| {
+            let name = model.get("name").and_then(|v| v.as_str())?;
+            if crate::proxy::common::model_mapping::normalize_to_standard_id(name)
+                .unwrap_or_else(|| name.to_string())
+                != model_name
+            {
+                return None;
+            }
+            let percentage = model.get("percentage").and_then(|v| v.as_i64())? as i32;
+            let reset = model
+                .get("reset_time")
+                .and_then(|v| v.as_str())
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| dt.timestamp());
+            Some((percentage, reset))
+        })
+    }
+
     /// 测试辅助函数：公开访问 get_model_quota_from_json
     #[cfg(test)]
     pub fn get_model_quota_from_json_for_test(account_path: &PathBuf, model_name: &str) -> Option<i32> {
@@ -672,6 +967,15 @@ impl TokenManager {
             std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap())
                 .map_err(|e| format!("写入文件失败: {}", e))?;
 
+            record_proxy_audit(
+                crate::modules::audit_db::AuditAction::ProtectedModelsChange,
+                account_id,
+                account_json,
+                None,
+                Some(serde_json::json!({ "protected_model_added": model_name })),
+                Some("quota_protection"),
+            );
+
             return Ok(true);
         }
 
@@ -715,10 +1019,31 @@ impl TokenManager {
             }
         }
 
+        let protected_before = account_json.get("protected_models").cloned();
         account_json["protected_models"] = serde_json::Value::Array(protected_list);
 
         let _ = std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap());
 
+        let account_id = account_json
+            .get("id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        record_proxy_audit(
+            crate::modules::audit_db::AuditAction::ProxyEnable,
+            &account_id,
+            account_json,
+            Some(serde_json::json!({
+                "proxy_disabled": true,
+                "protected_models": protected_before,
+            })),
+            Some(serde_json::json!({
+                "proxy_disabled": false,
+                "protected_models": account_json["protected_models"],
+            })),
+            Some("quota_protection_migrated"),
+        );
+
         false // 返回 false 表示现在已可以尝试加载该账号（模型级过滤会在 get_token 时发生）
     }
 
@@ -749,6 +1074,14 @@ impl TokenManager {
                     serde_json::to_string_pretty(account_json).unwrap(),
                 )
                 .map_err(|e| format!("写入文件失败: {}", e))?;
+                record_proxy_audit(
+                    crate::modules::audit_db::AuditAction::ProtectedModelsChange,
+                    account_id,
+                    account_json,
+                    None,
+                    Some(serde_json::json!({ "protected_model_removed": model_name })),
+                    Some("quota_restored"),
+                );
                 return Ok(true);
             }
         }
@@ -819,6 +1152,27 @@ impl TokenManager {
         Some(selected)
     }
 
+    /// 按调度模式选择候选账号
+    /// UseItOrLoseIt 下候选已按浪费分数排序，直接取第一个可用账号；其余模式使用 P2C
+    fn select_candidate<'a>(
+        &self,
+        mode: crate::proxy::sticky_config::SchedulingMode,
+        candidates: &'a [ProxyToken],
+        attempted: &HashSet<String>,
+        normalized_target: &str,
+        quota_protection_enabled: bool,
+    ) -> Option<&'a ProxyToken> {
+        if mode == crate::proxy::sticky_config::SchedulingMode::UseItOrLoseIt {
+            return candidates
+                .iter()
+                .filter(|t| !attempted.contains(&t.account_id))
+                .find(|t| {
+                    !quota_protection_enabled || !t.protected_models.contains(normalized_target)
+                });
+        }
+        self.select_with_p2c(candidates, attempted, normalized_target, quota_protection_enabled)
+    }
+
     /// 先发送取消信号，再带超时等待任务完成
     ///
     /// # 参数
@@ -840,6 +1194,8 @@ impl TokenManager {
     /// abort() 仅设置取消标志，必须 await 确认清理完成
     pub async fn abort_background_tasks(&self) {
         Self::abort_task(&self.auto_cleanup_handle, "Auto-cleanup task").await;
+        Self::abort_task(&self.codex_usage_handle, "Codex usage refresh task").await;
+        Self::abort_task(&self.model_discovery_handle, "Model discovery task").await;
     }
 
     /// 中止单个后台任务并记录结果
@@ -922,8 +1278,25 @@ impl TokenManager {
 
         tokens_snapshot.retain(|token| token.provider == target_provider);
 
+        // [NEW] 跳过不在可用时间窗口内或已触达用量上限的账号
+        self.prefetch_usage_snapshots().await;
+        let provider_total = tokens_snapshot.len();
+        tokens_snapshot.retain(|token| match self.ineligible_reasons(token).first() {
+            Some(reason) => {
+                tracing::debug!("Skipping ineligible account {}: {}", token.email, reason);
+                false
+            }
+            None => true,
+        });
+
         let total = tokens_snapshot.len();
         if total == 0 {
+            if provider_total > 0 {
+                return Err(format!(
+                    "All {} accounts are ineligible (usage caps or availability windows)",
+                    target_provider
+                ));
+            }
             return Err(format!(
                 "Token pool is empty for provider: {}",
                 target_provider
@@ -934,53 +1307,24 @@ impl TokenManager {
         // 优先级: 目标模型配额 > 健康分 > 订阅等级 > 刷新时间
         // -> 高配额账号优先被选中，避免 PRO/ULTRA 先用完丢失5小时刷新周期
         // [FIX] 使用目标模型的 quota 而非 max(所有模型)
-        const RESET_TIME_THRESHOLD_SECS: i64 = 600; // 10 分钟阈值，差异小于此值视为相同
-
         let normalized_target =
             crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
                 .unwrap_or_else(|| target_model.to_string());
 
-        tokens_snapshot.sort_by(|a, b| {
-            // Priority 1: 目标模型的 quota (higher is better) -> 保护低配额账号
-            let quota_a = Self::get_model_quota_from_json(&a.account_path, &normalized_target)
-                .unwrap_or(a.remaining_quota.unwrap_or(0));
-            let quota_b = Self::get_model_quota_from_json(&b.account_path, &normalized_target)
-                .unwrap_or(b.remaining_quota.unwrap_or(0));
-            let quota_cmp = quota_b.cmp(&quota_a);
-            if quota_cmp != std::cmp::Ordering::Equal {
-                return quota_cmp;
-            }
-
-            // Priority 2: Health score (higher is better)
-            let health_cmp = b.health_score.partial_cmp(&a.health_score)
-                .unwrap_or(std::cmp::Ordering::Equal);
-            if health_cmp != std::cmp::Ordering::Equal {
-                return health_cmp;
-            }
-
-            // Priority 3: Subscription tier (ULTRA > PRO > FREE) -> 平局时高级账号优先
-            let tier_priority = |tier: &Option<String>| {
-                let t = tier.as_deref().unwrap_or("").to_lowercase();
-                if t.contains("ultra") { 0 }
-                else if t.contains("pro") { 1 }
-                else if t.contains("free") { 2 }
-                else { 3 }
-            };
-            let tier_cmp = tier_priority(&a.subscription_tier)
-                .cmp(&tier_priority(&b.subscription_tier));
-            if tier_cmp != std::cmp::Ordering::Equal {
-                return tier_cmp;
-            }
+        // 0. 读取当前调度配置 ([NEW] 请求头 x-antigravity-scheduling 可覆盖调度模式)
+        let overrides = crate::proxy::request_context::current_overrides();
+        let mut scheduling = self.sticky_config.read().await.clone();
+        if let Some(mode) = overrides.scheduling {
+            scheduling.mode = mode;
+        }
+        use crate::proxy::sticky_config::SchedulingMode;
 
-            // Priority 4: Reset time (earlier is better, but only if diff > 10 min)
-            let reset_a = a.reset_time.unwrap_or(i64::MAX);
-            let reset_b = b.reset_time.unwrap_or(i64::MAX);
-            if (reset_a - reset_b).abs() >= RESET_TIME_THRESHOLD_SECS {
-                reset_a.cmp(&reset_b)
-            } else {
-                std::cmp::Ordering::Equal
-            }
-        });
+        Self::sort_candidates(
+            &mut tokens_snapshot,
+            &normalized_target,
+            scheduling.mode,
+            chrono::Utc::now().timestamp(),
+        );
 
         // 【调试日志】打印排序后的账号顺序（显示目标模型的 quota）
         tracing::debug!(
@@ -1003,20 +1347,22 @@ impl TokenManager {
             )).collect::<Vec<_>>()
         );
 
-        // 0. 读取当前调度配置
-        let scheduling = self.sticky_config.read().await.clone();
-        use crate::proxy::sticky_config::SchedulingMode;
-
         // 【新增】检查配额保护是否启用（如果关闭，则忽略 protected_models 检查）
         let quota_protection_enabled = crate::modules::config::load_app_config()
             .map(|cfg| cfg.quota_protection.enabled)
             .unwrap_or(false);
 
         // ===== [FIX #820] 固定账号模式：优先使用指定账号 =====
-        let preferred_id = self.preferred_account_id.read().await.clone();
+        // [NEW] 请求头 x-antigravity-account (id 或邮箱) 优先于全局固定账号
+        let preferred_id = match overrides.account {
+            Some(account) => Some(account),
+            None => self.preferred_account_id.read().await.clone(),
+        };
         if let Some(ref pref_id) = preferred_id {
             // 查找优先账号
-            if let Some(preferred_token) = tokens_snapshot.iter().find(|t| &t.account_id == pref_id)
+            if let Some(preferred_token) = tokens_snapshot
+                .iter()
+                .find(|t| &t.account_id == pref_id || t.email.eq_ignore_ascii_case(pref_id))
             {
                 // 检查账号是否可用（未限流、未被配额保护）
                 let normalized_target =
@@ -1050,9 +1396,11 @@ impl TokenManager {
                         }
                     }
 
-                    // 确保有 project_id
+                    // 确保有 project_id (Codex 账号不需要 project_id)
                     let project_id = if let Some(pid) = &token.project_id {
                         pid.clone()
+                    } else if token.provider == "codex" {
+                        String::new()
                     } else {
                         match crate::proxy::project_resolver::fetch_project_id(&token.access_token)
                             .await
@@ -1209,8 +1557,8 @@ impl TokenManager {
                         }
                     }
 
-                    if let Some(selected) = self.select_with_p2c(
-                        &non_limited, &attempted, &normalized_target, quota_protection_enabled
+                    if let Some(selected) = self.select_candidate(
+                        scheduling.mode, &non_limited, &attempted, &normalized_target, quota_protection_enabled
                     ) {
                         target_token = Some(selected.clone());
                         need_update_last_used = Some((selected.account_id.clone(), std::time::Instant::now()));
@@ -1244,8 +1592,8 @@ impl TokenManager {
                     }
                 }
 
-                if let Some(selected) = self.select_with_p2c(
-                    &non_limited, &attempted, &normalized_target, quota_protection_enabled
+                if let Some(selected) = self.select_candidate(
+                    scheduling.mode, &non_limited, &attempted, &normalized_target, quota_protection_enabled
                 ) {
                     tracing::debug!("  {} - SELECTED via P2C", selected.email);
                     target_token = Some(selected.clone());
@@ -1361,9 +1709,11 @@ impl TokenManager {
                 }
             }
 
-            // 4. 确保有 project_id
+            // 4. 确保有 project_id (Codex 账号跳过解析)
             let project_id = if let Some(pid) = &token.project_id {
                 pid.clone()
+            } else if token.provider == "codex" {
+                String::new()
             } else {
                 tracing::debug!("账号 {} 缺少 project_id，尝试获取...", token.email);
                 match crate::proxy::project_resolver::fetch_project_id(&token.access_token).await {
@@ -1437,6 +1787,15 @@ impl TokenManager {
         std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
+        record_proxy_audit(
+            crate::modules::audit_db::AuditAction::Disable,
+            account_id,
+            &content,
+            Some(serde_json::json!({ "disabled": false })),
+            Some(serde_json::json!({ "disabled": true })),
+            content["disabled_reason"].as_str(),
+        );
+
         // 【修复 Issue #3】从内存中移除禁用的账号，防止被60s锁定逻辑继续使用
         self.tokens.remove(account_id);
 
@@ -1565,6 +1924,294 @@ impl TokenManager {
         Ok(Some(token))
     }
 
+    // ===== [NEW] 账号用量上限与可用时间窗口 =====
+
+    /// 用量快照缓存有效期 (秒)
+    const USAGE_SNAPSHOT_TTL_SECS: i64 = 30;
+
+    /// 返回账号当前不可调度的全部原因 (为空表示可用)
+    /// 用量只读缓存，调用前需先刷新快照 (`prefetch_usage_snapshots` / `load_usage_snapshots`)
+    pub fn ineligible_reasons(&self, token: &ProxyToken) -> Vec<IneligibleReason> {
+        let mut reasons = Vec::new();
+
+        if !Self::is_within_availability(&token.availability_windows, &chrono::Local::now()) {
+            reasons.push(IneligibleReason::OutsideAvailabilityWindow);
+        }
+
+        if !token.usage_limits.is_empty() {
+            if let Some(usage) = self.get_usage_snapshot(&token.email) {
+                reasons.extend(token.usage_limits.exceeded(&usage));
+            }
+        }
+
+        reasons
+    }
+
+    /// 窗口列表为空表示全天可用，否则任一窗口命中即可用
+    pub(crate) fn is_within_availability<T: chrono::Datelike + chrono::Timelike>(
+        windows: &[AvailabilityWindow],
+        at: &T,
+    ) -> bool {
+        windows.is_empty() || windows.iter().any(|w| w.is_active_at(at))
+    }
+
+    /// 读取缓存中未过期的用量快照 (不访问数据库，可在异步路径中调用)
+    fn get_usage_snapshot(&self, email: &str) -> Option<AccountUsageSnapshot> {
+        let now = chrono::Utc::now().timestamp();
+        self.usage_snapshots
+            .get(email)
+            .filter(|entry| now - entry.0 < Self::USAGE_SNAPSHOT_TTL_SECS)
+            .map(|entry| entry.1)
+    }
+
+    /// 设置了用量上限且缓存已过期的账号
+    fn stale_usage_emails(&self) -> Vec<String> {
+        self.tokens
+            .iter()
+            .filter(|e| !e.usage_limits.is_empty() && self.get_usage_snapshot(&e.email).is_none())
+            .map(|e| e.email.clone())
+            .collect()
+    }
+
+    /// 从 token_stats 统计账号本地自然日/自然月的用量并写入缓存 (阻塞 SQLite 查询)
+    fn load_usage_snapshots(cache: &DashMap<String, (i64, AccountUsageSnapshot)>, emails: &[String]) {
+        let Some((day_start, month_start)) = Self::local_period_starts(&chrono::Local::now()) else {
+            return;
+        };
+        let now = chrono::Utc::now().timestamp();
+        for email in emails {
+            let usage = crate::modules::token_stats::get_account_usage_since(email, day_start).and_then(
+                |daily| {
+                    crate::modules::token_stats::get_account_usage_since(email, month_start)
+                        .map(|monthly| (daily, monthly))
+                },
+            );
+            match usage {
+                Ok(((daily_tokens, daily_requests), (monthly_tokens, monthly_requests))) => {
+                    let snapshot = AccountUsageSnapshot {
+                        daily_tokens,
+                        monthly_tokens,
+                        daily_requests,
+                        monthly_requests,
+                    };
+                    cache.insert(email.clone(), (now, snapshot));
+                }
+                Err(e) => tracing::warn!("Failed to load usage for {}: {}", email, e),
+            }
+        }
+    }
+
+    /// 在阻塞线程池中刷新过期的用量快照，避免在请求路径上同步访问 SQLite
+    async fn prefetch_usage_snapshots(&self) {
+        let emails = self.stale_usage_emails();
+        if emails.is_empty() {
+            return;
+        }
+        let cache = self.usage_snapshots.clone();
+        if let Err(e) =
+            tokio::task::spawn_blocking(move || Self::load_usage_snapshots(&cache, &emails)).await
+        {
+            tracing::warn!("Usage snapshot refresh task failed: {}", e);
+        }
+    }
+
+    /// 计算本地自然日与自然月的起始时间戳
+    fn local_period_starts(now: &chrono::DateTime<chrono::Local>) -> Option<(i64, i64)> {
+        use chrono::{Datelike, TimeZone};
+        let date = now.date_naive();
+        let day_start = chrono::Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?
+            .timestamp();
+        let month_start = chrono::Local
+            .from_local_datetime(&date.with_day(1)?.and_hms_opt(0, 0, 0)?)
+            .earliest()?
+            .timestamp();
+        Some((day_start, month_start))
+    }
+
+    /// 反代池中所有账号的调度资格 (供管理 API 展示，阻塞调用)
+    pub fn get_eligibility_report(&self) -> Vec<(ProxyToken, Vec<IneligibleReason>)> {
+        Self::load_usage_snapshots(&self.usage_snapshots, &self.stale_usage_emails());
+        let tokens: Vec<ProxyToken> = self.tokens.iter().map(|e| e.value().clone()).collect();
+        tokens
+            .into_iter()
+            .map(|t| {
+                let reasons = self.ineligible_reasons(&t);
+                (t, reasons)
+            })
+            .collect()
+    }
+
+    // ===== [NEW] Codex 사용량 기반 스케줄링 =====
+
+    /// Codex 사용량을 (잔여 퍼센트, 리셋 시각) 으로 환산
+    /// primary(5h) / secondary(주간) 윈도우 중 더 많이 소진된 쪽을 기준으로 함
+    pub(crate) fn codex_usage_to_quota(usage: &CodexUsageInfo) -> (Option<i32>, Option<i64>) {
+        let windows = [
+            (usage.primary_used_percent, usage.primary_resets_at),
+            (usage.secondary_used_percent, usage.secondary_resets_at),
+        ];
+        let busiest = windows
+            .iter()
+            .filter_map(|(used, reset)| used.map(|u| (u, *reset)))
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        match busiest {
+            Some((used, reset)) => {
+                let remaining = (100.0 - used).clamp(0.0, 100.0).round() as i32;
+                (Some(remaining), reset)
+            }
+            None => (None, None),
+        }
+    }
+
+    /// 소진된(100%) 윈도우가 있으면 모두 풀리는 시각을 반환
+    pub(crate) fn codex_exhausted_until(usage: &CodexUsageInfo) -> Option<i64> {
+        [
+            (usage.primary_used_percent, usage.primary_resets_at),
+            (usage.secondary_used_percent, usage.secondary_resets_at),
+        ]
+        .iter()
+        .filter(|(used, _)| used.map(|u| u >= 100.0).unwrap_or(false))
+        .filter_map(|(_, reset)| *reset)
+        .max()
+    }
+
+    /// Codex 429 응답 본문에서 리셋 시각 추출 (`resets_at` 또는 `resets_in_seconds`)
+    pub(crate) fn codex_reset_from_error_body(error_body: &str, now: i64) -> Option<i64> {
+        let json: serde_json::Value = serde_json::from_str(error_body).ok()?;
+        let err = json.get("error").unwrap_or(&json);
+        if let Some(ts) = err.get("resets_at").and_then(|v| v.as_i64()) {
+            return Some(ts);
+        }
+        err.get("resets_in_seconds")
+            .and_then(|v| v.as_i64())
+            .map(|secs| now + secs)
+    }
+
+    /// Codex 계정 사용량을 조회해 캐시와 토큰 풀(remaining_quota / reset_time)에 반영
+    /// 소진된 계정은 윈도우 리셋 시각까지 잠금. 갱신된 계정 수 반환
+    async fn refresh_codex_usage_into(
+        tokens: &DashMap<String, ProxyToken>,
+        usage_cache: &DashMap<String, CodexUsageInfo>,
+        tracker: &RateLimitTracker,
+        only_account: Option<&str>,
+    ) -> usize {
+        use crate::modules::codex::types::CodexAuthData;
+
+        let store = match crate::modules::codex::storage::load_codex_accounts() {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::debug!("[Codex Usage] 계정 로드 실패: {}", e);
+                return 0;
+            }
+        };
+        let accounts: Vec<_> = store
+            .accounts
+            .into_iter()
+            .filter(|a| matches!(a.auth_data, CodexAuthData::ChatGPT { .. }))
+            .filter(|a| only_account.map_or(true, |id| a.id == id))
+            .filter(|a| tokens.get(&a.id).map(|t| t.provider == "codex").unwrap_or(false))
+            .collect();
+        if accounts.is_empty() {
+            return 0;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut updated = 0;
+        for usage in crate::modules::codex::refresh_all_codex_usage(&accounts).await {
+            if usage.error.is_some() {
+                continue;
+            }
+            let (remaining, reset) = Self::codex_usage_to_quota(&usage);
+            if let Some(mut entry) = tokens.get_mut(&usage.account_id) {
+                entry.remaining_quota = remaining;
+                entry.reset_time = reset;
+            }
+            if let Some(until) = Self::codex_exhausted_until(&usage).filter(|ts| *ts > now) {
+                tracker.set_lockout_until(
+                    &usage.account_id,
+                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(until as u64),
+                    crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+                    None,
+                );
+            }
+            usage_cache.insert(usage.account_id.clone(), usage);
+            updated += 1;
+        }
+        updated
+    }
+
+    /// 모든 Codex 계정 사용량 즉시 갱신
+    pub async fn refresh_codex_usage(&self) -> usize {
+        Self::refresh_codex_usage_into(&self.tokens, &self.codex_usage, &self.rate_limit_tracker, None)
+            .await
+    }
+
+    /// 캐시된 Codex 사용량 조회
+    pub fn get_codex_usage(&self, account_id: &str) -> Option<CodexUsageInfo> {
+        self.codex_usage.get(account_id).map(|u| u.clone())
+    }
+
+    /// Codex 계정 429 처리
+    ///
+    /// 1. Retry-After 헤더 → 그대로 사용
+    /// 2. 응답 본문의 resets_at / resets_in_seconds → 해당 시각까지 잠금
+    /// 3. 사용량 실시간 조회 → 소진된 윈도우의 리셋 시각까지 잠금
+    /// 4. 지수 백오프
+    pub async fn mark_codex_rate_limited(
+        &self,
+        email: &str,
+        status: u16,
+        retry_after_header: Option<&str>,
+        error_body: &str,
+    ) {
+        let config = self.circuit_breaker_config.read().await.clone();
+        if !config.enabled {
+            return;
+        }
+
+        let account_id = self.email_to_account_id(email).unwrap_or_else(|| email.to_string());
+        let now = chrono::Utc::now().timestamp();
+
+        if retry_after_header.is_none() {
+            if let Some(until) = Self::codex_reset_from_error_body(error_body, now).filter(|ts| *ts > now) {
+                self.rate_limit_tracker.set_lockout_until(
+                    &account_id,
+                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(until as u64),
+                    crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+                    None,
+                );
+                return;
+            }
+
+            if Self::refresh_codex_usage_into(
+                &self.tokens,
+                &self.codex_usage,
+                &self.rate_limit_tracker,
+                Some(&account_id),
+            )
+            .await
+                > 0
+                && self.rate_limit_tracker.is_rate_limited(&account_id, None)
+            {
+                tracing::info!("[Codex] 계정 {} 사용량 리셋 시각까지 잠금", email);
+                return;
+            }
+        }
+
+        tracing::warn!("[Codex] 계정 {} 리셋 시각을 알 수 없어 지수 백오프 적용", email);
+        self.rate_limit_tracker.parse_from_error(
+            &account_id,
+            status,
+            retry_after_header,
+            error_body,
+            None,
+            &config.backoff_steps,
+        );
+    }
+
     pub fn len(&self) -> usize {
         self.tokens.len()
     }
@@ -1638,6 +2285,8 @@ impl TokenManager {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: Default::default(),
+            availability_windows: Vec::new(),
         };
 
         match self.refresh_token_for_account(&mut token).await {
@@ -1741,13 +2390,15 @@ impl TokenManager {
         self.rate_limit_tracker.mark_success(account_id);
     }
 
-    /// 检查是否有可用的 Google 账号
+    /// 检查目标模型所属账号池中是否有可用账号
     ///
-    /// 用于"仅兜底"模式的智能判断:当所有 Google 账号不可用时才使用外部提供商。
+    /// 用于"仅兜底"模式的智能判断:当所有 Google 账号不可用时才使用外部提供商;
+    /// PM Router 规则也用它判断候选模型 (含 fallback_models) 是否可用。
     ///
     /// # 参数
     /// - `quota_group`: 配额组("claude" 或 "gemini"),暂未使用但保留用于未来扩展
-    /// - `target_model`: 目标模型名称(已归一化),用于配额保护检查
+    /// - `target_model`: 目标模型名称,按 provider 选择账号池 (Codex / Google),
+    ///   归一化后用于模型级限流与配额保护检查
     ///
     /// # 返回值
     /// - `true`: 至少有一个可用账号(未限流且未被配额保护)
@@ -1766,26 +2417,55 @@ impl TokenManager {
         let quota_protection_enabled = crate::modules::config::load_app_config()
             .map(|cfg| cfg.quota_protection.enabled)
             .unwrap_or(false);
+        self.has_available_account_with(target_model, quota_protection_enabled)
+            .await
+    }
+
+    async fn has_available_account_with(&self, target_model: &str, quota_protection_enabled: bool) -> bool {
+        // 与 get_token_internal 一致: 按 provider 选择账号池，按标准模型 ID 检查模型级限流
+        let target_provider = if crate::proxy::common::model_mapping::is_codex_model(target_model) {
+            "codex"
+        } else {
+            "google"
+        };
+        let normalized_target = crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
+            .unwrap_or_else(|| target_model.to_string());
+
+        self.prefetch_usage_snapshots().await;
 
         // 遍历所有账号,检查是否有可用的
         for entry in self.tokens.iter() {
             let token = entry.value();
+            if token.provider != target_provider {
+                continue;
+            }
 
-            // 1. 检查是否被限流
-            if self.is_rate_limited(&token.account_id, None).await {
+            // 1. 检查是否被限流 (账号级或目标模型级)
+            if self.is_rate_limited(&token.account_id, Some(&normalized_target)).await {
                 tracing::debug!(
-                    "[Fallback Check] Account {} is rate-limited, skipping",
-                    token.email
+                    "[Fallback Check] Account {} is rate-limited for model {}, skipping",
+                    token.email,
+                    normalized_target
                 );
                 continue;
             }
 
             // 2. 检查是否被配额保护(如果启用)
-            if quota_protection_enabled && token.protected_models.contains(target_model) {
+            if quota_protection_enabled && token.protected_models.contains(&normalized_target) {
                 tracing::debug!(
                     "[Fallback Check] Account {} is quota-protected for model {}, skipping",
                     token.email,
-                    target_model
+                    normalized_target
+                );
+                continue;
+            }
+
+            // 3. [NEW] 检查用量上限与可用时间窗口
+            if let Some(reason) = self.ineligible_reasons(token).first() {
+                tracing::debug!(
+                    "[Fallback Check] Account {} is ineligible ({}), skipping",
+                    token.email,
+                    reason
                 );
                 continue;
             }
@@ -1801,7 +2481,8 @@ impl TokenManager {
 
         // 所有账号都不可用
         tracing::info!(
-            "[Fallback Check] No available Google accounts for model {}, fallback should be triggered",
+            "[Fallback Check] No available {} accounts for model {}, fallback should be triggered",
+            target_provider,
             target_model
         );
         false
@@ -2136,6 +2817,7 @@ impl TokenManager {
         // 3. 委托给 modules::account::add_account 处理 (包含文件写入、索引更新、锁)
         let email_clone = email.to_string();
         let refresh_token_clone = refresh_token.to_string();
+        let actor = crate::modules::audit_db::current_actor();
 
         tokio::task::spawn_blocking(move || {
             let token_data = crate::models::TokenData::new(
@@ -2147,7 +2829,17 @@ impl TokenManager {
                 None, // session_id
             );
 
-            crate::modules::account::upsert_account(email_clone, None, token_data)
+            let account = crate::modules::account::upsert_account(email_clone, None, token_data)?;
+            crate::modules::audit_db::record_with_actor(
+                actor,
+                crate::modules::audit_db::AuditAction::Add,
+                Some(&account.id),
+                Some(&account.email),
+                None,
+                None,
+                Some("refresh_token"),
+            );
+            Ok::<_, String>(account)
         })
         .await
         .map_err(|e| format!("Task join error: {}", e))?
@@ -2287,6 +2979,42 @@ impl TokenManager {
     }
 }
 
+/// 临期优先 (UseItOrLoseIt) 打分：刷新前每小时需要消耗多少百分比配额才不会浪费。
+/// 例如 20% 且 10 分钟后刷新 => 120，60% 且 5 天后刷新 => 0.5，前者应先被消耗。
+/// 刷新时间未知或已过去时返回 0 (无法预判，交由常规排序决定)。
+pub(crate) fn quota_waste_score(remaining_pct: i32, reset_at: Option<i64>, now: i64) -> f64 {
+    const MIN_HORIZON_SECS: i64 = 60;
+
+    let Some(reset_at) = reset_at else {
+        return 0.0;
+    };
+    let secs_to_reset = reset_at - now;
+    if secs_to_reset <= 0 || remaining_pct <= 0 {
+        return 0.0;
+    }
+    remaining_pct as f64 * 3600.0 / secs_to_reset.max(MIN_HORIZON_SECS) as f64
+}
+
+/// 反代运行时自动变更的审计记录 (来源固定为 Proxy)
+fn record_proxy_audit(
+    action: crate::modules::audit_db::AuditAction,
+    account_id: &str,
+    account_json: &serde_json::Value,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    reason: Option<&str>,
+) {
+    crate::modules::audit_db::record_with_actor(
+        crate::modules::audit_db::AuditActor::Proxy,
+        action,
+        Some(account_id),
+        account_json.get("email").and_then(|v| v.as_str()),
+        before,
+        after,
+        reason,
+    );
+}
+
 /// 截断过长的原因字符串
 fn truncate_reason(reason: &str, max_len: usize) -> String {
     if reason.len() <= max_len {
@@ -2327,6 +3055,8 @@ mod tests {
             reset_time,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: Default::default(),
+            availability_windows: Vec::new(),
         }
     }
 
@@ -2584,6 +3314,8 @@ mod tests {
             reset_time: None,
             validation_blocked: false,
             validation_blocked_until: 0,
+            usage_limits: Default::default(),
+            availability_windows: Vec::new(),
         }
     }
 
@@ -2686,4 +3418,162 @@ mod tests {
         let result = manager.select_with_p2c(&candidates, &attempted, "claude-sonnet", false);
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    async fn test_fallback_check_respects_model_rate_limit_and_provider() {
+        let manager = TokenManager::new(PathBuf::from("/tmp/test"));
+        let token = create_test_token_with_protected("a@test.com", Some(80), HashSet::new());
+        manager.tokens.insert(token.account_id.clone(), token);
+
+        // 主模型被模型级限流，回退模型仍可用
+        manager.rate_limit_tracker.set_lockout_until(
+            "a@test.com",
+            std::time::SystemTime::now() + std::time::Duration::from_secs(600),
+            crate::proxy::rate_limit::RateLimitReason::QuotaExhausted,
+            Some("primary-model".to_string()),
+        );
+        assert!(!manager.has_available_account_with("primary-model", false).await);
+        assert!(manager.has_available_account_with("fallback-model", false).await);
+
+        // Codex 模型只看 Codex 账号池
+        let codex_model = crate::proxy::common::model_registry::codex_models()
+            .into_iter()
+            .next()
+            .expect("registry has codex models");
+        assert!(!manager.has_available_account_with(&codex_model, false).await);
+    }
+
+    fn create_codex_usage(
+        primary: Option<(f64, i64)>,
+        secondary: Option<(f64, i64)>,
+    ) -> CodexUsageInfo {
+        let mut usage = CodexUsageInfo::error("codex-1".to_string(), String::new());
+        usage.error = None;
+        usage.primary_used_percent = primary.map(|p| p.0);
+        usage.primary_resets_at = primary.map(|p| p.1);
+        usage.secondary_used_percent = secondary.map(|s| s.0);
+        usage.secondary_resets_at = secondary.map(|s| s.1);
+        usage
+    }
+
+    #[test]
+    fn test_codex_usage_uses_busiest_window() {
+        // 주간 윈도우가 더 많이 소진되었으면 그쪽 잔여율/리셋 시각 사용
+        let usage = create_codex_usage(Some((20.0, 1_000)), Some((75.4, 9_000)));
+        assert_eq!(TokenManager::codex_usage_to_quota(&usage), (Some(25), Some(9_000)));
+
+        let usage = create_codex_usage(Some((90.0, 1_000)), Some((10.0, 9_000)));
+        assert_eq!(TokenManager::codex_usage_to_quota(&usage), (Some(10), Some(1_000)));
+    }
+
+    #[test]
+    fn test_codex_usage_without_windows() {
+        // API 키 계정 등 윈도우 정보가 없으면 None
+        let usage = create_codex_usage(None, None);
+        assert_eq!(TokenManager::codex_usage_to_quota(&usage), (None, None));
+        assert_eq!(TokenManager::codex_exhausted_until(&usage), None);
+    }
+
+    #[test]
+    fn test_codex_exhausted_until() {
+        let usage = create_codex_usage(Some((100.0, 1_000)), Some((40.0, 9_000)));
+        assert_eq!(TokenManager::codex_exhausted_until(&usage), Some(1_000));
+
+        // 두 윈도우 모두 소진되면 늦게 풀리는 쪽까지 잠금
+        let usage = create_codex_usage(Some((100.0, 1_000)), Some((100.0, 9_000)));
+        assert_eq!(TokenManager::codex_exhausted_until(&usage), Some(9_000));
+
+        let usage = create_codex_usage(Some((99.0, 1_000)), None);
+        assert_eq!(TokenManager::codex_exhausted_until(&usage), None);
+    }
+
+    #[test]
+    fn test_codex_reset_from_error_body() {
+        let body = r#"{"error":{"type":"usage_limit_reached","resets_at":1700000000}}"#;
+        assert_eq!(TokenManager::codex_reset_from_error_body(body, 0), Some(1_700_000_000));
+
+        let body = r#"{"error":{"type":"usage_limit_reached","resets_in_seconds":120}}"#;
+        assert_eq!(TokenManager::codex_reset_from_error_body(body, 1_000), Some(1_120));
+
+        assert_eq!(TokenManager::codex_reset_from_error_body("rate limited", 1_000), None);
+    }
+
+    fn window(days: &str, start: &str, end: &str) -> AvailabilityWindow {
+        AvailabilityWindow {
+            days: days.to_string(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn local_at(y: i32, m: u32, d: u32, h: u32, min: u32) -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_availability_empty_means_always() {
+        assert!(TokenManager::is_within_availability(&[], &local_at(2025, 1, 6, 3, 0)));
+    }
+
+    #[test]
+    fn test_availability_weekday_office_hours() {
+        // 2025-01-06 是周一, 2025-01-05 是周日
+        let windows = vec![window("1-5", "09:00", "18:00")];
+        assert!(TokenManager::is_within_availability(&windows, &local_at(2025, 1, 6, 9, 0)));
+        assert!(TokenManager::is_within_availability(&windows, &local_at(2025, 1, 6, 17, 59)));
+        assert!(!TokenManager::is_within_availability(&windows, &local_at(2025, 1, 6, 18, 0)));
+        assert!(!TokenManager::is_within_availability(&windows, &local_at(2025, 1, 5, 12, 0)));
+    }
+
+    #[test]
+    fn test_availability_overnight_window() {
+        // "工作时间以外使用": 18:00 - 09:00 跨午夜
+        let windows = vec![window("*", "18:00", "09:00")];
+        assert!(TokenManager::is_within_availability(&windows, &local_at(2025, 1, 6, 23, 30)));
+        assert!(TokenManager::is_within_availability(&windows, &local_at(2025, 1, 7, 8, 59)));
+        assert!(!TokenManager::is_within_availability(&windows, &local_at(2025, 1, 7, 12, 0)));
+
+        // 周五晚开始的窗口延续到周六凌晨，但周日晚不开始
+        let windows = vec![window("5", "22:00", "02:00")];
+        assert!(TokenManager::is_within_availability(&windows, &local_at(2025, 1, 11, 1, 0)));
+        assert!(!TokenManager::is_within_availability(&windows, &local_at(2025, 1, 12, 1, 0)));
+    }
+
+    #[test]
+    fn test_availability_window_validation() {
+        assert!(window("1-5", "09:00", "18:00").validate().is_ok());
+        assert!(window("0,6,7", "00:00", "24:00").validate().is_ok());
+        assert!(window("1-8", "09:00", "18:00").validate().is_err());
+        assert!(window("*", "25:00", "18:00").validate().is_err());
+        assert!(window("mon", "09:00", "18:00").validate().is_err());
+    }
+
+    #[test]
+    fn test_usage_limits_exceeded() {
+        let limits = AccountUsageLimits {
+            daily_tokens: Some(1_000),
+            monthly_requests: Some(50),
+            ..Default::default()
+        };
+        let usage = AccountUsageSnapshot {
+            daily_tokens: 999,
+            monthly_tokens: 100_000,
+            daily_requests: 10,
+            monthly_requests: 10,
+        };
+        assert!(limits.exceeded(&usage).is_empty());
+
+        let usage = AccountUsageSnapshot {
+            daily_tokens: 1_000,
+            monthly_requests: 50,
+            ..usage
+        };
+        let reasons = limits.exceeded(&usage);
+        assert_eq!(reasons.len(), 2);
+        assert_eq!(reasons[0].to_string(), "daily token cap reached (1000/1000)");
+        assert!(AccountUsageLimits::default().is_empty());
+    }
 }
@@ -1,6 +1,8 @@
 use crate::modules::config::load_app_config;
 use once_cell::sync::Lazy;
-use reqwest::{Client, Proxy};
+use crate::proxy::tls::{CertSource, TlsCertInfo};
+use reqwest::{Certificate, Client, Proxy};
+use std::sync::Mutex;
 
 /// Global shared HTTP client (15s timeout)
 /// Client has a built-in connection pool; cloning it is light and shares the pool
@@ -9,6 +11,28 @@ pub static SHARED_CLIENT: Lazy<Client> = Lazy::new(|| create_base_client(15));
 /// Global shared HTTP client (Long timeout: 60s, for warmup etc.)
 pub static SHARED_CLIENT_LONG: Lazy<Client> = Lazy::new(|| create_base_client(60));
 
+/// Loopback client for calling the local proxy over HTTPS (60s timeout), cached per listener certificate
+/// Never goes through the upstream proxy
+static LOOPBACK_CLIENT: Lazy<Mutex<Option<(Option<String>, Client)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Build the loopback client: the generated self-signed listener certificate is trusted explicitly
+/// as a root; operator-supplied certificates are verified normally
+fn create_loopback_client(cert: Option<&TlsCertInfo>) -> Client {
+    let mut builder = Client::builder()
+        .timeout(std::time::Duration::from_secs(60))
+        .no_proxy();
+
+    if let Some(cert) = cert.filter(|c| c.source == CertSource::SelfSigned) {
+        match std::fs::read(&cert.cert_path).map(|pem| Certificate::from_pem(&pem)) {
+            Ok(Ok(root)) => builder = builder.add_root_certificate(root),
+            Ok(Err(e)) => tracing::error!("invalid self-signed certificate {}: {}", cert.cert_path, e),
+            Err(e) => tracing::error!("failed to read self-signed certificate {}: {}", cert.cert_path, e),
+        }
+    }
+
+    builder.build().unwrap_or_else(|_| Client::new())
+}
+
 /// Base client creation logic
 fn create_base_client(timeout_secs: u64) -> Client {
     let mut builder = Client::builder().timeout(std::time::Duration::from_secs(timeout_secs));
@@ -43,3 +67,19 @@ pub fn get_client() -> Client {
 pub fn get_long_client() -> Client {
     SHARED_CLIENT_LONG.clone()
 }
+
+/// Get loopback HTTP client (local proxy over HTTPS)
+pub fn get_loopback_client() -> Client {
+    let cert = crate::proxy::tls::manager().status().cert;
+    let fingerprint = cert.as_ref().map(|c| c.fingerprint_sha256.clone());
+
+    let mut cached = LOOPBACK_CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_fingerprint, client)) = cached.as_ref() {
+        if *cached_fingerprint == fingerprint {
+            return client.clone();
+        }
+    }
+    let client = create_loopback_client(cert.as_ref());
+    *cached = Some((fingerprint, client.clone()));
+    client
+}
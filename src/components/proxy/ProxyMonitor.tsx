@@ -22,6 +22,7 @@ interface ProxyRequestLog {
     model?: string;
     mapped_model?: string;
     pm_selected_model?: string;  // PM 라우터가 선택한 모델 (있을 때만)
+    thinking_budget?: number;  // 实际生效的 thinking budget (0 表示被策略禁用)
     error?: string;
     request_body?: string;
     response_body?: string;
@@ -29,6 +30,8 @@ interface ProxyRequestLog {
     output_tokens?: number;
     account_email?: string;
     protocol?: string;  // "openai" | "anthropic" | "gemini"
+    redaction_count?: number;  // 持久化前脱敏命中次数
+    guard_result?: string;     // 内容守卫命中记录
 }
 
 interface ProxyStats {
@@ -14,9 +14,45 @@ interface IpWhitelistConfig {
     whitelist_priority: boolean;
 }
 
+interface AutoBanConfig {
+    enabled: boolean;
+    auth_failure_threshold: number;
+    auth_failure_window_minutes: number;
+    flood_threshold: number;
+    flood_window_seconds: number;
+    probe_threshold: number;
+    probe_window_minutes: number;
+    probe_patterns: string[];
+    ban_durations_minutes: number[];
+    offense_reset_hours: number;
+    exempt_ips: string[];
+}
+
+interface TrustedProxyConfig {
+    proxies: string[]; // IPv4/IPv6 地址或 CIDR
+    cloudflare: boolean;
+}
+
+type SecurityEventFormat = 'jsonl' | 'cef' | 'syslog';
+
+interface SecurityEventExportConfig {
+    include_access: boolean;
+    retention_days: number;
+    file_path?: string | null;
+    file_format: SecurityEventFormat;
+    syslog_enabled: boolean;
+    syslog_transport: 'udp' | 'tcp';
+    syslog_host: string;
+    syslog_port: number;
+    syslog_format: SecurityEventFormat;
+}
+
 interface SecurityMonitorConfig {
     blacklist: IpBlacklistConfig;
     whitelist: IpWhitelistConfig;
+    auto_ban?: AutoBanConfig;
+    trusted_proxies?: TrustedProxyConfig;
+    event_export?: SecurityEventExportConfig;
 }
 
 export const SecurityConfig: React.FC = () => {
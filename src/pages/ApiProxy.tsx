@@ -147,8 +147,13 @@ export default function ApiProxy() {
         pm_pro_model: 'claude-sonnet-4-5',
         fallback_model: 'gemini-2.5-flash',
         max_context_chars: 4000,
-        cli_user_agents: ['claude-code', 'claude-cli', 'claude'],
-        pro_keywords: ['security', 'auth', 'permission', 'payment', 'billing', 'oauth', 'refactor', 'migration', 'architecture', 'design', 'adr']
+        cli_user_agents: ['claude-code', 'claude-cli', 'claude', 'codex', 'geminicli', 'gemini-cli'],
+        pro_keywords: ['security', 'auth', 'permission', 'payment', 'billing', 'oauth', 'refactor', 'migration', 'architecture', 'design', 'adr'],
+        decision_cache_enabled: true,
+        decision_cache_max_turns: 6,
+        decision_cache_ttl_secs: 1800,
+        routing_mode: 'rules_then_llm',
+        rules: []
     };
 
     const [status, setStatus] = useState<ProxyStatus>({
@@ -1612,7 +1617,7 @@ print(response.text)`;
                                                 </div>
                                             </div>
                                             <div className="grid grid-cols-1 gap-2">
-                                                {(['CacheFirst', 'Balance', 'PerformanceFirst'] as const).map(mode => (
+                                                {(['CacheFirst', 'Balance', 'PerformanceFirst', 'UseItOrLoseIt'] as const).map(mode => (
                                                     <label
                                                         key={mode}
                                                         className={`flex items-start gap-3 p-3 rounded-xl border cursor-pointer transition-all duration-200 ${(appConfig.proxy.scheduling?.mode || 'Balance') === mode
@@ -2108,6 +2113,51 @@ print(response.text)`;
                                                 </div>
                                             </div>
 
+                                            <div className="space-y-1.5">
+                                                <label className="text-xs font-semibold text-gray-600 dark:text-gray-300">{t('proxy.pm_router.routing_mode', { defaultValue: 'Routing Mode' })}</label>
+                                                <div className="flex items-center gap-2">
+                                                    <button
+                                                        type="button"
+                                                        className={cn(
+                                                            'px-3 py-1 rounded-lg text-xs font-medium border transition-colors',
+                                                            (pmRouterConfig.routing_mode ?? 'rules_then_llm') === 'llm'
+                                                                ? 'bg-blue-500 text-white border-blue-500'
+                                                                : 'border-gray-200 dark:border-gray-600/80 text-gray-600 dark:text-gray-400 hover:border-blue-300 dark:hover:border-gray-500'
+                                                        )}
+                                                        onClick={() => updatePmRouterConfig({ routing_mode: 'llm' })}
+                                                    >
+                                                        {t('proxy.pm_router.mode_llm', { defaultValue: 'LLM Only' })}
+                                                    </button>
+                                                    <button
+                                                        type="button"
+                                                        className={cn(
+                                                            'px-3 py-1 rounded-lg text-xs font-medium border transition-colors',
+                                                            (pmRouterConfig.routing_mode ?? 'rules_then_llm') === 'rules_then_llm'
+                                                                ? 'bg-blue-500 text-white border-blue-500'
+                                                                : 'border-gray-200 dark:border-gray-600/80 text-gray-600 dark:text-gray-400 hover:border-blue-300 dark:hover:border-gray-500'
+                                                        )}
+                                                        onClick={() => updatePmRouterConfig({ routing_mode: 'rules_then_llm' })}
+                                                    >
+                                                        {t('proxy.pm_router.mode_rules_then_llm', { defaultValue: 'Rules → LLM' })}
+                                                    </button>
+                                                    <button
+                                                        type="button"
+                                                        className={cn(
+                                                            'px-3 py-1 rounded-lg text-xs font-medium border transition-colors',
+                                                            (pmRouterConfig.routing_mode ?? 'rules_then_llm') === 'rules_only'
+                                                                ? 'bg-blue-500 text-white border-blue-500'
+                                                                : 'border-gray-200 dark:border-gray-600/80 text-gray-600 dark:text-gray-400 hover:border-blue-300 dark:hover:border-gray-500'
+                                                        )}
+                                                        onClick={() => updatePmRouterConfig({ routing_mode: 'rules_only' })}
+                                                    >
+                                                        {t('proxy.pm_router.mode_rules_only', { defaultValue: 'Rules Only' })}
+                                                    </button>
+                                                </div>
+                                                <p className="text-[10px] text-gray-400 dark:text-gray-500">
+                                                    {t('proxy.pm_router.rules_hint', { defaultValue: 'Rules are evaluated top to bottom without an LLM call ({{count}} configured). Test them with POST /api/proxy/pm-router/dry-run.', count: pmRouterConfig.rules?.length ?? 0 })}
+                                                </p>
+                                            </div>
+
                                             <div className="grid grid-cols-1 md:grid-cols-3 gap-2">
                                                 <div className="space-y-1">
                                                     <label className="text-[11px] font-semibold text-gray-600 dark:text-gray-300">{t('proxy.pm_router.lite_model', { defaultValue: 'PM-lite Model' })}</label>
@@ -2152,6 +2202,27 @@ print(response.text)`;
                                                 />
                                                 <p className="text-[10px] text-gray-400 dark:text-gray-500">{t('proxy.pm_router.context_hint', { defaultValue: 'Used to cap the prompt sent to the router agent.' })}</p>
                                             </div>
+                                            <div className="space-y-1">
+                                                <label className="flex items-center justify-between text-[11px] font-semibold text-gray-600 dark:text-gray-300">
+                                                    {t('proxy.pm_router.decision_cache', { defaultValue: 'Session Decision Cache' })}
+                                                    <input
+                                                        type="checkbox"
+                                                        className="toggle toggle-xs bg-gray-200 dark:bg-gray-700 border-gray-300 dark:border-gray-600 checked:bg-blue-500 checked:border-blue-500"
+                                                        checked={pmRouterConfig.decision_cache_enabled ?? true}
+                                                        onChange={(e) => updatePmRouterConfig({ decision_cache_enabled: e.target.checked })}
+                                                    />
+                                                </label>
+                                                <input
+                                                    type="number"
+                                                    min={0}
+                                                    max={100}
+                                                    value={pmRouterConfig.decision_cache_max_turns ?? 6}
+                                                    disabled={!(pmRouterConfig.decision_cache_enabled ?? true)}
+                                                    onChange={(e) => updatePmRouterConfig({ decision_cache_max_turns: Number(e.target.value) })}
+                                                    className="input input-xs input-bordered w-full text-[11px] bg-white dark:bg-gray-800/80 dark:border-gray-600"
+                                                />
+                                                <p className="text-[10px] text-gray-400 dark:text-gray-500">{t('proxy.pm_router.decision_cache_hint', { defaultValue: 'Reuse the routing decision within a session; re-route after this many new user turns (0 = only on task change).' })}</p>
+                                            </div>
                                             <div className="space-y-1">
                                                 <label className="text-[11px] font-semibold text-gray-600 dark:text-gray-300">{t('proxy.pm_router.user_agents', { defaultValue: 'CLI User-Agent Keywords' })}</label>
                                                 <textarea
@@ -32,10 +32,28 @@ export interface Account {
     proxy_disabled_reason?: string;
     proxy_disabled_at?: number;
     protected_models?: string[];
+    /** 反代用量上限 (本地自然日/自然月) */
+    usage_limits?: AccountUsageLimits;
+    /** 反代可用时间窗口, 为空表示全天可用 */
+    availability_windows?: AvailabilityWindow[];
     created_at: number;
     last_used: number;
 }
 
+export interface AccountUsageLimits {
+    daily_tokens?: number;
+    monthly_tokens?: number;
+    daily_requests?: number;
+    monthly_requests?: number;
+}
+
+/** cron 风格时间窗口: days 为星期字段 (0-6, 0=周日), start/end 为本地 "HH:MM" */
+export interface AvailabilityWindow {
+    days: string;
+    start: string;
+    end: string;
+}
+
 export interface TokenData {
     access_token: string;
     refresh_token: string;
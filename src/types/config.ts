@@ -23,6 +23,14 @@ export interface ProxyConfig {
     user_agent_override?: string;
     saved_user_agent?: string;
     thinking_budget?: ThinkingBudgetConfig;
+    log_redaction?: LogRedactionConfig;
+    guardrails?: GuardrailConfig;
+    tls?: TlsConfig;
+    admin_auth?: AdminAuthConfig;
+    api_keys?: ApiKeyConfig[];
+    allow_request_overrides?: boolean; // 主 api_key 是否可使用 x-antigravity-* 路由覆盖请求头
+    model_overrides?: ModelCapabilityOverride[];
+    tool_result_compression?: ToolResultCompressionConfig;
 }
 
 // ============================================================================
@@ -38,6 +46,127 @@ export interface ThinkingBudgetConfig {
     mode: ThinkingBudgetMode;
     /** 自定义固定值（仅在 mode=custom 时生效），范围 1024-65536 */
     custom_value: number;
+    /** 按模型的策略 (按顺序匹配，支持 * 通配符)，未命中时使用 mode */
+    policies?: ThinkingPolicyRule[];
+}
+
+/** 单个模型的 Thinking 策略 */
+export type ThinkingPolicy =
+    | { action: 'passthrough' }
+    | { action: 'clamp'; min?: number; max?: number }
+    | { action: 'fixed'; value: number }
+    | { action: 'disable' }
+    | { action: 'scale'; ratio: number; min?: number; max?: number };
+
+export type ThinkingPolicyRule = { model: string } & ThinkingPolicy;
+
+/** 请求日志脱敏 (写入数据库 / 调试日志前执行) */
+export interface LogRedactionConfig {
+    enabled: boolean;
+    body_mode: 'full' | 'metadata_only';
+    /** 关闭的内置检测器: api_key / bearer_token / jwt / email / private_key / aws_key */
+    disabled_detectors?: string[];
+    custom_rules?: { name: string; pattern: string }[];
+}
+
+export type GuardAction = 'block' | 'redact' | 'tag';
+
+/** 内容守卫规则 (正则与关键词任一命中即视为命中) */
+export interface GuardRule {
+    name: string;
+    stage?: 'input' | 'output' | 'both';
+    action?: GuardAction;
+    pattern?: string;
+    /** 关键词 (不区分大小写) */
+    keywords?: string[];
+}
+
+/** 内容策略守卫 (入站提示词 / 出站补全) */
+export interface GuardrailConfig {
+    enabled: boolean;
+    rules?: GuardRule[];
+    /** 单个请求最大图片数量，0 表示不限制 */
+    max_images?: number;
+    /** 单张内联图片最大字节数，0 表示不限制 */
+    max_image_bytes?: number;
+    banned_tools?: string[];
+    banned_tool_action?: GuardAction;
+}
+
+/** HTTPS 监听 (证书留空时自动生成自签名证书) */
+export interface TlsConfig {
+    enabled: boolean;
+    cert_path?: string;
+    key_path?: string;
+    /** 自签名证书额外的域名 / IP */
+    self_signed_hosts?: string[];
+    /** 证书文件变更检查间隔 (秒)，0 表示仅手动重载 */
+    reload_interval_secs?: number;
+}
+
+/** 管理后台用户鉴权 */
+export interface AdminAuthConfig {
+    /** 登录会话有效期 (小时) */
+    session_ttl_hours: number;
+    /** 已创建管理用户后是否仍允许共享密码访问管理接口 */
+    allow_legacy_password: boolean;
+}
+
+export type AdminRole = 'viewer' | 'operator' | 'admin';
+
+/** 附加 API Key (可携带独立策略) */
+export interface ApiKeyConfig {
+    key: string;
+    name: string;
+    enabled: boolean;
+    thinking_policies?: ThinkingPolicyRule[];
+    allow_request_overrides?: boolean;
+    skip_body_logging?: boolean;
+}
+
+// ============================================================================
+// 工具结果压缩配置 (按工具选择压缩策略与字符预算)
+// ============================================================================
+
+/** 压缩策略: default 表示仅使用通用压缩 */
+export type ToolResultStrategyName = 'json' | 'log' | 'grep' | 'diff' | 'default';
+
+export interface ToolResultCompressionRule {
+    /** 工具名或通配符模式，如 mcp__github__* (不区分大小写) */
+    tool: string;
+    strategy: ToolResultStrategyName;
+    /** 该工具的字符预算 (未设置时使用 default_max_chars) */
+    max_chars?: number;
+}
+
+export interface ToolResultCompressionConfig {
+    enabled: boolean;
+    rules: ToolResultCompressionRule[];
+    default_max_chars: number;
+}
+
+// ============================================================================
+// 模型能力注册表覆盖 (与内置模型表合并，未填写字段保持内置值)
+// ============================================================================
+
+export type ModelProvider = 'gemini' | 'claude' | 'codex';
+
+export interface ModelCapabilityOverride {
+    id: string;
+    provider?: ModelProvider;
+    /** 实际发往上游的模型名 (空字符串表示与 id 相同) */
+    upstream?: string;
+    context_window?: number;
+    max_output_tokens?: number;
+    thinking?: boolean;
+    /** Auto 模式下的 thinkingBudget 上限 (0 表示不限制) */
+    thinking_budget_cap?: number;
+    image_input?: boolean;
+    audio_input?: boolean;
+    quota_group?: string;
+    aliases?: string[];
+    /** 是否作为 PM Router 候选模型 */
+    router?: boolean;
 }
 
 export interface DebugLoggingConfig {
@@ -45,7 +174,7 @@ export interface DebugLoggingConfig {
     output_dir?: string;
 }
 
-export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst';
+export type SchedulingMode = 'CacheFirst' | 'Balance' | 'PerformanceFirst' | 'UseItOrLoseIt';
 
 export interface StickySessionConfig {
     mode: SchedulingMode;
@@ -101,6 +230,29 @@ export interface ExperimentalConfig {
 
 export type PmRouterScope = 'cli_only' | 'all_requests';
 
+export type PmRoutingMode = 'llm' | 'rules_then_llm' | 'rules_only';
+
+export interface PmRuleConditions {
+    has_images?: boolean | null;
+    has_tools?: boolean | null;
+    tool_names?: string[];
+    min_tokens?: number | null;
+    max_tokens?: number | null;
+    thinking?: boolean | null;
+    keywords?: string[];
+    user_agents?: string[];
+    api_keys?: string[];
+    protocols?: string[];
+}
+
+export interface PmRoutingRule {
+    name: string;
+    enabled: boolean;
+    conditions: PmRuleConditions;
+    target_model: string;
+    fallback_models: string[];
+}
+
 export interface PmRouterConfig {
     enabled: boolean;
     scope: PmRouterScope;
@@ -110,6 +262,11 @@ export interface PmRouterConfig {
     cli_user_agents: string[];
     max_context_chars: number;
     fallback_model: string;
+    decision_cache_enabled?: boolean;
+    decision_cache_max_turns?: number;
+    decision_cache_ttl_secs?: number;
+    routing_mode?: PmRoutingMode;
+    rules?: PmRoutingRule[];
 }
 
 export interface CircuitBreakerConfig {
@@ -138,6 +295,37 @@ export interface AppConfig {
     proxy: ProxyConfig;
 }
 
+// ============================================================================
+// 配置版本历史 (校验 / 对比 / 回滚)
+// ============================================================================
+
+export type ConfigSource = 'baseline' | 'ui' | 'admin_api' | 'env' | 'rollback';
+
+export interface ConfigFieldError {
+    field: string; // 点分路径，如 proxy.upstream_proxy.url
+    message: string;
+}
+
+export interface ConfigCommit {
+    version_id?: number | null;
+    changed_sections: string[];
+    restart_required: string[]; // 需重启反代服务才能生效的分区
+}
+
+export interface ConfigVersion {
+    id: number;
+    timestamp: number; // unix 秒
+    source: ConfigSource;
+    actor?: string | null;
+    changed_sections: string[];
+}
+
+export interface ConfigDiffEntry {
+    path: string;
+    before: unknown;
+    after: unknown;
+}
+
 // ============================================================================
 // Cloudflared (CF隧道) 类型定义
 // ============================================================================
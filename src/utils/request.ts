@@ -119,6 +119,8 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'remove_ip_from_blacklist': { url: '/api/security/blacklist', method: 'DELETE' },
   'clear_ip_blacklist': { url: '/api/security/blacklist/clear', method: 'POST' },
   'check_ip_in_blacklist': { url: '/api/security/blacklist/check', method: 'GET' },
+  'get_auto_bans': { url: '/api/security/auto-bans', method: 'GET' },
+  'lift_auto_ban': { url: '/api/security/auto-bans', method: 'DELETE' },
   'get_ip_whitelist': { url: '/api/security/whitelist', method: 'GET' },
   'add_ip_to_whitelist': { url: '/api/security/whitelist', method: 'POST' },
   'remove_ip_from_whitelist': { url: '/api/security/whitelist', method: 'DELETE' },